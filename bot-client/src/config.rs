@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+pub const DEFAULT_TRAQ_ORIGIN: &str = "https://q.trap.jp";
+
+#[derive(Debug, Clone)]
+pub struct BotClientConfig {
+    /// `https://q.trap.jp`のようなtraQのオリジン
+    pub origin: String,
+    /// 1リクエストあたりのタイムアウト
+    pub timeout: Duration,
+    /// リトライできるエラーのときの最大リトライ回数
+    pub max_retries: u32,
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+    /// traQ APIへの同時リクエスト数の上限
+    pub max_concurrency: usize,
+}
+
+impl Default for BotClientConfig {
+    fn default() -> Self {
+        Self {
+            origin: DEFAULT_TRAQ_ORIGIN.to_string(),
+            timeout: Duration::from_secs(10),
+            max_retries: 3,
+            base_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_concurrency: 8,
+        }
+    }
+}
+
+impl BotClientConfig {
+    pub fn load_env() -> Result<Self, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        use std::env::var;
+        let default = Self::default();
+        let origin = var("TRAQ_ORIGIN").unwrap_or(default.origin.clone());
+        let timeout = match var("BOT_TIMEOUT_SECS") {
+            Ok(t) => Duration::from_secs(t.parse()?),
+            Err(_) => default.timeout,
+        };
+        let max_retries = match var("BOT_MAX_RETRIES") {
+            Ok(r) => r.parse()?,
+            Err(_) => default.max_retries,
+        };
+        let max_concurrency = match var("BOT_MAX_CONCURRENCY") {
+            Ok(c) => c.parse()?,
+            Err(_) => default.max_concurrency,
+        };
+        if max_concurrency == 0 {
+            return Err("BOT_MAX_CONCURRENCY must be positive".into());
+        }
+        Ok(Self {
+            origin,
+            timeout,
+            max_retries,
+            max_concurrency,
+            ..default
+        })
+    }
+
+    pub fn base_path(&self) -> String {
+        format!("{}/api/v3", self.origin.trim_end_matches('/'))
+    }
+
+    /// `attempt`回目の失敗の後に待つ時間 (full jitter)。`Retry-After`があっても`max_backoff`を超えない
+    pub fn backoff(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        use rand::Rng;
+        let mut rng = rand::thread_rng();
+        if let Some(retry_after) = retry_after {
+            let jitter = rng.gen_range(Duration::ZERO..=self.base_backoff);
+            return (retry_after + jitter).min(self.max_backoff);
+        }
+        let exp = self
+            .base_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff);
+        rng.gen_range(Duration::ZERO..=exp)
+    }
+}
@@ -1,33 +1,83 @@
+use std::time::Duration;
+
+use domain::bot_client::{BotClientError, ErrorKind};
 use reqwest::StatusCode;
-use traq::apis;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("http error: {0}")]
-    Reqwest(#[from] reqwest::Error),
+    Reqwest(reqwest::Error),
     #[error("json parse error: {0}")]
     Serde(#[from] serde_json::Error),
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
     #[error("traq api error: {}", .content)]
     ApiError { status: StatusCode, content: String },
+    #[error("not found: {}", .content)]
+    NotFound { content: String },
+    #[error("forbidden: {}", .content)]
+    Forbidden { content: String },
+    #[error("rate limited: {}", .content)]
+    RateLimited {
+        retry_after: Option<Duration>,
+        content: String,
+    },
+    #[error("request timed out")]
+    Timeout,
     #[error("unknown image type")]
     UnknownImageType,
+    #[error("webhook registry error: {0}")]
+    Registry(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-impl<T> From<apis::Error<T>> for Error {
-    fn from(e: apis::Error<T>) -> Self {
-        use apis::Error::*;
-        match e {
-            Reqwest(e) => e.into(),
-            Serde(e) => e.into(),
-            Io(e) => e.into(),
-            ResponseError(e) => Error::ApiError {
-                status: e.status,
-                content: e.content,
+impl Error {
+    pub fn from_status(status: StatusCode, content: String, retry_after: Option<Duration>) -> Self {
+        match status {
+            StatusCode::NOT_FOUND => Error::NotFound { content },
+            StatusCode::FORBIDDEN => Error::Forbidden { content },
+            StatusCode::TOO_MANY_REQUESTS => Error::RateLimited {
+                retry_after,
+                content,
             },
+            status => Error::ApiError { status, content },
+        }
+    }
+
+    /// 429, 503 はリトライする。502, 504 はサーバーで処理された可能性があるので
+    /// `idempotent`なリクエストのみリトライする
+    pub fn is_retryable(&self, idempotent: bool) -> bool {
+        match self {
+            Error::RateLimited { .. } => true,
+            Error::ApiError { status, .. } => match *status {
+                StatusCode::SERVICE_UNAVAILABLE => true,
+                StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT => idempotent,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+impl BotClientError for Error {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Error::NotFound { .. } => ErrorKind::NotFound,
+            Error::Forbidden { .. } => ErrorKind::Forbidden,
+            Error::RateLimited { .. } => ErrorKind::RateLimited,
+            Error::Timeout => ErrorKind::Timeout,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Error::Timeout
+        } else {
+            Error::Reqwest(e)
         }
     }
 }
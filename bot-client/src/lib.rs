@@ -1,60 +1,210 @@
+pub mod config;
 pub mod errors;
+pub mod webhook;
+pub use crate::config::*;
 pub use crate::errors::*;
+pub use crate::webhook::WebhookSink;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
 use async_trait::async_trait;
+use bytes::Bytes;
 use domain::bot_client::{
-    BotClient, ImageData, PostMessageParams, StampType, UploadFileParams, UploadFileResp,
+    BotClient, Image, ImageData, PostMessageParams, StampType, UploadFileParams, UploadFileResp,
 };
+use domain::delivery::DeliverySink;
 use reqwest::multipart::{Form, Part};
-use reqwest::Response;
+use reqwest::{Method, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
 use shaku::Component;
-use traq::apis::message_api;
-use traq::apis::{channel_api, configuration::Configuration, stamp_api, user_api};
-use traq::models::{ChannelList, FileInfo, PostMessageRequest, Stamp, User, UserDetail};
+use tokio::sync::Semaphore;
+use traq::apis::configuration::Configuration;
+use traq::models::{
+    ChannelList, DmChannel, FileInfo, PostMessageRequest, Stamp, User, UserDetail, UserGroup,
+};
+use uuid::Uuid;
 
 #[derive(Debug, Clone, Component)]
 #[shaku(interface = BotClient<Error = Error>)]
 pub struct BotClientImpl {
     conf: Configuration,
+    config: BotClientConfig,
+    limiter: Arc<Semaphore>,
 }
 
 impl BotClientImpl {
     pub fn new(bearer_access_token: String) -> Self {
+        Self::with_config(bearer_access_token, BotClientConfig::default())
+    }
+
+    pub fn with_config(bearer_access_token: String, config: BotClientConfig) -> Self {
         let conf = Configuration {
+            base_path: config.base_path(),
             bearer_access_token: Some(bearer_access_token),
             ..Default::default()
         };
-        Self { conf }
+        let limiter = Arc::new(Semaphore::new(config.max_concurrency));
+        Self {
+            conf,
+            config,
+            limiter,
+        }
+    }
+
+    /// 同時実行数の制限とタイムアウトをかけてリクエストを行い、
+    /// リトライできるエラーならバックオフを挟んでリトライする。
+    /// 冪等でない`method`では二重投稿を避けるため 429, 503 のみリトライする
+    async fn execute<T, F, Fut>(&self, method: Method, request: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let idempotent = method.is_idempotent();
+        let mut attempt = 0;
+        loop {
+            let result = {
+                let _permit = self.limiter.acquire().await.expect("semaphore closed");
+                tokio::time::timeout(self.config.timeout, request())
+                    .await
+                    .unwrap_or(Err(Error::Timeout))
+            };
+            let err = match result {
+                Ok(value) => return Ok(value),
+                Err(e) if e.is_retryable(idempotent) && attempt < self.config.max_retries => e,
+                Err(e) => return Err(e),
+            };
+            let retry_after = match &err {
+                Error::RateLimited { retry_after, .. } => *retry_after,
+                _ => None,
+            };
+            let wait = self.config.backoff(attempt, retry_after);
+            eprintln!("traq api error: {}, retrying in {:?}", err, wait);
+            tokio::time::sleep(wait).await;
+            attempt += 1;
+        }
+    }
+
+    /// traQ APIへのリクエスト。traqクレートの関数はレスポンスヘッダを返さず
+    /// Retry-Afterが読めないので、reqwestで直接送る
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let conf = &self.conf;
+        let token = conf.bearer_access_token.as_ref().unwrap();
+        let url = format!("{}{}", conf.base_path, path);
+        conf.client.request(method, url).bearer_auth(token)
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, path: &str, query: &[(&str, &str)]) -> Result<T> {
+        self.execute(Method::GET, || async {
+            let response = self.request(Method::GET, path).query(query).send().await?;
+            Ok(error_for_status(response).await?.json::<T>().await?)
+        })
+        .await
+    }
+
+    async fn get_image(&self, path: &str) -> Result<Image> {
+        self.execute(Method::GET, || async {
+            let response = self.request(Method::GET, path).send().await?;
+            let response = error_for_status(response).await?;
+            image_from_response(response).await
+        })
+        .await
+    }
+}
+
+/// `Retry-After`の秒数またはHTTP-date形式を待ち時間にする
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse() {
+        return Some(Duration::from_secs(secs));
     }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(std::time::SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
 }
 
-pub async fn image_from_response(response: Response) -> Result<ImageData> {
-    let mime_type = response
-        .headers()
-        .get(reqwest::header::CONTENT_TYPE)
-        .unwrap()
-        .to_str()
-        .unwrap();
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(reqwest::header::RETRY_AFTER)?;
+    parse_retry_after(value.to_str().ok()?)
+}
+
+pub(crate) async fn error_for_status(response: Response) -> Result<Response> {
+    let status = response.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return Ok(response);
+    }
+    let retry_after = retry_after(&response);
+    let content = response.text().await?;
+    Err(Error::from_status(status, content, retry_after))
+}
+
+/// `Content-Type`を信用できないときにマジックバイトから画像の種類を推定する
+pub fn sniff_image_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some("image/png");
+    }
+    if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        return Some("image/gif");
+    }
+    if data.starts_with(b"\xff\xd8\xff") {
+        return Some("image/jpeg");
+    }
+    if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+    let head = &data[..data.len().min(1024)];
+    let head = String::from_utf8_lossy(head);
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    if head.starts_with("<svg") || (head.starts_with("<?xml") && head.contains("<svg")) {
+        return Some("image/svg+xml");
+    }
+    None
+}
+
+/// `image/png; charset=binary`のようなパラメータ付きの`Content-Type`も受け付け、
+/// 不明なときはマジックバイトで判定する
+pub fn decode_image(content_type: Option<&str>, data: Bytes) -> Result<ImageData> {
+    let mime_type = content_type
+        .and_then(|c| c.split(';').next())
+        .map(|c| c.trim().to_ascii_lowercase());
+    let mime_type = match mime_type.as_deref() {
+        Some(m @ ("image/svg+xml" | "image/png" | "image/gif" | "image/jpeg" | "image/webp")) => m,
+        _ => sniff_image_type(&data).ok_or(Error::UnknownImageType)?,
+    };
     match mime_type {
         "image/svg+xml" => {
-            let data = response.text().await?;
+            let data = String::from_utf8(data.to_vec()).map_err(|_| Error::UnknownImageType)?;
             Ok(ImageData::Svg(data))
         }
-        "image/png" => {
-            let data = response.bytes().await?;
-            Ok(ImageData::Png(data))
-        }
-        "image/gif" => {
-            let data = response.bytes().await?;
-            Ok(ImageData::Gif(data))
-        }
-        "image/jpeg" => {
-            let data = response.bytes().await?;
-            Ok(ImageData::Jpeg(data))
-        }
+        "image/png" => Ok(ImageData::Png(data)),
+        "image/gif" => Ok(ImageData::Gif(data)),
+        "image/jpeg" => Ok(ImageData::Jpeg(data)),
+        "image/webp" => Ok(ImageData::Webp(data)),
         _ => Err(Error::UnknownImageType),
     }
 }
 
+pub async fn image_from_response(response: Response) -> Result<Image> {
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+    };
+    let content_type = header(reqwest::header::CONTENT_TYPE);
+    let etag = header(reqwest::header::ETAG);
+    let last_modified = header(reqwest::header::LAST_MODIFIED);
+    let data = response.bytes().await?;
+    Ok(Image {
+        data: decode_image(content_type.as_deref(), data)?,
+        etag,
+        last_modified,
+    })
+}
+
 fn to_param(st: StampType) -> Option<&'static str> {
     match st {
         StampType::Original => Some("original"),
@@ -68,91 +218,84 @@ impl BotClient for BotClientImpl {
     type Error = Error;
 
     async fn get_stamps(&self, r#type: StampType) -> Result<Vec<Stamp>> {
-        Ok(stamp_api::get_stamps(&self.conf, None, to_param(r#type)).await?)
+        let query: Vec<_> = to_param(r#type).map(|t| ("type", t)).into_iter().collect();
+        self.get_json("/stamps", &query).await
     }
 
-    async fn get_stamp_image(&self, stamp_id: &str) -> Result<ImageData> {
-        let conf = &self.conf;
-        let token = conf.bearer_access_token.as_ref().unwrap();
-        let client = &conf.client;
-        let url = format!("{}/stamps/{}/image", conf.base_path, stamp_id);
-        let request = client
-            .request(reqwest::Method::GET, url.as_str())
-            .bearer_auth(token);
-        let response = request.send().await?;
-        let status = response.status();
-        if !status.is_client_error() && !status.is_server_error() {
-            Ok(image_from_response(response).await?)
-        } else {
-            let content = response.text().await?;
-            Err(Error::ApiError { status, content })
-        }
+    async fn get_stamp_image(&self, stamp_id: &str) -> Result<Image> {
+        self.get_image(&format!("/stamps/{}/image", stamp_id)).await
     }
 
     async fn get_users<'a>(&'a self, name: Option<&'a str>) -> Result<Vec<User>> {
-        Ok(user_api::get_users(&self.conf, None, name).await?)
+        let query: Vec<_> = name.map(|n| ("name", n)).into_iter().collect();
+        self.get_json("/users", &query).await
+    }
+
+    async fn get_users_by_ids(&self, ids: &[Uuid]) -> Result<Vec<User>> {
+        let users = self.get_users(None).await?;
+        Ok(users.into_iter().filter(|u| ids.contains(&u.id)).collect())
     }
 
     async fn get_user(&self, user_id: &str) -> Result<UserDetail> {
-        Ok(user_api::get_user(&self.conf, user_id).await?)
+        self.get_json(&format!("/users/{}", user_id), &[]).await
     }
 
-    async fn get_user_icon(&self, user_id: &str) -> Result<ImageData> {
-        let conf = &self.conf;
-        let token = conf.bearer_access_token.as_ref().unwrap();
-        let client = &conf.client;
-        let url = format!("{}/users/{}/icon", conf.base_path, user_id);
-        let request = client
-            .request(reqwest::Method::GET, url.as_str())
-            .bearer_auth(token);
-        let response = request.send().await?;
-        let status = response.status();
-        if !status.is_client_error() && !status.is_server_error() {
-            Ok(image_from_response(response).await?)
-        } else {
-            let content = response.text().await?;
-            Err(Error::ApiError { status, content })
-        }
+    async fn get_user_icon(&self, user_id: &str) -> Result<Image> {
+        self.get_image(&format!("/users/{}/icon", user_id)).await
     }
 
     async fn get_channels(&self) -> Result<ChannelList> {
-        Ok(channel_api::get_channels(&self.conf, None).await?)
+        self.get_json("/channels", &[]).await
+    }
+    async fn get_user_dm_channel(&self, user_id: &str) -> Result<DmChannel> {
+        self.get_json(&format!("/users/{}/dm-channel", user_id), &[])
+            .await
+    }
+    async fn get_user_group(&self, group_id: &str) -> Result<UserGroup> {
+        self.get_json(&format!("/groups/{}", group_id), &[]).await
     }
     async fn post_message(&self, params: &PostMessageParams) -> Result<()> {
-        message_api::post_message(
-            &self.conf,
-            &params.channel_id.to_string(),
-            Some(PostMessageRequest {
-                content: params.content.clone(),
-                embed: Some(params.embed),
-            }),
-        )
-        .await?;
-        Ok(())
+        let path = format!("/channels/{}/messages", params.channel_id);
+        let body = PostMessageRequest {
+            content: params.content.clone(),
+            embed: Some(params.embed),
+        };
+        self.execute(Method::POST, || async {
+            let response = self.request(Method::POST, &path).json(&body).send().await?;
+            error_for_status(response).await?;
+            Ok(())
+        })
+        .await
     }
     async fn uplodad_file(&self, params: &UploadFileParams) -> Result<UploadFileResp> {
-        let conf = &self.conf;
-        let token = conf.bearer_access_token.as_ref().unwrap();
-        let client = &conf.client;
-        let url = format!("{}/files", conf.base_path);
-        let file = Part::bytes(params.content.to_vec())
-            .file_name(params.id.to_string())
-            .mime_str(&params.mime_type)?;
-        let form = Form::new()
-            .text("channelId", params.channel_id.to_string())
-            .part("file", file);
-        let request = client
-            .request(reqwest::Method::POST, url.as_str())
-            .bearer_auth(token)
-            .multipart(form);
-        let response = request.send().await?;
-        if !response.status().is_client_error() && !response.status().is_server_error() {
-            let json = response.json::<FileInfo>().await?;
+        self.execute(Method::POST, || async {
+            let file = Part::bytes(params.content.to_vec())
+                .file_name(params.id.to_string())
+                .mime_str(&params.mime_type)?;
+            let form = Form::new()
+                .text("channelId", params.channel_id.to_string())
+                .part("file", file);
+            let response = self
+                .request(Method::POST, "/files")
+                .multipart(form)
+                .send()
+                .await?;
+            let json = error_for_status(response).await?.json::<FileInfo>().await?;
             Ok(UploadFileResp { id: json.id })
-        } else {
-            let status = response.status();
-            let content = response.text().await?;
-            Err(Error::ApiError { status, content })
-        }
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl DeliverySink for BotClientImpl {
+    type Error = Error;
+
+    async fn post_message(&self, params: &PostMessageParams) -> Result<()> {
+        BotClient::post_message(self, params).await
+    }
+
+    async fn upload_channel(&self, channel_id: Uuid) -> Result<Uuid> {
+        Ok(channel_id)
     }
 }
@@ -0,0 +1,109 @@
+//! traQのIncoming Webhookによる投稿
+//!
+//! Webhookが登録されたチャンネルへはBOTが参加していなくても投稿できる。
+//! Webhookではファイルを送れないので、画像は`upload_channel`に上げてURLを載せる。
+
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use domain::bot_client::PostMessageParams;
+use domain::delivery::DeliverySink;
+use domain::repository::{CardRepository, WebhookModel};
+use hmac::{Hmac, Mac};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Method;
+use sha1::Sha1;
+use uuid::Uuid;
+
+use crate::{error_for_status, BotClientImpl, Error, Result};
+
+/// `X-TRAQ-Signature`に入れる、本文のHMAC-SHA1(hex)
+pub fn signature(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha1>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+impl BotClientImpl {
+    /// `webhook`で`params.channel_id`に投稿する
+    pub async fn post_webhook(
+        &self,
+        webhook: &WebhookModel,
+        params: &PostMessageParams,
+    ) -> Result<()> {
+        let url = format!("{}/webhooks/{}", self.conf.base_path, webhook.webhook_id);
+        let signature = signature(&webhook.secret, &params.content);
+        self.execute(Method::POST, || async {
+            let response = self
+                .conf
+                .client
+                .post(&url)
+                .query(&[("embed", if params.embed { "1" } else { "0" })])
+                .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+                .header("X-TRAQ-Signature", &signature)
+                .header("X-TRAQ-Channel-Id", params.channel_id.to_string())
+                .body(params.content.clone())
+                .send()
+                .await?;
+            error_for_status(response).await?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+/// Webhookが登録されたチャンネルにはWebhookで、それ以外にはBOTで投稿する
+pub struct WebhookSink<CR: CardRepository> {
+    registry: Arc<CR>,
+    client: BotClientImpl,
+    /// Webhookで投稿する画像を上げる、BOTが参加しているチャンネル
+    upload_channel: Option<Uuid>,
+}
+
+impl<CR: CardRepository<Error = impl Debug + Send>> WebhookSink<CR> {
+    pub fn new(registry: Arc<CR>, client: BotClientImpl) -> Self {
+        Self {
+            registry,
+            client,
+            upload_channel: None,
+        }
+    }
+
+    pub fn upload_channel(self, channel_id: Option<Uuid>) -> Self {
+        Self {
+            upload_channel: channel_id,
+            ..self
+        }
+    }
+
+    async fn webhook(&self, channel_id: Uuid) -> Result<Option<WebhookModel>> {
+        self.registry
+            .get_webhook(channel_id)
+            .await
+            .map_err(|e| Error::Registry(format!("{:?}", e)))
+    }
+}
+
+#[async_trait]
+impl<CR: CardRepository<Error = impl Debug + Send>> DeliverySink for WebhookSink<CR> {
+    type Error = Error;
+
+    async fn post_message(&self, params: &PostMessageParams) -> Result<()> {
+        match self.webhook(params.channel_id).await? {
+            Some(webhook) => self.client.post_webhook(&webhook, params).await,
+            None => DeliverySink::post_message(&self.client, params).await,
+        }
+    }
+
+    async fn upload_channel(&self, channel_id: Uuid) -> Result<Uuid> {
+        let Some(upload_channel) = self.upload_channel else {
+            return Ok(channel_id);
+        };
+        match self.webhook(channel_id).await? {
+            Some(_) => Ok(upload_channel),
+            None => Ok(channel_id),
+        }
+    }
+}
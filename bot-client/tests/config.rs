@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use bot_client::BotClientConfig;
+
+#[test]
+fn retry_after_is_capped_by_max_backoff() {
+    let config = BotClientConfig::default();
+    for attempt in 0..5 {
+        let wait = config.backoff(attempt, Some(Duration::from_secs(3600)));
+        assert_eq!(wait, config.max_backoff);
+    }
+}
+
+#[test]
+fn short_retry_after_is_waited_with_jitter() {
+    let config = BotClientConfig::default();
+    let retry_after = Duration::from_secs(2);
+    for _ in 0..20 {
+        let wait = config.backoff(0, Some(retry_after));
+        assert!(wait >= retry_after && wait <= retry_after + config.base_backoff);
+    }
+}
+
+#[test]
+fn backoff_without_retry_after_stays_under_max() {
+    let config = BotClientConfig::default();
+    for attempt in 0..40 {
+        assert!(config.backoff(attempt, None) <= config.max_backoff);
+    }
+}
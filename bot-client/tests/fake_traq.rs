@@ -0,0 +1,233 @@
+use std::time::{Duration, SystemTime};
+
+use bot_client::{parse_retry_after, BotClientConfig, BotClientImpl, Error};
+use bytes::Bytes;
+use domain::bot_client::{BotClient, ImageData, PostMessageParams, UploadFileParams};
+use domain::repository::WebhookModel;
+use fake_traq::{FakeTraq, Image, State};
+use uuid::Uuid;
+
+fn client(traq: &FakeTraq) -> BotClientImpl {
+    let config = BotClientConfig {
+        origin: traq.origin(),
+        base_backoff: Duration::from_millis(1),
+        ..Default::default()
+    };
+    BotClientImpl::with_config("token".to_string(), config)
+}
+
+#[tokio::test]
+async fn get_users_by_name() {
+    let mut state = State::default();
+    let user = state.add_user("alice");
+    state.add_user("bob");
+    let traq = FakeTraq::start(state).await.unwrap();
+    let client = client(&traq);
+
+    let users = client.get_users(Some("alice")).await.unwrap();
+    assert_eq!(users, vec![user.clone()]);
+    let dm = client
+        .get_user_dm_channel(&user.id.to_string())
+        .await
+        .unwrap();
+    assert_eq!(dm.user_id, user.id);
+}
+
+#[tokio::test]
+async fn get_users_by_ids() {
+    let mut state = State::default();
+    let alice = state.add_user("alice");
+    state.add_user("bob");
+    let carol = state.add_user("carol");
+    let traq = FakeTraq::start(state).await.unwrap();
+    let client = client(&traq);
+
+    let users = client
+        .get_users_by_ids(&[alice.id, carol.id, Uuid::new_v4()])
+        .await
+        .unwrap();
+    assert_eq!(users, vec![alice, carol]);
+}
+
+#[tokio::test]
+async fn get_user_group_members() {
+    let mut state = State::default();
+    let alice = state.add_user("alice");
+    let bob = state.add_user("bob");
+    let group = state.add_group("team", &[alice.id, bob.id]);
+    let traq = FakeTraq::start(state).await.unwrap();
+    let client = client(&traq);
+
+    let got = client.get_user_group(&group.id.to_string()).await.unwrap();
+    let members: Vec<_> = got.members.iter().map(|m| m.id).collect();
+    assert_eq!(members, vec![alice.id, bob.id]);
+    let err = client.get_user_group(&Uuid::new_v4().to_string()).await;
+    assert!(matches!(err, Err(Error::NotFound { .. })));
+}
+
+#[tokio::test]
+async fn unknown_user_is_not_found() {
+    let traq = FakeTraq::start(State::default()).await.unwrap();
+    let client = client(&traq);
+
+    let err = client.get_user(&Uuid::new_v4().to_string()).await;
+    assert!(matches!(err, Err(Error::NotFound { .. })));
+}
+
+#[tokio::test]
+async fn upload_file_and_post_message() {
+    let mut state = State::default();
+    let channel = state.add_channel("gps", None);
+    let traq = FakeTraq::start(state).await.unwrap();
+    let client = client(&traq);
+
+    let file = client
+        .uplodad_file(&UploadFileParams {
+            id: Uuid::new_v4(),
+            channel_id: channel.id,
+            content: "png".into(),
+            mime_type: "image/png".to_string(),
+        })
+        .await
+        .unwrap();
+    client
+        .post_message(&PostMessageParams {
+            channel_id: channel.id,
+            content: "hello".to_string(),
+            embed: false,
+        })
+        .await
+        .unwrap();
+
+    let state = traq.state();
+    assert_eq!(state.files.len(), 1);
+    assert_eq!(state.files[0].id, file.id);
+    assert_eq!(state.files[0].mime_type, "image/png");
+    assert_eq!(state.messages.len(), 1);
+    assert_eq!(state.messages[0].channel_id, channel.id);
+    assert_eq!(state.messages[0].content, "hello");
+}
+
+#[tokio::test]
+async fn post_message_via_webhook() {
+    let mut state = State::default();
+    let channel = state.add_channel("gps", None);
+    let webhook_id = state.add_webhook(channel.id, "secret");
+    let traq = FakeTraq::start(state).await.unwrap();
+    let client = client(&traq);
+
+    let params = PostMessageParams {
+        channel_id: channel.id,
+        content: "hello".to_string(),
+        embed: false,
+    };
+    let mut webhook = WebhookModel {
+        channel_id: channel.id,
+        webhook_id,
+        secret: "secret".to_string(),
+    };
+    client.post_webhook(&webhook, &params).await.unwrap();
+    webhook.secret = "wrong".to_string();
+    assert!(client.post_webhook(&webhook, &params).await.is_err());
+
+    let state = traq.state();
+    assert_eq!(state.messages.len(), 1);
+    assert_eq!(state.messages[0].channel_id, channel.id);
+    assert_eq!(state.messages[0].content, "hello");
+}
+
+#[tokio::test]
+async fn retry_on_rate_limit() {
+    let traq = FakeTraq::start(State::default()).await.unwrap();
+    let client = client(&traq);
+    traq.fail_next(fake_traq::StatusCode::TOO_MANY_REQUESTS, Some(0));
+    traq.fail_next(fake_traq::StatusCode::SERVICE_UNAVAILABLE, None);
+
+    let channels = client.get_channels().await.unwrap();
+    assert!(channels.public.is_empty());
+    assert_eq!(traq.state().requests.len(), 3);
+}
+
+#[tokio::test]
+async fn rate_limit_reads_retry_after() {
+    let traq = FakeTraq::start(State::default()).await.unwrap();
+    let config = BotClientConfig {
+        origin: traq.origin(),
+        max_retries: 0,
+        ..Default::default()
+    };
+    let client = BotClientImpl::with_config("token".to_string(), config);
+    traq.fail_next(fake_traq::StatusCode::TOO_MANY_REQUESTS, Some(7));
+
+    let err = client.get_channels().await.unwrap_err();
+    let Error::RateLimited { retry_after, .. } = err else {
+        panic!("unexpected error: {:?}", err);
+    };
+    assert_eq!(retry_after, Some(Duration::from_secs(7)));
+}
+
+#[test]
+fn parse_retry_after_http_date() {
+    assert_eq!(parse_retry_after(" 120 "), Some(Duration::from_secs(120)));
+    assert_eq!(
+        parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT"),
+        Some(Duration::ZERO)
+    );
+    let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(60));
+    let wait = parse_retry_after(&date).unwrap();
+    assert!(wait > Duration::from_secs(55) && wait <= Duration::from_secs(60));
+    assert_eq!(parse_retry_after("soon"), None);
+}
+
+#[tokio::test]
+async fn post_message_is_not_retried_on_bad_gateway() {
+    let mut state = State::default();
+    let channel = state.add_channel("gps", None);
+    let traq = FakeTraq::start(state).await.unwrap();
+    let client = client(&traq);
+    let params = PostMessageParams {
+        channel_id: channel.id,
+        content: "hello".to_string(),
+        embed: false,
+    };
+
+    traq.fail_next(fake_traq::StatusCode::BAD_GATEWAY, None);
+    assert!(client.post_message(&params).await.is_err());
+    assert_eq!(traq.state().requests.len(), 1);
+
+    // 503は処理されていないのでPOSTでもリトライする
+    traq.fail_next(fake_traq::StatusCode::SERVICE_UNAVAILABLE, None);
+    client.post_message(&params).await.unwrap();
+    let state = traq.state();
+    assert_eq!(state.requests.len(), 3);
+    assert_eq!(state.messages.len(), 1);
+}
+
+#[tokio::test]
+async fn stamp_image_content_type() {
+    let mut state = State::default();
+    let webp = state.add_stamp(
+        "webp",
+        Image {
+            mime_type: "image/webp; charset=binary".to_string(),
+            content: "RIFF\0\0\0\0WEBPVP8 ".into(),
+        },
+    );
+    let sniffed = state.add_stamp(
+        "sniffed",
+        Image {
+            mime_type: String::new(),
+            content: Bytes::from_static(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+        },
+    );
+    let traq = FakeTraq::start(state).await.unwrap();
+    let client = client(&traq);
+
+    let image = client.get_stamp_image(&webp.id.to_string()).await.unwrap();
+    assert!(matches!(image.data, ImageData::Webp(_)));
+    let image = client
+        .get_stamp_image(&sniffed.id.to_string())
+        .await
+        .unwrap();
+    assert!(matches!(image.data, ImageData::Png(_)));
+}
@@ -0,0 +1,35 @@
+//! 寄せ書きの参加者の画像をカードの画像に重ねる
+
+use std::io::Cursor;
+
+use bytes::Bytes;
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ImageFormat, ImageResult};
+
+/// `base`(PNG)に`layers`を順に重ねてPNGで返す
+///
+/// 大きさの違う画像はカードに合わせて伸縮する。読めない画像は飛ばす
+pub fn compose_layers(base: &[u8], layers: &[Bytes]) -> ImageResult<Bytes> {
+    let mut canvas = image::load_from_memory_with_format(base, ImageFormat::Png)?.into_rgba8();
+    let (width, height) = canvas.dimensions();
+    for layer in layers {
+        let layer = match image::load_from_memory(layer) {
+            Ok(layer) => layer,
+            Err(e) => {
+                eprintln!("failed to decode layer: {:?}", e);
+                continue;
+            }
+        };
+        let layer = if layer.width() == width && layer.height() == height {
+            layer.into_rgba8()
+        } else {
+            layer
+                .resize_exact(width, height, FilterType::Triangle)
+                .into_rgba8()
+        };
+        imageops::overlay(&mut canvas, &layer, 0, 0);
+    }
+    let mut png = Cursor::new(vec![]);
+    DynamicImage::ImageRgba8(canvas).write_to(&mut png, ImageFormat::Png)?;
+    Ok(png.into_inner().into())
+}
@@ -1,32 +1,85 @@
-use std::{fmt::Debug, sync::Arc};
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
 
 use async_trait::async_trait;
+use bot_client::DEFAULT_TRAQ_ORIGIN;
+use bytes::Bytes;
 use chrono::{Duration, Utc};
 use domain::{
-    bot_client::{BotClient, PostMessageParams, UploadFileParams},
+    bot_client::{BotClient, BotClientError, ErrorKind, PostMessageParams, UploadFileParams},
     cron::Cron,
-    repository::{CardRepository, ImageRepository},
+    delivery::DeliverySink,
+    recurrence::Recurrence,
+    repository::{
+        AuditAction, AuditEventModel, CardModel, CardRepository, CardStatus, ContributionModel,
+        DateTimeUtc, DeliveryLogModel, ImageKind, ImageRepository, PublishChannelModel,
+        RecurrenceModel, DEFAULT_AUDIT_RETENTION_DAYS, DEFAULT_TRASH_RETENTION_DAYS,
+    },
+    time_zone::{format_local, parse_time_zone, DEFAULT_TIME_ZONE},
 };
 use futures::future::join_all;
 use tokio_cron_scheduler::{Job, JobScheduler};
+use uuid::Uuid;
 
-pub struct CronImpl<CR: CardRepository, IR: ImageRepository, BC: BotClient> {
+use crate::compose::compose_layers;
+
+pub mod compose;
+
+pub struct CronImpl<CR: CardRepository, IR: ImageRepository, BC: BotClient, DS: DeliverySink> {
     card_repository: Arc<CR>,
     image_repository: Arc<IR>,
     bot_client: Arc<BC>,
+    /// 配信メッセージの投稿先。画像のアップロードは`bot_client`で行う
+    sink: Arc<DS>,
+    traq_origin: Arc<str>,
+    /// ゴミ箱に入れてから完全に消すまでの期間
+    trash_retention: Duration,
+    /// 監査ログを残す期間
+    audit_retention: Duration,
 }
 
 impl<
         CR: CardRepository<Error = impl Debug + Send>,
         IR: ImageRepository<Error = impl Debug + Send>,
-        BC: BotClient<Error = impl Debug + Send>,
-    > CronImpl<CR, IR, BC>
+        BC: BotClient<Error = impl Debug + Send + BotClientError>,
+        DS: DeliverySink<Error = impl Debug + Send>,
+    > CronImpl<CR, IR, BC, DS>
 {
-    pub fn new(card_repository: Arc<CR>, image_repository: Arc<IR>, bot_client: Arc<BC>) -> Self {
+    pub fn new(
+        card_repository: Arc<CR>,
+        image_repository: Arc<IR>,
+        bot_client: Arc<BC>,
+        sink: Arc<DS>,
+    ) -> Self {
         Self {
             card_repository,
             image_repository,
             bot_client,
+            sink,
+            traq_origin: DEFAULT_TRAQ_ORIGIN.into(),
+            trash_retention: Duration::days(DEFAULT_TRASH_RETENTION_DAYS),
+            audit_retention: Duration::days(DEFAULT_AUDIT_RETENTION_DAYS),
+        }
+    }
+
+    /// メッセージに埋め込むファイルURLのオリジン
+    pub fn traq_origin(self, origin: &str) -> Self {
+        Self {
+            traq_origin: origin.trim_end_matches('/').into(),
+            ..self
+        }
+    }
+
+    pub fn trash_retention(self, retention: Duration) -> Self {
+        Self {
+            trash_retention: retention,
+            ..self
+        }
+    }
+
+    pub fn audit_retention(self, retention: Duration) -> Self {
+        Self {
+            audit_retention: retention,
+            ..self
         }
     }
 }
@@ -35,20 +88,17 @@ impl<
 impl<
         CR: CardRepository<Error = impl Debug + Send>,
         IR: ImageRepository<Error = impl Debug + Send>,
-        BC: BotClient<Error = impl Debug + Send>,
-    > Cron for CronImpl<CR, IR, BC>
+        BC: BotClient<Error = impl Debug + Send + BotClientError>,
+        DS: DeliverySink<Error = impl Debug + Send>,
+    > Cron for CronImpl<CR, IR, BC, DS>
 {
     async fn run(self: Arc<Self>) -> () {
         let sched = JobScheduler::new().await.unwrap();
         sched
             .add(
                 Job::new_async("0 * * * * * *", move |_uuid, _l| {
-                    let card_repository = self.clone().card_repository.clone();
-                    let image_repository = self.clone().image_repository.clone();
-                    let bot_client = self.clone().bot_client.clone();
-                    Box::pin(
-                        async move { task(card_repository, image_repository, bot_client).await },
-                    )
+                    let cron = self.clone();
+                    Box::pin(async move { cron.tick().await })
                 })
                 .unwrap(),
             )
@@ -56,23 +106,224 @@ impl<
             .unwrap();
         sched.start().await.unwrap();
     }
+
+    async fn tick(&self) {
+        task(
+            self.card_repository.clone(),
+            self.image_repository.clone(),
+            self.bot_client.clone(),
+            self.sink.clone(),
+            &self.traq_origin,
+        )
+        .await;
+        purge_trash(
+            self.card_repository.as_ref(),
+            self.image_repository.as_ref(),
+            Utc::now() - self.trash_retention,
+        )
+        .await;
+        purge_audit_events(
+            self.card_repository.as_ref(),
+            Utc::now() - self.audit_retention,
+        )
+        .await
+    }
+
+    async fn deliver(&self, card_id: Uuid) -> Option<CardStatus> {
+        let now = Utc::now();
+        let card = match self.card_repository.get_card_by_id(card_id).await {
+            Ok(Some(card)) => card,
+            Ok(None) => return None,
+            Err(e) => {
+                eprintln!("failed to get card: {:?}", e);
+                return None;
+            }
+        };
+        if card.status != CardStatus::Scheduled
+            || card.deleted_at.is_some()
+            || card.publish_date.map_or(true, |d| d > now)
+        {
+            return None;
+        }
+        let channels = match self
+            .card_repository
+            .get_publish_channels_by_id(card_id)
+            .await
+        {
+            Ok(channels) => channels,
+            Err(e) => {
+                eprintln!("failed to get publish channels: {:?}", e);
+                return None;
+            }
+        };
+        let channels = channels
+            .into_iter()
+            .map(|id| PublishChannelModel { id, card_id })
+            .collect::<Vec<_>>();
+        deliver_due_card(
+            &card,
+            &channels,
+            self.card_repository.as_ref(),
+            self.image_repository.as_ref(),
+            self.bot_client.as_ref(),
+            self.sink.as_ref(),
+            &self.traq_origin,
+            now,
+        )
+        .await
+    }
+
+    async fn preview(&self, card: &CardModel, user_id: Uuid) -> bool {
+        let dm = match self
+            .bot_client
+            .get_user_dm_channel(&user_id.to_string())
+            .await
+        {
+            Ok(dm) => dm,
+            Err(e) => {
+                eprintln!("failed to get dm channel: {:?}", e);
+                return false;
+            }
+        };
+        let card_repository = self.card_repository.as_ref();
+        let bot_client = self.bot_client.as_ref();
+        let now = Utc::now();
+        let channels = match card_repository.get_publish_channels_by_id(card.id).await {
+            Ok(channels) => channels,
+            Err(e) => {
+                eprintln!("failed to get publish channels: {:?}", e);
+                return false;
+            }
+        };
+        // 配信と同じメンションを付け、宛先だけ自分のDMにする。配信ログは残さない
+        let members = resolve_group_members(card, card_repository, bot_client, now, false).await;
+        let has_channels = !channels.is_empty();
+        let mentions = resolve_mentions(has_channels, &members, &mut vec![], bot_client).await;
+        send_card(
+            card,
+            &[(dm.id, has_channels)],
+            &mentions,
+            card_repository,
+            self.image_repository.as_ref(),
+            bot_client,
+            self.sink.as_ref(),
+            &self.traq_origin,
+            now,
+        )
+        .await
+    }
+}
+
+/// `before`より前にゴミ箱に入れたカードを画像ごと消す。
+/// 画像を消せなかったカードは行を残し、次のtickでやり直す
+async fn purge_trash<
+    CR: CardRepository<Error = impl Debug + Send>,
+    IR: ImageRepository<Error = impl Debug + Send>,
+>(
+    card_repository: &CR,
+    image_repository: &IR,
+    before: DateTimeUtc,
+) {
+    let Ok(cards) = card_repository
+        .get_cards_trashed_before(before)
+        .await
+        .map_err(|e| {
+            eprintln!("failed to get trashed cards: {:?}", e);
+        })
+    else {
+        return;
+    };
+    for card in cards {
+        if !purge_images(&card, card_repository, image_repository).await {
+            continue;
+        }
+        if let Err(e) = card_repository.delete_card(card.id).await {
+            eprintln!("failed to delete card: {:?}", e);
+            continue;
+        }
+        let before = serde_json::json!({ "deleted_at": card.deleted_at });
+        let event = AuditEventModel {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            actor_id: None,
+            action: AuditAction::CardPurge,
+            card_id: Some(card.id),
+            asset_id: None,
+            before: Some(before.to_string()),
+            after: None,
+            request_id: None,
+        };
+        if let Err(e) = card_repository.save_audit_event(&event).await {
+            eprintln!("failed to save audit event: {:?}", e);
+        }
+    }
+}
+
+/// `before`より前の監査ログを消す
+async fn purge_audit_events<CR: CardRepository<Error = impl Debug + Send>>(
+    card_repository: &CR,
+    before: DateTimeUtc,
+) {
+    match card_repository.delete_audit_events_before(before).await {
+        Ok(0) => (),
+        Ok(n) => println!("deleted {} expired audit events", n),
+        Err(e) => eprintln!("failed to delete expired audit events: {:?}", e),
+    }
+}
+
+/// 現在の画像と全ての版を消し、全て消せたかを返す
+async fn purge_images<
+    CR: CardRepository<Error = impl Debug + Send>,
+    IR: ImageRepository<Error = impl Debug + Send>,
+>(
+    card: &CardModel,
+    card_repository: &CR,
+    image_repository: &IR,
+) -> bool {
+    if let Err(e) = image_repository.delete_svg(card.id).await {
+        eprintln!("failed to delete svg: {:?}", e);
+        return false;
+    }
+    if let Err(e) = image_repository.delete_png(card.id).await {
+        eprintln!("failed to delete png: {:?}", e);
+        return false;
+    }
+    for kind in [ImageKind::Svg, ImageKind::Png] {
+        let revisions = match card_repository.get_image_revisions(card.id, kind).await {
+            Ok(revisions) => revisions,
+            Err(e) => {
+                eprintln!("failed to get image revisions: {:?}", e);
+                return false;
+            }
+        };
+        for revision in revisions {
+            if let Err(e) = image_repository
+                .delete_revision(card.id, kind, revision.revision)
+                .await
+            {
+                eprintln!("failed to delete image revision: {:?}", e);
+                return false;
+            }
+        }
+    }
+    true
 }
 
 async fn task<
     CR: CardRepository<Error = impl Debug + Send>,
     IR: ImageRepository<Error = impl Debug + Send>,
-    BC: BotClient<Error = impl Debug + Send>,
+    BC: BotClient<Error = impl Debug + Send + BotClientError>,
+    DS: DeliverySink<Error = impl Debug + Send>,
 >(
     card_repository: Arc<CR>,
     image_repository: Arc<IR>,
     bot_client: Arc<BC>,
+    sink: Arc<DS>,
+    traq_origin: &str,
 ) {
-    use indoc::formatdoc;
     let now = Utc::now();
-    let start = now - Duration::minutes(1);
-    let end = now;
     let Ok(cards_with_channels) = card_repository
-        .get_card_with_channels_by_date(start, end)
+        .get_due_cards_with_channels(now)
         .await
         .map_err(|e| {
             eprintln!("failed to get cards: {:?}", e);
@@ -80,31 +331,309 @@ async fn task<
     else {
         return;
     };
-    let sends = cards_with_channels.iter().map(|(card, channels)| async {
-        let sends = channels.iter().map(|channel| async {
-            let Ok(png) = image_repository.get_png(card.id).await.map_err(|e| {
-                eprintln!("failed to get png: {:?}", e);
-            }) else {
-                return;
-            };
-            let Some(png) = png else {
-                eprintln!("png not found");
-                return;
-            };
-            let Ok(file_id) = bot_client
-                .uplodad_file(&UploadFileParams {
-                    id: card.id,
-                    channel_id: channel.id,
-                    content: png,
-                    mime_type: "image/png".to_string(),
-                })
+    let sends = cards_with_channels.iter().map(|(card, channels)| {
+        deliver_due_card(
+            card,
+            channels,
+            card_repository.as_ref(),
+            image_repository.as_ref(),
+            bot_client.as_ref(),
+            sink.as_ref(),
+            traq_origin,
+            now,
+        )
+    });
+    join_all(sends).await;
+}
+
+/// 投稿日時を過ぎた予約済みのカードを1枚配信し、最後のステータスを返す。
+/// 他で配信中にされていれば何もせず`None`
+#[allow(clippy::too_many_arguments)]
+async fn deliver_due_card<
+    CR: CardRepository<Error = impl Debug + Send>,
+    IR: ImageRepository<Error = impl Debug + Send>,
+    BC: BotClient<Error = impl Debug + Send + BotClientError>,
+    DS: DeliverySink<Error = impl Debug + Send>,
+>(
+    card: &CardModel,
+    channels: &[PublishChannelModel],
+    card_repository: &CR,
+    image_repository: &IR,
+    bot_client: &BC,
+    sink: &DS,
+    traq_origin: &str,
+    now: DateTimeUtc,
+) -> Option<CardStatus> {
+    // 他のtickと同じカードを二重に配信しないよう、先に配信中にする
+    match card_repository
+        .update_card_status(card.id, &[CardStatus::Scheduled], CardStatus::Delivering)
+        .await
+    {
+        Ok(Some(())) => {}
+        Ok(None) => return None,
+        Err(e) => {
+            eprintln!("failed to update card status: {:?}", e);
+            return None;
+        }
+    }
+    let recurrence = load_recurrence(card, card_repository).await;
+    let skipped = recurrence
+        .as_ref()
+        .is_some_and(|(_, _, skipped)| card.publish_date.is_some_and(|d| skipped.contains(&d)));
+    let delivered = if skipped {
+        None
+    } else {
+        Some(
+            deliver_card(
+                card,
+                channels,
+                card_repository,
+                image_repository,
+                bot_client,
+                sink,
+                traq_origin,
+                now,
+            )
+            .await,
+        )
+    };
+    let mut ever_delivered = delivered.is_some();
+    if let Some((model, recurrence, skipped)) = &recurrence {
+        ever_delivered |= model.last_occurrence.is_some();
+        if let (Some(_), Some(at)) = (delivered, card.publish_date) {
+            if let Err(e) = card_repository.update_last_occurrence(card.id, at).await {
+                eprintln!("failed to update last occurrence: {:?}", e);
+            }
+        }
+        // 止まっていた間の発生日時はまとめて配信せず、次の予定から再開する
+        let after = card.publish_date.map_or(now, |d| d.max(now));
+        if let Some(next) = recurrence.next_after(after, skipped) {
+            if let Err(e) = card_repository
+                .reschedule_card(card.id, &[CardStatus::Delivering], next)
                 .await
-                .map(|f| f.id)
-                .map_err(|e| {
-                    eprintln!("failed to upload file: {:?}", e);
-                })
-            else {
-                return;
+            {
+                eprintln!("failed to reschedule card: {:?}", e);
+            }
+            let status = CardStatus::Scheduled;
+            audit_delivery(card_repository, card, delivered, status, Some(next)).await;
+            return Some(status);
+        }
+    }
+    let status = match delivered {
+        Some(true) => CardStatus::Delivered,
+        Some(false) => CardStatus::Failed,
+        None if ever_delivered => CardStatus::Delivered,
+        None => CardStatus::Cancelled,
+    };
+    if let Err(e) = card_repository
+        .update_card_status(card.id, &[CardStatus::Delivering], status)
+        .await
+    {
+        eprintln!("failed to update card status: {:?}", e);
+    }
+    audit_delivery(card_repository, card, delivered, status, card.publish_date).await;
+    Some(status)
+}
+
+/// 配信の結果を監査ログに残す。`delivered`が`None`なら飛ばした発生日時
+async fn audit_delivery<CR: CardRepository<Error = impl Debug + Send>>(
+    card_repository: &CR,
+    card: &CardModel,
+    delivered: Option<bool>,
+    status: CardStatus,
+    publish_date: Option<DateTimeUtc>,
+) {
+    let result = match delivered {
+        Some(true) => "delivered",
+        Some(false) => "failed",
+        None => "skipped",
+    };
+    let before = serde_json::json!({
+        "status": CardStatus::Scheduled,
+        "publish_date": card.publish_date,
+    });
+    let after = serde_json::json!({
+        "status": status,
+        "publish_date": publish_date,
+        "result": result,
+    });
+    let event = AuditEventModel {
+        id: Uuid::new_v4(),
+        at: Utc::now(),
+        actor_id: None,
+        action: AuditAction::CardDeliver,
+        card_id: Some(card.id),
+        asset_id: None,
+        before: Some(before.to_string()),
+        after: Some(after.to_string()),
+        request_id: None,
+    };
+    if let Err(e) = card_repository.save_audit_event(&event).await {
+        eprintln!("failed to save audit event: {:?}", e);
+    }
+}
+
+/// グループメンバーへのメンションを作る。
+/// 投稿先チャンネルが無ければメンションせず、各メンバーを`recipients`に加えてDMに送る
+async fn resolve_mentions<BC: BotClient<Error = impl Debug + Send + BotClientError>>(
+    has_channels: bool,
+    members: &[Uuid],
+    recipients: &mut Vec<Uuid>,
+    bot_client: &BC,
+) -> String {
+    if has_channels {
+        return mention_users(members, bot_client).await;
+    }
+    for member in members {
+        if !recipients.contains(member) {
+            recipients.push(*member);
+        }
+    }
+    String::new()
+}
+
+/// カードを全ての宛先に送り、全て成功したかを返す
+#[allow(clippy::too_many_arguments)]
+async fn deliver_card<
+    CR: CardRepository<Error = impl Debug + Send>,
+    IR: ImageRepository<Error = impl Debug + Send>,
+    BC: BotClient<Error = impl Debug + Send + BotClientError>,
+    DS: DeliverySink<Error = impl Debug + Send>,
+>(
+    card: &CardModel,
+    channels: &[PublishChannelModel],
+    card_repository: &CR,
+    image_repository: &IR,
+    bot_client: &BC,
+    sink: &DS,
+    traq_origin: &str,
+    now: DateTimeUtc,
+) -> bool {
+    // (チャンネルID, グループメンバーをメンションするか)
+    let mut targets: Vec<_> = channels.iter().map(|c| (c.id, true)).collect();
+    let Ok(mut recipients) = card_repository
+        .get_recipients_by_id(card.id)
+        .await
+        .map_err(|e| {
+            eprintln!("failed to get recipients: {:?}", e);
+        })
+    else {
+        return false;
+    };
+    let members = resolve_group_members(card, card_repository, bot_client, now, true).await;
+    let mentions =
+        resolve_mentions(!channels.is_empty(), &members, &mut recipients, bot_client).await;
+    let mut failed = false;
+    for recipient in recipients {
+        match bot_client.get_user_dm_channel(&recipient.to_string()).await {
+            Ok(dm) => targets.push((dm.id, false)),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                eprintln!("recipient {} not found", recipient);
+            }
+            Err(e) => {
+                eprintln!("failed to get dm channel: {:?}", e);
+                failed = true;
+            }
+        }
+    }
+    let sent = send_card(
+        card,
+        &targets,
+        &mentions,
+        card_repository,
+        image_repository,
+        bot_client,
+        sink,
+        traq_origin,
+        now,
+    )
+    .await;
+    !failed && sent
+}
+
+/// 画像をアップロードしてメッセージを投稿する。全ての宛先に送れたかを返す
+#[allow(clippy::too_many_arguments)]
+async fn send_card<
+    CR: CardRepository<Error = impl Debug + Send>,
+    IR: ImageRepository<Error = impl Debug + Send>,
+    BC: BotClient<Error = impl Debug + Send + BotClientError>,
+    DS: DeliverySink<Error = impl Debug + Send>,
+>(
+    card: &CardModel,
+    targets: &[(Uuid, bool)],
+    mentions: &str,
+    card_repository: &CR,
+    image_repository: &IR,
+    bot_client: &BC,
+    sink: &DS,
+    traq_origin: &str,
+    now: DateTimeUtc,
+) -> bool {
+    // 作成者のタイムゾーンで表示する
+    let sent_at = parse_time_zone(&card.time_zone)
+        .or_else(|_| parse_time_zone(DEFAULT_TIME_ZONE))
+        .map(|tz| format_local(card.publish_date.unwrap_or(now), tz))
+        .unwrap_or_default();
+    let sent_at = &sent_at;
+    let Ok(png) = image_repository.get_png(card.id).await.map_err(|e| {
+        eprintln!("failed to get png: {:?}", e);
+    }) else {
+        return false;
+    };
+    let Some(png) = png else {
+        eprintln!("png not found");
+        return false;
+    };
+    let contributions = get_contributions(card, card_repository).await;
+    let png = compose_contributions(card, png, &contributions, image_repository).await;
+    let signatures = signatures(&contributions, bot_client).await;
+    let (png, signatures) = (&png, &signatures);
+    // Webhookで投稿するチャンネルにはBOTが参加していなくてもよいので、画像は別のチャンネルに上げる
+    let upload_channels = join_all(targets.iter().map(|(channel_id, _)| async move {
+        sink.upload_channel(*channel_id)
+            .await
+            .map_err(|e| {
+                eprintln!("failed to get upload channel for {}: {:?}", channel_id, e);
+            })
+            .ok()
+    }))
+    .await;
+    let mut distinct: Vec<_> = upload_channels.iter().flatten().copied().collect();
+    distinct.sort();
+    distinct.dedup();
+    let uploads = join_all(distinct.iter().map(|channel_id| async move {
+        bot_client
+            .uplodad_file(&UploadFileParams {
+                id: card.id,
+                channel_id: *channel_id,
+                content: png.clone(),
+                mime_type: "image/png".to_string(),
+            })
+            .await
+            .map(|f| f.id)
+            .map_err(|e| match e.kind() {
+                ErrorKind::Forbidden | ErrorKind::NotFound => {
+                    eprintln!("bot cannot post to channel {}: {:?}", channel_id, e);
+                }
+                ErrorKind::RateLimited | ErrorKind::Timeout => {
+                    eprintln!("traq api unavailable, giving up card {}: {:?}", card.id, e);
+                }
+                ErrorKind::Other => eprintln!("failed to upload file: {:?}", e),
+            })
+            .ok()
+    }))
+    .await;
+    // アップロード先のチャンネルからファイルID
+    let files: HashMap<Uuid, Uuid> = distinct
+        .into_iter()
+        .zip(uploads)
+        .filter_map(|(channel_id, file_id)| Some((channel_id, file_id?)))
+        .collect();
+    let files = &files;
+    let sends = targets.iter().zip(upload_channels).map(
+        |((channel_id, mention), upload_channel)| async move {
+            let Some(file_id) = upload_channel.and_then(|c| files.get(&c)) else {
+                return false;
             };
             let Ok(user) = bot_client
                 .get_user(&card.owner_id.to_string())
@@ -113,39 +642,212 @@ async fn task<
                     eprintln!("failed to get user: {:?}", e);
                 })
             else {
-                return;
-            };
-            let message = match card.message.as_ref() {
-                Some(m) => formatdoc! {
-                    r##"
-                        !{{"type":"user","raw":"@{}","id":"{}"}} からのQardです！
-                        {}
-
-                        https://q.trap.jp/files/{}
-                    "##,
-                    user.name, user.id, m, file_id
-                },
-                None => formatdoc! {
-                    r##"
-                        !{{"type":"user","raw":"@{}","id":"{}"}} からのQardです！
-
-                        https://q.trap.jp/files/{}
-                    "##,
-                    user.name, user.id, file_id
-                },
+                return false;
             };
-            let _ = bot_client
-                .post_message(&PostMessageParams {
-                    content: message,
-                    channel_id: channel.id,
-                    embed: false,
-                })
-                .await
-                .map_err(|e| {
-                    eprintln!("failed to post message: {:?}", e);
-                });
-        });
-        join_all(sends).await
-    });
-    join_all(sends).await;
+            let mut lines = vec![format!(
+                r#"!{{"type":"user","raw":"@{}","id":"{}"}} からのQardです！ ({})"#,
+                user.name, user.id, sent_at
+            )];
+            if *mention && !mentions.is_empty() {
+                lines.push(mentions.to_string());
+            }
+            if let Some(m) = card.message.as_ref() {
+                lines.push(m.clone());
+            }
+            lines.extend(signatures.iter().cloned());
+            lines.push(String::new());
+            lines.push(format!("{}/files/{}", traq_origin, file_id));
+            let message = lines.join("\n") + "\n";
+            sink.post_message(&PostMessageParams {
+                content: message,
+                channel_id: *channel_id,
+                embed: false,
+            })
+            .await
+            .map_err(|e| {
+                eprintln!("failed to post message: {:?}", e);
+            })
+            .is_ok()
+        },
+    );
+    let results = join_all(sends).await;
+    results.into_iter().all(|ok| ok)
+}
+
+/// 書き込み済みの寄せ書き
+async fn get_contributions<CR: CardRepository<Error = impl Debug + Send>>(
+    card: &CardModel,
+    card_repository: &CR,
+) -> Vec<ContributionModel> {
+    match card_repository.get_contributions(card.id).await {
+        Ok(contributions) => {
+            let mut contributions: Vec<_> = contributions
+                .into_iter()
+                .filter(|c| c.contributed_at.is_some())
+                .collect();
+            contributions.sort_by_key(|c| c.contributed_at);
+            contributions
+        }
+        Err(e) => {
+            eprintln!("failed to get contributions: {:?}", e);
+            vec![]
+        }
+    }
+}
+
+/// 参加者の画像を書き込んだ順に重ねる。重ねられなければ元の画像のまま
+async fn compose_contributions<IR: ImageRepository<Error = impl Debug + Send>>(
+    card: &CardModel,
+    png: Bytes,
+    contributions: &[ContributionModel],
+    image_repository: &IR,
+) -> Bytes {
+    let mut layers = vec![];
+    for layer_id in contributions.iter().filter_map(|c| c.layer_id) {
+        match image_repository.get_asset(layer_id).await {
+            Ok(Some((_, layer))) => layers.push(layer),
+            Ok(None) => eprintln!("layer {} not found", layer_id),
+            Err(e) => eprintln!("failed to get layer: {:?}", e),
+        }
+    }
+    if layers.is_empty() {
+        return png;
+    }
+    match compose_layers(&png, &layers) {
+        Ok(composed) => composed,
+        Err(e) => {
+            eprintln!("failed to compose card {}: {:?}", card.id, e);
+            png
+        }
+    }
+}
+
+/// 寄せ書きの署名の行。参加者をメンションはしない
+async fn signatures<BC: BotClient<Error = impl Debug + Send + BotClientError>>(
+    contributions: &[ContributionModel],
+    bot_client: &BC,
+) -> Vec<String> {
+    if contributions.is_empty() {
+        return vec![];
+    }
+    let ids: Vec<_> = contributions.iter().map(|c| c.user_id).collect();
+    let users = match bot_client.get_users_by_ids(&ids).await {
+        Ok(users) => users,
+        Err(e) => {
+            eprintln!("failed to get contributors: {:?}", e);
+            vec![]
+        }
+    };
+    let name = |id: Uuid| {
+        users
+            .iter()
+            .find(|u| u.id == id)
+            .map_or_else(|| id.to_string(), |u| u.name.clone())
+    };
+    let mut lines = vec![format!(
+        "寄せ書き: {}",
+        ids.iter()
+            .map(|id| format!("@{}", name(*id)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    )];
+    for contribution in contributions {
+        if let Some(message) = &contribution.message {
+            lines.push(format!("@{}: {}", name(contribution.user_id), message));
+        }
+    }
+    lines
+}
+
+/// 繰り返しのカードなら設定と飛ばす発生日時を返す
+async fn load_recurrence<CR: CardRepository<Error = impl Debug + Send>>(
+    card: &CardModel,
+    card_repository: &CR,
+) -> Option<(RecurrenceModel, Recurrence, Vec<DateTimeUtc>)> {
+    let model = match card_repository.get_recurrence(card.id).await {
+        Ok(model) => model?,
+        Err(e) => {
+            eprintln!("failed to get recurrence: {:?}", e);
+            return None;
+        }
+    };
+    let recurrence = match Recurrence::try_from(&model) {
+        Ok(recurrence) => recurrence,
+        Err(e) => {
+            eprintln!("invalid recurrence of card {}: {}", card.id, e);
+            return None;
+        }
+    };
+    let skipped = match card_repository.get_skipped_occurrences(card.id).await {
+        Ok(skipped) => skipped,
+        Err(e) => {
+            eprintln!("failed to get skipped occurrences: {:?}", e);
+            vec![]
+        }
+    };
+    Some((model, recurrence, skipped))
+}
+
+/// 宛先グループのメンバーを解決する。`record`なら配信ログに記録する
+async fn resolve_group_members<
+    CR: CardRepository<Error = impl Debug + Send>,
+    BC: BotClient<Error = impl Debug + Send + BotClientError>,
+>(
+    card: &CardModel,
+    card_repository: &CR,
+    bot_client: &BC,
+    now: DateTimeUtc,
+    record: bool,
+) -> Vec<Uuid> {
+    let groups = match card_repository.get_publish_groups_by_id(card.id).await {
+        Ok(groups) => groups,
+        Err(e) => {
+            eprintln!("failed to get publish groups: {:?}", e);
+            return vec![];
+        }
+    };
+    let mut logs = vec![];
+    for group_id in groups {
+        match bot_client.get_user_group(&group_id.to_string()).await {
+            Ok(group) => logs.extend(group.members.into_iter().map(|m| DeliveryLogModel {
+                card_id: card.id,
+                group_id,
+                user_id: m.id,
+                delivered_at: now,
+            })),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                eprintln!("group {} not found", group_id);
+            }
+            Err(e) => eprintln!("failed to get user group: {:?}", e),
+        }
+    }
+    if record {
+        if let Err(e) = card_repository.save_delivery_logs(&logs).await {
+            eprintln!("failed to save delivery logs: {:?}", e);
+        }
+    }
+    let mut members: Vec<_> = logs.into_iter().map(|l| l.user_id).collect();
+    members.sort();
+    members.dedup();
+    members
+}
+
+async fn mention_users<BC: BotClient<Error = impl Debug + Send + BotClientError>>(
+    user_ids: &[Uuid],
+    bot_client: &BC,
+) -> String {
+    if user_ids.is_empty() {
+        return String::new();
+    }
+    match bot_client.get_users_by_ids(user_ids).await {
+        Ok(users) => users
+            .iter()
+            .map(|u| format!(r#"!{{"type":"user","raw":"@{}","id":"{}"}}"#, u.name, u.id))
+            .collect::<Vec<_>>()
+            .join(" "),
+        Err(e) => {
+            eprintln!("failed to get group members: {:?}", e);
+            String::new()
+        }
+    }
 }
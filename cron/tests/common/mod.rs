@@ -0,0 +1,173 @@
+//! cronの結合テスト用のハーネス
+//!
+//! traQは`fake-traq`、リポジトリは`domain`のモックを使う。
+
+#![allow(dead_code)]
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bot_client::{BotClientConfig, BotClientImpl};
+use bytes::Bytes;
+use chrono::Utc;
+use domain::repository::{
+    AuditEventModel, CardModel, CardStatus, DateTimeUtc, DeliveryLogModel, MockCardRepository,
+    MockImageRepository, PublishChannelModel,
+};
+use fake_traq::FakeTraq;
+use uuid::Uuid;
+
+use cron::CronImpl;
+
+pub type TestCron = CronImpl<MockCardRepository, MockImageRepository, BotClientImpl, BotClientImpl>;
+
+pub const PNG: &[u8] = b"png";
+
+pub fn bot_client(traq: &FakeTraq) -> BotClientImpl {
+    let config = BotClientConfig {
+        origin: traq.origin(),
+        base_backoff: Duration::from_millis(1),
+        ..Default::default()
+    };
+    BotClientImpl::with_config("token".to_string(), config)
+}
+
+/// 投稿日時を過ぎた予約済みのカード
+pub fn card(owner_id: Uuid) -> CardModel {
+    CardModel {
+        id: Uuid::new_v4(),
+        owner_id,
+        publish_date: Some(Utc::now() - chrono::Duration::minutes(1)),
+        message: Some("おめでとう".to_string()),
+        status: CardStatus::Scheduled,
+        time_zone: "Asia/Tokyo".to_string(),
+        version: 1,
+        deleted_at: None,
+    }
+}
+
+/// カードの宛先
+#[derive(Debug, Clone, Default)]
+pub struct Destinations {
+    pub channels: Vec<Uuid>,
+    pub recipients: Vec<Uuid>,
+    pub groups: Vec<Uuid>,
+}
+
+impl Destinations {
+    pub fn channels(&self, card_id: Uuid) -> Vec<PublishChannelModel> {
+        self.channels
+            .iter()
+            .map(|id| PublishChannelModel { id: *id, card_id })
+            .collect()
+    }
+}
+
+/// リポジトリに書き込まれたもの
+#[derive(Debug, Default)]
+pub struct Recorded {
+    pub statuses: Vec<(Vec<CardStatus>, CardStatus)>,
+    pub delivery_logs: Vec<DeliveryLogModel>,
+    pub audit_events: Vec<AuditEventModel>,
+    /// `delete_audit_events_before`に渡した日時
+    pub audit_purges: Vec<DateTimeUtc>,
+    /// 完全に消したカード
+    pub deleted_cards: Vec<Uuid>,
+}
+
+/// 配信で読み書きするメソッドを用意したリポジトリ。`tick`で配信するカードは`due`
+pub fn card_repository(
+    card: &CardModel,
+    destinations: &Destinations,
+    due: bool,
+) -> (MockCardRepository, Arc<Mutex<Recorded>>) {
+    let recorded = Arc::<Mutex<Recorded>>::default();
+    let mut repo = MockCardRepository::new();
+    let due = due.then(|| (card.clone(), destinations.channels(card.id)));
+    repo.expect_get_due_cards_with_channels()
+        .returning(move |_| Ok(due.clone().into_iter().collect()));
+    repo.expect_get_cards_trashed_before()
+        .returning(|_| Ok(vec![]));
+    repo.expect_get_recurrence().returning(|_| Ok(None));
+    let channels = destinations.channels.clone();
+    repo.expect_get_publish_channels_by_id()
+        .returning(move |_| Ok(channels.clone()));
+    let recipients = destinations.recipients.clone();
+    repo.expect_get_recipients_by_id()
+        .returning(move |_| Ok(recipients.clone()));
+    let groups = destinations.groups.clone();
+    repo.expect_get_publish_groups_by_id()
+        .returning(move |_| Ok(groups.clone()));
+    repo.expect_get_contributions().returning(|_| Ok(vec![]));
+    let r = recorded.clone();
+    repo.expect_update_card_status()
+        .returning(move |_, from, to| {
+            r.lock().unwrap().statuses.push((from.to_vec(), to));
+            Ok(Some(()))
+        });
+    let r = recorded.clone();
+    repo.expect_save_delivery_logs().returning(move |logs| {
+        r.lock().unwrap().delivery_logs.extend_from_slice(logs);
+        Ok(())
+    });
+    let r = recorded.clone();
+    repo.expect_save_audit_event().returning(move |event| {
+        r.lock().unwrap().audit_events.push(event.clone());
+        Ok(())
+    });
+    let r = recorded.clone();
+    repo.expect_delete_audit_events_before()
+        .returning(move |before| {
+            r.lock().unwrap().audit_purges.push(before);
+            Ok(0)
+        });
+    (repo, recorded)
+}
+
+/// 配信するカードが無く、ゴミ箱に`trashed`があるリポジトリ
+pub fn trash_repository(trashed: &[CardModel]) -> (MockCardRepository, Arc<Mutex<Recorded>>) {
+    let recorded = Arc::<Mutex<Recorded>>::default();
+    let mut repo = MockCardRepository::new();
+    repo.expect_get_due_cards_with_channels()
+        .returning(|_| Ok(vec![]));
+    let trashed = trashed.to_vec();
+    repo.expect_get_cards_trashed_before()
+        .returning(move |_| Ok(trashed.clone()));
+    repo.expect_get_image_revisions()
+        .returning(|_, _| Ok(vec![]));
+    let r = recorded.clone();
+    repo.expect_delete_card().returning(move |id| {
+        r.lock().unwrap().deleted_cards.push(id);
+        Ok(Some(()))
+    });
+    let r = recorded.clone();
+    repo.expect_save_audit_event().returning(move |event| {
+        r.lock().unwrap().audit_events.push(event.clone());
+        Ok(())
+    });
+    repo.expect_delete_audit_events_before()
+        .returning(|_| Ok(0));
+    (repo, recorded)
+}
+
+/// カードのPNGを返すリポジトリ
+pub fn image_repository() -> MockImageRepository {
+    let mut repo = MockImageRepository::new();
+    repo.expect_get_png()
+        .returning(|_| Ok(Some(Bytes::from_static(PNG))));
+    repo
+}
+
+pub fn cron(
+    traq: &FakeTraq,
+    card_repository: MockCardRepository,
+    image_repository: MockImageRepository,
+) -> TestCron {
+    CronImpl::new(
+        Arc::new(card_repository),
+        Arc::new(image_repository),
+        Arc::new(bot_client(traq)),
+        Arc::new(bot_client(traq)),
+    )
+    .traq_origin(&traq.origin())
+}
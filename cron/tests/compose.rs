@@ -0,0 +1,67 @@
+use std::io::Cursor;
+
+use bytes::Bytes;
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+
+use cron::compose::compose_layers;
+
+fn png(image: RgbaImage) -> Bytes {
+    let mut png = Cursor::new(vec![]);
+    DynamicImage::ImageRgba8(image)
+        .write_to(&mut png, ImageFormat::Png)
+        .unwrap();
+    png.into_inner().into()
+}
+
+fn decode(png: &[u8]) -> RgbaImage {
+    image::load_from_memory_with_format(png, ImageFormat::Png)
+        .unwrap()
+        .into_rgba8()
+}
+
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+const CLEAR: Rgba<u8> = Rgba([0, 0, 0, 0]);
+
+#[test]
+fn resizes_layer_to_card() {
+    let base = png(RgbaImage::from_pixel(8, 8, WHITE));
+    // 左半分だけ赤い半分の大きさの画像
+    let layer = png(RgbaImage::from_fn(
+        4,
+        4,
+        |x, _| {
+            if x < 2 {
+                RED
+            } else {
+                CLEAR
+            }
+        },
+    ));
+
+    let composed = decode(&compose_layers(&base, &[layer]).unwrap());
+
+    assert_eq!(composed.dimensions(), (8, 8));
+    assert_eq!(*composed.get_pixel(0, 0), RED);
+    assert_eq!(*composed.get_pixel(0, 7), RED);
+    assert_eq!(*composed.get_pixel(7, 0), WHITE);
+    assert_eq!(*composed.get_pixel(7, 7), WHITE);
+}
+
+#[test]
+fn skips_layer_that_fails_to_decode() {
+    let base = png(RgbaImage::from_pixel(4, 4, WHITE));
+    let broken = Bytes::from_static(b"not an image");
+    let layer = png(RgbaImage::from_pixel(4, 4, RED));
+
+    let composed = decode(&compose_layers(&base, &[broken, layer]).unwrap());
+
+    assert_eq!(composed.dimensions(), (4, 4));
+    assert!(composed.pixels().all(|p| *p == RED));
+}
+
+#[test]
+fn rejects_base_that_is_not_png() {
+    let layer = png(RgbaImage::from_pixel(4, 4, RED));
+    assert!(compose_layers(b"not an image", &[layer]).is_err());
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use domain::cron::Cron;
+use fake_traq::{FakeTraq, State};
+
+use common::{card, card_repository, cron, image_repository, Destinations};
+
+fn mention(name: &str, id: uuid::Uuid) -> String {
+    format!(r#"!{{"type":"user","raw":"@{}","id":"{}"}}"#, name, id)
+}
+
+#[tokio::test]
+async fn group_members_are_mentioned_in_publish_channel() {
+    let mut state = State::default();
+    let owner = state.add_user("alice");
+    let bob = state.add_user("bob");
+    let carol = state.add_user("carol");
+    let channel = state.add_channel("gps", None);
+    let group = state.add_group("team", &[bob.id, carol.id]);
+    let traq = FakeTraq::start(state).await.unwrap();
+    let card = card(owner.id);
+    let destinations = Destinations {
+        channels: vec![channel.id],
+        groups: vec![group.id],
+        ..Default::default()
+    };
+    let (card_repo, recorded) = card_repository(&card, &destinations, true);
+    let cron = cron(&traq, card_repo, image_repository());
+
+    cron.tick().await;
+
+    let state = traq.state();
+    assert!(state.dm_channels.is_empty());
+    assert_eq!(state.messages.len(), 1);
+    let message = &state.messages[0];
+    assert_eq!(message.channel_id, channel.id);
+    assert!(message.content.contains(&mention("bob", bob.id)));
+    assert!(message.content.contains(&mention("carol", carol.id)));
+
+    let recorded = recorded.lock().unwrap();
+    let mut logged: Vec<_> = recorded
+        .delivery_logs
+        .iter()
+        .map(|l| (l.group_id, l.user_id))
+        .collect();
+    logged.sort();
+    let mut expected = vec![(group.id, bob.id), (group.id, carol.id)];
+    expected.sort();
+    assert_eq!(logged, expected);
+}
+
+#[tokio::test]
+async fn group_members_get_dm_without_publish_channel() {
+    let mut state = State::default();
+    let owner = state.add_user("alice");
+    let bob = state.add_user("bob");
+    let carol = state.add_user("carol");
+    let group = state.add_group("team", &[bob.id, carol.id]);
+    let traq = FakeTraq::start(state).await.unwrap();
+    let card = card(owner.id);
+    // 個別の宛先にもいるメンバーには1通だけ送る
+    let destinations = Destinations {
+        recipients: vec![bob.id],
+        groups: vec![group.id],
+        ..Default::default()
+    };
+    let (card_repo, _) = card_repository(&card, &destinations, true);
+    let cron = cron(&traq, card_repo, image_repository());
+
+    cron.tick().await;
+
+    let state = traq.state();
+    let mut sent: Vec<_> = state.messages.iter().map(|m| m.channel_id).collect();
+    sent.sort();
+    let mut expected = vec![state.dm_channels[&bob.id], state.dm_channels[&carol.id]];
+    expected.sort();
+    assert_eq!(sent, expected);
+    for message in &state.messages {
+        assert!(!message.content.contains(&mention("bob", bob.id)));
+        assert!(!message.content.contains(&mention("carol", carol.id)));
+    }
+}
+
+#[tokio::test]
+async fn recipients_get_dm_alongside_publish_channel() {
+    let mut state = State::default();
+    let owner = state.add_user("alice");
+    let bob = state.add_user("bob");
+    let channel = state.add_channel("gps", None);
+    let traq = FakeTraq::start(state).await.unwrap();
+    let card = card(owner.id);
+    let destinations = Destinations {
+        channels: vec![channel.id],
+        recipients: vec![bob.id],
+        ..Default::default()
+    };
+    let (card_repo, _) = card_repository(&card, &destinations, true);
+    let cron = cron(&traq, card_repo, image_repository());
+
+    cron.tick().await;
+
+    let state = traq.state();
+    let mut sent: Vec<_> = state.messages.iter().map(|m| m.channel_id).collect();
+    sent.sort();
+    let mut expected = vec![channel.id, state.dm_channels[&bob.id]];
+    expected.sort();
+    assert_eq!(sent, expected);
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use domain::cron::Cron;
+use fake_traq::{FakeTraq, State};
+
+use common::{card, card_repository, cron, image_repository, Destinations};
+
+fn mention(name: &str, id: uuid::Uuid) -> String {
+    format!(r#"!{{"type":"user","raw":"@{}","id":"{}"}}"#, name, id)
+}
+
+#[tokio::test]
+async fn preview_sends_channel_message_to_callers_dm() {
+    let mut state = State::default();
+    let owner = state.add_user("alice");
+    let bob = state.add_user("bob");
+    let channel = state.add_channel("gps", None);
+    let group = state.add_group("team", &[bob.id]);
+    let traq = FakeTraq::start(state).await.unwrap();
+    let card = card(owner.id);
+    let destinations = Destinations {
+        channels: vec![channel.id],
+        groups: vec![group.id],
+        ..Default::default()
+    };
+    let (card_repo, recorded) = card_repository(&card, &destinations, false);
+    let cron = cron(&traq, card_repo, image_repository());
+
+    assert!(cron.preview(&card, owner.id).await);
+
+    // 投稿先チャンネルと同じメンションを付けて、自分のDMにだけ送る
+    let state = traq.state();
+    assert_eq!(state.messages.len(), 1);
+    let message = &state.messages[0];
+    assert_eq!(message.channel_id, state.dm_channels[&owner.id]);
+    assert!(message.content.contains(&mention("bob", bob.id)));
+
+    let recorded = recorded.lock().unwrap();
+    assert!(recorded.delivery_logs.is_empty());
+    assert!(recorded.statuses.is_empty());
+}
+
+#[tokio::test]
+async fn preview_of_dm_card_has_no_mentions() {
+    let mut state = State::default();
+    let owner = state.add_user("alice");
+    let bob = state.add_user("bob");
+    let group = state.add_group("team", &[bob.id]);
+    let traq = FakeTraq::start(state).await.unwrap();
+    let card = card(owner.id);
+    let destinations = Destinations {
+        recipients: vec![bob.id],
+        groups: vec![group.id],
+        ..Default::default()
+    };
+    let (card_repo, _) = card_repository(&card, &destinations, false);
+    let cron = cron(&traq, card_repo, image_repository());
+
+    assert!(cron.preview(&card, owner.id).await);
+
+    let state = traq.state();
+    assert_eq!(state.messages.len(), 1);
+    assert_eq!(state.messages[0].channel_id, state.dm_channels[&owner.id]);
+    assert!(!state.messages[0].content.contains(&mention("bob", bob.id)));
+    assert!(!state.dm_channels.contains_key(&bob.id));
+}
@@ -0,0 +1,185 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use domain::cron::Cron;
+use domain::repository::{AuditAction, CardModel, CardStatus};
+use fake_traq::{FakeTraq, State};
+
+use common::{card, card_repository, cron, image_repository, Destinations, PNG};
+
+#[tokio::test]
+async fn tick_delivers_due_card() {
+    let mut state = State::default();
+    let owner = state.add_user("alice");
+    let channel = state.add_channel("gps", None);
+    let traq = FakeTraq::start(state).await.unwrap();
+    let card = card(owner.id);
+    let destinations = Destinations {
+        channels: vec![channel.id],
+        ..Default::default()
+    };
+    let (card_repo, recorded) = card_repository(&card, &destinations, true);
+    let cron = cron(&traq, card_repo, image_repository());
+
+    cron.tick().await;
+
+    let state = traq.state();
+    assert_eq!(state.files.len(), 1);
+    assert_eq!(state.files[0].channel_id, channel.id);
+    assert_eq!(state.files[0].content.as_ref(), PNG);
+    assert_eq!(state.messages.len(), 1);
+    let message = &state.messages[0];
+    assert_eq!(message.channel_id, channel.id);
+    assert!(message
+        .content
+        .contains(&format!(r#""raw":"@alice","id":"{}""#, owner.id)));
+    assert!(message.content.contains("おめでとう"));
+    let file_url = format!("{}/files/{}", traq.origin(), state.files[0].id);
+    assert!(message.content.contains(&file_url));
+
+    let recorded = recorded.lock().unwrap();
+    assert_eq!(
+        recorded.statuses,
+        vec![
+            (vec![CardStatus::Scheduled], CardStatus::Delivering),
+            (vec![CardStatus::Delivering], CardStatus::Delivered),
+        ]
+    );
+    assert_eq!(recorded.audit_events.len(), 1);
+    assert_eq!(recorded.audit_events[0].action, AuditAction::CardDeliver);
+    assert_eq!(recorded.audit_events[0].card_id, Some(card.id));
+}
+
+#[tokio::test]
+async fn tick_marks_card_failed_when_upload_is_forbidden() {
+    let mut state = State::default();
+    let owner = state.add_user("alice");
+    let channel = state.add_channel("gps", None);
+    let traq = FakeTraq::start(state).await.unwrap();
+    traq.fail_next(fake_traq::StatusCode::FORBIDDEN, None);
+    let card = card(owner.id);
+    let destinations = Destinations {
+        channels: vec![channel.id],
+        ..Default::default()
+    };
+    let (card_repo, recorded) = card_repository(&card, &destinations, true);
+    let cron = cron(&traq, card_repo, image_repository());
+
+    cron.tick().await;
+
+    assert!(traq.state().messages.is_empty());
+    let recorded = recorded.lock().unwrap();
+    assert_eq!(
+        recorded.statuses.last(),
+        Some(&(vec![CardStatus::Delivering], CardStatus::Failed))
+    );
+}
+
+#[tokio::test]
+async fn tick_skips_card_taken_by_another_tick() {
+    let mut state = State::default();
+    let owner = state.add_user("alice");
+    let channel = state.add_channel("gps", None);
+    let traq = FakeTraq::start(state).await.unwrap();
+    let card = card(owner.id);
+    let destinations = Destinations {
+        channels: vec![channel.id],
+        ..Default::default()
+    };
+    let (mut card_repo, _) = card_repository(&card, &destinations, true);
+    card_repo.checkpoint();
+    let due = vec![(card.clone(), destinations.channels(card.id))];
+    card_repo
+        .expect_get_due_cards_with_channels()
+        .returning(move |_| Ok(due.clone()));
+    card_repo
+        .expect_get_cards_trashed_before()
+        .returning(|_| Ok(vec![]));
+    card_repo
+        .expect_update_card_status()
+        .times(1)
+        .returning(|_, _, _| Ok(None));
+    card_repo
+        .expect_delete_audit_events_before()
+        .returning(|_| Ok(0));
+    let cron = cron(&traq, card_repo, image_repository());
+
+    cron.tick().await;
+
+    assert!(traq.state().messages.is_empty());
+}
+
+#[tokio::test]
+async fn tick_purges_expired_audit_events() {
+    let mut state = State::default();
+    let owner = state.add_user("alice");
+    let traq = FakeTraq::start(state).await.unwrap();
+    let card = card(owner.id);
+    let (card_repo, recorded) = card_repository(&card, &Destinations::default(), false);
+    let cron = cron(&traq, card_repo, image_repository()).audit_retention(Duration::days(7));
+
+    cron.tick().await;
+
+    let recorded = recorded.lock().unwrap();
+    assert_eq!(recorded.audit_purges.len(), 1);
+    let age = Utc::now() - recorded.audit_purges[0];
+    assert!(age >= Duration::days(7) && age < Duration::days(7) + Duration::minutes(1));
+}
+
+#[tokio::test]
+async fn deliver_sends_only_the_given_card() {
+    let mut state = State::default();
+    let owner = state.add_user("alice");
+    let channel = state.add_channel("gps", None);
+    let traq = FakeTraq::start(state).await.unwrap();
+    let card = card(owner.id);
+    let destinations = Destinations {
+        channels: vec![channel.id],
+        ..Default::default()
+    };
+    // 配信を待つカードの一覧からは探さない
+    let (mut card_repo, recorded) = card_repository(&card, &destinations, false);
+    let c = card.clone();
+    card_repo
+        .expect_get_card_by_id()
+        .returning(move |_| Ok(Some(c.clone())));
+    let cron = cron(&traq, card_repo, image_repository());
+
+    assert_eq!(cron.deliver(card.id).await, Some(CardStatus::Delivered));
+
+    assert_eq!(traq.state().messages.len(), 1);
+    assert_eq!(
+        recorded.lock().unwrap().statuses,
+        vec![
+            (vec![CardStatus::Scheduled], CardStatus::Delivering),
+            (vec![CardStatus::Delivering], CardStatus::Delivered),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn deliver_skips_card_not_yet_due() {
+    let mut state = State::default();
+    let owner = state.add_user("alice");
+    let channel = state.add_channel("gps", None);
+    let traq = FakeTraq::start(state).await.unwrap();
+    let card = CardModel {
+        publish_date: Some(Utc::now() + Duration::hours(1)),
+        ..card(owner.id)
+    };
+    let destinations = Destinations {
+        channels: vec![channel.id],
+        ..Default::default()
+    };
+    let (mut card_repo, recorded) = card_repository(&card, &destinations, false);
+    let c = card.clone();
+    card_repo
+        .expect_get_card_by_id()
+        .returning(move |_| Ok(Some(c.clone())));
+    let cron = cron(&traq, card_repo, image_repository());
+
+    assert_eq!(cron.deliver(card.id).await, None);
+
+    assert!(traq.state().messages.is_empty());
+    assert!(recorded.lock().unwrap().statuses.is_empty());
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use chrono::{Duration, Utc};
+use domain::cron::Cron;
+use domain::repository::{AuditAction, CardModel, MockImageRepository};
+use fake_traq::{FakeTraq, State};
+use uuid::Uuid;
+
+use common::{card, cron, trash_repository};
+
+#[tokio::test]
+async fn purge_skips_card_whose_image_delete_fails() {
+    let traq = FakeTraq::start(State::default()).await.unwrap();
+    let deleted_at = Some(Utc::now() - Duration::days(40));
+    let broken = CardModel {
+        deleted_at,
+        ..card(Uuid::new_v4())
+    };
+    let fine = CardModel {
+        deleted_at,
+        ..card(Uuid::new_v4())
+    };
+    let (card_repo, recorded) = trash_repository(&[broken.clone(), fine.clone()]);
+
+    let pngs = Arc::<Mutex<Vec<Uuid>>>::default();
+    let mut image_repo = MockImageRepository::new();
+    let broken_id = broken.id;
+    image_repo.expect_delete_svg().returning(move |id| {
+        if id == broken_id {
+            anyhow::bail!("unavailable");
+        }
+        Ok(())
+    });
+    let p = pngs.clone();
+    image_repo.expect_delete_png().returning(move |id| {
+        p.lock().unwrap().push(id);
+        Ok(())
+    });
+    let cron = cron(&traq, card_repo, image_repo);
+
+    cron.tick().await;
+
+    // 画像を消せなかったカードは次の実行まで残す
+    assert_eq!(*pngs.lock().unwrap(), vec![fine.id]);
+    let recorded = recorded.lock().unwrap();
+    assert_eq!(recorded.deleted_cards, vec![fine.id]);
+    assert_eq!(recorded.audit_events.len(), 1);
+    assert_eq!(recorded.audit_events[0].action, AuditAction::CardPurge);
+    assert_eq!(recorded.audit_events[0].card_id, Some(fine.id));
+}
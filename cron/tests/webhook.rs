@@ -0,0 +1,62 @@
+mod common;
+
+use std::sync::Arc;
+
+use bot_client::WebhookSink;
+use domain::cron::Cron;
+use domain::repository::{CardStatus, MockCardRepository, WebhookModel};
+use fake_traq::{FakeTraq, State};
+
+use common::{bot_client, card, card_repository, image_repository, Destinations, PNG};
+use cron::CronImpl;
+
+#[tokio::test]
+async fn webhook_delivery_does_not_need_bot_in_channel() {
+    let mut state = State::default();
+    let owner = state.add_user("alice");
+    let home = state.add_channel("bot-home", None);
+    let channel = state.add_channel("gps", None);
+    let webhook_id = state.add_webhook(channel.id, "secret");
+    state.bot_excluded.insert(channel.id);
+    let traq = FakeTraq::start(state).await.unwrap();
+    let card = card(owner.id);
+    let destinations = Destinations {
+        channels: vec![channel.id],
+        ..Default::default()
+    };
+    let (card_repo, recorded) = card_repository(&card, &destinations, true);
+    let mut registry = MockCardRepository::new();
+    let webhook = WebhookModel {
+        channel_id: channel.id,
+        webhook_id,
+        secret: "secret".to_string(),
+    };
+    registry
+        .expect_get_webhook()
+        .returning(move |id| Ok((id == webhook.channel_id).then(|| webhook.clone())));
+    let sink =
+        WebhookSink::new(Arc::new(registry), bot_client(&traq)).upload_channel(Some(home.id));
+    let cron = CronImpl::new(
+        Arc::new(card_repo),
+        Arc::new(image_repository()),
+        Arc::new(bot_client(&traq)),
+        Arc::new(sink),
+    )
+    .traq_origin(&traq.origin());
+
+    cron.tick().await;
+
+    let state = traq.state();
+    assert_eq!(state.files.len(), 1);
+    assert_eq!(state.files[0].channel_id, home.id);
+    assert_eq!(state.files[0].content.as_ref(), PNG);
+    assert_eq!(state.messages.len(), 1);
+    let message = &state.messages[0];
+    assert_eq!(message.channel_id, channel.id);
+    let file_url = format!("{}/files/{}", traq.origin(), state.files[0].id);
+    assert!(message.content.contains(&file_url));
+    assert_eq!(
+        recorded.lock().unwrap().statuses.last(),
+        Some(&(vec![CardStatus::Delivering], CardStatus::Delivered))
+    );
+}
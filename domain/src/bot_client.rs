@@ -2,33 +2,90 @@ use async_trait::async_trait;
 use bytes::Bytes;
 use mockall::automock;
 use shaku::Interface;
-pub use traq::models::{Channel, ChannelList, FileInfo, Stamp, User, UserDetail};
+pub use traq::models::{
+    Channel, ChannelList, DmChannel, FileInfo, Stamp, User, UserDetail, UserGroup, UserGroupMember,
+};
 use uuid::Uuid;
 
-#[automock(type Error = String;)]
+#[automock(type Error = anyhow::Error;)]
 #[async_trait]
 pub trait BotClient: Interface {
     type Error;
 
-    async fn get_stamp_image(&self, stamp_id: &str) -> Result<ImageData, Self::Error>;
+    async fn get_stamp_image(&self, stamp_id: &str) -> Result<Image, Self::Error>;
     async fn get_stamps(&self, r#type: StampType) -> Result<Vec<Stamp>, Self::Error>;
     async fn get_users<'a>(&'a self, name: Option<&'a str>) -> Result<Vec<User>, Self::Error>;
     async fn get_user(&self, id: &str) -> Result<UserDetail, Self::Error>;
-    async fn get_user_icon(&self, id: &str) -> Result<ImageData, Self::Error>;
+    /// 全ユーザー一覧から`ids`のユーザーを引く。見つからないIDは無視される
+    async fn get_users_by_ids(&self, ids: &[Uuid]) -> Result<Vec<User>, Self::Error>;
+    async fn get_user_icon(&self, id: &str) -> Result<Image, Self::Error>;
     async fn get_channels(&self) -> Result<ChannelList, Self::Error>;
+    async fn get_user_dm_channel(&self, user_id: &str) -> Result<DmChannel, Self::Error>;
+    async fn get_user_group(&self, group_id: &str) -> Result<UserGroup, Self::Error>;
     async fn post_message(&self, params: &PostMessageParams) -> Result<(), Self::Error>;
     async fn uplodad_file(&self, params: &UploadFileParams) -> Result<UploadFileResp, Self::Error>;
 }
 
+/// 呼び出し側が分岐に使うエラーの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    NotFound,
+    Forbidden,
+    RateLimited,
+    Timeout,
+    Other,
+}
+
+pub trait BotClientError {
+    fn kind(&self) -> ErrorKind;
+}
+
+impl BotClientError for String {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ImageData {
     Svg(String),
     Png(Bytes),
     Gif(Bytes),
     Jpeg(Bytes),
+    Webp(Bytes),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+impl ImageData {
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            ImageData::Svg(svg) => svg.as_bytes(),
+            ImageData::Png(data)
+            | ImageData::Gif(data)
+            | ImageData::Jpeg(data)
+            | ImageData::Webp(data) => data,
+        }
+    }
+}
+
+/// traQから取得した画像と、プロキシ時にそのまま返すキャッシュ関連ヘッダ
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub data: ImageData,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl From<ImageData> for Image {
+    fn from(data: ImageData) -> Self {
+        Self {
+            data,
+            etag: None,
+            last_modified: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum StampType {
     Original,
     Unicode,
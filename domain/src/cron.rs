@@ -2,8 +2,19 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use shaku::Interface;
+use uuid::Uuid;
+
+use crate::repository::{CardModel, CardStatus};
 
 #[async_trait]
 pub trait Cron: Interface {
     async fn run(self: Arc<Self>) -> ();
+    /// 投稿日時を過ぎた予約済みのカードを配信する。繰り返しのカードは次の発生日時で予約し直す
+    async fn tick(&self);
+    /// `tick`と同じように`card_id`のカードだけを配信する。投稿日時を過ぎた予約済みでなければ何もしない。
+    /// 配信した後のステータスを返す
+    async fn deliver(&self, card_id: Uuid) -> Option<CardStatus>;
+    /// 配信と同じ画像とメッセージを`user_id`のDMだけに送る。ステータスや配信ログは変えない。
+    /// 送れたかを返す
+    async fn preview(&self, card: &CardModel, user_id: Uuid) -> bool;
 }
@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+use mockall::automock;
+use shaku::Interface;
+use uuid::Uuid;
+
+use crate::bot_client::PostMessageParams;
+
+/// cronが配信メッセージを投稿する手段。BOTまたはWebhook
+#[automock(type Error = anyhow::Error;)]
+#[async_trait]
+pub trait DeliverySink: Interface {
+    type Error;
+    async fn post_message(&self, params: &PostMessageParams) -> Result<(), Self::Error>;
+    /// `channel_id`に送るカードの画像をBOTがアップロードするチャンネル
+    async fn upload_channel(&self, channel_id: Uuid) -> Result<Uuid, Self::Error>;
+}
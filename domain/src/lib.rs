@@ -1,3 +1,6 @@
 pub mod bot_client;
 pub mod cron;
+pub mod delivery;
+pub mod recurrence;
 pub mod repository;
+pub mod time_zone;
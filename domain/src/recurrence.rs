@@ -0,0 +1,324 @@
+//! iCalendarのRRULE(RFC 5545)のうち、誕生日や記念日に使う範囲の実装
+//!
+//! 対応するのは`FREQ=YEARLY|MONTHLY|WEEKLY`と`INTERVAL`・`COUNT`・`UNTIL`、
+//! 週単位の時のみ`BYDAY`。展開は`time_zone`の壁時計で行うので、夏時間を挟んでも同じ時刻に届く。
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+
+use crate::repository::{DateTimeUtc, RecurrenceModel};
+use crate::time_zone::parse_time_zone;
+
+/// 発生しない期間が続いても無限に探さないための上限
+const MAX_PERIODS: u32 = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Frequency {
+    Yearly,
+    Monthly,
+    Weekly,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Until {
+    DateTime(DateTimeUtc),
+    /// 展開先のタイムゾーンでのこの日まで
+    Date(NaiveDate),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<Until>,
+    /// 空なら起点の曜日
+    pub by_day: Vec<Weekday>,
+}
+
+impl FromStr for RRule {
+    type Err = String;
+
+    /// `FREQ=YEARLY;COUNT=3`形式。先頭の`RRULE:`は省略できる
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_prefix("RRULE:").unwrap_or(s);
+        let mut freq = None;
+        let mut interval = 1;
+        let mut count = None;
+        let mut until = None;
+        let mut by_day = vec![];
+        for part in s.split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("invalid rule part `{}`", part))?;
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.to_ascii_uppercase().as_str() {
+                        "YEARLY" => Frequency::Yearly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "WEEKLY" => Frequency::Weekly,
+                        _ => return Err(format!("unsupported FREQ `{}`", value)),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .parse()
+                        .ok()
+                        .filter(|i| *i > 0)
+                        .ok_or_else(|| format!("invalid INTERVAL `{}`", value))?
+                }
+                "COUNT" => {
+                    count = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|c| *c > 0)
+                            .ok_or_else(|| format!("invalid COUNT `{}`", value))?,
+                    )
+                }
+                "UNTIL" => until = Some(parse_until(value)?),
+                "BYDAY" => {
+                    by_day = value
+                        .split(',')
+                        .map(parse_weekday)
+                        .collect::<Result<_, _>>()?
+                }
+                _ => return Err(format!("unsupported rule part `{}`", key)),
+            }
+        }
+        let freq = freq.ok_or("FREQ is required")?;
+        if count.is_some() && until.is_some() {
+            return Err("COUNT and UNTIL must not occur together".to_string());
+        }
+        if !by_day.is_empty() && freq != Frequency::Weekly {
+            return Err("BYDAY is only supported with FREQ=WEEKLY".to_string());
+        }
+        by_day.sort_by_key(|d: &Weekday| d.num_days_from_monday());
+        by_day.dedup();
+        Ok(Self {
+            freq,
+            interval,
+            count,
+            until,
+            by_day,
+        })
+    }
+}
+
+impl fmt::Display for RRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let freq = match self.freq {
+            Frequency::Yearly => "YEARLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Weekly => "WEEKLY",
+        };
+        write!(f, "FREQ={}", freq)?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        match self.until {
+            Some(Until::DateTime(until)) => write!(f, ";UNTIL={}", until.format("%Y%m%dT%H%M%SZ"))?,
+            Some(Until::Date(until)) => write!(f, ";UNTIL={}", until.format("%Y%m%d"))?,
+            None => {}
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<_> = self.by_day.iter().map(|d| weekday_code(*d)).collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_until(value: &str) -> Result<Until, String> {
+    if let Ok(until) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ") {
+        return Ok(Until::DateTime(until.and_utc()));
+    }
+    NaiveDate::parse_from_str(value, "%Y%m%d")
+        .map(Until::Date)
+        .map_err(|_| format!("invalid UNTIL `{}`", value))
+}
+
+fn parse_weekday(value: &str) -> Result<Weekday, String> {
+    match value.to_ascii_uppercase().as_str() {
+        "MO" => Ok(Weekday::Mon),
+        "TU" => Ok(Weekday::Tue),
+        "WE" => Ok(Weekday::Wed),
+        "TH" => Ok(Weekday::Thu),
+        "FR" => Ok(Weekday::Fri),
+        "SA" => Ok(Weekday::Sat),
+        "SU" => Ok(Weekday::Sun),
+        _ => Err(format!("invalid BYDAY `{}`", value)),
+    }
+}
+
+fn weekday_code(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+/// 起点とタイムゾーンの決まった繰り返し
+#[derive(Clone, Debug)]
+pub struct Recurrence {
+    pub rule: RRule,
+    pub time_zone: Tz,
+    pub dtstart: DateTimeUtc,
+}
+
+impl TryFrom<&RecurrenceModel> for Recurrence {
+    type Error = String;
+
+    fn try_from(value: &RecurrenceModel) -> Result<Self, Self::Error> {
+        Ok(Self {
+            rule: value.rrule.parse()?,
+            time_zone: parse_time_zone(&value.time_zone)?,
+            dtstart: value.dtstart,
+        })
+    }
+}
+
+impl Recurrence {
+    pub fn occurrences(&self) -> Occurrences<'_> {
+        Occurrences {
+            recurrence: self,
+            start: self.dtstart.with_timezone(&self.time_zone).naive_local(),
+            period: 0,
+            emitted: 0,
+            buffer: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// `after`より後で、`skipped`に含まれない最初の発生日時
+    pub fn next_after(&self, after: DateTimeUtc, skipped: &[DateTimeUtc]) -> Option<DateTimeUtc> {
+        self.occurrences()
+            .skip_while(|o| *o <= after)
+            .find(|o| !skipped.contains(o))
+    }
+
+    /// `at`が発生日時の1つか
+    pub fn contains(&self, at: DateTimeUtc) -> bool {
+        self.occurrences().find(|o| *o >= at) == Some(at)
+    }
+}
+
+/// 発生日時を昇順に返す
+pub struct Occurrences<'a> {
+    recurrence: &'a Recurrence,
+    start: NaiveDateTime,
+    period: u32,
+    emitted: u32,
+    buffer: VecDeque<DateTimeUtc>,
+    done: bool,
+}
+
+impl Occurrences<'_> {
+    /// `period`番目の期間に含まれる壁時計の日付
+    fn dates(&self, period: u32) -> Vec<NaiveDate> {
+        let rule = &self.recurrence.rule;
+        let start = self.start.date();
+        let Some(step) = period.checked_mul(rule.interval) else {
+            return vec![];
+        };
+        match rule.freq {
+            // 2/29や31日のように存在しない日は飛ばす
+            Frequency::Yearly => start
+                .year()
+                .checked_add(step as i32)
+                .and_then(|y| NaiveDate::from_ymd_opt(y, start.month(), start.day()))
+                .into_iter()
+                .collect(),
+            Frequency::Monthly => {
+                let first = start.with_day(1).unwrap();
+                first
+                    .checked_add_months(Months::new(step))
+                    .and_then(|m| m.with_day(start.day()))
+                    .into_iter()
+                    .collect()
+            }
+            Frequency::Weekly => {
+                let monday = start - Days::new(start.weekday().num_days_from_monday().into());
+                let Some(monday) = monday.checked_add_days(Days::new(u64::from(step) * 7)) else {
+                    return vec![];
+                };
+                let days = if rule.by_day.is_empty() {
+                    vec![start.weekday()]
+                } else {
+                    rule.by_day.clone()
+                };
+                days.into_iter()
+                    .filter_map(|d| {
+                        monday.checked_add_days(Days::new(d.num_days_from_monday().into()))
+                    })
+                    .filter(|d| *d >= start)
+                    .collect()
+            }
+        }
+    }
+
+    /// 夏時間で存在しない時刻は1時間後ろにずらし、重複する時刻は早い方にする
+    fn to_utc(&self, date: NaiveDate, time: NaiveTime) -> Option<DateTimeUtc> {
+        let tz = &self.recurrence.time_zone;
+        let local = date.and_time(time);
+        tz.from_local_datetime(&local)
+            .earliest()
+            .or_else(|| {
+                tz.from_local_datetime(&(local + chrono::Duration::hours(1)))
+                    .earliest()
+            })
+            .map(|d| d.with_timezone(&Utc))
+    }
+}
+
+impl Iterator for Occurrences<'_> {
+    type Item = DateTimeUtc;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.done {
+                return None;
+            }
+            if let Some(at) = self.buffer.pop_front() {
+                let rule = &self.recurrence.rule;
+                let over = match rule.until {
+                    Some(Until::DateTime(until)) => at > until,
+                    Some(Until::Date(until)) => {
+                        at.with_timezone(&self.recurrence.time_zone).date_naive() > until
+                    }
+                    None => false,
+                };
+                if over || rule.count.is_some_and(|c| self.emitted >= c) {
+                    self.done = true;
+                    return None;
+                }
+                self.emitted += 1;
+                return Some(at);
+            }
+            if self.period >= MAX_PERIODS {
+                self.done = true;
+                return None;
+            }
+            let time = self.start.time();
+            let dates = self.dates(self.period);
+            self.buffer = dates
+                .into_iter()
+                .filter_map(|d| self.to_utc(d, time))
+                .collect();
+            self.period += 1;
+        }
+    }
+}
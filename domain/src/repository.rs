@@ -1,32 +1,189 @@
 use async_trait::async_trait;
 use bytes::Bytes;
 use mockall::automock;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use shaku::Interface;
 use uuid::Uuid;
 
-#[automock(type Error = String;)]
+#[automock(type Error = anyhow::Error;)]
 #[async_trait]
 pub trait CardRepository: Interface {
     type Error;
 
     async fn migrate(&self, strategy: MigrationStrategy) -> Result<(), Self::Error>;
-    async fn get_card_with_channels_by_date(
+    /// 投稿日時を過ぎた`Scheduled`のカード
+    async fn get_due_cards_with_channels(
         &self,
-        start: DateTimeUtc,
-        end: DateTimeUtc,
+        now: DateTimeUtc,
     ) -> Result<Vec<(CardModel, Vec<PublishChannelModel>)>, Self::Error>;
     async fn save_card(&self, params: &SaveCardParams) -> Result<(), Self::Error>;
-    async fn update_card(&self, params: &SaveCardParams) -> Result<Option<()>, Self::Error>;
+    /// `Some`の項目だけを更新する。差出人とステータスは変えない。
+    /// 下書きか予約済みで、版が`params.version`の時だけ更新して版を1つ進める。更新できなければ`None`
+    async fn patch_card(&self, params: &PatchCardParams) -> Result<Option<()>, Self::Error>;
+    /// ステータスが`from`のいずれかの時だけ`to`に変える。変えられなければ`None`
+    async fn update_card_status(
+        &self,
+        card_id: Uuid,
+        from: &[CardStatus],
+        to: CardStatus,
+    ) -> Result<Option<()>, Self::Error>;
+    /// ステータスが`from`のいずれかの時だけ投稿日時を変えて`Scheduled`にする
+    async fn reschedule_card(
+        &self,
+        card_id: Uuid,
+        from: &[CardStatus],
+        publish_date: DateTimeUtc,
+    ) -> Result<Option<()>, Self::Error>;
+    /// `stuck_before`より前から配信中のままの時だけ投稿日時を変えて`Scheduled`にする
+    async fn reschedule_stuck_card(
+        &self,
+        card_id: Uuid,
+        stuck_before: DateTimeUtc,
+        publish_date: DateTimeUtc,
+    ) -> Result<Option<()>, Self::Error>;
+    /// ゴミ箱のカードは除く
     async fn get_all_cards(&self) -> Result<Vec<CardModel>, Self::Error>;
+    /// メンバーになっている(所有者を含む)カード
     async fn get_my_cards(&self, user_id: Uuid) -> Result<Vec<CardModel>, Self::Error>;
     async fn get_card_by_id(&self, card_id: Uuid) -> Result<Option<CardModel>, Self::Error>;
     async fn get_publish_channels_by_id(&self, card_id: Uuid) -> Result<Vec<Uuid>, Self::Error>;
+    async fn get_recipients_by_id(&self, card_id: Uuid) -> Result<Vec<Uuid>, Self::Error>;
+    async fn get_publish_groups_by_id(&self, card_id: Uuid) -> Result<Vec<Uuid>, Self::Error>;
+    /// 配信時に解決したグループメンバーを記録する
+    async fn save_delivery_logs(&self, logs: &[DeliveryLogModel]) -> Result<(), Self::Error>;
+    async fn get_delivery_logs_by_id(
+        &self,
+        card_id: Uuid,
+    ) -> Result<Vec<DeliveryLogModel>, Self::Error>;
     async fn delete_publish_channel(
         &self,
         card_id: Uuid,
         channel_id: Uuid,
     ) -> Result<Option<()>, Self::Error>;
+    async fn delete_recipient(
+        &self,
+        card_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<()>, Self::Error>;
+    async fn get_recurrence(&self, card_id: Uuid) -> Result<Option<RecurrenceModel>, Self::Error>;
+    /// 繰り返しのうち最後に配信した日時を記録する
+    async fn update_last_occurrence(
+        &self,
+        card_id: Uuid,
+        at: DateTimeUtc,
+    ) -> Result<Option<()>, Self::Error>;
+    /// 繰り返し設定と飛ばす発生日時をまとめて消す
+    async fn delete_recurrence(&self, card_id: Uuid) -> Result<Option<()>, Self::Error>;
+    async fn get_skipped_occurrences(&self, card_id: Uuid)
+        -> Result<Vec<DateTimeUtc>, Self::Error>;
+    async fn save_skipped_occurrence(
+        &self,
+        card_id: Uuid,
+        at: DateTimeUtc,
+    ) -> Result<(), Self::Error>;
+    async fn delete_skipped_occurrence(
+        &self,
+        card_id: Uuid,
+        at: DateTimeUtc,
+    ) -> Result<Option<()>, Self::Error>;
+    async fn get_contributions(&self, card_id: Uuid)
+        -> Result<Vec<ContributionModel>, Self::Error>;
+    /// 招待されているカードの寄せ書き
+    async fn get_contributions_by_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<ContributionModel>, Self::Error>;
+    /// 同じカード・ユーザーの寄せ書きがあれば置き換える
+    async fn save_contribution(&self, contribution: &ContributionModel) -> Result<(), Self::Error>;
+    async fn delete_contribution(
+        &self,
+        card_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<()>, Self::Error>;
+    async fn get_card_members(&self, card_id: Uuid) -> Result<Vec<CardMemberModel>, Self::Error>;
+    /// 同じカード・ユーザーのメンバーがいればロールを置き換える
+    async fn save_card_member(&self, member: &CardMemberModel) -> Result<(), Self::Error>;
+    async fn delete_card_member(
+        &self,
+        card_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<()>, Self::Error>;
+    /// `owner_id`を`to`に変え、元の所有者は編集者にする。所有者が`from`でなければ`None`
+    async fn transfer_card_ownership(
+        &self,
+        card_id: Uuid,
+        from: Uuid,
+        to: Uuid,
+    ) -> Result<Option<()>, Self::Error>;
+    async fn get_share_links(&self, card_id: Uuid) -> Result<Vec<ShareLinkModel>, Self::Error>;
+    async fn get_share_link(&self, id: Uuid) -> Result<Option<ShareLinkModel>, Self::Error>;
+    async fn save_share_link(&self, link: &ShareLinkModel) -> Result<(), Self::Error>;
+    /// 失効させる
+    async fn delete_share_link(&self, id: Uuid) -> Result<Option<()>, Self::Error>;
+    /// 追記のみ。消すのは`delete_audit_events_before`による保持期間切れのものだけ
+    async fn save_audit_event(&self, event: &AuditEventModel) -> Result<(), Self::Error>;
+    /// 新しい順
+    async fn get_audit_events(
+        &self,
+        query: &AuditQuery,
+    ) -> Result<Vec<AuditEventModel>, Self::Error>;
+    /// 消した件数を返す
+    async fn delete_audit_events_before(&self, before: DateTimeUtc) -> Result<u64, Self::Error>;
+    /// 版の番号の順
+    async fn get_image_revisions(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+    ) -> Result<Vec<ImageRevisionModel>, Self::Error>;
+    async fn get_image_revision(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+        revision: u32,
+    ) -> Result<Option<ImageRevisionModel>, Self::Error>;
+    /// 同じカード・種類・番号の版が既にあれば`None`
+    async fn save_image_revision(
+        &self,
+        revision: &ImageRevisionModel,
+    ) -> Result<Option<()>, Self::Error>;
+    async fn delete_image_revision(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+        revision: u32,
+    ) -> Result<Option<()>, Self::Error>;
+    /// 同じIDのアセットが既にあれば`None`
+    async fn save_asset(&self, asset: &AssetModel) -> Result<Option<()>, Self::Error>;
+    async fn get_asset(&self, id: Uuid) -> Result<Option<AssetModel>, Self::Error>;
+    async fn get_webhooks(&self) -> Result<Vec<WebhookModel>, Self::Error>;
+    async fn get_webhook(&self, channel_id: Uuid) -> Result<Option<WebhookModel>, Self::Error>;
+    /// チャンネルに登録済みのWebhookがあれば置き換える
+    async fn save_webhook(&self, webhook: &WebhookModel) -> Result<(), Self::Error>;
+    async fn delete_webhook(&self, channel_id: Uuid) -> Result<Option<()>, Self::Error>;
+    async fn delete_publish_group(
+        &self,
+        card_id: Uuid,
+        group_id: Uuid,
+    ) -> Result<Option<()>, Self::Error>;
+    /// 下書きか予約済みのカードをゴミ箱に入れる。ゴミ箱のカードは`get_card_by_id`などで見えなくなる
+    async fn trash_card(&self, card_id: Uuid, at: DateTimeUtc) -> Result<Option<()>, Self::Error>;
+    /// `since`以降にゴミ箱に入れたカードだけを戻す。戻せなければ`None`
+    async fn restore_card(
+        &self,
+        card_id: Uuid,
+        since: DateTimeUtc,
+    ) -> Result<Option<()>, Self::Error>;
+    /// 所有しているゴミ箱のカード
+    async fn get_trashed_cards(&self, user_id: Uuid) -> Result<Vec<CardModel>, Self::Error>;
+    async fn get_trashed_card_by_id(&self, card_id: Uuid)
+        -> Result<Option<CardModel>, Self::Error>;
+    /// `before`より前にゴミ箱に入れたカード
+    async fn get_cards_trashed_before(
+        &self,
+        before: DateTimeUtc,
+    ) -> Result<Vec<CardModel>, Self::Error>;
+    /// 付随する行もまとめて消す。監査ログは残す
     async fn delete_card(&self, card_id: Uuid) -> Result<Option<()>, Self::Error>;
 }
 
@@ -55,12 +212,92 @@ impl std::str::FromStr for MigrationStrategy {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CardStatus {
+    /// 作成中。配信されない
+    Draft,
+    /// `publish_date`にcronが配信する
+    Scheduled,
+    Delivering,
+    Delivered,
+    /// 一部または全部の配信先に送れなかった
+    Failed,
+    Cancelled,
+}
+
+impl CardStatus {
+    /// 配信が始まっていれば宛先から見える
+    pub fn is_published(self) -> bool {
+        matches!(self, Self::Delivering | Self::Delivered | Self::Failed)
+    }
+
+    /// 所有者が内容を編集・削除できる
+    pub fn is_editable(self) -> bool {
+        matches!(self, Self::Draft | Self::Scheduled)
+    }
+}
+
+impl std::str::FromStr for CardStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "draft" => Ok(Self::Draft),
+            "scheduled" => Ok(Self::Scheduled),
+            "delivering" => Ok(Self::Delivering),
+            "delivered" => Ok(Self::Delivered),
+            "failed" => Ok(Self::Failed),
+            "cancelled" => Ok(Self::Cancelled),
+            s => Err(format!("unknown status `{}`", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CardRole {
+    /// メンバーの管理と削除もできる。`CardModel::owner_id`は常にこのロール
+    Owner,
+    Editor,
+    /// 配信前でも閲覧だけできる
+    Viewer,
+}
+
+impl CardRole {
+    /// 内容・画像・予約を編集できる
+    pub fn can_edit(self) -> bool {
+        matches!(self, Self::Owner | Self::Editor)
+    }
+
+    /// メンバーの管理とカードの削除ができる
+    pub fn can_manage(self) -> bool {
+        matches!(self, Self::Owner)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CardMemberModel {
+    pub card_id: Uuid,
+    pub user_id: Uuid,
+    pub role: CardRole,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CardModel {
     pub id: Uuid,
+    /// 配信メッセージの差出人
     pub owner_id: Uuid,
-    pub publish_date: DateTimeUtc,
+    /// 下書きでは未定でもよい
+    pub publish_date: Option<DateTimeUtc>,
     pub message: Option<String>,
+    pub status: CardStatus,
+    /// 作成者のIANAのタイムゾーン。表示やメッセージの日時に使う
+    pub time_zone: String,
+    /// 作成時は1で、`patch_card`のたびに増える
+    pub version: u32,
+    /// ゴミ箱に入れた日時。入っていなければ`None`
+    pub deleted_at: Option<DateTimeUtc>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -69,13 +306,294 @@ pub struct PublishChannelModel {
     pub card_id: Uuid,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecipientModel {
+    pub id: Uuid,
+    pub card_id: Uuid,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublishGroupModel {
+    pub id: Uuid,
+    pub card_id: Uuid,
+}
+
+/// グループ宛てカードの配信時点でのメンバー
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeliveryLogModel {
+    pub card_id: Uuid,
+    pub group_id: Uuid,
+    pub user_id: Uuid,
+    pub delivered_at: DateTimeUtc,
+}
+
+/// チャンネルへの投稿に使うtraQのIncoming Webhook
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookModel {
+    pub channel_id: Uuid,
+    pub webhook_id: Uuid,
+    pub secret: String,
+}
+
+/// 繰り返し配信の設定。`dtstart`を起点に`rrule`を`time_zone`の壁時計で展開する
+///
+/// 配信するたびにカードの`publish_date`を次の発生日時に進める
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecurrenceModel {
+    pub card_id: Uuid,
+    pub rrule: String,
+    /// IANAのタイムゾーン名
+    pub time_zone: String,
+    pub dtstart: DateTimeUtc,
+    pub last_occurrence: Option<DateTimeUtc>,
+}
+
+/// 寄せ書きカードの参加者1人分
+///
+/// 所有者が招待した時点では`contributed_at`が`None`で、本人が書き込むと埋まる
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContributionModel {
+    pub card_id: Uuid,
+    pub user_id: Uuid,
+    pub message: Option<String>,
+    /// `/api/images`に上げた画像。カードの画像に重ねる
+    pub layer_id: Option<Uuid>,
+    pub contributed_at: Option<DateTimeUtc>,
+}
+
+/// 認証なしでカードを見られる共有リンク。トークン自体は保存せず、行を消せば失効する
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShareLinkModel {
+    pub id: Uuid,
+    pub card_id: Uuid,
+    pub created_by: Uuid,
+    pub created_at: DateTimeUtc,
+    pub expires_at: DateTimeUtc,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum AuditAction {
+    #[serde(rename = "card.create")]
+    CardCreate,
+    #[serde(rename = "card.update")]
+    CardUpdate,
+    /// ゴミ箱に入れる
+    #[serde(rename = "card.delete")]
+    CardDelete,
+    #[serde(rename = "card.restore")]
+    CardRestore,
+    /// cronによるゴミ箱からの完全な削除。`actor_id`は`None`
+    #[serde(rename = "card.purge")]
+    CardPurge,
+    #[serde(rename = "card.schedule")]
+    CardSchedule,
+    #[serde(rename = "card.unschedule")]
+    CardUnschedule,
+    #[serde(rename = "card.svg")]
+    CardSvg,
+    #[serde(rename = "card.png")]
+    CardPng,
+    /// cronによる配信。`actor_id`は`None`
+    #[serde(rename = "card.deliver")]
+    CardDeliver,
+    #[serde(rename = "card.cancel")]
+    CardCancel,
+    #[serde(rename = "card.reassign")]
+    CardReassign,
+    #[serde(rename = "image.upload")]
+    ImageUpload,
+    /// 差出人の譲渡
+    #[serde(rename = "card.transfer")]
+    CardTransfer,
+    /// メンバーの追加とロールの変更
+    #[serde(rename = "member.update")]
+    MemberUpdate,
+    #[serde(rename = "member.remove")]
+    MemberRemove,
+    #[serde(rename = "contribution.invite")]
+    ContributionInvite,
+    #[serde(rename = "contribution.remove")]
+    ContributionRemove,
+    /// 参加者本人による書き込み
+    #[serde(rename = "contribution.update")]
+    ContributionUpdate,
+    #[serde(rename = "share.create")]
+    ShareCreate,
+    #[serde(rename = "share.revoke")]
+    ShareRevoke,
+}
+
+impl AuditAction {
+    pub const ALL: [Self; 21] = [
+        Self::CardCreate,
+        Self::CardUpdate,
+        Self::CardDelete,
+        Self::CardRestore,
+        Self::CardPurge,
+        Self::CardSchedule,
+        Self::CardUnschedule,
+        Self::CardSvg,
+        Self::CardPng,
+        Self::CardDeliver,
+        Self::CardCancel,
+        Self::CardReassign,
+        Self::ImageUpload,
+        Self::CardTransfer,
+        Self::MemberUpdate,
+        Self::MemberRemove,
+        Self::ContributionInvite,
+        Self::ContributionRemove,
+        Self::ContributionUpdate,
+        Self::ShareCreate,
+        Self::ShareRevoke,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::CardCreate => "card.create",
+            Self::CardUpdate => "card.update",
+            Self::CardDelete => "card.delete",
+            Self::CardRestore => "card.restore",
+            Self::CardPurge => "card.purge",
+            Self::CardSchedule => "card.schedule",
+            Self::CardUnschedule => "card.unschedule",
+            Self::CardSvg => "card.svg",
+            Self::CardPng => "card.png",
+            Self::CardDeliver => "card.deliver",
+            Self::CardCancel => "card.cancel",
+            Self::CardReassign => "card.reassign",
+            Self::ImageUpload => "image.upload",
+            Self::CardTransfer => "card.transfer",
+            Self::MemberUpdate => "member.update",
+            Self::MemberRemove => "member.remove",
+            Self::ContributionInvite => "contribution.invite",
+            Self::ContributionRemove => "contribution.remove",
+            Self::ContributionUpdate => "contribution.update",
+            Self::ShareCreate => "share.create",
+            Self::ShareRevoke => "share.revoke",
+        }
+    }
+}
+
+impl std::str::FromStr for AuditAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|a| a.as_str() == s)
+            .ok_or_else(|| format!("unknown action `{}`", s))
+    }
+}
+
+/// カードの画像の種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageKind {
+    Svg,
+    Png,
+}
+
+impl ImageKind {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Svg => "svg",
+            Self::Png => "png",
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::Svg => "image/svg+xml",
+            Self::Png => "image/png",
+        }
+    }
+}
+
+/// 保存したカードの画像の版。内容は`ImageRepository`に版の番号ごとに置く
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageRevisionModel {
+    pub card_id: Uuid,
+    pub kind: ImageKind,
+    /// カード・種類ごとに1から増える
+    pub revision: u32,
+    pub author_id: Uuid,
+    pub created_at: DateTimeUtc,
+    pub size: u64,
+    /// 内容のSHA-256(16進)
+    pub hash: String,
+}
+
+/// `/api/images`に上げた画像を誰が上げたか。内容は`ImageRepository`にIDで置く
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetModel {
+    pub id: Uuid,
+    pub uploaded_by: Uuid,
+    pub created_at: DateTimeUtc,
+}
+
+/// 監査ログを残す日数の既定値
+pub const DEFAULT_AUDIT_RETENTION_DAYS: i64 = 365;
+
+/// ゴミ箱から元に戻せる日数の既定値
+pub const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+
+/// カードや画像への変更の記録
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEventModel {
+    pub id: Uuid,
+    pub at: DateTimeUtc,
+    /// cronなら`None`
+    pub actor_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub card_id: Option<Uuid>,
+    pub asset_id: Option<Uuid>,
+    /// 変わった項目の変更前の値(JSON)
+    pub before: Option<String>,
+    /// 変わった項目の変更後の値(JSON)
+    pub after: Option<String>,
+    pub request_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AuditQuery {
+    pub card_id: Option<Uuid>,
+    pub actor_id: Option<Uuid>,
+    pub action: Option<AuditAction>,
+    pub since: Option<DateTimeUtc>,
+    pub until: Option<DateTimeUtc>,
+    pub limit: u64,
+    pub offset: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct SaveCardParams {
     pub id: Uuid,
     pub owner_id: Uuid,
-    pub publish_date: DateTimeUtc,
+    pub publish_date: Option<DateTimeUtc>,
     pub message: Option<String>,
+    pub status: CardStatus,
+    pub time_zone: String,
     pub channels: Vec<Uuid>,
+    pub recipients: Vec<Uuid>,
+    pub groups: Vec<Uuid>,
+    pub recurrence: Option<RecurrenceModel>,
+}
+
+/// `None`の項目は変えない。`Some(None)`は消す
+#[derive(Debug, Clone, Default)]
+pub struct PatchCardParams {
+    pub id: Uuid,
+    /// 更新前の版
+    pub version: u32,
+    pub publish_date: Option<Option<DateTimeUtc>>,
+    pub message: Option<Option<String>>,
+    pub time_zone: Option<String>,
+    /// 投稿先・宛先・グループは指定すると置き換える
+    pub channels: Option<Vec<Uuid>>,
+    pub recipients: Option<Vec<Uuid>>,
+    pub groups: Option<Vec<Uuid>>,
+    pub recurrence: Option<Option<RecurrenceModel>>,
 }
 
 #[derive(Debug, Clone)]
@@ -85,7 +603,7 @@ pub struct SaveImageParams {
     pub content: Vec<u8>,
 }
 
-#[automock(type Error = String;)]
+#[automock(type Error = anyhow::Error;)]
 #[async_trait]
 pub trait ImageRepository: Interface {
     type Error;
@@ -103,4 +621,23 @@ pub trait ImageRepository: Interface {
     async fn delete_png(&self, card_id: Uuid) -> Result<(), Self::Error>;
     async fn delete_svg(&self, card_id: Uuid) -> Result<(), Self::Error>;
     async fn delete_asset(&self, id: Uuid) -> Result<(), Self::Error>;
+    async fn save_revision(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+        revision: u32,
+        content: &Bytes,
+    ) -> Result<(), Self::Error>;
+    async fn get_revision(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+        revision: u32,
+    ) -> Result<Option<Bytes>, Self::Error>;
+    async fn delete_revision(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+        revision: u32,
+    ) -> Result<(), Self::Error>;
 }
@@ -0,0 +1,38 @@
+//! IANAのタイムゾーンと壁時計の日時
+
+use chrono::offset::LocalResult;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::repository::DateTimeUtc;
+
+/// タイムゾーンを指定しなかった時に使う
+pub const DEFAULT_TIME_ZONE: &str = "Asia/Tokyo";
+
+/// `Asia/Tokyo`などの名前から引く
+pub fn parse_time_zone(name: &str) -> Result<Tz, String> {
+    name.parse()
+        .map_err(|_| format!("unknown time zone `{}`", name))
+}
+
+/// 壁時計の日時をUTCにする
+///
+/// 夏時間の終わりで2回ある時刻は早い方にし、始まりで存在しない時刻はエラーにする
+pub fn local_to_utc(local: NaiveDateTime, tz: Tz) -> Result<DateTimeUtc, String> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(at) | LocalResult::Ambiguous(at, _) => Ok(at.with_timezone(&Utc)),
+        LocalResult::None => Err(format!("{} does not exist in {}", local, tz.name())),
+    }
+}
+
+/// `2024-12-24T09:00:00+09:00`
+pub fn to_local_rfc3339(at: DateTimeUtc, tz: Tz) -> String {
+    at.with_timezone(&tz).to_rfc3339()
+}
+
+/// メッセージに載せる`2024/12/24 09:00 JST`形式
+pub fn format_local(at: DateTimeUtc, tz: Tz) -> String {
+    at.with_timezone(&tz)
+        .format("%Y/%m/%d %H:%M %Z")
+        .to_string()
+}
@@ -0,0 +1,104 @@
+use chrono::{DateTime, TimeZone, Utc};
+use domain::recurrence::{RRule, Recurrence};
+use domain::repository::RecurrenceModel;
+use uuid::Uuid;
+
+fn recurrence(rrule: &str, time_zone: &str, dtstart: &str) -> Recurrence {
+    Recurrence::try_from(&RecurrenceModel {
+        card_id: Uuid::nil(),
+        rrule: rrule.to_string(),
+        time_zone: time_zone.to_string(),
+        dtstart: dtstart.parse().unwrap(),
+        last_occurrence: None,
+    })
+    .unwrap()
+}
+
+fn take(recurrence: &Recurrence, n: usize) -> Vec<String> {
+    recurrence
+        .occurrences()
+        .take(n)
+        .map(|o| o.to_rfc3339())
+        .collect()
+}
+
+#[test]
+fn yearly_skips_missing_leap_day() {
+    let r = recurrence("FREQ=YEARLY;COUNT=2", "Asia/Tokyo", "2024-02-29T00:00:00Z");
+    assert_eq!(
+        take(&r, 5),
+        ["2024-02-29T00:00:00+00:00", "2028-02-29T00:00:00+00:00"]
+    );
+}
+
+#[test]
+fn monthly_until_date_in_local_zone() {
+    // 東京で毎月31日の朝9時
+    let r = recurrence(
+        "FREQ=MONTHLY;UNTIL=20240531",
+        "Asia/Tokyo",
+        "2024-01-31T00:00:00Z",
+    );
+    assert_eq!(
+        take(&r, 10),
+        [
+            "2024-01-31T00:00:00+00:00",
+            "2024-03-31T00:00:00+00:00",
+            "2024-05-31T00:00:00+00:00",
+        ]
+    );
+}
+
+#[test]
+fn weekly_keeps_wall_clock_across_dst() {
+    // 2024-03-10に夏時間が始まる
+    let r = recurrence(
+        "FREQ=WEEKLY;BYDAY=FR,SA",
+        "America/New_York",
+        "2024-03-01T14:00:00Z",
+    );
+    assert_eq!(
+        take(&r, 4),
+        [
+            "2024-03-01T14:00:00+00:00",
+            "2024-03-02T14:00:00+00:00",
+            "2024-03-08T14:00:00+00:00",
+            "2024-03-09T14:00:00+00:00",
+        ]
+    );
+    let next = r.next_after(Utc.with_ymd_and_hms(2024, 3, 10, 0, 0, 0).unwrap(), &[]);
+    assert_eq!(
+        next,
+        Some(
+            DateTime::parse_from_rfc3339("2024-03-15T13:00:00Z")
+                .unwrap()
+                .into()
+        )
+    );
+}
+
+#[test]
+fn next_after_skips_skipped() {
+    let r = recurrence("FREQ=YEARLY", "UTC", "2024-12-24T00:00:00Z");
+    let skipped = ["2025-12-24T00:00:00Z".parse().unwrap()];
+    let next = r.next_after("2025-01-01T00:00:00Z".parse().unwrap(), &skipped);
+    assert_eq!(next, Some("2026-12-24T00:00:00Z".parse().unwrap()));
+    assert!(r.contains("2030-12-24T00:00:00Z".parse().unwrap()));
+    assert!(!r.contains("2030-12-25T00:00:00Z".parse().unwrap()));
+}
+
+#[test]
+fn rejects_unsupported_rules() {
+    assert!("FREQ=DAILY".parse::<RRule>().is_err());
+    assert!("FREQ=YEARLY;COUNT=1;UNTIL=20250101"
+        .parse::<RRule>()
+        .is_err());
+    assert!("FREQ=MONTHLY;BYDAY=MO".parse::<RRule>().is_err());
+    assert_eq!(
+        "RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=SU,MO"
+            .parse::<RRule>()
+            .unwrap()
+            .to_string(),
+        "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,SU"
+    );
+}
@@ -0,0 +1,25 @@
+use chrono::NaiveDateTime;
+use domain::time_zone::{format_local, local_to_utc, parse_time_zone};
+
+fn local(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M").unwrap()
+}
+
+#[test]
+fn local_to_utc_handles_dst_edges() {
+    let tz = parse_time_zone("America/New_York").unwrap();
+    // 夏時間の始まりで存在しない
+    assert!(local_to_utc(local("2024-03-10T02:30"), tz).is_err());
+    // 夏時間の終わりで2回ある時刻は早い方
+    let at = local_to_utc(local("2024-11-03T01:30"), tz).unwrap();
+    assert_eq!(at.to_rfc3339(), "2024-11-03T05:30:00+00:00");
+    assert_eq!(format_local(at, tz), "2024/11/03 01:30 EDT");
+}
+
+#[test]
+fn rejects_unknown_time_zone() {
+    assert!(parse_time_zone("Asia/Tokio").is_err());
+    let tz = parse_time_zone("Asia/Tokyo").unwrap();
+    let at = local_to_utc(local("2024-12-24T09:00"), tz).unwrap();
+    assert_eq!(format_local(at, tz), "2024/12/24 09:00 JST");
+}
@@ -7,13 +7,17 @@ use traq::models::UserAccountState;
 use uuid::{uuid, Uuid};
 
 use domain::bot_client::{
-    BotClient, ChannelList, ImageData, PostMessageParams, Stamp, StampType, UploadFileParams,
-    UploadFileResp, User, UserDetail,
+    BotClient, ChannelList, DmChannel, Image, PostMessageParams, Stamp, StampType,
+    UploadFileParams, UploadFileResp, User, UserDetail, UserGroup, UserGroupMember,
 };
 use domain::cron::Cron;
+use domain::delivery::DeliverySink;
 use domain::repository::ImageRepository;
 use domain::repository::{
-    CardModel, CardRepository, DateTimeUtc, MigrationStrategy, PublishChannelModel, SaveCardParams,
+    AssetModel, AuditEventModel, AuditQuery, CardMemberModel, CardModel, CardRepository,
+    CardStatus, ContributionModel, DateTimeUtc, DeliveryLogModel, ImageKind, ImageRevisionModel,
+    MigrationStrategy, PatchCardParams, PublishChannelModel, RecurrenceModel, SaveCardParams,
+    ShareLinkModel, WebhookModel,
 };
 
 use cron::CronImpl;
@@ -29,21 +33,25 @@ impl MockBotClient {
 
 #[async_trait]
 impl BotClient for MockBotClient {
-    type Error = anyhow::Error;
+    type Error = String;
 
-    async fn get_stamp_image(&self, _stamp_id: &str) -> anyhow::Result<ImageData> {
-        Err(anyhow::anyhow!("unsupported"))
+    async fn get_stamp_image(&self, _stamp_id: &str) -> Result<Image, Self::Error> {
+        Err("unsupported".to_string())
+    }
+
+    async fn get_stamps(&self, _type: StampType) -> Result<Vec<Stamp>, Self::Error> {
+        Ok(vec![])
     }
 
-    async fn get_stamps(&self, _type: StampType) -> anyhow::Result<Vec<Stamp>> {
+    async fn get_users<'a>(&'a self, _name: Option<&'a str>) -> Result<Vec<User>, Self::Error> {
         Ok(vec![])
     }
 
-    async fn get_users<'a>(&'a self, _name: Option<&'a str>) -> anyhow::Result<Vec<User>> {
+    async fn get_users_by_ids(&self, _ids: &[Uuid]) -> Result<Vec<User>, Self::Error> {
         Ok(vec![])
     }
 
-    async fn get_user(&self, id: &str) -> anyhow::Result<UserDetail> {
+    async fn get_user(&self, id: &str) -> Result<UserDetail, Self::Error> {
         println!("get_user: {}", id);
         Ok(UserDetail {
             id: uuid!("00000000-0000-0000-0000-000000000000"),
@@ -62,20 +70,40 @@ impl BotClient for MockBotClient {
         })
     }
 
-    async fn get_user_icon(&self, _id: &str) -> anyhow::Result<ImageData> {
-        Err(anyhow::anyhow!("unsupported"))
+    async fn get_user_icon(&self, _id: &str) -> Result<Image, Self::Error> {
+        Err("unsupported".to_string())
     }
 
-    async fn get_channels(&self) -> anyhow::Result<ChannelList> {
-        Err(anyhow::anyhow!("unsupported"))
+    async fn get_channels(&self) -> Result<ChannelList, Self::Error> {
+        Err("unsupported".to_string())
+    }
+
+    async fn get_user_dm_channel(&self, user_id: &str) -> Result<DmChannel, Self::Error> {
+        println!("get_user_dm_channel: {}", user_id);
+        Ok(DmChannel {
+            id: uuid!("00000000-0000-0000-0000-000000000000"),
+            user_id: user_id.parse().map_err(|e| format!("{}", e))?,
+        })
     }
 
-    async fn post_message(&self, params: &PostMessageParams) -> anyhow::Result<()> {
+    async fn get_user_group(&self, group_id: &str) -> Result<UserGroup, Self::Error> {
+        println!("get_user_group: {}", group_id);
+        Ok(UserGroup {
+            id: group_id.parse().map_err(|e| format!("{}", e))?,
+            members: vec![UserGroupMember {
+                id: uuid!("00000000-0000-0000-0000-000000000000"),
+                role: String::new(),
+            }],
+            ..Default::default()
+        })
+    }
+
+    async fn post_message(&self, params: &PostMessageParams) -> Result<(), Self::Error> {
         println!("post_message: {:?}", params);
         Ok(())
     }
 
-    async fn uplodad_file(&self, params: &UploadFileParams) -> anyhow::Result<UploadFileResp> {
+    async fn uplodad_file(&self, params: &UploadFileParams) -> Result<UploadFileResp, Self::Error> {
         println!("upload_file: {:?}", params);
         Ok(UploadFileResp {
             id: uuid!("00000000-0000-0000-0000-000000000000"),
@@ -83,6 +111,20 @@ impl BotClient for MockBotClient {
     }
 }
 
+#[async_trait]
+impl DeliverySink for MockBotClient {
+    type Error = String;
+
+    async fn post_message(&self, params: &PostMessageParams) -> Result<(), Self::Error> {
+        println!("deliver: {:?}", params);
+        Ok(())
+    }
+
+    async fn upload_channel(&self, channel_id: Uuid) -> Result<Uuid, Self::Error> {
+        Ok(channel_id)
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default)]
 struct MockCardRepository;
 
@@ -99,12 +141,11 @@ impl CardRepository for MockCardRepository {
     async fn migrate(&self, _strategy: MigrationStrategy) -> Result<(), Self::Error> {
         Err(anyhow::anyhow!("unsupported"))
     }
-    async fn get_card_with_channels_by_date(
+    async fn get_due_cards_with_channels(
         &self,
-        start: DateTimeUtc,
-        end: DateTimeUtc,
+        now: DateTimeUtc,
     ) -> Result<Vec<(CardModel, Vec<PublishChannelModel>)>, Self::Error> {
-        println!("get_card_with_channels_by_date: {:?} - {:?}", start, end);
+        println!("get_due_cards_with_channels: {:?}", now);
         let channels = vec![PublishChannelModel {
             id: uuid!("00000000-0000-0000-0000-000000000000"),
             card_id: uuid!("00000000-0000-0000-0000-000000000000"),
@@ -112,17 +153,30 @@ impl CardRepository for MockCardRepository {
         let card = CardModel {
             id: uuid!("00000000-0000-0000-0000-000000000000"),
             owner_id: uuid!("00000000-0000-0000-0000-000000000000"),
-            publish_date: start,
+            publish_date: Some(now),
             message: None,
+            status: CardStatus::Scheduled,
+            time_zone: "Asia/Tokyo".to_string(),
+            version: 1,
+            deleted_at: None,
         };
         Ok(vec![(card, channels)])
     }
     async fn save_card(&self, _params: &SaveCardParams) -> Result<(), Self::Error> {
         Err(anyhow::anyhow!("unsupported"))
     }
-    async fn update_card(&self, _params: &SaveCardParams) -> Result<Option<()>, Self::Error> {
+    async fn patch_card(&self, _params: &PatchCardParams) -> Result<Option<()>, Self::Error> {
         Err(anyhow::anyhow!("unsupported"))
     }
+    async fn update_card_status(
+        &self,
+        card_id: Uuid,
+        from: &[CardStatus],
+        to: CardStatus,
+    ) -> Result<Option<()>, Self::Error> {
+        println!("update_card_status: {:?} {:?} -> {:?}", card_id, from, to);
+        Ok(Some(()))
+    }
     async fn get_all_cards(&self) -> Result<Vec<CardModel>, Self::Error> {
         Err(anyhow::anyhow!("unsupported"))
     }
@@ -135,6 +189,24 @@ impl CardRepository for MockCardRepository {
     async fn get_publish_channels_by_id(&self, _card_id: Uuid) -> Result<Vec<Uuid>, Self::Error> {
         Err(anyhow::anyhow!("unsupported"))
     }
+    async fn get_recipients_by_id(&self, card_id: Uuid) -> Result<Vec<Uuid>, Self::Error> {
+        println!("get_recipients_by_id: {:?}", card_id);
+        Ok(vec![uuid!("00000000-0000-0000-0000-000000000000")])
+    }
+    async fn get_publish_groups_by_id(&self, card_id: Uuid) -> Result<Vec<Uuid>, Self::Error> {
+        println!("get_publish_groups_by_id: {:?}", card_id);
+        Ok(vec![uuid!("00000000-0000-0000-0000-000000000000")])
+    }
+    async fn save_delivery_logs(&self, logs: &[DeliveryLogModel]) -> Result<(), Self::Error> {
+        println!("save_delivery_logs: {:?}", logs);
+        Ok(())
+    }
+    async fn get_delivery_logs_by_id(
+        &self,
+        _card_id: Uuid,
+    ) -> Result<Vec<DeliveryLogModel>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
     async fn delete_publish_channel(
         &self,
         _card_id: Uuid,
@@ -142,6 +214,224 @@ impl CardRepository for MockCardRepository {
     ) -> Result<Option<()>, Self::Error> {
         Err(anyhow::anyhow!("unsupported"))
     }
+    async fn delete_recipient(
+        &self,
+        _card_id: Uuid,
+        _user_id: Uuid,
+    ) -> Result<Option<()>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn reschedule_card(
+        &self,
+        card_id: Uuid,
+        from: &[CardStatus],
+        publish_date: DateTimeUtc,
+    ) -> Result<Option<()>, Self::Error> {
+        println!(
+            "reschedule_card: {:?} {:?} -> {:?}",
+            card_id, from, publish_date
+        );
+        Ok(Some(()))
+    }
+    async fn reschedule_stuck_card(
+        &self,
+        card_id: Uuid,
+        stuck_before: DateTimeUtc,
+        publish_date: DateTimeUtc,
+    ) -> Result<Option<()>, Self::Error> {
+        println!(
+            "reschedule_stuck_card: {:?} before {:?} -> {:?}",
+            card_id, stuck_before, publish_date
+        );
+        Ok(Some(()))
+    }
+    async fn get_recurrence(&self, _card_id: Uuid) -> Result<Option<RecurrenceModel>, Self::Error> {
+        Ok(None)
+    }
+    async fn update_last_occurrence(
+        &self,
+        _card_id: Uuid,
+        _at: DateTimeUtc,
+    ) -> Result<Option<()>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn delete_recurrence(&self, _card_id: Uuid) -> Result<Option<()>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn get_skipped_occurrences(
+        &self,
+        _card_id: Uuid,
+    ) -> Result<Vec<DateTimeUtc>, Self::Error> {
+        Ok(vec![])
+    }
+    async fn save_skipped_occurrence(
+        &self,
+        _card_id: Uuid,
+        _at: DateTimeUtc,
+    ) -> Result<(), Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn delete_skipped_occurrence(
+        &self,
+        _card_id: Uuid,
+        _at: DateTimeUtc,
+    ) -> Result<Option<()>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn get_contributions(
+        &self,
+        _card_id: Uuid,
+    ) -> Result<Vec<ContributionModel>, Self::Error> {
+        Ok(vec![])
+    }
+    async fn get_contributions_by_user(
+        &self,
+        _user_id: Uuid,
+    ) -> Result<Vec<ContributionModel>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn save_contribution(
+        &self,
+        _contribution: &ContributionModel,
+    ) -> Result<(), Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn delete_contribution(
+        &self,
+        _card_id: Uuid,
+        _user_id: Uuid,
+    ) -> Result<Option<()>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn get_card_members(&self, _card_id: Uuid) -> Result<Vec<CardMemberModel>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn save_card_member(&self, _member: &CardMemberModel) -> Result<(), Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn delete_card_member(
+        &self,
+        _card_id: Uuid,
+        _user_id: Uuid,
+    ) -> Result<Option<()>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn transfer_card_ownership(
+        &self,
+        _card_id: Uuid,
+        _from: Uuid,
+        _to: Uuid,
+    ) -> Result<Option<()>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn get_share_links(&self, _card_id: Uuid) -> Result<Vec<ShareLinkModel>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn get_share_link(&self, _id: Uuid) -> Result<Option<ShareLinkModel>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn save_share_link(&self, _link: &ShareLinkModel) -> Result<(), Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn delete_share_link(&self, _id: Uuid) -> Result<Option<()>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn save_audit_event(&self, _event: &AuditEventModel) -> Result<(), Self::Error> {
+        Ok(())
+    }
+    async fn get_audit_events(
+        &self,
+        _query: &AuditQuery,
+    ) -> Result<Vec<AuditEventModel>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn delete_audit_events_before(&self, _before: DateTimeUtc) -> Result<u64, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn get_image_revisions(
+        &self,
+        _card_id: Uuid,
+        _kind: ImageKind,
+    ) -> Result<Vec<ImageRevisionModel>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn get_image_revision(
+        &self,
+        _card_id: Uuid,
+        _kind: ImageKind,
+        _revision: u32,
+    ) -> Result<Option<ImageRevisionModel>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn save_image_revision(
+        &self,
+        _revision: &ImageRevisionModel,
+    ) -> Result<Option<()>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn delete_image_revision(
+        &self,
+        _card_id: Uuid,
+        _kind: ImageKind,
+        _revision: u32,
+    ) -> Result<Option<()>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn save_asset(&self, _asset: &AssetModel) -> Result<Option<()>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn get_asset(&self, _id: Uuid) -> Result<Option<AssetModel>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn get_webhooks(&self) -> Result<Vec<WebhookModel>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn get_webhook(&self, _channel_id: Uuid) -> Result<Option<WebhookModel>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn save_webhook(&self, _webhook: &WebhookModel) -> Result<(), Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn delete_webhook(&self, _channel_id: Uuid) -> Result<Option<()>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn delete_publish_group(
+        &self,
+        _card_id: Uuid,
+        _group_id: Uuid,
+    ) -> Result<Option<()>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn trash_card(
+        &self,
+        _card_id: Uuid,
+        _at: DateTimeUtc,
+    ) -> Result<Option<()>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn restore_card(
+        &self,
+        _card_id: Uuid,
+        _since: DateTimeUtc,
+    ) -> Result<Option<()>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn get_trashed_cards(&self, _user_id: Uuid) -> Result<Vec<CardModel>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn get_trashed_card_by_id(
+        &self,
+        _card_id: Uuid,
+    ) -> Result<Option<CardModel>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn get_cards_trashed_before(
+        &self,
+        before: DateTimeUtc,
+    ) -> Result<Vec<CardModel>, Self::Error> {
+        println!("get_cards_trashed_before: {:?}", before);
+        Ok(vec![])
+    }
     async fn delete_card(&self, _card_id: Uuid) -> Result<Option<()>, Self::Error> {
         Err(anyhow::anyhow!("unsupported"))
     }
@@ -193,6 +483,31 @@ impl ImageRepository for MockImageRepository {
     async fn delete_asset(&self, _id: Uuid) -> Result<(), Self::Error> {
         Err(anyhow::anyhow!("unsupported"))
     }
+    async fn save_revision(
+        &self,
+        _card_id: Uuid,
+        _kind: ImageKind,
+        _revision: u32,
+        _content: &Bytes,
+    ) -> Result<(), Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn get_revision(
+        &self,
+        _card_id: Uuid,
+        _kind: ImageKind,
+        _revision: u32,
+    ) -> Result<Option<Bytes>, Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
+    async fn delete_revision(
+        &self,
+        _card_id: Uuid,
+        _kind: ImageKind,
+        _revision: u32,
+    ) -> Result<(), Self::Error> {
+        Err(anyhow::anyhow!("unsupported"))
+    }
 }
 
 #[tokio::main]
@@ -204,6 +519,7 @@ async fn main() -> anyhow::Result<()> {
         Arc::new(card_repository),
         Arc::new(image_repository),
         Arc::new(client),
+        Arc::new(client),
     ));
     tokio::spawn(async move { cron.run().await });
     sleep(Duration::from_secs(100)).await;
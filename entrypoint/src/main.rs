@@ -1,20 +1,26 @@
 use std::sync::Arc;
+use std::time::Duration;
 
-use crate::wrappers::CardRepositoryWrapper;
 use anyhow::{Context, Result};
-use bot_client::BotClientImpl;
+use bot_client::{BotClientConfig, BotClientImpl, WebhookSink};
 use cron::CronImpl;
 use domain::repository::{CardRepository, MigrationStrategy};
+use entrypoint::wrappers::{self, CacheConfig, CachingBotClient, CardRepositoryWrapper};
 use once_cell::sync::Lazy;
 use repository::card::{CardRepositoryConfig, CardRepositoryImpl};
 use repository::image::{ImageRepositoryConfig, ImageRepositoryImpl};
 use rocket::{fairing::AdHoc, http::Method, routes};
-use traq_bot_http::RequestParser;
+use traq_bot_http::{Event, RequestParser};
 
 use domain::cron::Cron;
+use handler::audit::AuditConfig;
+use handler::auth::AdminConfig;
+use handler::bot::EventListeners;
+use handler::catalog::Catalog;
 use handler::cors::{options, CorsConfig};
-
-mod wrappers;
+use handler::revisions::RevisionConfig;
+use handler::share::ShareConfig;
+use handler::trash::TrashConfig;
 
 static CORS_CONFIG: Lazy<CorsConfig> =
     Lazy::new(|| CorsConfig::load_env().expect("failed to load CORS config"));
@@ -23,7 +29,7 @@ static CORS_CONFIG: Lazy<CorsConfig> =
 async fn main() -> Result<()> {
     use std::env::var;
 
-    use handler::{BC, CR, IR};
+    use handler::{BC, CR, CRON, IR};
 
     let verification_token =
         var("VERIFICATION_TOKEN").context("env var VERIFICATION_TOKEN is unset")?;
@@ -33,7 +39,26 @@ async fn main() -> Result<()> {
         .and_then(|c| c.parse::<bool>().ok())
         .unwrap_or(true);
     let parser = RequestParser::new(&verification_token);
-    let client = BotClientImpl::new(access_token);
+    let share_config = ShareConfig::load_env()
+        .map_err(|e| anyhow::anyhow!(e))
+        .context("failed to load share link config")?;
+    let admin_config = AdminConfig::load_env()
+        .map_err(|e| anyhow::anyhow!(e))
+        .context("failed to load admin config")?;
+    let revision_config = RevisionConfig::load_env()
+        .map_err(|e| anyhow::anyhow!(e))
+        .context("failed to load image revision config")?;
+    let trash_config = TrashConfig::load_env()
+        .map_err(|e| anyhow::anyhow!(e))
+        .context("failed to load trash config")?;
+    let audit_config = AuditConfig::load_env()
+        .map_err(|e| anyhow::anyhow!(e))
+        .context("failed to load audit config")?;
+    let bot_client_config = BotClientConfig::load_env()
+        .map_err(|e| anyhow::anyhow!(e))
+        .context("failed to load bot client config")?;
+    let traq_origin = bot_client_config.origin.clone();
+    let client = BotClientImpl::with_config(access_token, bot_client_config);
     let card_repository = {
         let load = |s: &str| CardRepositoryConfig::load_env_with_prefix(s);
         let config = load("")
@@ -56,13 +81,77 @@ async fn main() -> Result<()> {
     let card_repository = Arc::new(card_repository);
     let image_repository = wrappers::ImageRepositoryWrapper(image_repository);
     let image_repository = Arc::new(image_repository);
+    let upload_channel = var("WEBHOOK_UPLOAD_CHANNEL_ID")
+        .ok()
+        .filter(|c| !c.is_empty())
+        .map(|c| c.parse())
+        .transpose()
+        .context("failed to parse WEBHOOK_UPLOAD_CHANNEL_ID")?;
+    let sink =
+        WebhookSink::new(card_repository.clone(), client.clone()).upload_channel(upload_channel);
     let cron = CronImpl::new(
         card_repository.clone(),
         image_repository.clone(),
         Arc::new(client.clone()),
-    );
+        Arc::new(sink),
+    )
+    .traq_origin(&traq_origin)
+    .trash_retention(trash_config.retention)
+    .audit_retention(audit_config.retention);
     let cron = Arc::new(cron);
-    let client: BC = wrappers::BotClientWrapper(client).into();
+    let client = CachingBotClient::new(client, CacheConfig::default());
+    let catalog = Catalog::default();
+    let listeners = EventListeners::default().listen({
+        let (client, catalog) = (client.clone(), catalog.clone());
+        move |event| match event {
+            Event::StampCreated(_) => {
+                client.invalidate_stamps();
+                let (client, catalog) = (client.clone(), catalog.clone());
+                tokio::spawn(async move {
+                    if let Err(e) = catalog.refresh_stamps(&client).await {
+                        eprintln!("failed to refresh stamp catalog: {:?}", e);
+                    }
+                });
+            }
+            Event::UserCreated(_) => client.invalidate_users(),
+            Event::ChannelCreated(_) | Event::ChannelTopicChanged(_) => {
+                client.invalidate_channels();
+                let (client, catalog) = (client.clone(), catalog.clone());
+                tokio::spawn(async move {
+                    if let Err(e) = catalog.refresh_channels(&client).await {
+                        eprintln!("failed to refresh channel catalog: {:?}", e);
+                    }
+                });
+            }
+            _ => (),
+        }
+    });
+    tokio::spawn({
+        let client = client.clone();
+        async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(10 * 60));
+            loop {
+                interval.tick().await;
+                for stats in client.stats() {
+                    println!("bot client cache: {}", stats);
+                }
+            }
+        }
+    });
+    tokio::spawn({
+        let (client, catalog) = (client.clone(), catalog.clone());
+        async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(5 * 60));
+            loop {
+                interval.tick().await;
+                if let Err(e) = catalog.refresh(&client).await {
+                    eprintln!("failed to refresh catalog: {:?}", e);
+                }
+            }
+        }
+    });
+    let client: BC = client.into();
+    let cron_state: CRON = cron.clone().into();
     tokio::spawn(async move { cron.run().await });
     let migration_strategy = var("MIGRATION")
         .ok()
@@ -73,20 +162,26 @@ async fn main() -> Result<()> {
         .await
         .context("failed white migration")?;
     let card_repository: CR = CR(card_repository);
-    rocket::build()
-        .mount("/api", routes![handler::ping])
-        .mount("/api/cards", handler::cards::routes())
-        .mount("/api/images", handler::images::routes())
-        .mount("/bot", routes![handler::bot::bot_event])
-        .mount("/api/stamps", handler::traq_api::stamps::routes())
-        .mount("/api/users", handler::traq_api::users::routes())
-        .mount("/api/channels", handler::traq_api::channels::routes())
+    let rocket = handler::mounts()
+        .into_iter()
+        .fold(rocket::build(), |rocket, (base, routes)| {
+            rocket.mount(base, routes)
+        });
+    rocket
         .mount("/", routes![options])
         .manage(parser)
+        .manage(listeners)
         .manage(client)
+        .manage(catalog)
         .manage(handler::auth::AuthUserConfig(check_auth))
+        .manage(share_config)
+        .manage(admin_config)
+        .manage(revision_config)
+        .manage(trash_config)
+        .manage(cron_state)
         .manage(card_repository)
         .manage(IR(image_repository))
+        .attach(handler::audit::request_id_fairing())
         .attach(AdHoc::on_response("CORS wrapper", |req, res| {
             Box::pin(async move {
                 use rocket::http::hyper::header::ORIGIN;
@@ -100,6 +195,7 @@ async fn main() -> Result<()> {
                 };
                 res.set_header(origin_header);
                 res.set_header(CORS_CONFIG.render_credentials());
+                res.set_header(CORS_CONFIG.render_expose_headers());
                 if req.method() != Method::Options {
                     println!("CORS wrapper: method is not OPTION");
                     return;
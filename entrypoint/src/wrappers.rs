@@ -1,14 +1,23 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
 use async_trait::async_trait;
 use bytes::Bytes;
 use uuid::Uuid;
 
 use domain::bot_client::{
-    BotClient, ChannelList, ImageData, PostMessageParams, Stamp, StampType, UploadFileParams,
-    UploadFileResp, User, UserDetail,
+    BotClient, BotClientError, ChannelList, DmChannel, ErrorKind, Image, PostMessageParams, Stamp,
+    StampType, UploadFileParams, UploadFileResp, User, UserDetail, UserGroup,
 };
 use domain::repository::{
-    CardModel, CardRepository, DateTimeUtc, ImageRepository, MigrationStrategy,
-    PublishChannelModel, SaveCardParams,
+    AssetModel, AuditEventModel, AuditQuery, CardMemberModel, CardModel, CardRepository,
+    CardStatus, ContributionModel, DateTimeUtc, DeliveryLogModel, ImageKind, ImageRepository,
+    ImageRevisionModel, MigrationStrategy, PatchCardParams, PublishChannelModel, RecurrenceModel,
+    SaveCardParams, ShareLinkModel, WebhookModel,
 };
 
 pub struct BotClientWrapper<T: BotClient>(pub T);
@@ -23,21 +32,30 @@ where
     async fn get_stamps(&self, stamp_type: StampType) -> anyhow::Result<Vec<Stamp>> {
         Ok(self.0.get_stamps(stamp_type).await?)
     }
-    async fn get_stamp_image(&self, stamp_id: &str) -> anyhow::Result<ImageData> {
+    async fn get_stamp_image(&self, stamp_id: &str) -> anyhow::Result<Image> {
         Ok(self.0.get_stamp_image(stamp_id).await?)
     }
     async fn get_users<'a>(&'a self, name: Option<&'a str>) -> anyhow::Result<Vec<User>> {
         Ok(self.0.get_users(name).await?)
     }
+    async fn get_users_by_ids(&self, ids: &[Uuid]) -> anyhow::Result<Vec<User>> {
+        Ok(self.0.get_users_by_ids(ids).await?)
+    }
     async fn get_user(&self, user_id: &str) -> anyhow::Result<UserDetail> {
         Ok(self.0.get_user(user_id).await?)
     }
-    async fn get_user_icon(&self, user_id: &str) -> anyhow::Result<ImageData> {
+    async fn get_user_icon(&self, user_id: &str) -> anyhow::Result<Image> {
         Ok(self.0.get_user_icon(user_id).await?)
     }
     async fn get_channels(&self) -> anyhow::Result<ChannelList> {
         Ok(self.0.get_channels().await?)
     }
+    async fn get_user_dm_channel(&self, user_id: &str) -> anyhow::Result<DmChannel> {
+        Ok(self.0.get_user_dm_channel(user_id).await?)
+    }
+    async fn get_user_group(&self, group_id: &str) -> anyhow::Result<UserGroup> {
+        Ok(self.0.get_user_group(group_id).await?)
+    }
     async fn post_message(&self, params: &PostMessageParams) -> Result<(), Self::Error> {
         Ok(self.0.post_message(params).await?)
     }
@@ -46,6 +64,354 @@ where
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    pub stamps_ttl: Duration,
+    pub stamp_image_ttl: Duration,
+    pub users_ttl: Duration,
+    pub user_ttl: Duration,
+    pub user_icon_ttl: Duration,
+    pub channels_ttl: Duration,
+    pub dm_channel_ttl: Duration,
+    /// 失敗したリクエストを覚えておく時間
+    pub negative_ttl: Duration,
+    /// メソッドごとのエントリ数の上限
+    pub capacity: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        let minutes = |m: u64| Duration::from_secs(m * 60);
+        Self {
+            stamps_ttl: minutes(10),
+            stamp_image_ttl: minutes(60),
+            users_ttl: minutes(5),
+            user_ttl: minutes(5),
+            user_icon_ttl: minutes(10),
+            channels_ttl: minutes(5),
+            dm_channel_ttl: minutes(24 * 60),
+            negative_ttl: Duration::from_secs(30),
+            capacity: 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub name: &'static str,
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+impl CacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+impl std::fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: hits={} misses={} entries={} hit_rate={:.3}",
+            self.name,
+            self.hits,
+            self.misses,
+            self.entries,
+            self.hit_rate()
+        )
+    }
+}
+
+struct CacheEntry<V, E> {
+    inserted_at: Instant,
+    expires_at: Instant,
+    /// `Err`はnegative cache。NotFoundのみ覚える
+    value: Result<V, Arc<E>>,
+}
+
+struct TtlCache<K, V, E> {
+    name: &'static str,
+    ttl: Duration,
+    negative_ttl: Duration,
+    capacity: usize,
+    entries: Mutex<HashMap<K, CacheEntry<V, E>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<K: Clone + Eq + Hash, V: Clone, E: BotClientError> TtlCache<K, V, E> {
+    fn new(name: &'static str, ttl: Duration, config: &CacheConfig) -> Self {
+        Self {
+            name,
+            ttl,
+            negative_ttl: config.negative_ttl,
+            capacity: config.capacity,
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn lookup(&self, key: &K) -> Option<Result<V, Arc<E>>> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.get(key)?;
+        (entry.expires_at > Instant::now()).then(|| entry.value.clone())
+    }
+
+    fn store(&self, key: K, value: Result<V, Arc<E>>) {
+        let now = Instant::now();
+        let ttl = if value.is_ok() {
+            self.ttl
+        } else {
+            self.negative_ttl
+        };
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.capacity {
+            entries.retain(|_, e| e.expires_at > now);
+        }
+        while entries.len() >= self.capacity {
+            let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, e)| e.inserted_at)
+                .map(|(k, _)| k.clone())
+            else {
+                break;
+            };
+            entries.remove(&oldest);
+        }
+        entries.insert(
+            key,
+            CacheEntry {
+                inserted_at: now,
+                expires_at: now + ttl,
+                value,
+            },
+        );
+    }
+
+    /// 存在しないものだけnegative cacheする。レート制限やタイムアウトなどの一時的な失敗は覚えない
+    async fn get_or_fetch<F>(&self, key: K, fetch: F) -> Result<V, Arc<E>>
+    where
+        F: Future<Output = Result<V, E>>,
+    {
+        if let Some(value) = self.lookup(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return value;
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        match fetch.await {
+            Ok(value) => {
+                self.store(key, Ok(value.clone()));
+                Ok(value)
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let e = Arc::new(e);
+                self.store(key, Err(e.clone()));
+                Err(e)
+            }
+            Err(e) => Err(Arc::new(e)),
+        }
+    }
+
+    fn invalidate(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    fn stats(&self) -> CacheStats {
+        CacheStats {
+            name: self.name,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.lock().unwrap().len(),
+        }
+    }
+}
+
+struct Caches<E> {
+    stamps: TtlCache<StampType, Vec<Stamp>, E>,
+    stamp_image: TtlCache<String, Image, E>,
+    users: TtlCache<Option<String>, Vec<User>, E>,
+    user: TtlCache<String, UserDetail, E>,
+    user_icon: TtlCache<String, Image, E>,
+    channels: TtlCache<(), ChannelList, E>,
+    dm_channel: TtlCache<String, DmChannel, E>,
+}
+
+/// traQ APIへのGETリクエストの結果をメモリ上にキャッシュする
+pub struct CachingBotClient<T: BotClient> {
+    inner: Arc<T>,
+    caches: Arc<Caches<T::Error>>,
+}
+
+impl<T: BotClient> Clone for CachingBotClient<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            caches: self.caches.clone(),
+        }
+    }
+}
+
+impl<T: BotClient> CachingBotClient<T>
+where
+    T::Error: BotClientError,
+{
+    pub fn new(inner: T, config: CacheConfig) -> Self {
+        let c = &config;
+        let caches = Caches {
+            stamps: TtlCache::new("stamps", c.stamps_ttl, c),
+            stamp_image: TtlCache::new("stamp_image", c.stamp_image_ttl, c),
+            users: TtlCache::new("users", c.users_ttl, c),
+            user: TtlCache::new("user", c.user_ttl, c),
+            user_icon: TtlCache::new("user_icon", c.user_icon_ttl, c),
+            channels: TtlCache::new("channels", c.channels_ttl, c),
+            dm_channel: TtlCache::new("dm_channel", c.dm_channel_ttl, c),
+        };
+        Self {
+            inner: Arc::new(inner),
+            caches: Arc::new(caches),
+        }
+    }
+
+    pub fn invalidate_stamps(&self) {
+        self.caches.stamps.invalidate();
+        self.caches.stamp_image.invalidate();
+    }
+
+    pub fn invalidate_users(&self) {
+        self.caches.users.invalidate();
+        self.caches.user.invalidate();
+        self.caches.user_icon.invalidate();
+    }
+
+    pub fn invalidate_channels(&self) {
+        self.caches.channels.invalidate();
+        self.caches.dm_channel.invalidate();
+    }
+
+    pub fn stats(&self) -> Vec<CacheStats> {
+        let c = &self.caches;
+        vec![
+            c.stamps.stats(),
+            c.stamp_image.stats(),
+            c.users.stats(),
+            c.user.stats(),
+            c.user_icon.stats(),
+            c.channels.stats(),
+            c.dm_channel.stats(),
+        ]
+    }
+}
+
+/// 元のエラーは`Arc`に包んだまま`anyhow::Error`にする
+fn shared<E>(e: Arc<E>) -> anyhow::Error
+where
+    E: std::fmt::Debug + std::fmt::Display + Send + Sync + 'static,
+{
+    anyhow::Error::msg(e)
+}
+
+#[async_trait]
+impl<T: BotClient> BotClient for CachingBotClient<T>
+where
+    T::Error: BotClientError + std::fmt::Debug + std::fmt::Display + Send + Sync + 'static,
+{
+    type Error = anyhow::Error;
+
+    async fn get_stamps(&self, stamp_type: StampType) -> anyhow::Result<Vec<Stamp>> {
+        let fetch = self.inner.get_stamps(stamp_type);
+        self.caches
+            .stamps
+            .get_or_fetch(stamp_type, fetch)
+            .await
+            .map_err(shared)
+    }
+    async fn get_stamp_image(&self, stamp_id: &str) -> anyhow::Result<Image> {
+        let fetch = self.inner.get_stamp_image(stamp_id);
+        let key = stamp_id.to_string();
+        self.caches
+            .stamp_image
+            .get_or_fetch(key, fetch)
+            .await
+            .map_err(shared)
+    }
+    async fn get_users<'a>(&'a self, name: Option<&'a str>) -> anyhow::Result<Vec<User>> {
+        let fetch = self.inner.get_users(name);
+        let key = name.map(|n| n.to_string());
+        self.caches
+            .users
+            .get_or_fetch(key, fetch)
+            .await
+            .map_err(shared)
+    }
+    async fn get_users_by_ids(&self, ids: &[Uuid]) -> anyhow::Result<Vec<User>> {
+        // キャッシュされた全ユーザー一覧から引く
+        let users = self.get_users(None).await?;
+        Ok(users.into_iter().filter(|u| ids.contains(&u.id)).collect())
+    }
+    async fn get_user(&self, user_id: &str) -> anyhow::Result<UserDetail> {
+        let fetch = self.inner.get_user(user_id);
+        let key = user_id.to_string();
+        self.caches
+            .user
+            .get_or_fetch(key, fetch)
+            .await
+            .map_err(shared)
+    }
+    async fn get_user_icon(&self, user_id: &str) -> anyhow::Result<Image> {
+        let fetch = self.inner.get_user_icon(user_id);
+        let key = user_id.to_string();
+        self.caches
+            .user_icon
+            .get_or_fetch(key, fetch)
+            .await
+            .map_err(shared)
+    }
+    async fn get_channels(&self) -> anyhow::Result<ChannelList> {
+        let fetch = self.inner.get_channels();
+        self.caches
+            .channels
+            .get_or_fetch((), fetch)
+            .await
+            .map_err(shared)
+    }
+    async fn get_user_dm_channel(&self, user_id: &str) -> anyhow::Result<DmChannel> {
+        let fetch = self.inner.get_user_dm_channel(user_id);
+        let key = user_id.to_string();
+        self.caches
+            .dm_channel
+            .get_or_fetch(key, fetch)
+            .await
+            .map_err(shared)
+    }
+    async fn get_user_group(&self, group_id: &str) -> anyhow::Result<UserGroup> {
+        // 配信時点のメンバーを使うためキャッシュしない
+        self.inner
+            .get_user_group(group_id)
+            .await
+            .map_err(anyhow::Error::msg)
+    }
+    async fn post_message(&self, params: &PostMessageParams) -> Result<(), Self::Error> {
+        self.inner
+            .post_message(params)
+            .await
+            .map_err(anyhow::Error::msg)
+    }
+    async fn uplodad_file(&self, params: &UploadFileParams) -> Result<UploadFileResp, Self::Error> {
+        self.inner
+            .uplodad_file(params)
+            .await
+            .map_err(anyhow::Error::msg)
+    }
+}
+
 pub struct CardRepositoryWrapper<T: CardRepository>(pub T);
 
 #[async_trait]
@@ -61,8 +427,16 @@ where
     async fn save_card(&self, params: &SaveCardParams) -> Result<(), Self::Error> {
         Ok(self.0.save_card(params).await?)
     }
-    async fn update_card(&self, params: &SaveCardParams) -> Result<Option<()>, Self::Error> {
-        Ok(self.0.update_card(params).await?)
+    async fn patch_card(&self, params: &PatchCardParams) -> Result<Option<()>, Self::Error> {
+        Ok(self.0.patch_card(params).await?)
+    }
+    async fn update_card_status(
+        &self,
+        card_id: Uuid,
+        from: &[CardStatus],
+        to: CardStatus,
+    ) -> Result<Option<()>, Self::Error> {
+        Ok(self.0.update_card_status(card_id, from, to).await?)
     }
     async fn get_all_cards(&self) -> Result<Vec<CardModel>, Self::Error> {
         Ok(self.0.get_all_cards().await?)
@@ -76,6 +450,21 @@ where
     async fn get_publish_channels_by_id(&self, card_id: Uuid) -> Result<Vec<Uuid>, Self::Error> {
         Ok(self.0.get_publish_channels_by_id(card_id).await?)
     }
+    async fn get_recipients_by_id(&self, card_id: Uuid) -> Result<Vec<Uuid>, Self::Error> {
+        Ok(self.0.get_recipients_by_id(card_id).await?)
+    }
+    async fn get_publish_groups_by_id(&self, card_id: Uuid) -> Result<Vec<Uuid>, Self::Error> {
+        Ok(self.0.get_publish_groups_by_id(card_id).await?)
+    }
+    async fn save_delivery_logs(&self, logs: &[DeliveryLogModel]) -> Result<(), Self::Error> {
+        Ok(self.0.save_delivery_logs(logs).await?)
+    }
+    async fn get_delivery_logs_by_id(
+        &self,
+        card_id: Uuid,
+    ) -> Result<Vec<DeliveryLogModel>, Self::Error> {
+        Ok(self.0.get_delivery_logs_by_id(card_id).await?)
+    }
     async fn delete_publish_channel(
         &self,
         card_id: Uuid,
@@ -83,15 +472,222 @@ where
     ) -> Result<Option<()>, Self::Error> {
         Ok(self.0.delete_publish_channel(card_id, channel_id).await?)
     }
+    async fn delete_recipient(
+        &self,
+        card_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<()>, Self::Error> {
+        Ok(self.0.delete_recipient(card_id, user_id).await?)
+    }
+    async fn reschedule_card(
+        &self,
+        card_id: Uuid,
+        from: &[CardStatus],
+        publish_date: DateTimeUtc,
+    ) -> Result<Option<()>, Self::Error> {
+        Ok(self.0.reschedule_card(card_id, from, publish_date).await?)
+    }
+    async fn reschedule_stuck_card(
+        &self,
+        card_id: Uuid,
+        stuck_before: DateTimeUtc,
+        publish_date: DateTimeUtc,
+    ) -> Result<Option<()>, Self::Error> {
+        Ok(self
+            .0
+            .reschedule_stuck_card(card_id, stuck_before, publish_date)
+            .await?)
+    }
+    async fn get_recurrence(&self, card_id: Uuid) -> Result<Option<RecurrenceModel>, Self::Error> {
+        Ok(self.0.get_recurrence(card_id).await?)
+    }
+    async fn update_last_occurrence(
+        &self,
+        card_id: Uuid,
+        at: DateTimeUtc,
+    ) -> Result<Option<()>, Self::Error> {
+        Ok(self.0.update_last_occurrence(card_id, at).await?)
+    }
+    async fn delete_recurrence(&self, card_id: Uuid) -> Result<Option<()>, Self::Error> {
+        Ok(self.0.delete_recurrence(card_id).await?)
+    }
+    async fn get_skipped_occurrences(
+        &self,
+        card_id: Uuid,
+    ) -> Result<Vec<DateTimeUtc>, Self::Error> {
+        Ok(self.0.get_skipped_occurrences(card_id).await?)
+    }
+    async fn save_skipped_occurrence(
+        &self,
+        card_id: Uuid,
+        at: DateTimeUtc,
+    ) -> Result<(), Self::Error> {
+        Ok(self.0.save_skipped_occurrence(card_id, at).await?)
+    }
+    async fn delete_skipped_occurrence(
+        &self,
+        card_id: Uuid,
+        at: DateTimeUtc,
+    ) -> Result<Option<()>, Self::Error> {
+        Ok(self.0.delete_skipped_occurrence(card_id, at).await?)
+    }
+    async fn get_contributions(
+        &self,
+        card_id: Uuid,
+    ) -> Result<Vec<ContributionModel>, Self::Error> {
+        Ok(self.0.get_contributions(card_id).await?)
+    }
+    async fn get_contributions_by_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<ContributionModel>, Self::Error> {
+        Ok(self.0.get_contributions_by_user(user_id).await?)
+    }
+    async fn save_contribution(&self, contribution: &ContributionModel) -> Result<(), Self::Error> {
+        Ok(self.0.save_contribution(contribution).await?)
+    }
+    async fn delete_contribution(
+        &self,
+        card_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<()>, Self::Error> {
+        Ok(self.0.delete_contribution(card_id, user_id).await?)
+    }
+    async fn get_card_members(&self, card_id: Uuid) -> Result<Vec<CardMemberModel>, Self::Error> {
+        Ok(self.0.get_card_members(card_id).await?)
+    }
+    async fn save_card_member(&self, member: &CardMemberModel) -> Result<(), Self::Error> {
+        Ok(self.0.save_card_member(member).await?)
+    }
+    async fn delete_card_member(
+        &self,
+        card_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<()>, Self::Error> {
+        Ok(self.0.delete_card_member(card_id, user_id).await?)
+    }
+    async fn transfer_card_ownership(
+        &self,
+        card_id: Uuid,
+        from: Uuid,
+        to: Uuid,
+    ) -> Result<Option<()>, Self::Error> {
+        Ok(self.0.transfer_card_ownership(card_id, from, to).await?)
+    }
+    async fn get_share_links(&self, card_id: Uuid) -> Result<Vec<ShareLinkModel>, Self::Error> {
+        Ok(self.0.get_share_links(card_id).await?)
+    }
+    async fn get_share_link(&self, id: Uuid) -> Result<Option<ShareLinkModel>, Self::Error> {
+        Ok(self.0.get_share_link(id).await?)
+    }
+    async fn save_share_link(&self, link: &ShareLinkModel) -> Result<(), Self::Error> {
+        Ok(self.0.save_share_link(link).await?)
+    }
+    async fn delete_share_link(&self, id: Uuid) -> Result<Option<()>, Self::Error> {
+        Ok(self.0.delete_share_link(id).await?)
+    }
+    async fn save_audit_event(&self, event: &AuditEventModel) -> Result<(), Self::Error> {
+        Ok(self.0.save_audit_event(event).await?)
+    }
+    async fn get_audit_events(
+        &self,
+        query: &AuditQuery,
+    ) -> Result<Vec<AuditEventModel>, Self::Error> {
+        Ok(self.0.get_audit_events(query).await?)
+    }
+    async fn delete_audit_events_before(&self, before: DateTimeUtc) -> Result<u64, Self::Error> {
+        Ok(self.0.delete_audit_events_before(before).await?)
+    }
+    async fn get_image_revisions(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+    ) -> Result<Vec<ImageRevisionModel>, Self::Error> {
+        Ok(self.0.get_image_revisions(card_id, kind).await?)
+    }
+    async fn get_image_revision(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+        revision: u32,
+    ) -> Result<Option<ImageRevisionModel>, Self::Error> {
+        Ok(self.0.get_image_revision(card_id, kind, revision).await?)
+    }
+    async fn save_image_revision(
+        &self,
+        revision: &ImageRevisionModel,
+    ) -> Result<Option<()>, Self::Error> {
+        Ok(self.0.save_image_revision(revision).await?)
+    }
+    async fn delete_image_revision(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+        revision: u32,
+    ) -> Result<Option<()>, Self::Error> {
+        Ok(self
+            .0
+            .delete_image_revision(card_id, kind, revision)
+            .await?)
+    }
+    async fn save_asset(&self, asset: &AssetModel) -> Result<Option<()>, Self::Error> {
+        Ok(self.0.save_asset(asset).await?)
+    }
+    async fn get_asset(&self, id: Uuid) -> Result<Option<AssetModel>, Self::Error> {
+        Ok(self.0.get_asset(id).await?)
+    }
+    async fn get_webhooks(&self) -> Result<Vec<WebhookModel>, Self::Error> {
+        Ok(self.0.get_webhooks().await?)
+    }
+    async fn get_webhook(&self, channel_id: Uuid) -> Result<Option<WebhookModel>, Self::Error> {
+        Ok(self.0.get_webhook(channel_id).await?)
+    }
+    async fn save_webhook(&self, webhook: &WebhookModel) -> Result<(), Self::Error> {
+        Ok(self.0.save_webhook(webhook).await?)
+    }
+    async fn delete_webhook(&self, channel_id: Uuid) -> Result<Option<()>, Self::Error> {
+        Ok(self.0.delete_webhook(channel_id).await?)
+    }
+    async fn delete_publish_group(
+        &self,
+        card_id: Uuid,
+        group_id: Uuid,
+    ) -> Result<Option<()>, Self::Error> {
+        Ok(self.0.delete_publish_group(card_id, group_id).await?)
+    }
+    async fn trash_card(&self, card_id: Uuid, at: DateTimeUtc) -> Result<Option<()>, Self::Error> {
+        Ok(self.0.trash_card(card_id, at).await?)
+    }
+    async fn restore_card(
+        &self,
+        card_id: Uuid,
+        since: DateTimeUtc,
+    ) -> Result<Option<()>, Self::Error> {
+        Ok(self.0.restore_card(card_id, since).await?)
+    }
+    async fn get_trashed_cards(&self, user_id: Uuid) -> Result<Vec<CardModel>, Self::Error> {
+        Ok(self.0.get_trashed_cards(user_id).await?)
+    }
+    async fn get_trashed_card_by_id(
+        &self,
+        card_id: Uuid,
+    ) -> Result<Option<CardModel>, Self::Error> {
+        Ok(self.0.get_trashed_card_by_id(card_id).await?)
+    }
+    async fn get_cards_trashed_before(
+        &self,
+        before: DateTimeUtc,
+    ) -> Result<Vec<CardModel>, Self::Error> {
+        Ok(self.0.get_cards_trashed_before(before).await?)
+    }
     async fn delete_card(&self, card_id: Uuid) -> Result<Option<()>, Self::Error> {
         Ok(self.0.delete_card(card_id).await?)
     }
-    async fn get_card_with_channels_by_date(
+    async fn get_due_cards_with_channels(
         &self,
-        start: DateTimeUtc,
-        end: DateTimeUtc,
+        now: DateTimeUtc,
     ) -> Result<Vec<(CardModel, Vec<PublishChannelModel>)>, Self::Error> {
-        Ok(self.0.get_card_with_channels_by_date(start, end).await?)
+        Ok(self.0.get_due_cards_with_channels(now).await?)
     }
 }
 
@@ -136,4 +732,32 @@ where
     async fn delete_asset(&self, id: Uuid) -> Result<(), Self::Error> {
         Ok(self.0.delete_asset(id).await?)
     }
+    async fn save_revision(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+        revision: u32,
+        content: &Bytes,
+    ) -> Result<(), Self::Error> {
+        Ok(self
+            .0
+            .save_revision(card_id, kind, revision, content)
+            .await?)
+    }
+    async fn get_revision(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+        revision: u32,
+    ) -> Result<Option<Bytes>, Self::Error> {
+        Ok(self.0.get_revision(card_id, kind, revision).await?)
+    }
+    async fn delete_revision(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+        revision: u32,
+    ) -> Result<(), Self::Error> {
+        Ok(self.0.delete_revision(card_id, kind, revision).await?)
+    }
 }
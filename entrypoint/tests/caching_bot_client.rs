@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use bot_client::{BotClientConfig, BotClientImpl};
+use domain::bot_client::BotClient;
+use entrypoint::wrappers::{CacheConfig, CachingBotClient};
+use fake_traq::{FakeTraq, State, StatusCode};
+use uuid::Uuid;
+
+fn client(traq: &FakeTraq, config: CacheConfig) -> CachingBotClient<BotClientImpl> {
+    let bot_config = BotClientConfig {
+        origin: traq.origin(),
+        base_backoff: Duration::from_millis(1),
+        ..Default::default()
+    };
+    let inner = BotClientImpl::with_config("token".to_string(), bot_config);
+    CachingBotClient::new(inner, config)
+}
+
+/// `path`へのリクエスト回数
+fn requests(traq: &FakeTraq, path: &str) -> usize {
+    traq.state()
+        .requests
+        .iter()
+        .filter(|(_, p)| p == path)
+        .count()
+}
+
+#[tokio::test]
+async fn caches_until_ttl_expires() {
+    let mut state = State::default();
+    let user = state.add_user("alice");
+    let traq = FakeTraq::start(state).await.unwrap();
+    let config = CacheConfig {
+        user_ttl: Duration::from_millis(200),
+        ..Default::default()
+    };
+    let client = client(&traq, config);
+    let id = user.id.to_string();
+    let path = format!("/api/v3/users/{}", id);
+
+    assert_eq!(client.get_user(&id).await.unwrap().name, "alice");
+    assert_eq!(client.get_user(&id).await.unwrap().name, "alice");
+    assert_eq!(requests(&traq, &path), 1);
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    client.get_user(&id).await.unwrap();
+    assert_eq!(requests(&traq, &path), 2);
+
+    let stats = client.stats();
+    let stats = stats.iter().find(|s| s.name == "user").unwrap();
+    assert_eq!((stats.hits, stats.misses, stats.entries), (1, 2, 1));
+}
+
+#[tokio::test]
+async fn evicts_oldest_entry_at_capacity() {
+    let mut state = State::default();
+    let users: Vec<_> = ["alice", "bob", "carol"]
+        .into_iter()
+        .map(|name| state.add_user(name))
+        .collect();
+    let traq = FakeTraq::start(state).await.unwrap();
+    let config = CacheConfig {
+        capacity: 2,
+        ..Default::default()
+    };
+    let client = client(&traq, config);
+    let ids: Vec<_> = users.iter().map(|u| u.id.to_string()).collect();
+
+    for id in &ids {
+        client.get_user(id).await.unwrap();
+    }
+    let stats = client.stats();
+    assert_eq!(stats.iter().find(|s| s.name == "user").unwrap().entries, 2);
+
+    // 最後の2件は残り、最初の1件は追い出されている
+    client.get_user(&ids[2]).await.unwrap();
+    client.get_user(&ids[1]).await.unwrap();
+    client.get_user(&ids[0]).await.unwrap();
+    for (id, expected) in ids.iter().zip([2, 1, 1]) {
+        let path = format!("/api/v3/users/{}", id);
+        assert_eq!(requests(&traq, &path), expected);
+    }
+}
+
+#[tokio::test]
+async fn invalidates_related_caches() {
+    let mut state = State::default();
+    let user = state.add_user("alice");
+    let traq = FakeTraq::start(state).await.unwrap();
+    let client = client(&traq, CacheConfig::default());
+    let id = user.id.to_string();
+    let path = format!("/api/v3/users/{}", id);
+
+    client.get_user(&id).await.unwrap();
+    client.get_users(None).await.unwrap();
+    client.invalidate_stamps();
+    client.get_user(&id).await.unwrap();
+    assert_eq!(requests(&traq, &path), 1);
+
+    client.invalidate_users();
+    client.get_user(&id).await.unwrap();
+    client.get_users(None).await.unwrap();
+    assert_eq!(requests(&traq, &path), 2);
+    assert_eq!(requests(&traq, "/api/v3/users"), 2);
+}
+
+#[tokio::test]
+async fn negative_caches_only_not_found() {
+    let mut state = State::default();
+    let user = state.add_user("alice");
+    let traq = FakeTraq::start(state).await.unwrap();
+    let client = client(&traq, CacheConfig::default());
+
+    let missing = Uuid::new_v4().to_string();
+    let path = format!("/api/v3/users/{}", missing);
+    let first = client.get_user(&missing).await.unwrap_err();
+    let second = client.get_user(&missing).await.unwrap_err();
+    assert_eq!(first.to_string(), second.to_string());
+    assert_eq!(requests(&traq, &path), 1);
+
+    // 一時的な失敗は覚えずに次のリクエストで取り直す
+    let id = user.id.to_string();
+    let path = format!("/api/v3/users/{}", id);
+    traq.fail_next(StatusCode::INTERNAL_SERVER_ERROR, None);
+    client.get_user(&id).await.unwrap_err();
+    assert_eq!(client.get_user(&id).await.unwrap().name, "alice");
+    assert_eq!(requests(&traq, &path), 2);
+}
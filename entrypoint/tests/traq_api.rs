@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use bot_client::{BotClientConfig, BotClientImpl};
+use entrypoint::wrappers::{CacheConfig, CachingBotClient};
+use fake_traq::{FakeTraq, Image, State};
+use handler::auth::AuthUserConfig;
+use handler::traq_api::{channels, stamps, users};
+use handler::BC;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::Value;
+
+/// 本番と同じBOTクライアントで`fake-traq`に繋いだRocket
+async fn client(traq: &FakeTraq) -> Client {
+    let config = BotClientConfig {
+        origin: traq.origin(),
+        base_backoff: Duration::from_millis(1),
+        ..Default::default()
+    };
+    let bot_client = BotClientImpl::with_config("token".to_string(), config);
+    let bot_client = CachingBotClient::new(bot_client, CacheConfig::default());
+    let rocket = rocket::build()
+        .mount("/api/users", rocket::routes![users::get_all])
+        .mount(
+            "/api/channels",
+            rocket::routes![channels::get_all, channels::resolve],
+        )
+        .mount("/api/stamps", rocket::routes![stamps::get_all])
+        .manage(AuthUserConfig(true))
+        .manage(BC::from(bot_client));
+    Client::tracked(rocket).await.unwrap()
+}
+
+fn as_user(name: &str) -> Header<'static> {
+    Header::new("X-Forwarded-User", name.to_string())
+}
+
+async fn get_json(client: &Client, uri: &str) -> (Status, Option<Value>) {
+    let res = client
+        .get(uri.to_string())
+        .header(as_user("alice"))
+        .dispatch()
+        .await;
+    (res.status(), res.into_json().await)
+}
+
+#[tokio::test]
+async fn authenticates_against_traq_users() {
+    let mut state = State::default();
+    let alice = state.add_user("alice");
+    let traq = FakeTraq::start(state).await.unwrap();
+    let client = client(&traq).await;
+
+    let (status, users) = get_json(&client, "/api/users?name=alice").await;
+    assert_eq!(status, Status::Ok);
+    assert_eq!(users.unwrap()[0]["id"], alice.id.to_string());
+
+    // traQにいないユーザーは通さない
+    let res = client
+        .get("/api/users")
+        .header(as_user("mallory"))
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Unauthorized);
+}
+
+#[tokio::test]
+async fn proxies_channels_and_stamps() {
+    let mut state = State::default();
+    state.add_user("alice");
+    let gps = state.add_channel("gps", None);
+    let times = state.add_channel("times", Some(gps.id));
+    let stamp = state.add_stamp(
+        "kusa",
+        Image {
+            mime_type: "image/png".to_string(),
+            content: "png".into(),
+        },
+    );
+    let traq = FakeTraq::start(state).await.unwrap();
+    let client = client(&traq).await;
+
+    let (status, resolved) = get_json(&client, "/api/channels/resolve?path=%23gps/times").await;
+    assert_eq!(status, Status::Ok);
+    assert_eq!(resolved.unwrap()["id"], times.id.to_string());
+    let (status, _) = get_json(&client, "/api/channels/resolve?path=gps/nowhere").await;
+    assert_eq!(status, Status::NotFound);
+
+    let (status, stamps) = get_json(&client, "/api/stamps").await;
+    assert_eq!(status, Status::Ok);
+    assert_eq!(stamps.unwrap()[0]["name"], stamp.name);
+}
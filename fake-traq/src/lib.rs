@@ -0,0 +1,481 @@
+//! テスト用のインプロセスなtraQ APIサーバー
+//!
+//! `FakeTraq::start`でランダムなポートにサーバーを立て、`origin()`を
+//! `BotClientConfig`や`CronImpl`に渡すとオフラインで結合テストができる。
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use bytes::Bytes;
+use hyper::header::{CONTENT_TYPE, RETRY_AFTER};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+pub use hyper::{Method, StatusCode};
+use serde::Serialize;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+pub use traq::models::{
+    Channel, ChannelList, DmChannel, FileInfo, Message, Stamp, User, UserAccountState, UserDetail,
+    UserGroup, UserGroupMember,
+};
+
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub mime_type: String,
+    pub content: Bytes,
+}
+
+#[derive(Debug, Clone)]
+pub struct UploadedFile {
+    pub id: Uuid,
+    pub channel_id: Uuid,
+    pub name: String,
+    pub mime_type: String,
+    pub content: Bytes,
+}
+
+#[derive(Debug, Clone)]
+pub struct PostedMessage {
+    pub id: Uuid,
+    pub channel_id: Uuid,
+    pub content: String,
+    pub embed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    pub channel_id: Uuid,
+    pub secret: String,
+}
+
+/// 次のリクエストに強制的に返すエラー
+#[derive(Debug, Clone)]
+pub struct InjectedFailure {
+    pub status: StatusCode,
+    pub retry_after: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct State {
+    pub users: Vec<User>,
+    pub channels: Vec<Channel>,
+    pub stamps: Vec<Stamp>,
+    pub groups: Vec<UserGroup>,
+    /// スタンプ画像とユーザーアイコン (スタンプID・ユーザーIDがキー)
+    pub images: HashMap<Uuid, Image>,
+    /// ユーザーIDからDMチャンネルID
+    pub dm_channels: HashMap<Uuid, Uuid>,
+    pub files: Vec<UploadedFile>,
+    pub messages: Vec<PostedMessage>,
+    pub webhooks: HashMap<Uuid, Webhook>,
+    /// BOTが参加していないチャンネル。BOTのアップロードと投稿は403になる
+    pub bot_excluded: HashSet<Uuid>,
+    pub failures: VecDeque<InjectedFailure>,
+    pub requests: Vec<(Method, String)>,
+}
+
+impl State {
+    pub fn add_user(&mut self, name: &str) -> User {
+        let user = User {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            display_name: name.to_string(),
+            icon_file_id: Uuid::new_v4(),
+            state: UserAccountState::Active,
+            ..Default::default()
+        };
+        self.users.push(user.clone());
+        user
+    }
+
+    /// `parent`の子チャンネルを作る
+    pub fn add_channel(&mut self, name: &str, parent: Option<Uuid>) -> Channel {
+        let channel = Channel {
+            id: Uuid::new_v4(),
+            parent_id: parent,
+            name: name.to_string(),
+            ..Default::default()
+        };
+        if let Some(parent) = self.channels.iter_mut().find(|c| Some(c.id) == parent) {
+            parent.children.push(channel.id);
+        }
+        self.channels.push(channel.clone());
+        channel
+    }
+
+    pub fn add_group(&mut self, name: &str, members: &[Uuid]) -> UserGroup {
+        let group = UserGroup {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            members: members
+                .iter()
+                .map(|id| UserGroupMember {
+                    id: *id,
+                    role: String::new(),
+                })
+                .collect(),
+            ..Default::default()
+        };
+        self.groups.push(group.clone());
+        group
+    }
+
+    /// `channel_id`に投稿するWebhookを作り、そのIDを返す
+    pub fn add_webhook(&mut self, channel_id: Uuid, secret: &str) -> Uuid {
+        let id = Uuid::new_v4();
+        let webhook = Webhook {
+            channel_id,
+            secret: secret.to_string(),
+        };
+        self.webhooks.insert(id, webhook);
+        id
+    }
+
+    pub fn add_stamp(&mut self, name: &str, image: Image) -> Stamp {
+        let stamp = Stamp {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            file_id: Uuid::new_v4(),
+            ..Default::default()
+        };
+        self.images.insert(stamp.id, image);
+        self.stamps.push(stamp.clone());
+        stamp
+    }
+}
+
+pub struct FakeTraq {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl FakeTraq {
+    pub async fn start(state: State) -> Result<Self, hyper::Error> {
+        let state = Arc::new(Mutex::new(state));
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let state = state.clone();
+                    async move { Ok::<_, Infallible>(handle(state, req).await) }
+                }))
+            }
+        });
+        let server = Server::try_bind(&([127, 0, 0, 1], 0).into())?.serve(make_service);
+        let addr = server.local_addr();
+        let (tx, rx) = oneshot::channel::<()>();
+        let server = server.with_graceful_shutdown(async {
+            rx.await.ok();
+        });
+        tokio::spawn(async move {
+            if let Err(e) = server.await {
+                eprintln!("fake traq server error: {}", e);
+            }
+        });
+        Ok(Self {
+            addr,
+            state,
+            shutdown: Some(tx),
+        })
+    }
+
+    /// `http://127.0.0.1:<port>` (末尾に`/api/v3`は付かない)
+    pub fn origin(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    pub fn fail_next(&self, status: StatusCode, retry_after: Option<u64>) {
+        self.state().failures.push_back(InjectedFailure {
+            status,
+            retry_after,
+        });
+    }
+}
+
+impl Drop for FakeTraq {
+    fn drop(&mut self) {
+        if let Some(tx) = self.shutdown.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+fn json(value: &impl Serialize) -> Response<Body> {
+    let body = serde_json::to_vec(value).unwrap();
+    Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(body.into())
+        .unwrap()
+}
+
+/// `mime_type`が空なら`Content-Type`を付けない
+fn image(image: &Image) -> Response<Body> {
+    let mut res = Response::builder();
+    if !image.mime_type.is_empty() {
+        res = res.header(CONTENT_TYPE, image.mime_type.as_str());
+    }
+    res.body(image.content.clone().into()).unwrap()
+}
+
+fn status(status: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::from(status.to_string()))
+        .unwrap()
+}
+
+fn query<'a>(req: &'a Request<Body>, key: &str) -> Option<&'a str> {
+    req.uri()
+        .query()?
+        .split('&')
+        .filter_map(|kv| kv.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| v)
+}
+
+fn user_detail(user: &User) -> UserDetail {
+    UserDetail {
+        id: user.id,
+        state: user.state,
+        bot: user.bot,
+        icon_file_id: user.icon_file_id,
+        display_name: user.display_name.clone(),
+        name: user.name.clone(),
+        updated_at: user.updated_at.clone(),
+        ..Default::default()
+    }
+}
+
+async fn handle(state: Arc<Mutex<State>>, req: Request<Body>) -> Response<Body> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    {
+        let mut state = state.lock().unwrap();
+        state.requests.push((method.clone(), path.clone()));
+        if let Some(failure) = state.failures.pop_front() {
+            let mut res = self::status(failure.status);
+            if let Some(retry_after) = failure.retry_after {
+                res.headers_mut()
+                    .insert(RETRY_AFTER, retry_after.to_string().parse().unwrap());
+            }
+            return res;
+        }
+    }
+    let Some(path) = path.strip_prefix("/api/v3") else {
+        return status(StatusCode::NOT_FOUND);
+    };
+    let segments: Vec<_> = path.trim_matches('/').split('/').collect();
+    match (&method, segments.as_slice()) {
+        (&Method::POST, ["files"]) => return upload_file(state, req).await,
+        (&Method::POST, ["webhooks", id]) => {
+            let Ok(webhook_id) = id.parse() else {
+                return status(StatusCode::BAD_REQUEST);
+            };
+            return post_webhook(state, webhook_id, req).await;
+        }
+        (&Method::POST, ["channels", id, "messages"]) => {
+            let Ok(channel_id) = id.parse() else {
+                return status(StatusCode::BAD_REQUEST);
+            };
+            if state.lock().unwrap().bot_excluded.contains(&channel_id) {
+                return status(StatusCode::FORBIDDEN);
+            }
+            return post_message(state, channel_id, req).await;
+        }
+        _ => (),
+    }
+    let mut state = state.lock().unwrap();
+    let find_user = |id: &str| state.users.iter().find(|u| u.id.to_string() == id);
+    match (&method, segments.as_slice()) {
+        (&Method::GET, ["users"]) => {
+            let users: Vec<_> = match query(&req, "name") {
+                Some(name) => state.users.iter().filter(|u| u.name == name).collect(),
+                None => state.users.iter().collect(),
+            };
+            json(&users)
+        }
+        (&Method::GET, ["users", id]) => match find_user(id) {
+            Some(user) => json(&user_detail(user)),
+            None => status(StatusCode::NOT_FOUND),
+        },
+        (&Method::GET, ["users", id, "icon"]) => {
+            match find_user(id).and_then(|u| state.images.get(&u.id)) {
+                Some(icon) => image(icon),
+                None => status(StatusCode::NOT_FOUND),
+            }
+        }
+        (&Method::GET, ["users", id, "dm-channel"]) => {
+            let Some(user_id) = find_user(id).map(|u| u.id) else {
+                return status(StatusCode::NOT_FOUND);
+            };
+            let channel_id = *state
+                .dm_channels
+                .entry(user_id)
+                .or_insert_with(Uuid::new_v4);
+            json(&DmChannel {
+                id: channel_id,
+                user_id,
+            })
+        }
+        (&Method::GET, ["groups", id]) => {
+            match state.groups.iter().find(|g| g.id.to_string() == *id) {
+                Some(group) => json(group),
+                None => status(StatusCode::NOT_FOUND),
+            }
+        }
+        (&Method::GET, ["channels"]) => json(&ChannelList::new(state.channels.clone())),
+        (&Method::GET, ["stamps"]) => {
+            let stamps: Vec<_> = match query(&req, "type") {
+                Some("unicode") => state.stamps.iter().filter(|s| s.is_unicode).collect(),
+                Some("original") => state.stamps.iter().filter(|s| !s.is_unicode).collect(),
+                _ => state.stamps.iter().collect(),
+            };
+            json(&stamps)
+        }
+        (&Method::GET, ["stamps", id, "image"]) => {
+            let stamp = state.stamps.iter().find(|s| s.id.to_string() == *id);
+            match stamp.and_then(|s| state.images.get(&s.id)) {
+                Some(stamp) => image(stamp),
+                None => status(StatusCode::NOT_FOUND),
+            }
+        }
+        _ => status(StatusCode::NOT_FOUND),
+    }
+}
+
+async fn upload_file(state: Arc<Mutex<State>>, req: Request<Body>) -> Response<Body> {
+    let Some(boundary) = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|c| c.to_str().ok())
+        .and_then(|c| multer::parse_boundary(c).ok())
+    else {
+        return status(StatusCode::BAD_REQUEST);
+    };
+    let mut multipart = multer::Multipart::new(req.into_body(), boundary);
+    let mut channel_id = None;
+    let mut file = None;
+    while let Ok(Some(field)) = multipart.next_field().await {
+        match field.name() {
+            Some("channelId") => channel_id = field.text().await.ok().and_then(|t| t.parse().ok()),
+            Some("file") => {
+                let name = field.file_name().unwrap_or_default().to_string();
+                let mime_type = field
+                    .content_type()
+                    .map(|m| m.to_string())
+                    .unwrap_or_default();
+                let Ok(content) = field.bytes().await else {
+                    return status(StatusCode::BAD_REQUEST);
+                };
+                file = Some((name, mime_type, content));
+            }
+            _ => (),
+        }
+    }
+    let (Some(channel_id), Some((name, mime_type, content))) = (channel_id, file) else {
+        return status(StatusCode::BAD_REQUEST);
+    };
+    if state.lock().unwrap().bot_excluded.contains(&channel_id) {
+        return status(StatusCode::FORBIDDEN);
+    }
+    let file = UploadedFile {
+        id: Uuid::new_v4(),
+        channel_id,
+        name,
+        mime_type,
+        content,
+    };
+    let info = FileInfo {
+        id: file.id,
+        name: file.name.clone(),
+        mime: file.mime_type.clone(),
+        size: file.content.len() as i64,
+        channel_id: Some(channel_id),
+        ..Default::default()
+    };
+    state.lock().unwrap().files.push(file);
+    json(&info)
+}
+
+async fn post_message(
+    state: Arc<Mutex<State>>,
+    channel_id: Uuid,
+    req: Request<Body>,
+) -> Response<Body> {
+    #[derive(serde::Deserialize)]
+    struct Params {
+        content: String,
+        #[serde(default)]
+        embed: bool,
+    }
+    let Ok(body) = hyper::body::to_bytes(req.into_body()).await else {
+        return status(StatusCode::BAD_REQUEST);
+    };
+    let Ok(params) = serde_json::from_slice::<Params>(&body) else {
+        return status(StatusCode::BAD_REQUEST);
+    };
+    let message = PostedMessage {
+        id: Uuid::new_v4(),
+        channel_id,
+        content: params.content,
+        embed: params.embed,
+    };
+    let res = Message {
+        id: message.id,
+        channel_id,
+        content: message.content.clone(),
+        ..Default::default()
+    };
+    state.lock().unwrap().messages.push(message);
+    json(&res)
+}
+
+/// `X-TRAQ-Signature`を検証し、`X-TRAQ-Channel-Id`かWebhookのチャンネルに投稿する
+async fn post_webhook(
+    state: Arc<Mutex<State>>,
+    webhook_id: Uuid,
+    req: Request<Body>,
+) -> Response<Body> {
+    use hmac::{Hmac, Mac};
+
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let signature = header("X-TRAQ-Signature");
+    let channel_id = header("X-TRAQ-Channel-Id").and_then(|c| c.parse().ok());
+    let embed = query(&req, "embed") == Some("1");
+    let Some(webhook) = state.lock().unwrap().webhooks.get(&webhook_id).cloned() else {
+        return status(StatusCode::NOT_FOUND);
+    };
+    let Ok(body) = hyper::body::to_bytes(req.into_body()).await else {
+        return status(StatusCode::BAD_REQUEST);
+    };
+    let mut mac = Hmac::<sha1::Sha1>::new_from_slice(webhook.secret.as_bytes()).unwrap();
+    mac.update(&body);
+    if signature != Some(hex::encode(mac.finalize().into_bytes())) {
+        return status(StatusCode::BAD_REQUEST);
+    }
+    let Ok(content) = String::from_utf8(body.to_vec()) else {
+        return status(StatusCode::BAD_REQUEST);
+    };
+    let message = PostedMessage {
+        id: Uuid::new_v4(),
+        channel_id: channel_id.unwrap_or(webhook.channel_id),
+        content,
+        embed,
+    };
+    state.lock().unwrap().messages.push(message);
+    status(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,354 @@
+//! 管理者用のAPI
+//!
+//! 他人のカードの検索や、止まった配信の立て直しに使う。全て`AdminUser`が必要。
+
+use chrono::{Duration, Utc};
+use rocket::http::Status;
+use rocket::request::FromParam;
+use rocket::serde::json::{Json, Value};
+use rocket::{Route, State};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use domain::repository::{
+    AuditAction, AuditEventModel, AuditQuery, CardModel, CardStatus, DateTimeUtc,
+};
+
+use crate::audit::{AuditEvent, RequestId};
+use crate::auth::AdminUser;
+use crate::cards::{complete_card_response, complete_card_response_one, with_owners, CardResponse};
+use crate::catalog::Page;
+use crate::{DateTimeParam, UuidParam, BC, CR, CRON};
+
+/// 強制配信できる状態。配信中のものは`STUCK_AFTER_MINUTES`を過ぎて止まっている時だけ
+const FORCE_DELIVERABLE: [CardStatus; 2] = [CardStatus::Scheduled, CardStatus::Failed];
+
+/// 配信中のままこの分数が経ったら止まったとみなす
+const STUCK_AFTER_MINUTES: i64 = 10;
+
+/// 監査ログを1回で返す最大件数
+const MAX_AUDIT_LIMIT: u64 = 200;
+
+const DEFAULT_AUDIT_LIMIT: u64 = 50;
+
+/// 取り消せる状態
+const CANCELLABLE: [CardStatus; 4] = [
+    CardStatus::Draft,
+    CardStatus::Scheduled,
+    CardStatus::Delivering,
+    CardStatus::Failed,
+];
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ReassignRequest {
+    pub owner_id: Uuid,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct QueueEntry {
+    pub id: Uuid,
+    pub owner_id: Uuid,
+    pub status: CardStatus,
+    pub publish_date: Option<DateTimeUtc>,
+    /// 投稿日時を過ぎても配信が終わっていない
+    pub overdue: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+#[schemars(rename = "AuditEvent")]
+pub struct AuditEventResponse {
+    pub id: Uuid,
+    pub at: DateTimeUtc,
+    pub actor_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub card_id: Option<Uuid>,
+    pub asset_id: Option<Uuid>,
+    /// 変わった項目の変更前の値
+    pub before: Option<Value>,
+    /// 変わった項目の変更後の値
+    pub after: Option<Value>,
+    pub request_id: Option<String>,
+}
+
+impl From<AuditEventModel> for AuditEventResponse {
+    fn from(value: AuditEventModel) -> Self {
+        let parse = |v: Option<String>| v.and_then(|v| rocket::serde::json::from_str(&v).ok());
+        Self {
+            id: value.id,
+            at: value.at,
+            actor_id: value.actor_id,
+            action: value.action,
+            card_id: value.card_id,
+            asset_id: value.asset_id,
+            before: parse(value.before),
+            after: parse(value.after),
+            request_id: value.request_id,
+        }
+    }
+}
+
+async fn get_all_cards(card_repo: &State<CR>) -> Result<Vec<CardModel>, Status> {
+    card_repo.0.get_all_cards().await.map_err(|e| {
+        eprintln!("error in get all cards: {}", e);
+        Status::InternalServerError
+    })
+}
+
+async fn get_card(id: Uuid, card_repo: &State<CR>) -> Result<CardModel, Status> {
+    card_repo
+        .0
+        .get_card_by_id(id)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get card by id: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)
+}
+
+async fn card_response(card: &CardModel, card_repo: &State<CR>) -> Result<CardResponse, Status> {
+    complete_card_response_one(card, card_repo)
+        .await
+        .map_err(|e| {
+            eprintln!("error in complete card response: {}", e);
+            Status::InternalServerError
+        })
+}
+
+/// `q`はカードIDの前方一致かメッセージの部分一致(大文字小文字を区別しない)
+fn matches_query(card: &CardModel, q: &str) -> bool {
+    let q = q.to_lowercase();
+    card.id.to_string().starts_with(&q)
+        || card
+            .message
+            .as_ref()
+            .is_some_and(|m| m.to_lowercase().contains(&q))
+}
+
+/// 全てのカードを投稿日時の新しい順に返す。投稿日時の無い下書きは最後
+#[rocket::get("/cards?<q>&<status>&<owner_id>&<expand>&<limit>&<offset>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_cards(
+    q: Option<&str>,
+    status: Option<&str>,
+    owner_id: Option<&str>,
+    expand: Option<&str>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    card_repo: &State<CR>,
+    client: &State<BC>,
+    _admin: AdminUser,
+) -> Result<Json<Page<CardResponse>>, Status> {
+    let status: Option<CardStatus> = status
+        .map(|s| s.parse())
+        .transpose()
+        .map_err(|_| Status::BadRequest)?;
+    let owner_id: Option<Uuid> = owner_id
+        .map(|s| s.parse())
+        .transpose()
+        .map_err(|_| Status::BadRequest)?;
+    let mut cards: Vec<_> = get_all_cards(card_repo)
+        .await?
+        .into_iter()
+        .filter(|c| status.map_or(true, |s| c.status == s))
+        .filter(|c| owner_id.map_or(true, |o| c.owner_id == o))
+        .filter(|c| q.map_or(true, |q| matches_query(c, q)))
+        .collect();
+    cards.sort_by(|a, b| b.publish_date.cmp(&a.publish_date));
+    let page = Page::new(&cards, offset, limit);
+    let items = complete_card_response(&page.items, card_repo)
+        .await
+        .map_err(|e| {
+            eprintln!("error in complete card response: {}", e);
+            Status::InternalServerError
+        })?;
+    let items = with_owners(items, expand, client).await?;
+    Ok(Json(Page {
+        items,
+        total: page.total,
+        offset: page.offset,
+        limit: page.limit,
+    }))
+}
+
+/// 投稿日時を待たずに配信する。繰り返しのカードは次の発生日時から通常通り続く
+#[rocket::post("/cards/<id>/deliver")]
+pub async fn deliver(
+    id: UuidParam,
+    card_repo: &State<CR>,
+    cron: &State<CRON>,
+    admin: AdminUser,
+    request_id: RequestId,
+) -> Result<Json<CardResponse>, Status> {
+    let card = get_card(id.0, card_repo).await?;
+    let now = Utc::now();
+    let publish_date = card.publish_date.map_or(now, |d| d.min(now));
+    let rescheduled = if card.status == CardStatus::Delivering {
+        card_repo
+            .0
+            .reschedule_stuck_card(
+                id.0,
+                now - Duration::minutes(STUCK_AFTER_MINUTES),
+                publish_date,
+            )
+            .await
+    } else {
+        card_repo
+            .0
+            .reschedule_card(id.0, &FORCE_DELIVERABLE, publish_date)
+            .await
+    };
+    rescheduled
+        .map_err(|e| {
+            eprintln!("error in reschedule card: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::Conflict)?;
+    AuditEvent::new(AuditAction::CardDeliver, Some(&admin.0), &request_id)
+        .card(id.0)
+        .change(Some(&card.publish_date), Some(&Some(publish_date)))
+        .record(card_repo)
+        .await;
+    cron.0.deliver(id.0).await;
+    let card = get_card(id.0, card_repo).await?;
+    Ok(Json(card_response(&card, card_repo).await?))
+}
+
+#[rocket::post("/cards/<id>/cancel")]
+pub async fn cancel(
+    id: UuidParam,
+    card_repo: &State<CR>,
+    admin: AdminUser,
+    request_id: RequestId,
+) -> Result<Status, Status> {
+    let card = get_card(id.0, card_repo).await?;
+    card_repo
+        .0
+        .update_card_status(id.0, &CANCELLABLE, CardStatus::Cancelled)
+        .await
+        .map_err(|e| {
+            eprintln!("error in update card status: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::Conflict)?;
+    AuditEvent::new(AuditAction::CardCancel, Some(&admin.0), &request_id)
+        .card(id.0)
+        .change(Some(&card.status), Some(&CardStatus::Cancelled))
+        .record(card_repo)
+        .await;
+    Ok(Status::NoContent)
+}
+
+/// 差出人を付け替える。元の差出人は編集者として残る
+#[rocket::post("/cards/<id>/reassign", data = "<reassign>")]
+pub async fn reassign(
+    id: UuidParam,
+    reassign: Json<ReassignRequest>,
+    card_repo: &State<CR>,
+    admin: AdminUser,
+    request_id: RequestId,
+) -> Result<Status, Status> {
+    let card = get_card(id.0, card_repo).await?;
+    if card.owner_id == reassign.owner_id {
+        return Ok(Status::NoContent);
+    }
+    card_repo
+        .0
+        .transfer_card_ownership(id.0, card.owner_id, reassign.owner_id)
+        .await
+        .map_err(|e| {
+            eprintln!("error in transfer card ownership: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::Conflict)?;
+    AuditEvent::new(AuditAction::CardReassign, Some(&admin.0), &request_id)
+        .card(id.0)
+        .change(Some(&card.owner_id), Some(&reassign.owner_id))
+        .record(card_repo)
+        .await;
+    Ok(Status::NoContent)
+}
+
+/// 予約済みと配信中のカードを配信順に返す
+#[rocket::get("/queue")]
+pub async fn get_queue(
+    card_repo: &State<CR>,
+    _admin: AdminUser,
+) -> Result<Json<Vec<QueueEntry>>, Status> {
+    let now = Utc::now();
+    let mut queue: Vec<_> = get_all_cards(card_repo)
+        .await?
+        .into_iter()
+        .filter(|c| matches!(c.status, CardStatus::Scheduled | CardStatus::Delivering))
+        .map(|c| QueueEntry {
+            id: c.id,
+            owner_id: c.owner_id,
+            status: c.status,
+            publish_date: c.publish_date,
+            overdue: c.publish_date.is_some_and(|d| d <= now),
+        })
+        .collect();
+    queue.sort_by_key(|e| e.publish_date);
+    Ok(Json(queue))
+}
+
+/// 次の定期実行を待たずにcronを1回動かす
+#[rocket::post("/cron/tick")]
+pub async fn tick(cron: &State<CRON>, _admin: AdminUser) -> Status {
+    cron.0.tick().await;
+    Status::NoContent
+}
+
+/// 監査ログを新しい順に返す
+#[rocket::get("/audit?<card_id>&<actor_id>&<action>&<since>&<until>&<limit>&<offset>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_audit(
+    card_id: Option<&str>,
+    actor_id: Option<&str>,
+    action: Option<&str>,
+    since: Option<&str>,
+    until: Option<&str>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+    card_repo: &State<CR>,
+    _admin: AdminUser,
+) -> Result<Json<Vec<AuditEventResponse>>, Status> {
+    let action: Option<AuditAction> = action
+        .map(|s| s.parse())
+        .transpose()
+        .map_err(|_| Status::BadRequest)?;
+    let uuid = |s: Option<&str>| {
+        s.map(|s| s.parse::<Uuid>())
+            .transpose()
+            .map_err(|_| Status::BadRequest)
+    };
+    let date = |s: Option<&str>| {
+        s.map(DateTimeParam::from_param)
+            .transpose()
+            .map_err(|_| Status::BadRequest)
+    };
+    let query = AuditQuery {
+        card_id: uuid(card_id)?,
+        actor_id: uuid(actor_id)?,
+        action,
+        since: date(since)?.map(|d| d.0),
+        until: date(until)?.map(|d| d.0),
+        limit: limit.unwrap_or(DEFAULT_AUDIT_LIMIT).min(MAX_AUDIT_LIMIT),
+        offset: offset.unwrap_or(0),
+    };
+    let events = card_repo.0.get_audit_events(&query).await.map_err(|e| {
+        eprintln!("error in get audit events: {}", e);
+        Status::InternalServerError
+    })?;
+    Ok(Json(
+        events.into_iter().map(AuditEventResponse::from).collect(),
+    ))
+}
+
+pub fn routes() -> Vec<Route> {
+    rocket::routes![get_cards, deliver, cancel, reassign, get_queue, tick, get_audit]
+}
@@ -0,0 +1,163 @@
+//! カード・画像への変更の監査ログ
+//!
+//! 変更を行うルートは処理が成功した後に`AuditEvent`を記録する。記録に失敗しても
+//! リクエストは失敗させない。`X-Request-Id`でアクセスログと突き合わせられる。
+
+use chrono::{Duration, Utc};
+use rocket::async_trait;
+use rocket::fairing::AdHoc;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::serde::json::{to_value, Value};
+use rocket::State;
+use serde::Serialize;
+use uuid::Uuid;
+
+use domain::bot_client::User;
+use domain::repository::{AuditAction, AuditEventModel, DEFAULT_AUDIT_RETENTION_DAYS};
+
+use crate::CR;
+
+#[derive(Debug, Clone)]
+pub struct AuditConfig {
+    /// 監査ログを残す期間。過ぎたものはcronで消す
+    pub retention: Duration,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            retention: Duration::days(DEFAULT_AUDIT_RETENTION_DAYS),
+        }
+    }
+}
+
+impl AuditConfig {
+    pub fn load_env() -> Result<Self, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let mut config = Self::default();
+        if let Ok(days) = std::env::var("AUDIT_RETENTION_DAYS") {
+            config = config.retention(Duration::days(days.parse()?));
+        }
+        if config.retention <= Duration::zero() {
+            return Err("AUDIT_RETENTION_DAYS must be positive".into());
+        }
+        Ok(config)
+    }
+
+    pub fn retention(self, value: Duration) -> Self {
+        Self { retention: value }
+    }
+}
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// 受け取った`X-Request-Id`をそのまま使う時の最大長
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// リクエストごとのID。リバースプロキシが付けた`X-Request-Id`があればそれを使う
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    pub fn of<'r>(req: &'r Request<'_>) -> &'r RequestId {
+        req.local_cache(|| {
+            let id = req
+                .headers()
+                .get_one(REQUEST_ID_HEADER)
+                .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| Uuid::new_v4().to_string());
+            RequestId(id)
+        })
+    }
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for RequestId {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(RequestId::of(req).clone())
+    }
+}
+
+/// レスポンスに`X-Request-Id`を付ける
+pub fn request_id_fairing() -> AdHoc {
+    AdHoc::on_response("Request ID", |req, res| {
+        Box::pin(async move {
+            res.set_raw_header(REQUEST_ID_HEADER, RequestId::of(req).0.clone());
+        })
+    })
+}
+
+/// 両方がオブジェクトなら、変わった項目だけを残した`(変更前, 変更後)`を返す
+pub fn diff(before: Option<Value>, after: Option<Value>) -> (Option<Value>, Option<Value>) {
+    match (before, after) {
+        (Some(Value::Object(mut before)), Some(Value::Object(mut after))) => {
+            let keys: Vec<_> = before.keys().chain(after.keys()).cloned().collect();
+            for key in keys {
+                if before.get(&key) == after.get(&key) {
+                    before.remove(&key);
+                    after.remove(&key);
+                }
+            }
+            (Some(Value::Object(before)), Some(Value::Object(after)))
+        }
+        other => other,
+    }
+}
+
+/// 画像は内容を残さず、大きさとハッシュだけを記録する
+pub fn image_digest(content: &[u8]) -> Value {
+    rocket::serde::json::json!({
+        "size": content.len(),
+        "etag": crate::cache::content_etag(content),
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditEvent(AuditEventModel);
+
+impl AuditEvent {
+    pub fn new(action: AuditAction, actor: Option<&User>, request_id: &RequestId) -> Self {
+        Self(AuditEventModel {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            actor_id: actor.map(|u| u.id),
+            action,
+            card_id: None,
+            asset_id: None,
+            before: None,
+            after: None,
+            request_id: Some(request_id.0.clone()),
+        })
+    }
+
+    pub fn card(mut self, card_id: Uuid) -> Self {
+        self.0.card_id = Some(card_id);
+        self
+    }
+
+    pub fn asset(mut self, asset_id: Uuid) -> Self {
+        self.0.asset_id = Some(asset_id);
+        self
+    }
+
+    /// 変わった項目だけを記録する
+    pub fn change<T: Serialize>(mut self, before: Option<&T>, after: Option<&T>) -> Self {
+        let to_value = |v: &T| {
+            to_value(v)
+                .map_err(|e| eprintln!("error in serializing audit value: {}", e))
+                .ok()
+        };
+        let (before, after) = diff(before.and_then(to_value), after.and_then(to_value));
+        self.0.before = before.map(|v| v.to_string());
+        self.0.after = after.map(|v| v.to_string());
+        self
+    }
+
+    pub async fn record(self, card_repo: &State<CR>) {
+        if let Err(e) = card_repo.0.save_audit_event(&self.0).await {
+            eprintln!("error in save audit event: {}", e);
+        }
+    }
+}
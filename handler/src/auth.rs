@@ -1,6 +1,10 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
 use rocket::async_trait;
 use rocket::http::Status;
 use rocket::request::{FromRequest, Outcome, Request};
+use uuid::Uuid;
 
 use domain::bot_client::User;
 
@@ -56,3 +60,121 @@ impl<'r> FromRequest<'r> for AuthUser {
         Outcome::Success(AuthUser(Some(user)))
     }
 }
+
+/// 管理者グループのメンバーを覚えておく期間
+pub const ADMIN_GROUP_TTL: Duration = Duration::from_secs(60);
+
+/// グループのメンバーと取った時刻
+type GroupMembers = Option<(Vec<Uuid>, Instant)>;
+
+/// 管理者。traQ IDかグループのメンバーで指定する。どちらも無ければ誰も管理者でない
+#[derive(Clone, Debug)]
+pub struct AdminConfig {
+    pub users: Vec<String>,
+    pub group: Option<Uuid>,
+    /// グループのメンバーを取り直すまでの期間
+    pub group_ttl: Duration,
+    /// 最後に取ったグループのメンバー
+    group_members: Arc<Mutex<GroupMembers>>,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self::new(std::iter::empty())
+    }
+}
+
+impl AdminConfig {
+    pub fn new<'s>(users: impl Iterator<Item = &'s str>) -> Self {
+        Self {
+            users: users.map(|s| s.to_string()).collect(),
+            group: None,
+            group_ttl: ADMIN_GROUP_TTL,
+            group_members: Arc::default(),
+        }
+    }
+
+    pub fn load_env() -> Result<Self, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        use std::env::var;
+        let users = var("ADMIN_USERS").unwrap_or_default();
+        let config = Self::new(users.split(' ').filter(|s| !s.is_empty()));
+        match var("ADMIN_GROUP") {
+            Ok(group) if !group.is_empty() => Ok(config.group(group.parse()?)),
+            _ => Ok(config),
+        }
+    }
+
+    pub fn group(self, group: Uuid) -> Self {
+        Self {
+            group: Some(group),
+            ..self
+        }
+    }
+
+    pub fn group_ttl(self, ttl: Duration) -> Self {
+        Self {
+            group_ttl: ttl,
+            ..self
+        }
+    }
+
+    async fn is_admin(&self, user: &User, bot_client: &BC) -> anyhow::Result<bool> {
+        if self.users.contains(&user.name) {
+            return Ok(true);
+        }
+        let Some(group) = self.group else {
+            return Ok(false);
+        };
+        let cached = self
+            .group_members
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|(_, fetched_at)| fetched_at.elapsed() < self.group_ttl)
+            .map(|(members, _)| members.contains(&user.id));
+        if let Some(is_member) = cached {
+            return Ok(is_member);
+        }
+        // 配信時のグループ展開と違い、管理者の判定は少し古いメンバーでよい
+        let group = bot_client.0.get_user_group(&group.to_string()).await?;
+        let members: Vec<Uuid> = group.members.iter().map(|m| m.id).collect();
+        let is_member = members.contains(&user.id);
+        *self.group_members.lock().unwrap() = Some((members, Instant::now()));
+        Ok(is_member)
+    }
+}
+
+/// `AuthUser`のうち管理者のみ
+#[derive(Debug)]
+pub struct AdminUser(pub User);
+
+#[async_trait]
+impl<'r> FromRequest<'r> for AdminUser {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let user = match req.guard::<AuthUser>().await {
+            Outcome::Success(AuthUser(Some(user))) => user,
+            Outcome::Success(AuthUser(None)) => {
+                return Outcome::Error((Status::Unauthorized, ()));
+            }
+            Outcome::Error(e) => return Outcome::Error(e),
+            Outcome::Forward(s) => return Outcome::Forward(s),
+        };
+        let Some(config) = req.rocket().state::<AdminConfig>() else {
+            return Outcome::Error((Status::Forbidden, ()));
+        };
+        let Some(bot_client) = req.rocket().state::<BC>() else {
+            eprintln!("BC unmanaged");
+            return Outcome::Error((Status::InternalServerError, ()));
+        };
+        match config.is_admin(&user, bot_client).await {
+            Ok(true) => Outcome::Success(AdminUser(user)),
+            Ok(false) => Outcome::Error((Status::Forbidden, ())),
+            Err(e) => {
+                eprintln!("error in checking admin: {}", e);
+                Outcome::Error((Status::InternalServerError, ()))
+            }
+        }
+    }
+}
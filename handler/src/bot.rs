@@ -2,6 +2,7 @@ use async_trait::async_trait;
 use rocket::data::{Data, FromData, Outcome, ToByteUnit};
 use rocket::http::Status;
 use rocket::request::Request;
+use rocket::State;
 
 use traq_bot_http::{Event, RequestParser};
 
@@ -20,6 +21,19 @@ impl From<BotEvent> for Event {
     }
 }
 
+pub type EventListener = Box<dyn Fn(&Event) + Send + Sync>;
+
+/// BOTイベントを受け取ったときに呼ばれるコールバックの一覧
+#[derive(Default)]
+pub struct EventListeners(pub Vec<EventListener>);
+
+impl EventListeners {
+    pub fn listen(mut self, listener: impl Fn(&Event) + Send + Sync + 'static) -> Self {
+        self.0.push(Box::new(listener));
+        self
+    }
+}
+
 #[async_trait]
 impl<'a> FromData<'a> for BotEvent {
     type Error = ();
@@ -53,8 +67,11 @@ impl<'a> FromData<'a> for BotEvent {
 }
 
 #[rocket::post("/", data = "<event>")]
-pub async fn bot_event(event: BotEvent) -> Status {
+pub async fn bot_event(event: BotEvent, listeners: &State<EventListeners>) -> Status {
     println!("event kind: {}", event.0.kind());
+    for listener in &listeners.0 {
+        listener(&event.0);
+    }
     // match event.0 {
     //     _ => (),
     // };
@@ -0,0 +1,140 @@
+use rocket::http::hyper::header::{CACHE_CONTROL, ETAG, IF_MATCH, IF_NONE_MATCH, LAST_MODIFIED};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::response::{Responder, Response};
+use rocket::{async_trait, Request};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// 変更されうるもの。毎回ETagで再検証させる
+    Revalidate,
+    /// 指定秒数はキャッシュしてよい
+    MaxAge(u32),
+    /// IDに対して内容が変わらないもの
+    Immutable,
+}
+
+impl CachePolicy {
+    pub fn header_value(&self) -> String {
+        match self {
+            CachePolicy::Revalidate => "private, no-cache".to_string(),
+            CachePolicy::MaxAge(secs) => format!("private, max-age={}", secs),
+            CachePolicy::Immutable => "private, max-age=31536000, immutable".to_string(),
+        }
+    }
+}
+
+/// 内容のSHA-256から作る強いETag
+pub fn content_etag(content: &[u8]) -> String {
+    let digest = Sha256::digest(content);
+    format!("\"{}\"", hex::encode(&digest[..16]))
+}
+
+/// `If-None-Match`のいずれかが`etag`と一致するか (弱い比較)
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let strip = |t: &str| t.trim().trim_start_matches("W/").to_string();
+    let etag = strip(etag);
+    if_none_match
+        .split(',')
+        .any(|t| t.trim() == "*" || strip(t) == etag)
+}
+
+/// ETag, Cache-Controlを付け、`If-None-Match`が一致すれば304を返すラッパー
+#[derive(Debug, Clone)]
+pub struct Cached<R> {
+    pub inner: R,
+    pub etag: String,
+    pub policy: CachePolicy,
+    pub last_modified: Option<String>,
+}
+
+impl<R> Cached<R> {
+    pub fn new(inner: R, etag: String, policy: CachePolicy) -> Self {
+        Self {
+            inner,
+            etag,
+            policy,
+            last_modified: None,
+        }
+    }
+
+    pub fn last_modified(self, last_modified: Option<String>) -> Self {
+        Self {
+            last_modified,
+            ..self
+        }
+    }
+}
+
+impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for Cached<R> {
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'o> {
+        let not_modified = request
+            .headers()
+            .get_one(IF_NONE_MATCH.as_str())
+            .is_some_and(|h| etag_matches(h, &self.etag));
+        let mut res = if not_modified {
+            Response::build().status(Status::NotModified).finalize()
+        } else {
+            self.inner.respond_to(request)?
+        };
+        res.set_raw_header(ETAG.as_str(), self.etag);
+        res.set_raw_header(CACHE_CONTROL.as_str(), self.policy.header_value());
+        if let Some(last_modified) = self.last_modified {
+            res.set_raw_header(LAST_MODIFIED.as_str(), last_modified);
+        }
+        Ok(res)
+    }
+}
+
+/// 版の番号から作る強いETag。`prefix`で種類を分ける
+pub fn version_etag(prefix: &str, version: u32) -> String {
+    format!("\"{}{}\"", prefix, version)
+}
+
+/// 更新前に`If-Match`で今の版を確かめさせる
+#[derive(Debug, Clone)]
+pub struct IfMatch(pub Option<String>);
+
+impl IfMatch {
+    /// 無ければ428、`etag`と一致しなければ412 (強い比較)。`etag`が`None`なら`*`も一致しない
+    pub fn check(&self, etag: Option<&str>) -> Result<(), Status> {
+        let if_match = self.0.as_deref().ok_or(Status::PreconditionRequired)?;
+        let matches = if_match.split(',').map(str::trim).any(|t| match etag {
+            Some(etag) => t == "*" || !t.starts_with("W/") && t == etag,
+            None => false,
+        });
+        matches.then_some(()).ok_or(Status::PreconditionFailed)
+    }
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for IfMatch {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let if_match = req.headers().get_one(IF_MATCH.as_str());
+        Outcome::Success(IfMatch(if_match.map(|h| h.to_string())))
+    }
+}
+
+/// 更新後の版のETagを付けるラッパー
+#[derive(Debug, Clone)]
+pub struct Tagged<R> {
+    pub inner: R,
+    pub etag: String,
+}
+
+impl<R> Tagged<R> {
+    pub fn new(inner: R, etag: String) -> Self {
+        Self { inner, etag }
+    }
+}
+
+impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for Tagged<R> {
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'o> {
+        let mut res = self.inner.respond_to(request)?;
+        res.set_raw_header(ETAG.as_str(), self.etag);
+        Ok(res)
+    }
+}
@@ -2,38 +2,216 @@ use std::io::Cursor;
 
 use async_trait::async_trait;
 use bytes::Bytes;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use rocket::data::{Data, FromData, Outcome, ToByteUnit};
 use rocket::http::{ContentType, Status};
 use rocket::response::Responder;
-use rocket::serde::json::Json;
+use rocket::serde::json::{Json, Value};
 use rocket::{Request, Response, Route, State};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use domain::bot_client::User;
-use domain::repository::{CardModel, DateTimeUtc, SaveCardParams};
+use domain::repository::{
+    AuditAction, CardModel, CardRole, CardStatus, DateTimeUtc, ImageKind, PatchCardParams,
+    SaveCardParams,
+};
+use domain::time_zone::{local_to_utc, parse_time_zone, to_local_rfc3339, DEFAULT_TIME_ZONE};
 
+use crate::audit::{image_digest, AuditEvent, RequestId};
 use crate::auth::AuthUser;
-use crate::{UuidParam, CR, IR};
+use crate::cache::{content_etag, version_etag, CachePolicy, Cached, IfMatch, Tagged};
+use crate::members::{card_role, CardMember};
+use crate::recurrence::{build_recurrence, RecurrenceRequest, RecurrenceResponse};
+use crate::resolve::{resolve_channels, resolve_users, ChannelRef, UserRef};
+use crate::revisions::{
+    check_current, latest_revision, next_revision, revision_etag, save_image, RevisionConfig,
+};
+use crate::{UuidParam, BC, CR, CRON, IR};
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(crate = "rocket::serde")]
 pub struct CardResponse {
     pub id: Uuid,
     pub owner_id: Uuid,
-    pub publish_date: DateTimeUtc,
+    /// 下書きなら`null`の場合がある
+    pub publish_date: Option<DateTimeUtc>,
+    /// `publish_date`を`time_zone`で表したもの
+    pub local_publish_date: Option<String>,
+    pub time_zone: String,
+    pub status: CardStatus,
     pub publish_channels: Vec<Uuid>,
+    pub recipients: Vec<Uuid>,
+    pub publish_groups: Vec<Uuid>,
+    /// 配信時点のグループメンバー
+    pub group_members: Vec<Uuid>,
+    /// 寄せ書きに招待された人
+    pub contributors: Vec<Uuid>,
+    /// 所有者を含む、カードを共同で作る人
+    pub members: Vec<CardMember>,
     pub message: Option<String>,
+    pub recurrence: Option<RecurrenceResponse>,
+    /// 更新のたびに増える。`PATCH`の`If-Match`にはこれから作るETagを使う
+    pub version: u32,
+    /// `?expand=owner`の時のみ埋める
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<CardOwner>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct CardOwner {
+    pub name: String,
+    pub display_name: String,
+    pub icon_url: String,
+}
+
+impl From<&User> for CardOwner {
+    fn from(user: &User) -> Self {
+        Self {
+            name: user.name.clone(),
+            display_name: user.display_name.clone(),
+            icon_url: format!("/api/users/{}/icon", user.id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(crate = "rocket::serde")]
 pub struct CardRequest {
     pub owner_id: Uuid,
-    pub publish_date: DateTimeUtc,
-    pub publish_channels: Vec<Uuid>,
+    /// オフセットの無い日時は`time_zone`の壁時計とみなす。省略すると下書きとして保存する
+    #[serde(default)]
+    pub publish_date: Option<PublishDate>,
+    /// IANAのタイムゾーン。省略すると作成時は`Asia/Tokyo`、更新時は元のまま
+    #[serde(default)]
+    pub time_zone: Option<String>,
+    /// UUIDまたは`#gps/times/foo`形式のパス
+    pub publish_channels: Vec<ChannelRef>,
+    /// UUIDまたは`@name`形式のユーザー名
+    #[serde(default)]
+    pub recipients: Vec<UserRef>,
+    /// 投稿先チャンネルがあればメンバーをメンションし、無ければ各メンバーのDMに送る
+    #[serde(default)]
+    pub publish_groups: Vec<Uuid>,
     pub message: Option<String>,
     pub images: Vec<Uuid>,
+    /// 指定すると`publish_date`を起点に繰り返し配信する
+    #[serde(default)]
+    pub recurrence: Option<RecurrenceRequest>,
+}
+
+/// `PATCH /api/cards/<id>`の本文。省略した項目は`None`、`null`は`Some(None)`
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+#[schemars(description = "JSON Merge Patch。省略した項目は変えず、`null`にした項目は消す")]
+pub struct CardPatch {
+    /// 変えられない。今の差出人と違えば400
+    #[serde(default)]
+    pub owner_id: Option<Uuid>,
+    /// オフセットの無い日時は`time_zone`の壁時計とみなす。予約済みのカードでは消せない
+    #[serde(default, deserialize_with = "nullable")]
+    pub publish_date: Option<Option<PublishDate>>,
+    /// 消せない。`null`なら400
+    #[serde(default, deserialize_with = "nullable")]
+    pub time_zone: Option<Option<String>>,
+    /// UUIDまたは`#gps/times/foo`形式のパス。`null`は空の配列と同じ
+    #[serde(default, deserialize_with = "nullable")]
+    pub publish_channels: Option<Option<Vec<ChannelRef>>>,
+    /// UUIDまたは`@name`形式のユーザー名。`null`は空の配列と同じ
+    #[serde(default, deserialize_with = "nullable")]
+    pub recipients: Option<Option<Vec<UserRef>>>,
+    /// `null`は空の配列と同じ
+    #[serde(default, deserialize_with = "nullable")]
+    pub publish_groups: Option<Option<Vec<Uuid>>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub message: Option<Option<String>>,
+    /// `null`にすると繰り返しをやめる
+    #[serde(default, deserialize_with = "nullable")]
+    pub recurrence: Option<Option<RecurrenceRequest>>,
+}
+
+/// 項目があれば`null`でも`Some`にする。省略は`#[serde(default)]`で`None`
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: rocket::serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// `2024-12-24T09:00:00+09:00`のようなオフセット付きの日時、または`2024-12-24T09:00`のような壁時計の日時
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum PublishDate {
+    Absolute(DateTimeUtc),
+    Local(NaiveDateTime),
+}
+
+impl JsonSchema for PublishDate {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "PublishDate".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let mut schema = String::json_schema(gen).into_object();
+        schema
+            .extensions
+            .insert("example".to_string(), "2024-12-24T09:00".into());
+        schema.into()
+    }
+}
+
+impl TryFrom<String> for PublishDate {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if let Ok(at) = DateTime::parse_from_rfc3339(&value) {
+            return Ok(Self::Absolute(at.with_timezone(&Utc)));
+        }
+        ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M"]
+            .iter()
+            .find_map(|f| NaiveDateTime::parse_from_str(&value, f).ok())
+            .map(Self::Local)
+            .ok_or_else(|| format!("invalid date time `{}`", value))
+    }
+}
+
+impl From<PublishDate> for String {
+    fn from(value: PublishDate) -> Self {
+        match value {
+            PublishDate::Absolute(at) => at.to_rfc3339(),
+            PublishDate::Local(at) => at.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        }
+    }
+}
+
+/// タイムゾーンと投稿日時を検証してUTCにする
+fn resolve_publish_date(
+    publish_date: Option<PublishDate>,
+    time_zone: &str,
+) -> Result<Option<DateTimeUtc>, Status> {
+    let tz = parse_time_zone(time_zone).map_err(|e| {
+        eprintln!("{}", e);
+        Status::BadRequest
+    })?;
+    let Some(publish_date) = publish_date else {
+        return Ok(None);
+    };
+    match publish_date {
+        PublishDate::Absolute(at) => Ok(Some(at)),
+        PublishDate::Local(at) => local_to_utc(at, tz).map(Some).map_err(|e| {
+            eprintln!("{}", e);
+            Status::BadRequest
+        }),
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -80,7 +258,7 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for Svg {
 }
 
 #[derive(Debug, Clone)]
-pub struct Png(Bytes);
+pub struct Png(pub(crate) Bytes);
 
 #[async_trait]
 impl<'a> FromData<'a> for Png {
@@ -120,17 +298,55 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for Png {
     }
 }
 
-/// `自分のもの || 投稿済み` ならば閲覧可能(削除・編集は別)
-fn visible_card(user: &User, card: &CardModel, now: DateTimeUtc) -> bool {
-    user.id == card.owner_id || card.publish_date <= now
+/// `メンバー || 寄せ書きの参加者 || (投稿済み && (チャンネルに投稿 || 自分宛て || 宛先グループのメンバー))` ならば閲覧可能(削除・編集は別)
+///
+/// 繰り返しのカードは1回でも配信していれば投稿済みとみなす
+fn visible_card(user: &User, card: &CardResponse) -> bool {
+    let published = card.status.is_published()
+        || card
+            .recurrence
+            .as_ref()
+            .is_some_and(|r| r.last_occurrence.is_some());
+    card.members.iter().any(|m| m.user_id == user.id)
+        || card.contributors.contains(&user.id)
+        || published
+            && (!card.publish_channels.is_empty()
+                || card.recipients.contains(&user.id)
+                || card.group_members.contains(&user.id))
 }
 
-/// `自分のもの && 未投稿` ならば編集可能(削除含む)
-fn editable_card(user: &User, card: &CardModel, now: DateTimeUtc) -> bool {
-    user.id == card.owner_id && card.publish_date > now
+async fn get_group_members(card_id: Uuid, card_repo: &State<CR>) -> anyhow::Result<Vec<Uuid>> {
+    let mut members: Vec<_> = card_repo
+        .0
+        .get_delivery_logs_by_id(card_id)
+        .await?
+        .into_iter()
+        .map(|l| l.user_id)
+        .collect();
+    members.sort();
+    members.dedup();
+    Ok(members)
+}
+
+/// `所有者か編集者 && (下書き || 予約済み)` ならば編集可能
+pub(crate) async fn editable_card(
+    user: &User,
+    card: &CardModel,
+    card_repo: &State<CR>,
+) -> Result<bool, Status> {
+    if !card.status.is_editable() {
+        return Ok(false);
+    }
+    let role = card_role(user.id, card.id, card_repo).await?;
+    Ok(role.is_some_and(CardRole::can_edit))
 }
 
-async fn complete_card_response_one(
+/// カードのETag。版の番号から作る
+pub(crate) fn card_etag(version: u32) -> String {
+    version_etag("v", version)
+}
+
+pub(crate) async fn complete_card_response_one(
     model: &CardModel,
     card_repo: &State<CR>,
 ) -> anyhow::Result<CardResponse> {
@@ -139,52 +355,153 @@ async fn complete_card_response_one(
         owner_id,
         publish_date,
         message,
+        status,
+        time_zone,
+        version,
+        deleted_at: _,
     } = model;
     let publish_channels = card_repo.0.get_publish_channels_by_id(*id).await?;
+    let recipients = card_repo.0.get_recipients_by_id(*id).await?;
+    let publish_groups = card_repo.0.get_publish_groups_by_id(*id).await?;
+    let group_members = get_group_members(*id, card_repo).await?;
+    let recurrence = card_repo.0.get_recurrence(*id).await?;
+    let contributors = card_repo
+        .0
+        .get_contributions(*id)
+        .await?
+        .into_iter()
+        .map(|c| c.user_id)
+        .collect();
+    let members = card_repo
+        .0
+        .get_card_members(*id)
+        .await?
+        .into_iter()
+        .map(CardMember::from)
+        .collect();
     let res = CardResponse {
         id: *id,
         owner_id: *owner_id,
         publish_date: *publish_date,
+        local_publish_date: parse_time_zone(time_zone)
+            .ok()
+            .and_then(|tz| publish_date.map(|d| to_local_rfc3339(d, tz))),
+        time_zone: time_zone.clone(),
+        status: *status,
         publish_channels,
+        recipients,
+        publish_groups,
+        group_members,
+        contributors,
+        members,
         message: message.clone(),
+        recurrence: recurrence.map(RecurrenceResponse::from),
+        version: *version,
+        owner: None,
     };
     Ok(res)
 }
 
-async fn complete_card_response(
+pub(crate) async fn complete_card_response(
     models: &[CardModel],
     card_repo: &State<CR>,
 ) -> anyhow::Result<Vec<CardResponse>> {
     let mut completed = vec![];
-    for CardModel {
-        id,
-        owner_id,
-        publish_date,
-        message,
-    } in models
-    {
+    for model in models {
         // WARN: N+1
-        let publish_channels = card_repo.0.get_publish_channels_by_id(*id).await?;
-        let res = CardResponse {
-            id: *id,
-            owner_id: *owner_id,
-            publish_date: *publish_date,
-            publish_channels,
-            message: message.clone(),
-        };
-        completed.push(res);
+        completed.push(complete_card_response_one(model, card_repo).await?);
     }
     Ok(completed)
 }
 
-#[rocket::get("/")]
+pub(crate) async fn with_owners(
+    mut cards: Vec<CardResponse>,
+    expand: Option<&str>,
+    client: &State<BC>,
+) -> Result<Vec<CardResponse>, Status> {
+    if expand_owner(expand) {
+        fill_owners(&mut cards, client).await.map_err(|e| {
+            eprintln!("error in expanding owners: {}", e);
+            Status::InternalServerError
+        })?;
+    }
+    Ok(cards)
+}
+
+/// `?expand=owner`(カンマ区切り)が指定されているか
+fn expand_owner(expand: Option<&str>) -> bool {
+    expand.is_some_and(|e| e.split(',').any(|e| e.trim() == "owner"))
+}
+
+/// 全ユーザー一覧から所有者を一括で引いて埋める
+async fn fill_owners(cards: &mut [CardResponse], client: &State<BC>) -> anyhow::Result<()> {
+    let mut ids: Vec<_> = cards.iter().map(|c| c.owner_id).collect();
+    ids.sort();
+    ids.dedup();
+    let users = client.0.get_users_by_ids(&ids).await?;
+    for card in cards {
+        card.owner = users
+            .iter()
+            .find(|u| u.id == card.owner_id)
+            .map(CardOwner::from);
+    }
+    Ok(())
+}
+
+/// 監査ログ用のカードの状態。取れなければ`None`
+pub(crate) async fn snapshot(id: Uuid, card_repo: &State<CR>) -> Option<CardResponse> {
+    let card = card_repo
+        .0
+        .get_card_by_id(id)
+        .await
+        .map_err(|e| eprintln!("error in get card by id: {}", e))
+        .ok()??;
+    complete_card_response_one(&card, card_repo)
+        .await
+        .map_err(|e| eprintln!("error in complete card response: {}", e))
+        .ok()
+}
+
+async fn svg_digest(id: Uuid, image_repo: &State<IR>) -> Option<Value> {
+    let svg = image_repo
+        .0
+        .get_svg(id)
+        .await
+        .map_err(|e| eprintln!("error in get svg: {}", e))
+        .ok()??;
+    Some(image_digest(svg.as_bytes()))
+}
+
+async fn png_digest(id: Uuid, image_repo: &State<IR>) -> Option<Value> {
+    let png = image_repo
+        .0
+        .get_png(id)
+        .await
+        .map_err(|e| eprintln!("error in get png: {}", e))
+        .ok()??;
+    Some(image_digest(&png))
+}
+
+#[rocket::get("/?<expand>")]
 pub async fn get_all(
+    expand: Option<&str>,
     card_repo: &State<CR>,
+    client: &State<BC>,
     user: AuthUser,
 ) -> Result<(Status, Json<Vec<CardResponse>>), Status> {
     let user = user.0.ok_or(Status::Unauthorized)?;
-    let now = chrono::Utc::now();
 
+    let member_of: Vec<_> = card_repo
+        .0
+        .get_my_cards(user.id)
+        .await
+        .map_err(|e| {
+            eprintln!("Error in get my cards: {}", e);
+            Status::InternalServerError
+        })?
+        .into_iter()
+        .map(|c| c.id)
+        .collect();
     let card_models: Vec<_> = card_repo
         .0
         .get_all_cards()
@@ -194,14 +511,18 @@ pub async fn get_all(
             Status::InternalServerError
         })?
         .into_iter()
-        .filter(|c| visible_card(&user, c, now))
+        .filter(|c| member_of.contains(&c.id) || c.status != CardStatus::Draft)
         .collect();
     let response = complete_card_response(&card_models, card_repo)
         .await
         .map_err(|e| {
             eprintln!("Error in fetching publish dates: {}", e);
             Status::InternalServerError
-        })?;
+        })?
+        .into_iter()
+        .filter(|c| visible_card(&user, c))
+        .collect();
+    let response = with_owners(response, expand, client).await?;
     Ok((Status::Ok, Json(response)))
 }
 
@@ -209,7 +530,9 @@ pub async fn get_all(
 pub async fn post(
     card: Json<CardRequest>,
     card_repo: &State<CR>,
+    client: &State<BC>,
     user: AuthUser,
+    request_id: RequestId,
 ) -> Result<(Status, String), Status> {
     // TODO: imagesのIDをDBにcard_idとのrelationで入れたい
     // GCのため
@@ -217,30 +540,62 @@ pub async fn post(
     let CardRequest {
         owner_id,
         publish_date,
+        time_zone,
         publish_channels,
+        recipients,
+        publish_groups,
         message,
         images: _image,
+        recurrence,
     } = card.0;
     if user.id != owner_id {
         return Err(Status::Forbidden);
     }
+    let id = Uuid::new_v4();
+    let time_zone = time_zone.unwrap_or_else(|| DEFAULT_TIME_ZONE.to_string());
+    let publish_date = resolve_publish_date(publish_date, &time_zone)?;
+    let (recurrence, publish_date) = match recurrence {
+        Some(r) => {
+            let (recurrence, first) = build_recurrence(id, r, publish_date, &time_zone, None)?;
+            (Some(recurrence), Some(first))
+        }
+        None => (None, publish_date),
+    };
+    let status = if publish_date.is_some() {
+        CardStatus::Scheduled
+    } else {
+        CardStatus::Draft
+    };
     let params = SaveCardParams {
-        id: Uuid::new_v4(),
+        id,
         owner_id,
         publish_date,
         message,
-        channels: publish_channels,
+        status,
+        time_zone,
+        channels: resolve_channels(&publish_channels, client).await?,
+        recipients: resolve_users(&recipients, client).await?,
+        groups: publish_groups,
+        recurrence,
     };
     card_repo.0.save_card(&params).await.map_err(|e| {
         eprintln!("error in post card: {}", e);
         Status::InternalServerError
     })?;
+    let after = snapshot(id, card_repo).await;
+    AuditEvent::new(AuditAction::CardCreate, Some(&user), &request_id)
+        .card(id)
+        .change(None, after.as_ref())
+        .record(card_repo)
+        .await;
     Ok((Status::Ok, params.id.to_string()))
 }
 
-#[rocket::get("/me")]
+#[rocket::get("/me?<expand>")]
 pub async fn get_mine(
+    expand: Option<&str>,
     card_repo: &State<CR>,
+    client: &State<BC>,
     user: AuthUser,
 ) -> Result<(Status, Json<Vec<CardResponse>>), Status> {
     let user = user.0.ok_or(Status::Unauthorized)?;
@@ -254,17 +609,59 @@ pub async fn get_mine(
             eprintln!("error in completing publish dates: {}", e);
             Status::InternalServerError
         })?;
+    let response = with_owners(response, expand, client).await?;
+    Ok((Status::Ok, Json(response)))
+}
+
+/// 寄せ書きに招待されているカード一覧
+#[rocket::get("/contributing?<expand>")]
+pub async fn get_contributing(
+    expand: Option<&str>,
+    card_repo: &State<CR>,
+    client: &State<BC>,
+    user: AuthUser,
+) -> Result<(Status, Json<Vec<CardResponse>>), Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let contributions = card_repo
+        .0
+        .get_contributions_by_user(user.id)
+        .await
+        .map_err(|e| {
+            eprintln!("Error in get contributions: {}", e);
+            Status::InternalServerError
+        })?;
+    let mut card_models = vec![];
+    for contribution in contributions {
+        // WARN: N+1
+        let card = card_repo
+            .0
+            .get_card_by_id(contribution.card_id)
+            .await
+            .map_err(|e| {
+                eprintln!("error in get card by id: {}", e);
+                Status::InternalServerError
+            })?;
+        card_models.extend(card);
+    }
+    let response = complete_card_response(&card_models, card_repo)
+        .await
+        .map_err(|e| {
+            eprintln!("error in completing publish dates: {}", e);
+            Status::InternalServerError
+        })?;
+    let response = with_owners(response, expand, client).await?;
     Ok((Status::Ok, Json(response)))
 }
 
-#[rocket::get("/<id>")]
+#[rocket::get("/<id>?<expand>")]
 pub async fn get_one(
     id: UuidParam,
+    expand: Option<&str>,
     card_repo: &State<CR>,
+    client: &State<BC>,
     user: AuthUser,
-) -> Result<(Status, Json<CardResponse>), Status> {
+) -> Result<Tagged<(Status, Json<CardResponse>)>, Status> {
     let user = user.0.ok_or(Status::Unauthorized)?;
-    let now = chrono::Utc::now();
     let card_model = card_repo
         .0
         .get_card_by_id(id.0)
@@ -274,25 +671,36 @@ pub async fn get_one(
             Status::InternalServerError
         })?
         .ok_or(Status::NotFound)?;
-    if !visible_card(&user, &card_model, now) {
-        return Err(Status::NotFound);
-    }
     let res = complete_card_response_one(&card_model, card_repo)
         .await
         .map_err(|e| {
             eprintln!("error in complete publish date: {}", e);
             Status::InternalServerError
         })?;
-    Ok((Status::Ok, Json(res)))
+    if !visible_card(&user, &res) {
+        return Err(Status::NotFound);
+    }
+    let res = with_owners(vec![res], expand, client)
+        .await?
+        .pop()
+        .ok_or(Status::InternalServerError)?;
+    Ok(Tagged::new(
+        (Status::Ok, Json(res)),
+        card_etag(card_model.version),
+    ))
 }
 
-#[rocket::patch("/<id>", data = "<card>")]
+/// JSON Merge Patch (RFC 7396)。省略した項目は変えず、`null`にした項目は消す
+#[rocket::patch("/<id>", data = "<patch>")]
 pub async fn update(
     id: UuidParam,
-    card: Json<CardRequest>,
+    patch: Json<CardPatch>,
     card_repo: &State<CR>,
+    client: &State<BC>,
+    if_match: IfMatch,
     user: AuthUser,
-) -> Result<Status, Status> {
+    request_id: RequestId,
+) -> Result<Tagged<Status>, Status> {
     let id = id.0;
     let user = user.0.ok_or(Status::Unauthorized)?;
     let card_model = card_repo
@@ -304,42 +712,116 @@ pub async fn update(
             Status::InternalServerError
         })?
         .ok_or(Status::NotFound)?;
-    let now = chrono::Utc::now();
-    if !editable_card(&user, &card_model, now) {
+    if !editable_card(&user, &card_model, card_repo).await? {
         return Err(Status::Forbidden);
     }
-    let CardRequest {
+    if_match.check(Some(&card_etag(card_model.version)))?;
+    let CardPatch {
         owner_id,
         publish_date,
+        time_zone,
         publish_channels,
+        recipients,
+        publish_groups,
         message,
-        images: _image,
-    } = card.0;
-    let params = SaveCardParams {
+        recurrence,
+    } = patch.0;
+    // 差出人は`transfer`でのみ変えられる
+    if owner_id.is_some_and(|o| o != card_model.owner_id) {
+        return Err(Status::BadRequest);
+    }
+    let time_zone = match time_zone {
+        Some(None) => return Err(Status::BadRequest),
+        t => t.flatten(),
+    };
+    let publish_channels = publish_channels.map(Option::unwrap_or_default);
+    let recipients = recipients.map(Option::unwrap_or_default);
+    let publish_groups = publish_groups.map(Option::unwrap_or_default);
+    let tz = time_zone
+        .clone()
+        .unwrap_or_else(|| card_model.time_zone.clone());
+    let publish_date = match publish_date {
+        Some(p) => Some(resolve_publish_date(p, &tz)?),
+        None => {
+            resolve_publish_date(None, &tz)?;
+            None
+        }
+    };
+    let current = card_repo.0.get_recurrence(id).await.map_err(|e| {
+        eprintln!("error in get recurrence: {}", e);
+        Status::InternalServerError
+    })?;
+    // 投稿日時かタイムゾーンだけ変えた時は、今の規則を新しい起点で作り直す
+    let recurrence = match recurrence {
+        None if publish_date.is_some() || time_zone.is_some() => current.as_ref().map(|c| {
+            Some(RecurrenceRequest {
+                rrule: c.rrule.clone(),
+                time_zone: Some(c.time_zone.clone()),
+            })
+        }),
+        r => r,
+    };
+    let (recurrence, publish_date) = match recurrence {
+        Some(Some(r)) => {
+            let date = publish_date.unwrap_or(card_model.publish_date);
+            let current = current.as_ref().map(|c| (c, card_model.publish_date));
+            let (recurrence, first) = build_recurrence(id, r, date, &tz, current)?;
+            (Some(Some(recurrence)), Some(Some(first)))
+        }
+        r => (r.map(|_| None), publish_date),
+    };
+    // 予約済みのカードは投稿日時を消せない。先に`unschedule`する
+    if card_model.status == CardStatus::Scheduled && publish_date == Some(None) {
+        return Err(Status::BadRequest);
+    }
+    let channels = match publish_channels {
+        Some(c) => Some(resolve_channels(&c, client).await?),
+        None => None,
+    };
+    let recipients = match recipients {
+        Some(r) => Some(resolve_users(&r, client).await?),
+        None => None,
+    };
+    let params = PatchCardParams {
         id,
-        owner_id,
+        version: card_model.version,
         publish_date,
         message,
-        channels: publish_channels,
+        time_zone,
+        channels,
+        recipients,
+        groups: publish_groups,
+        recurrence,
     };
+    let before = snapshot(id, card_repo).await;
     card_repo
         .0
-        .update_card(&params)
+        .patch_card(&params)
         .await
         .map_err(|e| {
-            eprintln!("error in update card: {}", e);
+            eprintln!("error in patch card: {}", e);
             Status::InternalServerError
         })?
-        .ok_or(Status::NotFound)?;
-    Ok(Status::NoContent)
+        // 読んでから更新するまでに他の更新があった
+        .ok_or(Status::PreconditionFailed)?;
+    let after = snapshot(id, card_repo).await;
+    AuditEvent::new(AuditAction::CardUpdate, Some(&user), &request_id)
+        .card(id)
+        .change(before.as_ref(), after.as_ref())
+        .record(card_repo)
+        .await;
+    Ok(Tagged::new(
+        Status::NoContent,
+        card_etag(card_model.version + 1),
+    ))
 }
 
 #[rocket::delete("/<id>")]
 pub async fn delete_one(
     id: UuidParam,
     card_repo: &State<CR>,
-    image_repo: &State<IR>,
     user: AuthUser,
+    request_id: RequestId,
 ) -> Result<Status, Status> {
     let user = user.0.ok_or(Status::Unauthorized)?;
 
@@ -354,47 +836,29 @@ pub async fn delete_one(
             Status::InternalServerError
         })?
         .ok_or(Status::NotFound)?;
-    let now = chrono::Utc::now();
-    if !editable_card(&user, &card, now) {
+    // 削除は所有者のみ
+    let role = card_role(user.id, id, card_repo).await?;
+    if !role.is_some_and(CardRole::can_manage) || !card.status.is_editable() {
         return Err(Status::Forbidden);
     }
 
+    let before = snapshot(id, card_repo).await;
+    // 画像や付随する行は、復元できる期間が過ぎてからcronで消す
     card_repo
         .0
-        .delete_card(id)
+        .trash_card(id, Utc::now())
         .await
         .map_err(|e| {
-            eprintln!("error in delete card: {}", e);
+            eprintln!("error in trash card: {}", e);
             Status::InternalServerError
         })?
-        .ok_or(Status::NotFound)?;
-    let pub_chans = card_repo
-        .0
-        .get_publish_channels_by_id(id)
-        .await
-        .map_err(|e| {
-            eprintln!("error in get pub_chans: {}", e);
-            Status::InternalServerError
-        })?;
-    for channel_id in pub_chans {
-        card_repo
-            .0
-            .delete_publish_channel(id, channel_id)
-            .await
-            .map_err(|e| {
-                eprintln!("error in delete publish channel: {}", e);
-                Status::InternalServerError
-            })?
-            .ok_or(Status::InternalServerError)?;
-    }
-    image_repo.0.delete_svg(id).await.map_err(|e| {
-        eprintln!("error in delete svg: {}", e);
-        Status::InternalServerError
-    })?;
-    image_repo.0.delete_png(id).await.map_err(|e| {
-        eprintln!("error in delete png: {}", e);
-        Status::InternalServerError
-    })?;
+        // 確認してから配信が始まった
+        .ok_or(Status::Conflict)?;
+    AuditEvent::new(AuditAction::CardDelete, Some(&user), &request_id)
+        .card(id)
+        .change(before.as_ref(), None)
+        .record(card_repo)
+        .await;
     Ok(Status::NoContent)
 }
 
@@ -404,7 +868,7 @@ pub async fn get_svg(
     card_repo: &State<CR>,
     image_repo: &State<IR>,
     user: AuthUser,
-) -> Result<Svg, Status> {
+) -> Result<Cached<Svg>, Status> {
     let user = user.0.ok_or(Status::Unauthorized)?;
     let card_model = card_repo
         .0
@@ -415,8 +879,13 @@ pub async fn get_svg(
             Status::InternalServerError
         })?
         .ok_or(Status::NotFound)?;
-    let now = chrono::Utc::now();
-    if !visible_card(&user, &card_model, now) {
+    let card = complete_card_response_one(&card_model, card_repo)
+        .await
+        .map_err(|e| {
+            eprintln!("error in complete publish date: {}", e);
+            Status::InternalServerError
+        })?;
+    if !visible_card(&user, &card) {
         return Err(Status::NotFound);
     }
     let res = image_repo
@@ -428,7 +897,11 @@ pub async fn get_svg(
             Status::InternalServerError
         })?
         .ok_or(Status::NotFound)?;
-    Ok(Svg(res))
+    let etag = match latest_revision(id.0, ImageKind::Svg, card_repo).await? {
+        Some(revision) => revision_etag(revision),
+        None => content_etag(res.as_bytes()),
+    };
+    Ok(Cached::new(Svg(res), etag, CachePolicy::Revalidate))
 }
 
 #[rocket::post("/<id>/svg", data = "<svg>")]
@@ -437,7 +910,9 @@ pub async fn post_svg(
     id: UuidParam,
     card_repo: &State<CR>,
     image_repo: &State<IR>,
+    revision_config: &State<RevisionConfig>,
     user: AuthUser,
+    request_id: RequestId,
 ) -> Result<Status, Status> {
     let user = user.0.ok_or(Status::Unauthorized)?;
     let card = card_repo
@@ -449,25 +924,45 @@ pub async fn post_svg(
             Status::InternalServerError
         })?
         .ok_or(Status::NotFound)?;
-    let now = chrono::Utc::now();
-    if !editable_card(&user, &card, now) {
+    if !editable_card(&user, &card, card_repo).await? {
         return Err(Status::Forbidden);
     }
-    image_repo.0.save_svg(id.0, &svg.0).await.map_err(|e| {
-        eprintln!("error in create svg: {}", e);
-        Status::InternalServerError
-    })?;
+    let before = svg_digest(id.0, image_repo).await;
+    let next = next_revision(id.0, ImageKind::Svg, card_repo).await?;
+    let revision = save_image(
+        id.0,
+        ImageKind::Svg,
+        next,
+        &Bytes::from(svg.0.clone()),
+        &user,
+        card_repo,
+        image_repo,
+        revision_config,
+    )
+    .await?
+    .ok_or(Status::Conflict)?;
+    let mut after = image_digest(svg.0.as_ref());
+    after["revision"] = revision.revision.into();
+    AuditEvent::new(AuditAction::CardSvg, Some(&user), &request_id)
+        .card(id.0)
+        .change(before.as_ref(), Some(&after))
+        .record(card_repo)
+        .await;
     Ok(Status::NoContent)
 }
 
 #[rocket::patch("/<id>/svg", data = "<svg>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn patch_svg(
     svg: Svg,
     id: UuidParam,
     card_repo: &State<CR>,
     image_repo: &State<IR>,
+    revision_config: &State<RevisionConfig>,
+    if_match: IfMatch,
     user: AuthUser,
-) -> Result<Status, Status> {
+    request_id: RequestId,
+) -> Result<Tagged<Status>, Status> {
     let user = user.0.ok_or(Status::Unauthorized)?;
     let card = card_repo
         .0
@@ -478,15 +973,34 @@ pub async fn patch_svg(
             Status::InternalServerError
         })?
         .ok_or(Status::NotFound)?;
-    let now = chrono::Utc::now();
-    if user.id != card.owner_id || card.publish_date <= now {
+    if !editable_card(&user, &card, card_repo).await? {
         return Err(Status::Forbidden);
     }
-    image_repo.0.save_svg(id.0, &svg.0).await.map_err(|e| {
-        eprintln!("error in update svg: {}", e);
-        Status::InternalServerError
-    })?;
-    Ok(Status::NoContent)
+    let next = check_current(id.0, ImageKind::Svg, &if_match, card_repo, image_repo).await?;
+    let before = svg_digest(id.0, image_repo).await;
+    let revision = save_image(
+        id.0,
+        ImageKind::Svg,
+        next,
+        &Bytes::from(svg.0.clone()),
+        &user,
+        card_repo,
+        image_repo,
+        revision_config,
+    )
+    .await?
+    .ok_or(Status::PreconditionFailed)?;
+    let mut after = image_digest(svg.0.as_ref());
+    after["revision"] = revision.revision.into();
+    AuditEvent::new(AuditAction::CardSvg, Some(&user), &request_id)
+        .card(id.0)
+        .change(before.as_ref(), Some(&after))
+        .record(card_repo)
+        .await;
+    Ok(Tagged::new(
+        Status::NoContent,
+        revision_etag(revision.revision),
+    ))
 }
 
 #[rocket::get("/<id>/png")]
@@ -495,7 +1009,7 @@ pub async fn get_png(
     card_repo: &State<CR>,
     image_repo: &State<IR>,
     user: AuthUser,
-) -> Result<Png, Status> {
+) -> Result<Cached<Png>, Status> {
     let user = user.0.ok_or(Status::Unauthorized)?;
     let card = card_repo
         .0
@@ -506,8 +1020,13 @@ pub async fn get_png(
             Status::InternalServerError
         })?
         .ok_or(Status::NotFound)?;
-    let now = chrono::Utc::now();
-    if !visible_card(&user, &card, now) {
+    let card = complete_card_response_one(&card, card_repo)
+        .await
+        .map_err(|e| {
+            eprintln!("error in complete publish date: {}", e);
+            Status::InternalServerError
+        })?;
+    if !visible_card(&user, &card) {
         return Err(Status::NotFound);
     }
     let png = image_repo
@@ -519,7 +1038,11 @@ pub async fn get_png(
             Status::InternalServerError
         })?
         .ok_or(Status::NotFound)?;
-    Ok(Png(png))
+    let etag = match latest_revision(id.0, ImageKind::Png, card_repo).await? {
+        Some(revision) => revision_etag(revision),
+        None => content_etag(&png),
+    };
+    Ok(Cached::new(Png(png), etag, CachePolicy::Revalidate))
 }
 
 #[rocket::post("/<id>/png", data = "<png>")]
@@ -528,7 +1051,9 @@ pub async fn post_png(
     id: UuidParam,
     card_repo: &State<CR>,
     image_repo: &State<IR>,
+    revision_config: &State<RevisionConfig>,
     user: AuthUser,
+    request_id: RequestId,
 ) -> Result<Status, Status> {
     let user = user.0.ok_or(Status::Unauthorized)?;
     let card = card_repo
@@ -540,24 +1065,93 @@ pub async fn post_png(
             Status::InternalServerError
         })?
         .ok_or(Status::NotFound)?;
-    let now = chrono::Utc::now();
-    if !editable_card(&user, &card, now) {
+    if !editable_card(&user, &card, card_repo).await? {
         return Err(Status::Forbidden);
     }
-    image_repo.0.save_png(id.0, &png.0).await.map_err(|e| {
-        eprintln!("error in create png: {}", e);
-        Status::InternalServerError
-    })?;
+    let before = png_digest(id.0, image_repo).await;
+    let next = next_revision(id.0, ImageKind::Png, card_repo).await?;
+    let revision = save_image(
+        id.0,
+        ImageKind::Png,
+        next,
+        &png.0,
+        &user,
+        card_repo,
+        image_repo,
+        revision_config,
+    )
+    .await?
+    .ok_or(Status::Conflict)?;
+    let mut after = image_digest(png.0.as_ref());
+    after["revision"] = revision.revision.into();
+    AuditEvent::new(AuditAction::CardPng, Some(&user), &request_id)
+        .card(id.0)
+        .change(before.as_ref(), Some(&after))
+        .record(card_repo)
+        .await;
     Ok(Status::NoContent)
 }
 
 #[rocket::patch("/<id>/png", data = "<png>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn patch_png(
     png: Png,
     id: UuidParam,
     card_repo: &State<CR>,
     image_repo: &State<IR>,
+    revision_config: &State<RevisionConfig>,
+    if_match: IfMatch,
     user: AuthUser,
+    request_id: RequestId,
+) -> Result<Tagged<Status>, Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let card = card_repo
+        .0
+        .get_card_by_id(id.0)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get card by id: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)?;
+    if !editable_card(&user, &card, card_repo).await? {
+        return Err(Status::Forbidden);
+    }
+    let next = check_current(id.0, ImageKind::Png, &if_match, card_repo, image_repo).await?;
+    let before = png_digest(id.0, image_repo).await;
+    let revision = save_image(
+        id.0,
+        ImageKind::Png,
+        next,
+        &png.0,
+        &user,
+        card_repo,
+        image_repo,
+        revision_config,
+    )
+    .await?
+    .ok_or(Status::PreconditionFailed)?;
+    let mut after = image_digest(png.0.as_ref());
+    after["revision"] = revision.revision.into();
+    AuditEvent::new(AuditAction::CardPng, Some(&user), &request_id)
+        .card(id.0)
+        .change(before.as_ref(), Some(&after))
+        .record(card_repo)
+        .await;
+    Ok(Tagged::new(
+        Status::NoContent,
+        revision_etag(revision.revision),
+    ))
+}
+
+/// 下書きを予約済みにする。未来の投稿日時とPNG、1つ以上の宛先が無ければ400
+#[rocket::post("/<id>/schedule")]
+pub async fn schedule(
+    id: UuidParam,
+    card_repo: &State<CR>,
+    image_repo: &State<IR>,
+    user: AuthUser,
+    request_id: RequestId,
 ) -> Result<Status, Status> {
     let user = user.0.ok_or(Status::Unauthorized)?;
     let card = card_repo
@@ -569,20 +1163,228 @@ pub async fn patch_png(
             Status::InternalServerError
         })?
         .ok_or(Status::NotFound)?;
-    let now = chrono::Utc::now();
-    if !editable_card(&user, &card, now) {
+    let role = card_role(user.id, card.id, card_repo).await?;
+    if !role.is_some_and(CardRole::can_edit) {
         return Err(Status::Forbidden);
     }
-    image_repo.0.save_png(id.0, &png.0).await.map_err(|e| {
-        eprintln!("error in update png: {}", e);
-        Status::InternalServerError
-    })?;
+    if card.status != CardStatus::Draft {
+        return Err(Status::Conflict);
+    }
+    if !card.publish_date.is_some_and(|d| d > chrono::Utc::now()) {
+        return Err(Status::BadRequest);
+    }
+    let deliverable = deliverable(card.id, card_repo, image_repo)
+        .await
+        .map_err(|e| {
+            eprintln!("error in checking deliverable: {}", e);
+            Status::InternalServerError
+        })?;
+    if !deliverable {
+        return Err(Status::BadRequest);
+    }
+    card_repo
+        .0
+        .update_card_status(id.0, &[CardStatus::Draft], CardStatus::Scheduled)
+        .await
+        .map_err(|e| {
+            eprintln!("error in update card status: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::Conflict)?;
+    AuditEvent::new(AuditAction::CardSchedule, Some(&user), &request_id)
+        .card(id.0)
+        .change(Some(&CardStatus::Draft), Some(&CardStatus::Scheduled))
+        .record(card_repo)
+        .await;
     Ok(Status::NoContent)
 }
 
+/// 予約を取り消して下書きに戻す
+#[rocket::post("/<id>/unschedule")]
+pub async fn unschedule(
+    id: UuidParam,
+    card_repo: &State<CR>,
+    user: AuthUser,
+    request_id: RequestId,
+) -> Result<Status, Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let card = card_repo
+        .0
+        .get_card_by_id(id.0)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get card by id: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)?;
+    let role = card_role(user.id, card.id, card_repo).await?;
+    if !role.is_some_and(CardRole::can_edit) {
+        return Err(Status::Forbidden);
+    }
+    if card.status != CardStatus::Scheduled {
+        return Err(Status::Conflict);
+    }
+    // 確認してから配信が始まっていれば`None`になる
+    card_repo
+        .0
+        .update_card_status(id.0, &[CardStatus::Scheduled], CardStatus::Draft)
+        .await
+        .map_err(|e| {
+            eprintln!("error in update card status: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::Conflict)?;
+    AuditEvent::new(AuditAction::CardUnschedule, Some(&user), &request_id)
+        .card(id.0)
+        .change(Some(&CardStatus::Scheduled), Some(&CardStatus::Draft))
+        .record(card_repo)
+        .await;
+    Ok(Status::NoContent)
+}
+
+/// 配信と同じ画像とメッセージを自分のDMに送って確かめる。カードは変えない。
+/// PNGか宛先が無ければ400
+#[rocket::post("/<id>/preview-delivery")]
+pub async fn preview_delivery(
+    id: UuidParam,
+    card_repo: &State<CR>,
+    image_repo: &State<IR>,
+    cron: &State<CRON>,
+    user: AuthUser,
+) -> Result<Status, Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let card = card_repo
+        .0
+        .get_card_by_id(id.0)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get card by id: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)?;
+    let role = card_role(user.id, card.id, card_repo).await?;
+    if !role.is_some_and(CardRole::can_edit) {
+        return Err(Status::Forbidden);
+    }
+    let deliverable = deliverable(card.id, card_repo, image_repo)
+        .await
+        .map_err(|e| {
+            eprintln!("error in checking deliverable: {}", e);
+            Status::InternalServerError
+        })?;
+    if !deliverable {
+        return Err(Status::BadRequest);
+    }
+    if !cron.0.preview(&card, user.id).await {
+        return Err(Status::BadGateway);
+    }
+    Ok(Status::NoContent)
+}
+
+/// PNGと1つ以上の宛先があり、配信できるか
+async fn deliverable(
+    id: Uuid,
+    card_repo: &State<CR>,
+    image_repo: &State<IR>,
+) -> anyhow::Result<bool> {
+    if image_repo.0.get_png(id).await?.is_none() {
+        return Ok(false);
+    }
+    Ok(
+        !card_repo.0.get_publish_channels_by_id(id).await?.is_empty()
+            || !card_repo.0.get_recipients_by_id(id).await?.is_empty()
+            || !card_repo.0.get_publish_groups_by_id(id).await?.is_empty(),
+    )
+}
+
+/// 投稿日時を待たずに配信する。繰り返しのカードは次の発生日時から通常通り続く。
+/// PNGか宛先が無ければ400。他で配信中で結果を待てなければ202
+#[rocket::post("/<id>/send-now")]
+pub async fn send_now(
+    id: UuidParam,
+    card_repo: &State<CR>,
+    image_repo: &State<IR>,
+    cron: &State<CRON>,
+    user: AuthUser,
+    request_id: RequestId,
+) -> Result<(Status, Json<CardResponse>), Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let card = card_repo
+        .0
+        .get_card_by_id(id.0)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get card by id: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)?;
+    let role = card_role(user.id, card.id, card_repo).await?;
+    if !role.is_some_and(CardRole::can_edit) {
+        return Err(Status::Forbidden);
+    }
+    // 配信に失敗すると編集できなくなるので、先に確かめる
+    let deliverable = deliverable(card.id, card_repo, image_repo)
+        .await
+        .map_err(|e| {
+            eprintln!("error in checking deliverable: {}", e);
+            Status::InternalServerError
+        })?;
+    if !deliverable {
+        return Err(Status::BadRequest);
+    }
+    let now = Utc::now();
+    let publish_date = card.publish_date.map_or(now, |d| d.min(now));
+    // 配信が始まっていれば`None`になる
+    card_repo
+        .0
+        .reschedule_card(
+            id.0,
+            &[CardStatus::Draft, CardStatus::Scheduled],
+            publish_date,
+        )
+        .await
+        .map_err(|e| {
+            eprintln!("error in reschedule card: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::Conflict)?;
+    AuditEvent::new(AuditAction::CardDeliver, Some(&user), &request_id)
+        .card(id.0)
+        .change(Some(&card.publish_date), Some(&Some(publish_date)))
+        .record(card_repo)
+        .await;
+    let delivered = cron.0.deliver(id.0).await;
+    let card = snapshot(id.0, card_repo)
+        .await
+        .ok_or(Status::InternalServerError)?;
+    match delivered {
+        // 繰り返しのカードは次の発生日時で予約し直されている
+        Some(CardStatus::Delivered | CardStatus::Scheduled) => Ok((Status::Ok, Json(card))),
+        Some(CardStatus::Failed) => Err(Status::BadGateway),
+        Some(_) => Err(Status::Conflict),
+        // 他の配信に先を越された。結果はまだ分からない
+        None => Ok((Status::Accepted, Json(card))),
+    }
+}
+
 pub fn routes() -> Vec<Route> {
     rocket::routes![
-        get_all, post, get_mine, get_one, delete_one, get_svg, post_svg, patch_svg, get_png,
-        post_png, patch_png
+        get_all,
+        post,
+        get_mine,
+        get_contributing,
+        get_one,
+        update,
+        delete_one,
+        schedule,
+        unschedule,
+        preview_delivery,
+        send_now,
+        get_svg,
+        post_svg,
+        patch_svg,
+        get_png,
+        post_png,
+        patch_png
     ]
 }
@@ -0,0 +1,342 @@
+//! チャンネル・スタンプ一覧の検索用インデックス
+//!
+//! traQの一覧APIは大きいので、定期的に`refresh`したものをメモリに持って検索する。
+
+use std::sync::{Arc, RwLock};
+
+use rocket::form::{self, FromFormField, ValueField};
+use schemars::JsonSchema;
+use serde::Serialize;
+use uuid::Uuid;
+
+use domain::bot_client::{BotClient, Stamp, StampType};
+
+use crate::resolve::{normalize_path, ChannelPathIndex};
+
+pub const DEFAULT_PAGE_LIMIT: usize = 50;
+pub const MAX_PAGE_LIMIT: usize = 200;
+
+/// パス付きのチャンネル
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct FlatChannel {
+    pub id: Uuid,
+    pub parent_id: Option<Uuid>,
+    pub name: String,
+    /// `gps/times/foo`
+    pub path: String,
+    pub topic: String,
+    pub archived: bool,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl<T: Clone> Page<T> {
+    pub fn new(items: &[T], offset: Option<usize>, limit: Option<usize>) -> Self {
+        let offset = offset.unwrap_or(0);
+        let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+        Self {
+            items: items.iter().skip(offset).take(limit).cloned().collect(),
+            total: items.len(),
+            offset,
+            limit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatchMode {
+    #[default]
+    Prefix,
+    Fuzzy,
+}
+
+impl<'r> FromFormField<'r> for MatchMode {
+    fn from_value(field: ValueField<'r>) -> form::Result<'r, Self> {
+        match field.value {
+            "prefix" => Ok(Self::Prefix),
+            "fuzzy" => Ok(Self::Fuzzy),
+            _ => Err(form::Error::validation("invalid match mode"))?,
+        }
+    }
+}
+
+impl MatchMode {
+    /// 一致しなければ`None`、一致すれば小さいほど良いスコア
+    pub fn score(self, haystack: &str, needle: &str) -> Option<usize> {
+        let haystack = haystack.to_lowercase();
+        let needle = needle.to_lowercase();
+        match self {
+            Self::Prefix => haystack.starts_with(&needle).then_some(0),
+            Self::Fuzzy => fuzzy_score(&haystack, &needle),
+        }
+    }
+}
+
+/// `needle`の文字が順番通りに現れれば、その間に挟まった文字数を返す
+fn fuzzy_score(haystack: &str, needle: &str) -> Option<usize> {
+    let mut chars = haystack.chars().enumerate();
+    let mut first = None;
+    let mut last = 0;
+    for n in needle.chars() {
+        let (i, _) = chars.find(|(_, h)| *h == n)?;
+        first.get_or_insert(i);
+        last = i;
+    }
+    let Some(first) = first else {
+        return Some(0);
+    };
+    Some(last + 1 - first - needle.chars().count())
+}
+
+/// スコア順(同点ならキー順)に並べる
+fn search<'a, T: Clone + 'a>(
+    items: impl Iterator<Item = &'a T>,
+    key: impl Fn(&T) -> &str,
+    query: Option<&str>,
+    mode: MatchMode,
+) -> Vec<T> {
+    let mut matched: Vec<_> = items
+        .filter_map(|item| {
+            let score = match query {
+                Some(q) => mode.score(key(item), q)?,
+                None => 0,
+            };
+            Some((score, item))
+        })
+        .collect();
+    matched.sort_by(|(a, x), (b, y)| a.cmp(b).then_with(|| key(x).cmp(key(y))));
+    matched.into_iter().map(|(_, item)| item.clone()).collect()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    channels: Arc<RwLock<Arc<Vec<FlatChannel>>>>,
+    stamps: Arc<RwLock<Arc<Vec<Stamp>>>>,
+}
+
+impl Catalog {
+    pub async fn refresh<B>(&self, client: &B) -> Result<(), B::Error>
+    where
+        B: BotClient + ?Sized,
+    {
+        self.refresh_channels(client).await?;
+        self.refresh_stamps(client).await
+    }
+
+    /// チャンネルが作られた時などにチャンネルだけ取り直す
+    pub async fn refresh_channels<B>(&self, client: &B) -> Result<(), B::Error>
+    where
+        B: BotClient + ?Sized,
+    {
+        let list = client.get_channels().await?;
+        let index = ChannelPathIndex::new(&list);
+        let channels = list
+            .public
+            .into_iter()
+            .filter_map(|c| {
+                Some(FlatChannel {
+                    path: index.path(c.id)?.to_string(),
+                    id: c.id,
+                    parent_id: c.parent_id,
+                    name: c.name,
+                    topic: c.topic,
+                    archived: c.archived,
+                })
+            })
+            .collect();
+        *self.channels.write().unwrap() = Arc::new(channels);
+        Ok(())
+    }
+
+    pub async fn refresh_stamps<B>(&self, client: &B) -> Result<(), B::Error>
+    where
+        B: BotClient + ?Sized,
+    {
+        let stamps = client.get_stamps(StampType::None).await?;
+        *self.stamps.write().unwrap() = Arc::new(stamps);
+        Ok(())
+    }
+
+    pub fn search_channels(
+        &self,
+        query: Option<&str>,
+        mode: MatchMode,
+        archived: bool,
+    ) -> Vec<FlatChannel> {
+        let channels = self.channels.read().unwrap().clone();
+        let query = query.map(normalize_path);
+        let channels = channels.iter().filter(|c| archived || !c.archived);
+        search(channels, |c| &c.path, query.as_deref(), mode)
+    }
+
+    pub fn search_stamps(
+        &self,
+        query: Option<&str>,
+        mode: MatchMode,
+        stamp_type: StampType,
+    ) -> Vec<Stamp> {
+        let stamps = self.stamps.read().unwrap().clone();
+        let stamps = stamps.iter().filter(|s| match stamp_type {
+            StampType::Original => !s.is_unicode,
+            StampType::Unicode => s.is_unicode,
+            StampType::None => true,
+        });
+        let query = query.map(|q| q.trim_matches(':'));
+        search(stamps, |s| &s.name, query, mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use domain::bot_client::{Channel, ChannelList, MockBotClient};
+
+    use super::*;
+
+    fn stamp(name: &str, is_unicode: bool) -> Stamp {
+        Stamp {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            is_unicode,
+            ..Default::default()
+        }
+    }
+
+    /// `gps`、`gps/times`、アーカイブ済みの`gps/old`と、スタンプ
+    async fn catalog() -> Catalog {
+        let gps = Channel {
+            id: Uuid::new_v4(),
+            name: "gps".to_string(),
+            ..Default::default()
+        };
+        let times = Channel {
+            id: Uuid::new_v4(),
+            parent_id: Some(gps.id),
+            name: "times".to_string(),
+            ..Default::default()
+        };
+        let old = Channel {
+            id: Uuid::new_v4(),
+            parent_id: Some(gps.id),
+            name: "old".to_string(),
+            archived: true,
+            ..Default::default()
+        };
+        let gps = Channel {
+            children: vec![times.id, old.id],
+            ..gps
+        };
+        let list = ChannelList {
+            public: vec![gps, times, old],
+            ..Default::default()
+        };
+        let mut client = MockBotClient::new();
+        client
+            .expect_get_channels()
+            .returning(move || Ok(list.clone()));
+        client.expect_get_stamps().returning(|_| {
+            Ok(vec![
+                stamp("kusa", false),
+                stamp("kusa_kusa", false),
+                stamp("kaisou", false),
+                stamp("smile", true),
+            ])
+        });
+        let catalog = Catalog::default();
+        catalog.refresh(&client).await.unwrap();
+        catalog
+    }
+
+    fn paths(channels: &[FlatChannel]) -> Vec<&str> {
+        channels.iter().map(|c| c.path.as_str()).collect()
+    }
+
+    fn names(stamps: &[Stamp]) -> Vec<&str> {
+        stamps.iter().map(|s| s.name.as_str()).collect()
+    }
+
+    #[test]
+    fn fuzzy_score_counts_skipped_characters() {
+        assert_eq!(fuzzy_score("kusa", "kusa"), Some(0));
+        assert_eq!(fuzzy_score("kaisou", "ksu"), Some(3));
+        assert_eq!(fuzzy_score("kusa", ""), Some(0));
+        assert_eq!(fuzzy_score("kusa", "ask"), None);
+        assert_eq!(MatchMode::Fuzzy.score("KUSA", "ks"), Some(1));
+        assert_eq!(MatchMode::Prefix.score("Kusa", "ku"), Some(0));
+        assert_eq!(MatchMode::Prefix.score("kusa", "us"), None);
+    }
+
+    #[tokio::test]
+    async fn ranks_by_score_then_name() {
+        let catalog = catalog().await;
+        let found = catalog.search_stamps(Some("ks"), MatchMode::Fuzzy, StampType::None);
+        assert_eq!(names(&found), vec!["kusa", "kusa_kusa", "kaisou"]);
+        let found = catalog.search_stamps(Some("ku"), MatchMode::Prefix, StampType::None);
+        assert_eq!(names(&found), vec!["kusa", "kusa_kusa"]);
+    }
+
+    #[tokio::test]
+    async fn trims_colons_and_filters_stamp_type() {
+        let catalog = catalog().await;
+        let found = catalog.search_stamps(Some(":kusa:"), MatchMode::Prefix, StampType::None);
+        assert_eq!(names(&found), vec!["kusa", "kusa_kusa"]);
+        let found = catalog.search_stamps(None, MatchMode::Prefix, StampType::Unicode);
+        assert_eq!(names(&found), vec!["smile"]);
+        let found = catalog.search_stamps(None, MatchMode::Prefix, StampType::Original);
+        assert_eq!(found.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn hides_archived_channels_unless_asked() {
+        let catalog = catalog().await;
+        let found = catalog.search_channels(Some("#gps/"), MatchMode::Prefix, false);
+        assert_eq!(paths(&found), vec!["gps", "gps/times"]);
+        let found = catalog.search_channels(Some("gps/"), MatchMode::Prefix, true);
+        assert_eq!(paths(&found), vec!["gps", "gps/old", "gps/times"]);
+    }
+
+    #[tokio::test]
+    async fn refresh_channels_picks_up_new_channels() {
+        let catalog = catalog().await;
+        let sugar = Channel {
+            id: Uuid::new_v4(),
+            name: "sugar".to_string(),
+            ..Default::default()
+        };
+        let mut client = MockBotClient::new();
+        client.expect_get_channels().returning(move || {
+            Ok(ChannelList {
+                public: vec![sugar.clone()],
+                ..Default::default()
+            })
+        });
+        catalog.refresh_channels(&client).await.unwrap();
+        let found = catalog.search_channels(None, MatchMode::Prefix, false);
+        assert_eq!(paths(&found), vec!["sugar"]);
+        // スタンプは取り直さない
+        let found = catalog.search_stamps(None, MatchMode::Prefix, StampType::None);
+        assert_eq!(found.len(), 4);
+    }
+
+    #[test]
+    fn page_clamps_offset_and_limit() {
+        let items: Vec<_> = (0..300).collect();
+        let page = Page::new(&items, None, None);
+        assert_eq!(page.items.len(), DEFAULT_PAGE_LIMIT);
+        assert_eq!((page.total, page.offset), (300, 0));
+        let page = Page::new(&items, Some(10), Some(1000));
+        assert_eq!(page.limit, MAX_PAGE_LIMIT);
+        assert_eq!(page.items.first(), Some(&10));
+        assert_eq!(page.items.len(), MAX_PAGE_LIMIT);
+        let page = Page::new(&items, Some(500), Some(10));
+        assert!(page.items.is_empty());
+        assert_eq!(page.total, 300);
+    }
+}
@@ -0,0 +1,253 @@
+//! 寄せ書きカードの参加者と、それぞれの書き込み
+//!
+//! 所有者か編集者が参加者を招待し、参加者は投稿日時までに自分の分だけを書き込める。
+//! 配信時にcronが全員分の画像を重ね、署名を並べたメッセージを送る。
+
+use chrono::Utc;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{Route, State};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use domain::repository::{AuditAction, CardModel, CardRole, ContributionModel, DateTimeUtc};
+
+use crate::audit::{AuditEvent, RequestId};
+use crate::auth::AuthUser;
+use crate::images::uploader;
+use crate::members::{card_role, get_members};
+use crate::{UuidParam, CR, IR};
+
+/// 重ねられる画像の形式
+pub const LAYER_MIME_TYPES: [&str; 3] = ["image/png", "image/jpeg", "image/gif"];
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+#[schemars(rename = "Contribution")]
+pub struct ContributionResponse {
+    pub user_id: Uuid,
+    pub message: Option<String>,
+    pub layer_id: Option<Uuid>,
+    /// まだ書き込んでいなければ`null`
+    pub contributed_at: Option<DateTimeUtc>,
+}
+
+impl From<ContributionModel> for ContributionResponse {
+    fn from(value: ContributionModel) -> Self {
+        Self {
+            user_id: value.user_id,
+            message: value.message,
+            layer_id: value.layer_id,
+            contributed_at: value.contributed_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ContributionRequest {
+    pub message: Option<String>,
+    /// `/api/images`に上げたPNG・JPEG・GIF。カードの画像に重ねる
+    pub layer_id: Option<Uuid>,
+}
+
+/// 下書きか予約済みで、投稿日時を過ぎていなければ書き込める
+fn accepting(card: &CardModel) -> bool {
+    card.status.is_editable() && card.publish_date.map_or(true, |d| d > Utc::now())
+}
+
+async fn get_card(id: Uuid, card_repo: &State<CR>) -> Result<CardModel, Status> {
+    card_repo
+        .0
+        .get_card_by_id(id)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get card by id: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)
+}
+
+async fn get_contributions(
+    id: Uuid,
+    card_repo: &State<CR>,
+) -> Result<Vec<ContributionModel>, Status> {
+    card_repo.0.get_contributions(id).await.map_err(|e| {
+        eprintln!("error in get contributions: {}", e);
+        Status::InternalServerError
+    })
+}
+
+/// メンバーと参加者だけが見られる
+#[rocket::get("/<id>/contributions")]
+pub async fn get_all(
+    id: UuidParam,
+    card_repo: &State<CR>,
+    user: AuthUser,
+) -> Result<Json<Vec<ContributionResponse>>, Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    get_card(id.0, card_repo).await?;
+    let role = card_role(user.id, id.0, card_repo).await?;
+    let contributions = get_contributions(id.0, card_repo).await?;
+    if role.is_none() && !contributions.iter().any(|c| c.user_id == user.id) {
+        return Err(Status::NotFound);
+    }
+    let res = contributions
+        .into_iter()
+        .map(ContributionResponse::from)
+        .collect();
+    Ok(Json(res))
+}
+
+/// 所有者か編集者が参加者を招待する。招待済みなら何もしない
+#[rocket::put("/<id>/contributors/<user_id>")]
+pub async fn invite(
+    id: UuidParam,
+    user_id: UuidParam,
+    card_repo: &State<CR>,
+    user: AuthUser,
+    request_id: RequestId,
+) -> Result<Status, Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let card = get_card(id.0, card_repo).await?;
+    let role = card_role(user.id, id.0, card_repo).await?;
+    if !role.is_some_and(CardRole::can_edit) || !accepting(&card) {
+        return Err(Status::Forbidden);
+    }
+    let contributions = get_contributions(id.0, card_repo).await?;
+    if contributions.iter().any(|c| c.user_id == user_id.0) {
+        return Ok(Status::NoContent);
+    }
+    let contribution = ContributionModel {
+        card_id: id.0,
+        user_id: user_id.0,
+        message: None,
+        layer_id: None,
+        contributed_at: None,
+    };
+    card_repo
+        .0
+        .save_contribution(&contribution)
+        .await
+        .map_err(|e| {
+            eprintln!("error in save contribution: {}", e);
+            Status::InternalServerError
+        })?;
+    AuditEvent::new(AuditAction::ContributionInvite, Some(&user), &request_id)
+        .card(id.0)
+        .change(None, Some(&contribution))
+        .record(card_repo)
+        .await;
+    Ok(Status::NoContent)
+}
+
+/// 所有者か編集者が招待を取り消すか、参加者が抜ける
+#[rocket::delete("/<id>/contributors/<user_id>")]
+pub async fn remove(
+    id: UuidParam,
+    user_id: UuidParam,
+    card_repo: &State<CR>,
+    user: AuthUser,
+    request_id: RequestId,
+) -> Result<Status, Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let card = get_card(id.0, card_repo).await?;
+    let role = card_role(user.id, id.0, card_repo).await?;
+    if !role.is_some_and(CardRole::can_edit) && user.id != user_id.0 {
+        return Err(Status::Forbidden);
+    }
+    if !accepting(&card) {
+        return Err(Status::Forbidden);
+    }
+    let before = get_contributions(id.0, card_repo)
+        .await?
+        .into_iter()
+        .find(|c| c.user_id == user_id.0);
+    card_repo
+        .0
+        .delete_contribution(id.0, user_id.0)
+        .await
+        .map_err(|e| {
+            eprintln!("error in delete contribution: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)?;
+    AuditEvent::new(AuditAction::ContributionRemove, Some(&user), &request_id)
+        .card(id.0)
+        .change(before.as_ref(), None)
+        .record(card_repo)
+        .await;
+    Ok(Status::NoContent)
+}
+
+/// 招待された本人が自分の分を書き込む
+#[rocket::put("/<id>/contributions/me", data = "<contribution>")]
+pub async fn put_mine(
+    id: UuidParam,
+    contribution: Json<ContributionRequest>,
+    card_repo: &State<CR>,
+    image_repo: &State<IR>,
+    user: AuthUser,
+    request_id: RequestId,
+) -> Result<Status, Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let card = get_card(id.0, card_repo).await?;
+    let before = get_contributions(id.0, card_repo)
+        .await?
+        .into_iter()
+        .find(|c| c.user_id == user.id)
+        .ok_or(Status::NotFound)?;
+    if !accepting(&card) {
+        return Err(Status::Forbidden);
+    }
+    let ContributionRequest { message, layer_id } = contribution.0;
+    if let Some(layer_id) = layer_id {
+        let (mime_type, _) = image_repo
+            .0
+            .get_asset(layer_id)
+            .await
+            .map_err(|e| {
+                eprintln!("error in get asset: {}", e);
+                Status::InternalServerError
+            })?
+            .ok_or(Status::BadRequest)?;
+        if !LAYER_MIME_TYPES.contains(&mime_type.as_str()) {
+            eprintln!("layer must be png, jpeg or gif, found {}", mime_type);
+            return Err(Status::BadRequest);
+        }
+        // 他人の画像を勝手に重ねさせない。本人かカードのメンバーが上げたものだけ
+        let Some(uploader) = uploader(layer_id, card_repo).await? else {
+            return Err(Status::Forbidden);
+        };
+        let members = get_members(id.0, card_repo).await?;
+        if uploader != user.id && !members.iter().any(|m| m.user_id == uploader) {
+            return Err(Status::Forbidden);
+        }
+    }
+    let contribution = ContributionModel {
+        card_id: id.0,
+        user_id: user.id,
+        message,
+        layer_id,
+        contributed_at: Some(Utc::now()),
+    };
+    card_repo
+        .0
+        .save_contribution(&contribution)
+        .await
+        .map_err(|e| {
+            eprintln!("error in save contribution: {}", e);
+            Status::InternalServerError
+        })?;
+    AuditEvent::new(AuditAction::ContributionUpdate, Some(&user), &request_id)
+        .card(id.0)
+        .change(Some(&before), Some(&contribution))
+        .record(card_repo)
+        .await;
+    Ok(Status::NoContent)
+}
+
+pub fn routes() -> Vec<Route> {
+    rocket::routes![get_all, invite, remove, put_mine]
+}
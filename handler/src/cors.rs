@@ -3,9 +3,11 @@ use rocket::http::{hyper, Header, Method, Status};
 
 use hyper::header::{
     ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
-    ACCESS_CONTROL_ALLOW_ORIGIN,
+    ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS, ETAG,
 };
 
+use crate::audit::REQUEST_ID_HEADER;
+
 #[derive(Debug, Clone)]
 pub struct CorsConfig {
     pub origins: Vec<String>,
@@ -99,6 +101,14 @@ impl CorsConfig {
             Header::new(ACCESS_CONTROL_ALLOW_HEADERS.as_str(), "*")
         }
     }
+
+    /// `If-Match`に使うETagをブラウザから読めるようにする
+    pub fn render_expose_headers(&self) -> Header<'static> {
+        Header::new(
+            ACCESS_CONTROL_EXPOSE_HEADERS.as_str(),
+            format!("{}, {}", ETAG.as_str(), REQUEST_ID_HEADER),
+        )
+    }
 }
 
 #[rocket::options("/<_..>")]
@@ -2,15 +2,24 @@ use std::io::Cursor;
 
 use async_trait::async_trait;
 use bytes::Bytes;
+use chrono::Utc;
 use rocket::data::ToByteUnit;
 use rocket::form::{self, error::ErrorKind, DataField, Form, FromFormField};
 use rocket::http::hyper::header::CONTENT_TYPE;
 use rocket::http::Status;
 use rocket::response::Responder;
 use rocket::{routes, FromForm, Response, Route, State};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject};
+use schemars::JsonSchema;
+use uuid::Uuid;
 
+use domain::repository::{AssetModel, AuditAction};
+
+use crate::audit::{image_digest, AuditEvent, RequestId};
 use crate::auth::AuthUser;
-use crate::{UuidParam, IR};
+use crate::cache::{content_etag, CachePolicy, Cached};
+use crate::{UuidParam, CR, IR};
 
 #[derive(Debug, Clone)]
 pub enum FormImage {
@@ -20,6 +29,25 @@ pub enum FormImage {
     Gif(Bytes),
 }
 
+impl JsonSchema for FormImage {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "FormImage".to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            format: Some("binary".to_string()),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
 #[async_trait]
 impl<'r> FromFormField<'r> for FormImage {
     async fn from_data(field: DataField<'r, '_>) -> form::Result<'r, Self> {
@@ -43,9 +71,29 @@ impl<'r> FromFormField<'r> for FormImage {
     }
 }
 
-#[derive(Debug, Clone, FromForm)]
+impl FormImage {
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            FormImage::Svg(_) => "image/svg+xml",
+            FormImage::Png(_) => "image/png",
+            FormImage::Jpeg(_) => "image/jpeg",
+            FormImage::Gif(_) => "image/gif",
+        }
+    }
+
+    pub fn content(&self) -> &[u8] {
+        match self {
+            FormImage::Svg(svg) => svg.as_bytes(),
+            FormImage::Png(data) | FormImage::Jpeg(data) | FormImage::Gif(data) => data,
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromForm, JsonSchema)]
 pub struct ImageForm<'r> {
+    #[schemars(with = "uuid::Uuid")]
     pub id: &'r str,
+    /// SVG, PNG, JPEG, GIFのいずれか(2MiBまで)
     pub image: FormImage,
 }
 
@@ -62,12 +110,21 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for ImageResponse {
     }
 }
 
+/// アセットを上げたユーザー。記録が無ければ`None`
+pub(crate) async fn uploader(id: Uuid, card_repo: &State<CR>) -> Result<Option<Uuid>, Status> {
+    let asset = card_repo.0.get_asset(id).await.map_err(|e| {
+        eprintln!("error in get asset: {}", e);
+        Status::InternalServerError
+    })?;
+    Ok(asset.map(|a| a.uploaded_by))
+}
+
 #[rocket::get("/<id>")]
 pub async fn get_one(
     id: UuidParam,
     image_repo: &State<IR>,
     _user: AuthUser,
-) -> Result<ImageResponse, Status> {
+) -> Result<Cached<ImageResponse>, Status> {
     let image = image_repo
         .0
         .get_asset(id.0)
@@ -77,17 +134,43 @@ pub async fn get_one(
             Status::InternalServerError
         })?
         .ok_or(Status::NotFound)?;
-    Ok(ImageResponse(image.0, image.1))
+    let (content_type, content) = image;
+    // アセットはIDごとに内容が変わらない
+    let etag = content_etag(&content);
+    Ok(Cached::new(
+        ImageResponse(content_type, content),
+        etag,
+        CachePolicy::Immutable,
+    ))
 }
 
 #[rocket::post("/", data = "<form_data>")]
 pub async fn post(
     form_data: Form<ImageForm<'_>>,
     image_repo: &State<IR>,
-    _user: AuthUser,
+    card_repo: &State<CR>,
+    user: AuthUser,
+    request_id: RequestId,
 ) -> Result<Status, Status> {
     let ImageForm { id, image } = form_data.into_inner();
     let id = id.parse().map_err(|_| Status::BadRequest)?;
+    // 寄せ書きのレイヤーに使えるか確かめるため、誰が上げたかを残す。他人のIDには上書きさせない
+    if let Some(user) = &user.0 {
+        let asset = AssetModel {
+            id,
+            uploaded_by: user.id,
+            created_at: Utc::now(),
+        };
+        let saved = card_repo.0.save_asset(&asset).await.map_err(|e| {
+            eprintln!("error in save asset: {}", e);
+            Status::InternalServerError
+        })?;
+        if saved.is_none() && uploader(id, card_repo).await? != Some(user.id) {
+            return Err(Status::Forbidden);
+        }
+    }
+    let mut after = image_digest(image.content());
+    after["mime_type"] = image.mime_type().into();
     match image {
         FormImage::Svg(svg) => image_repo
             .0
@@ -122,6 +205,11 @@ pub async fn post(
                 Status::InternalServerError
             })?,
     }
+    AuditEvent::new(AuditAction::ImageUpload, user.0.as_ref(), &request_id)
+        .asset(id)
+        .change(None, Some(&after))
+        .record(card_repo)
+        .await;
     Ok(Status::NoContent)
 }
 
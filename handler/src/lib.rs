@@ -1,25 +1,72 @@
+#![recursion_limit = "256"]
+
 use std::str::FromStr;
 use std::sync::Arc;
 
-use rocket::get;
+use chrono::{DateTime, Utc};
 use rocket::request::FromParam;
+use rocket::{get, routes, Route};
 use uuid::Uuid;
 
 use domain::bot_client::BotClient;
+use domain::cron::Cron;
 use domain::repository::{CardRepository, ImageRepository};
 
+pub mod admin;
+pub mod audit;
 pub mod auth;
 pub mod bot;
+pub mod cache;
 pub mod cards;
+pub mod catalog;
+pub mod contributions;
 pub mod cors;
 pub mod images;
+pub mod members;
+pub mod openapi;
+pub mod recurrence;
+pub mod resolve;
+pub mod revisions;
+pub mod share;
 pub mod traq_api;
+pub mod trash;
+pub mod webhooks;
 
 #[get("/ping")]
 pub fn ping() -> &'static str {
     "pong"
 }
 
+/// `(マウント先, ルート)`の一覧。OpenAPIのドキュメントとの突き合わせにも使う
+pub fn mounts() -> Vec<(&'static str, Vec<Route>)> {
+    vec![
+        (
+            "/api",
+            routes![
+                ping,
+                openapi::get_spec,
+                openapi::get_docs,
+                openapi::get_docs_asset
+            ],
+        ),
+        ("/api/cards", cards::routes()),
+        ("/api/cards", recurrence::routes()),
+        ("/api/cards", contributions::routes()),
+        ("/api/cards", members::routes()),
+        ("/api/cards", share::routes()),
+        ("/api/cards", revisions::routes()),
+        ("/api/cards", trash::routes()),
+        ("/api/images", images::routes()),
+        ("/bot", routes![bot::bot_event]),
+        ("/share", share::public_routes()),
+        ("/api/stamps", traq_api::stamps::routes()),
+        ("/api/users", traq_api::users::routes()),
+        ("/api/channels", traq_api::channels::routes()),
+        ("/api/webhooks", webhooks::routes()),
+        ("/api/admin", admin::routes()),
+    ]
+}
+
 pub struct UuidParam(pub Uuid);
 
 impl<'r> FromParam<'r> for UuidParam {
@@ -31,6 +78,18 @@ impl<'r> FromParam<'r> for UuidParam {
     }
 }
 
+/// RFC 3339形式の日時
+pub struct DateTimeParam(pub DateTime<Utc>);
+
+impl<'r> FromParam<'r> for DateTimeParam {
+    type Error = chrono::ParseError;
+
+    fn from_param(param: &'r str) -> Result<Self, Self::Error> {
+        let at = DateTime::parse_from_rfc3339(param)?;
+        Ok(Self(at.with_timezone(&Utc)))
+    }
+}
+
 pub struct CR(pub Arc<dyn CardRepository<Error = anyhow::Error>>);
 
 impl<T> From<T> for CR
@@ -63,3 +122,14 @@ where
         BC(Arc::new(value))
     }
 }
+
+pub struct CRON(pub Arc<dyn Cron>);
+
+impl<T> From<Arc<T>> for CRON
+where
+    T: Cron,
+{
+    fn from(value: Arc<T>) -> Self {
+        CRON(value)
+    }
+}
@@ -0,0 +1,217 @@
+//! カードを共同で作るメンバーとロール
+//!
+//! 所有者(`owner`)はメンバーの管理と削除、編集者(`editor`)は内容・画像・予約の編集、
+//! 閲覧者(`viewer`)は配信前の閲覧だけができる。`CardModel::owner_id`は配信メッセージの差出人で、
+//! 常に所有者のロールを持つ。
+
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{Route, State};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use domain::repository::{AuditAction, CardMemberModel, CardModel, CardRole};
+
+use crate::audit::{AuditEvent, RequestId};
+use crate::auth::AuthUser;
+use crate::{UuidParam, CR};
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct CardMember {
+    pub user_id: Uuid,
+    pub role: CardRole,
+}
+
+impl From<CardMemberModel> for CardMember {
+    fn from(value: CardMemberModel) -> Self {
+        Self {
+            user_id: value.user_id,
+            role: value.role,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct MemberRequest {
+    pub role: CardRole,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct TransferRequest {
+    pub user_id: Uuid,
+}
+
+pub(crate) async fn get_members(
+    card_id: Uuid,
+    card_repo: &State<CR>,
+) -> Result<Vec<CardMemberModel>, Status> {
+    card_repo.0.get_card_members(card_id).await.map_err(|e| {
+        eprintln!("error in get card members: {}", e);
+        Status::InternalServerError
+    })
+}
+
+/// メンバーでなければ`None`
+pub(crate) async fn card_role(
+    user_id: Uuid,
+    card_id: Uuid,
+    card_repo: &State<CR>,
+) -> Result<Option<CardRole>, Status> {
+    let members = get_members(card_id, card_repo).await?;
+    Ok(members
+        .into_iter()
+        .find(|m| m.user_id == user_id)
+        .map(|m| m.role))
+}
+
+async fn get_card(id: Uuid, card_repo: &State<CR>) -> Result<CardModel, Status> {
+    card_repo
+        .0
+        .get_card_by_id(id)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get card by id: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)
+}
+
+/// メンバーだけが見られる
+#[rocket::get("/<id>/members")]
+pub async fn get_all(
+    id: UuidParam,
+    card_repo: &State<CR>,
+    user: AuthUser,
+) -> Result<Json<Vec<CardMember>>, Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    get_card(id.0, card_repo).await?;
+    let members = get_members(id.0, card_repo).await?;
+    if !members.iter().any(|m| m.user_id == user.id) {
+        return Err(Status::NotFound);
+    }
+    Ok(Json(members.into_iter().map(CardMember::from).collect()))
+}
+
+/// 所有者がメンバーを追加するか、ロールを変える
+#[rocket::put("/<id>/members/<user_id>", data = "<member>")]
+pub async fn put(
+    id: UuidParam,
+    user_id: UuidParam,
+    member: Json<MemberRequest>,
+    card_repo: &State<CR>,
+    user: AuthUser,
+    request_id: RequestId,
+) -> Result<Status, Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let card = get_card(id.0, card_repo).await?;
+    let members = get_members(id.0, card_repo).await?;
+    let role = members
+        .iter()
+        .find(|m| m.user_id == user.id)
+        .map(|m| m.role);
+    let before = members.into_iter().find(|m| m.user_id == user_id.0);
+    if !role.is_some_and(CardRole::can_manage) {
+        return Err(Status::Forbidden);
+    }
+    // 差出人は所有者のままにする。変えるなら譲渡する
+    if user_id.0 == card.owner_id && member.role != CardRole::Owner {
+        return Err(Status::BadRequest);
+    }
+    let member = CardMemberModel {
+        card_id: id.0,
+        user_id: user_id.0,
+        role: member.role,
+    };
+    card_repo.0.save_card_member(&member).await.map_err(|e| {
+        eprintln!("error in save card member: {}", e);
+        Status::InternalServerError
+    })?;
+    AuditEvent::new(AuditAction::MemberUpdate, Some(&user), &request_id)
+        .card(id.0)
+        .change(before.as_ref(), Some(&member))
+        .record(card_repo)
+        .await;
+    Ok(Status::NoContent)
+}
+
+/// 所有者がメンバーを外すか、メンバーが自分で抜ける
+#[rocket::delete("/<id>/members/<user_id>")]
+pub async fn delete(
+    id: UuidParam,
+    user_id: UuidParam,
+    card_repo: &State<CR>,
+    user: AuthUser,
+    request_id: RequestId,
+) -> Result<Status, Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let card = get_card(id.0, card_repo).await?;
+    let members = get_members(id.0, card_repo).await?;
+    let role = members
+        .iter()
+        .find(|m| m.user_id == user.id)
+        .map(|m| m.role);
+    let before = members.into_iter().find(|m| m.user_id == user_id.0);
+    if !role.is_some_and(CardRole::can_manage) && user.id != user_id.0 {
+        return Err(Status::Forbidden);
+    }
+    if user_id.0 == card.owner_id {
+        return Err(Status::BadRequest);
+    }
+    card_repo
+        .0
+        .delete_card_member(id.0, user_id.0)
+        .await
+        .map_err(|e| {
+            eprintln!("error in delete card member: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)?;
+    AuditEvent::new(AuditAction::MemberRemove, Some(&user), &request_id)
+        .card(id.0)
+        .change(before.as_ref(), None)
+        .record(card_repo)
+        .await;
+    Ok(Status::NoContent)
+}
+
+/// 差出人を譲る。元の差出人は編集者として残る
+#[rocket::post("/<id>/transfer", data = "<transfer>")]
+pub async fn transfer(
+    id: UuidParam,
+    transfer: Json<TransferRequest>,
+    card_repo: &State<CR>,
+    user: AuthUser,
+    request_id: RequestId,
+) -> Result<Status, Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let card = get_card(id.0, card_repo).await?;
+    if user.id != card.owner_id {
+        return Err(Status::Forbidden);
+    }
+    if transfer.user_id == user.id {
+        return Ok(Status::NoContent);
+    }
+    card_repo
+        .0
+        .transfer_card_ownership(id.0, user.id, transfer.user_id)
+        .await
+        .map_err(|e| {
+            eprintln!("error in transfer card ownership: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::Conflict)?;
+    AuditEvent::new(AuditAction::CardTransfer, Some(&user), &request_id)
+        .card(id.0)
+        .change(Some(&card.owner_id), Some(&transfer.user_id))
+        .record(card_repo)
+        .await;
+    Ok(Status::NoContent)
+}
+
+pub fn routes() -> Vec<Route> {
+    rocket::routes![get_all, put, delete, transfer]
+}
@@ -0,0 +1,915 @@
+//! OpenAPI 3のドキュメントとSwagger UI
+//!
+//! 各ルートの定義は`spec`に手で書き、スキーマは`schemars`でRustの型から作る。
+//! `tests/openapi.rs`で`crate::mounts`とメソッド・パス・パラメータが一致しているか、
+//! スキーマがRustの型をシリアライズしたものと一致しているかを検査する。
+//!
+//! Swagger UIは`assets/swagger-ui`に同梱したものを配信する。
+
+use rocket::http::ContentType;
+use rocket::response::content::RawHtml;
+use rocket::serde::json::serde_json::Map;
+use rocket::serde::json::{json, to_value, Json, Value};
+use schemars::gen::SchemaSettings;
+use schemars::JsonSchema;
+
+use crate::admin::{AuditEventResponse, QueueEntry, ReassignRequest};
+use crate::cache::{content_etag, CachePolicy, Cached};
+use crate::cards::{CardPatch, CardRequest, CardResponse};
+use crate::catalog::{FlatChannel, Page};
+use crate::contributions::{ContributionRequest, ContributionResponse};
+use crate::images::ImageForm;
+use crate::members::{MemberRequest, TransferRequest};
+use crate::recurrence::OccurrenceResponse;
+use crate::revisions::ImageRevision;
+use crate::share::{ShareLinkResponse, ShareRequest};
+use crate::traq_api::channels::ResolvedChannel;
+use crate::trash::TrashedCard;
+use crate::webhooks::{WebhookRequest, WebhookResponse};
+
+const SWAGGER_UI_CSS: &[u8] = include_bytes!("../assets/swagger-ui/swagger-ui.css");
+const SWAGGER_UI_BUNDLE: &[u8] = include_bytes!("../assets/swagger-ui/swagger-ui-bundle.js");
+
+fn schema(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn array(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+fn uuid() -> Value {
+    json!({ "type": "string", "format": "uuid" })
+}
+
+fn path_param(name: &str) -> Value {
+    json!({ "name": name, "in": "path", "required": true, "schema": uuid() })
+}
+
+fn date_time_path_param(name: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "path",
+        "required": true,
+        "schema": { "type": "string", "format": "date-time" },
+    })
+}
+
+fn revision_path_param() -> Value {
+    json!({
+        "name": "revision",
+        "in": "path",
+        "required": true,
+        "schema": { "type": "integer", "minimum": 1 },
+    })
+}
+
+fn if_match_param() -> Value {
+    json!({
+        "name": "If-Match",
+        "in": "header",
+        "required": true,
+        "schema": { "type": "string" },
+        "description": "`GET`で受け取った`ETag`。無ければ428、今の版と違えば412",
+    })
+}
+
+/// `If-Match`付きの更新のレスポンス。新しい版の`ETag`を返す
+fn precondition_responses() -> Value {
+    json!({
+        "204": {
+            "description": "No Content",
+            "headers": { "ETag": { "schema": { "type": "string" } } },
+        },
+        "412": { "description": "Precondition Failed" },
+        "428": { "description": "Precondition Required" },
+    })
+}
+
+/// 版を戻した時のレスポンス。戻した後の版とその`ETag`を返す
+fn restore_responses() -> Value {
+    json!({
+        "200": {
+            "description": "OK",
+            "headers": { "ETag": { "schema": { "type": "string" } } },
+            "content": { "application/json": { "schema": schema("ImageRevision") } },
+        },
+        "412": { "description": "Precondition Failed" },
+        "428": { "description": "Precondition Required" },
+    })
+}
+
+fn token_path_param() -> Value {
+    json!({ "name": "token", "in": "path", "required": true, "schema": { "type": "string" } })
+}
+
+fn query_param(name: &str, schema: Value, description: &str) -> Value {
+    json!({ "name": name, "in": "query", "schema": schema, "description": description })
+}
+
+fn expand_param() -> Value {
+    query_param(
+        "expand",
+        json!({ "type": "string", "enum": ["owner"] }),
+        "`owner`を指定すると所有者の名前とアイコンURLを埋め込む",
+    )
+}
+
+fn page_params() -> [Value; 4] {
+    [
+        query_param("q", json!({ "type": "string" }), "検索文字列"),
+        query_param(
+            "mode",
+            json!({ "type": "string", "enum": ["prefix", "fuzzy"], "default": "prefix" }),
+            "一致方法",
+        ),
+        query_param(
+            "limit",
+            json!({ "type": "integer", "default": 50, "maximum": 200 }),
+            "",
+        ),
+        query_param("offset", json!({ "type": "integer", "default": 0 }), ""),
+    ]
+}
+
+fn body(content_type: &str, schema: Value) -> Value {
+    json!({ "required": true, "content": { content_type: { "schema": schema } } })
+}
+
+fn ok(content_type: &str, schema: Value) -> Value {
+    json!({ "200": { "description": "OK", "content": { content_type: { "schema": schema } } } })
+}
+
+fn binary() -> Value {
+    json!({ "type": "string", "format": "binary" })
+}
+
+fn no_content() -> Value {
+    json!({ "204": { "description": "No Content" } })
+}
+
+fn image_response() -> Value {
+    json!({
+        "200": {
+            "description": "OK",
+            "content": {
+                "image/png": { "schema": binary() },
+                "image/gif": { "schema": binary() },
+                "image/jpeg": { "schema": binary() },
+                "image/webp": { "schema": binary() },
+                "image/svg+xml": { "schema": { "type": "string" } },
+            },
+        },
+        "304": { "description": "Not Modified" },
+    })
+}
+
+/// Rustの型から作るスキーマ
+fn derived_schemas() -> Map<String, Value> {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+    gen.subschema_for::<CardRequest>();
+    gen.subschema_for::<CardPatch>();
+    gen.subschema_for::<CardResponse>();
+    gen.subschema_for::<TrashedCard>();
+    gen.subschema_for::<ContributionRequest>();
+    gen.subschema_for::<ContributionResponse>();
+    gen.subschema_for::<MemberRequest>();
+    gen.subschema_for::<TransferRequest>();
+    gen.subschema_for::<ImageRevision>();
+    gen.subschema_for::<ImageForm>();
+    gen.subschema_for::<ShareRequest>();
+    gen.subschema_for::<ShareLinkResponse>();
+    gen.subschema_for::<OccurrenceResponse>();
+    gen.subschema_for::<ReassignRequest>();
+    gen.subschema_for::<QueueEntry>();
+    gen.subschema_for::<AuditEventResponse>();
+    gen.subschema_for::<WebhookRequest>();
+    gen.subschema_for::<WebhookResponse>();
+    gen.subschema_for::<FlatChannel>();
+    gen.subschema_for::<ResolvedChannel>();
+    // `Page<T>`は型引数ごとに名前を付ける
+    let pages = [
+        ("CardPage", Page::<CardResponse>::json_schema(&mut gen)),
+        ("ChannelPage", Page::<FlatChannel>::json_schema(&mut gen)),
+    ];
+    let mut schemas = gen.take_definitions();
+    schemas.extend(pages.map(|(name, schema)| (name.to_string(), schema)));
+    schemas
+        .into_iter()
+        .map(|(name, mut schema)| {
+            for visitor in gen.visitors_mut() {
+                visitor.visit_schema(&mut schema);
+            }
+            (name, to_value(schema).unwrap())
+        })
+        .collect()
+}
+
+/// traQのAPIをそのまま返す型。外部のクレートの型なので手で書く
+fn traq_schemas() -> Value {
+    json!({
+        "Stamp": {
+            "type": "object",
+            "properties": {
+                "id": uuid(),
+                "name": { "type": "string" },
+                "creatorId": uuid(),
+                "createdAt": { "type": "string", "format": "date-time" },
+                "updatedAt": { "type": "string", "format": "date-time" },
+                "fileId": uuid(),
+                "isUnicode": { "type": "boolean" },
+            },
+        },
+        "User": {
+            "type": "object",
+            "properties": {
+                "id": uuid(),
+                "name": { "type": "string" },
+                "displayName": { "type": "string" },
+                "iconFileId": uuid(),
+                "bot": { "type": "boolean" },
+                "state": { "type": "integer" },
+                "updatedAt": { "type": "string", "format": "date-time" },
+            },
+        },
+        "UserDetail": {
+            "allOf": [
+                schema("User"),
+                {
+                    "type": "object",
+                    "properties": {
+                        "twitterId": { "type": "string" },
+                        "lastOnline": { "type": "string", "nullable": true },
+                        "tags": array(json!({ "type": "object" })),
+                        "groups": array(uuid()),
+                        "bio": { "type": "string" },
+                        "homeChannel": { "type": "string", "format": "uuid", "nullable": true },
+                    },
+                },
+            ],
+        },
+        "Channel": {
+            "type": "object",
+            "properties": {
+                "id": uuid(),
+                "parentId": { "type": "string", "format": "uuid", "nullable": true },
+                "archived": { "type": "boolean" },
+                "force": { "type": "boolean" },
+                "topic": { "type": "string" },
+                "name": { "type": "string" },
+                "children": array(uuid()),
+            },
+        },
+        "ChannelList": {
+            "type": "object",
+            "properties": {
+                "public": array(schema("Channel")),
+                "dm": array(json!({ "type": "object" })),
+            },
+        },
+        "StampPage": {
+            "type": "object",
+            "properties": {
+                "items": array(schema("Stamp")),
+                "total": { "type": "integer" },
+                "offset": { "type": "integer" },
+                "limit": { "type": "integer" },
+            },
+        },
+    })
+}
+
+fn components() -> Value {
+    let mut schemas = derived_schemas();
+    schemas.extend(traq_schemas().as_object().unwrap().clone());
+    json!({
+        "securitySchemes": {
+            "forwardedUser": { "type": "apiKey", "in": "header", "name": "X-Forwarded-User" },
+        },
+        "schemas": schemas,
+    })
+}
+
+/// `/api/openapi.json`で返すドキュメント
+pub fn spec() -> Value {
+    let [q, mode, limit, offset] = page_params();
+    let stamp_type = query_param(
+        "type",
+        json!({ "type": "string", "enum": ["original", "unicode"] }),
+        "",
+    );
+    let cards = array(schema("CardResponse"));
+    json!({
+        "openapi": "3.0.3",
+        "info": { "title": "Qard API", "version": env!("CARGO_PKG_VERSION") },
+        "paths": {
+            "/api/ping": {
+                "get": { "tags": ["misc"], "responses": ok("text/plain", json!({ "type": "string" })) },
+            },
+            "/api/openapi.json": {
+                "get": { "tags": ["misc"], "summary": "このドキュメント", "responses": ok("application/json", json!({ "type": "object" })) },
+            },
+            "/api/docs": {
+                "get": { "tags": ["misc"], "summary": "Swagger UI", "responses": ok("text/html", json!({ "type": "string" })) },
+            },
+            "/api/docs/{file}": {
+                "get": {
+                    "tags": ["misc"],
+                    "summary": "同梱したSwagger UIのファイル",
+                    "parameters": [{
+                        "name": "file",
+                        "in": "path",
+                        "required": true,
+                        "schema": { "type": "string", "enum": ["swagger-ui.css", "swagger-ui-bundle.js"] },
+                    }],
+                    "responses": {
+                        "200": {
+                            "description": "OK",
+                            "content": {
+                                "text/css": { "schema": { "type": "string" } },
+                                "text/javascript": { "schema": { "type": "string" } },
+                            },
+                        },
+                        "404": { "description": "Not Found" },
+                    },
+                },
+            },
+            "/api/cards": {
+                "get": {
+                    "tags": ["cards"],
+                    "summary": "閲覧できるカード一覧",
+                    "parameters": [expand_param()],
+                    "responses": ok("application/json", cards.clone()),
+                },
+                "post": {
+                    "tags": ["cards"],
+                    "summary": "カードを作成し、そのIDを返す",
+                    "requestBody": body("application/json", schema("CardRequest")),
+                    "responses": ok("text/plain", uuid()),
+                },
+            },
+            "/api/cards/me": {
+                "get": {
+                    "tags": ["cards"],
+                    "summary": "自分のカード一覧",
+                    "parameters": [expand_param()],
+                    "responses": ok("application/json", cards),
+                },
+            },
+            "/api/cards/trash": {
+                "get": {
+                    "tags": ["cards"],
+                    "summary": "所有しているゴミ箱のカード一覧。新しく削除した順",
+                    "parameters": [expand_param()],
+                    "responses": ok("application/json", array(schema("TrashedCard"))),
+                },
+            },
+            "/api/cards/contributing": {
+                "get": {
+                    "tags": ["cards"],
+                    "summary": "寄せ書きに招待されているカード一覧",
+                    "parameters": [expand_param()],
+                    "responses": ok("application/json", array(schema("CardResponse"))),
+                },
+            },
+            "/api/cards/{id}/contributions": {
+                "get": {
+                    "tags": ["contributions"],
+                    "summary": "寄せ書きの一覧。メンバーと参加者のみ",
+                    "parameters": [path_param("id")],
+                    "responses": ok("application/json", array(schema("Contribution"))),
+                },
+            },
+            "/api/cards/{id}/contributions/me": {
+                "put": {
+                    "tags": ["contributions"],
+                    "summary": "招待された本人が投稿日時までに自分の分を書き込む",
+                    "parameters": [path_param("id")],
+                    "requestBody": body("application/json", schema("ContributionRequest")),
+                    "responses": {
+                        "204": { "description": "No Content" },
+                        "403": { "description": "締め切った、または本人かメンバー以外が上げた画像を重ねようとした" },
+                    },
+                },
+            },
+            "/api/cards/{id}/contributors/{user_id}": {
+                "put": {
+                    "tags": ["contributions"],
+                    "summary": "所有者か編集者が参加者を招待する",
+                    "parameters": [path_param("id"), path_param("user_id")],
+                    "responses": no_content(),
+                },
+                "delete": {
+                    "tags": ["contributions"],
+                    "summary": "所有者か編集者が招待を取り消すか、参加者が抜ける",
+                    "parameters": [path_param("id"), path_param("user_id")],
+                    "responses": no_content(),
+                },
+            },
+            "/api/cards/{id}/members": {
+                "get": {
+                    "tags": ["members"],
+                    "summary": "メンバーの一覧。メンバーのみ",
+                    "parameters": [path_param("id")],
+                    "responses": ok("application/json", array(schema("CardMember"))),
+                },
+            },
+            "/api/cards/{id}/members/{user_id}": {
+                "put": {
+                    "tags": ["members"],
+                    "summary": "所有者がメンバーを追加するか、ロールを変える",
+                    "parameters": [path_param("id"), path_param("user_id")],
+                    "requestBody": body("application/json", schema("MemberRequest")),
+                    "responses": no_content(),
+                },
+                "delete": {
+                    "tags": ["members"],
+                    "summary": "所有者がメンバーを外すか、メンバーが自分で抜ける。差出人は外せない",
+                    "parameters": [path_param("id"), path_param("user_id")],
+                    "responses": no_content(),
+                },
+            },
+            "/api/cards/{id}/transfer": {
+                "post": {
+                    "tags": ["members"],
+                    "summary": "差出人(owner_id)を譲る。元の差出人は編集者になる",
+                    "parameters": [path_param("id")],
+                    "requestBody": body("application/json", schema("TransferRequest")),
+                    "responses": no_content(),
+                },
+            },
+            "/api/cards/{id}/shares": {
+                "get": {
+                    "tags": ["shares"],
+                    "summary": "期限内の共有リンクの一覧。所有者のみ",
+                    "parameters": [path_param("id")],
+                    "responses": ok("application/json", array(schema("ShareLink"))),
+                },
+                "post": {
+                    "tags": ["shares"],
+                    "summary": "投稿済みのカードの共有リンクを作る。所有者のみ",
+                    "parameters": [path_param("id")],
+                    "requestBody": body("application/json", schema("ShareRequest")),
+                    "responses": ok("application/json", schema("ShareLink")),
+                },
+            },
+            "/api/cards/{id}/shares/{share_id}": {
+                "delete": {
+                    "tags": ["shares"],
+                    "summary": "共有リンクを失効させる。所有者のみ",
+                    "parameters": [path_param("id"), path_param("share_id")],
+                    "responses": no_content(),
+                },
+            },
+            "/share/{token}": {
+                "get": {
+                    "tags": ["shares"],
+                    "summary": "認証なし。OpenGraph・Twitterのメタタグを持つHTML",
+                    "parameters": [token_path_param()],
+                    "responses": ok("text/html", json!({ "type": "string" })),
+                },
+            },
+            "/share/{token}/png": {
+                "get": {
+                    "tags": ["shares"],
+                    "summary": "認証なし。カードのPNG",
+                    "parameters": [token_path_param()],
+                    "responses": ok("image/png", binary()),
+                },
+            },
+            "/api/cards/{id}": {
+                "get": {
+                    "tags": ["cards"],
+                    "parameters": [path_param("id"), expand_param()],
+                    "responses": ok("application/json", schema("CardResponse")),
+                },
+                "patch": {
+                    "tags": ["cards"],
+                    "summary": "下書きか予約済みのカードを所有者か編集者が更新する。owner_idは変えられない",
+                    "parameters": [path_param("id"), if_match_param()],
+                    "requestBody": body("application/merge-patch+json", schema("CardPatch")),
+                    "responses": precondition_responses(),
+                },
+                "delete": {
+                    "tags": ["cards"],
+                    "summary": "ゴミ箱に入れる。期限までは`/restore`で戻せる",
+                    "parameters": [path_param("id")],
+                    "responses": {
+                        "204": { "description": "No Content" },
+                        "409": { "description": "配信が始まった" },
+                    },
+                },
+            },
+            "/api/cards/{id}/restore": {
+                "post": {
+                    "tags": ["cards"],
+                    "summary": "ゴミ箱から戻す。ゴミ箱にある間に投稿日時を過ぎた予約は下書きに戻す",
+                    "parameters": [path_param("id")],
+                    "responses": {
+                        "200": {
+                            "description": "OK",
+                            "content": {
+                                "application/json": { "schema": schema("CardResponse") },
+                            },
+                        },
+                        "410": { "description": "戻せる期間を過ぎた" },
+                    },
+                },
+            },
+            "/api/cards/{id}/schedule": {
+                "post": {
+                    "tags": ["cards"],
+                    "summary": "下書きを予約済みにする",
+                    "parameters": [path_param("id")],
+                    "responses": {
+                        "204": { "description": "No Content" },
+                        "400": { "description": "未来の投稿日時かPNGか宛先が無い" },
+                        "409": { "description": "下書きでない" },
+                    },
+                },
+            },
+            "/api/cards/{id}/unschedule": {
+                "post": {
+                    "tags": ["cards"],
+                    "summary": "予約を取り消して下書きに戻す",
+                    "parameters": [path_param("id")],
+                    "responses": {
+                        "204": { "description": "No Content" },
+                        "409": { "description": "予約済みでない" },
+                    },
+                },
+            },
+            "/api/cards/{id}/preview-delivery": {
+                "post": {
+                    "tags": ["cards"],
+                    "summary": "配信と同じ画像とメッセージを自分のDMに送る。カードは変えない",
+                    "parameters": [path_param("id")],
+                    "responses": {
+                        "204": { "description": "No Content" },
+                        "400": { "description": "PNGか宛先が無い" },
+                        "502": { "description": "traQに送れなかった" },
+                    },
+                },
+            },
+            "/api/cards/{id}/send-now": {
+                "post": {
+                    "tags": ["cards"],
+                    "summary": "投稿日時を待たずに配信する。繰り返しのカードは次の発生日時から続く",
+                    "parameters": [path_param("id")],
+                    "responses": {
+                        "200": {
+                            "description": "OK",
+                            "content": {
+                                "application/json": { "schema": schema("CardResponse") },
+                            },
+                        },
+                        "202": {
+                            "description": "他で配信中で、まだ結果が分からない",
+                            "content": {
+                                "application/json": { "schema": schema("CardResponse") },
+                            },
+                        },
+                        "400": { "description": "PNGか宛先が無い" },
+                        "409": { "description": "下書きでも予約済みでもないか、配信を取りやめた" },
+                        "502": { "description": "配信に失敗した" },
+                    },
+                },
+            },
+            "/api/cards/{id}/occurrences": {
+                "get": {
+                    "tags": ["cards"],
+                    "summary": "繰り返しのカードの今後の発生日時",
+                    "parameters": [
+                        path_param("id"),
+                        query_param(
+                            "count",
+                            json!({ "type": "integer", "default": 10, "maximum": 100 }),
+                            "",
+                        ),
+                    ],
+                    "responses": ok("application/json", array(schema("Occurrence"))),
+                },
+            },
+            "/api/cards/{id}/occurrences/{at}/skip": {
+                "put": {
+                    "tags": ["cards"],
+                    "summary": "発生日時を1回だけ飛ばす",
+                    "parameters": [path_param("id"), date_time_path_param("at")],
+                    "responses": no_content(),
+                },
+                "delete": {
+                    "tags": ["cards"],
+                    "summary": "飛ばすのを取り消す",
+                    "parameters": [path_param("id"), date_time_path_param("at")],
+                    "responses": no_content(),
+                },
+            },
+            "/api/cards/{id}/svg": {
+                "get": { "tags": ["cards"], "parameters": [path_param("id")], "responses": image_response() },
+                "post": {
+                    "tags": ["cards"],
+                    "parameters": [path_param("id")],
+                    "requestBody": body("image/svg+xml", json!({ "type": "string" })),
+                    "responses": no_content(),
+                },
+                "patch": {
+                    "tags": ["cards"],
+                    "parameters": [path_param("id"), if_match_param()],
+                    "requestBody": body("image/svg+xml", json!({ "type": "string" })),
+                    "responses": precondition_responses(),
+                },
+            },
+            "/api/cards/{id}/png": {
+                "get": { "tags": ["cards"], "parameters": [path_param("id")], "responses": image_response() },
+                "post": {
+                    "tags": ["cards"],
+                    "parameters": [path_param("id")],
+                    "requestBody": body("image/png", binary()),
+                    "responses": no_content(),
+                },
+                "patch": {
+                    "tags": ["cards"],
+                    "parameters": [path_param("id"), if_match_param()],
+                    "requestBody": body("image/png", binary()),
+                    "responses": precondition_responses(),
+                },
+            },
+            "/api/cards/{id}/svg/revisions": {
+                "get": {
+                    "tags": ["revisions"],
+                    "summary": "SVGの版を新しい順に返す。メンバーのみ",
+                    "parameters": [path_param("id")],
+                    "responses": ok("application/json", array(schema("ImageRevision"))),
+                },
+            },
+            "/api/cards/{id}/svg/revisions/{revision}": {
+                "get": {
+                    "tags": ["revisions"],
+                    "parameters": [path_param("id"), revision_path_param()],
+                    "responses": image_response(),
+                },
+            },
+            "/api/cards/{id}/svg/revisions/{revision}/restore": {
+                "post": {
+                    "tags": ["revisions"],
+                    "summary": "版の内容を現在のSVGにし、新しい版として残す",
+                    "parameters": [path_param("id"), revision_path_param(), if_match_param()],
+                    "responses": restore_responses(),
+                },
+            },
+            "/api/cards/{id}/png/revisions": {
+                "get": {
+                    "tags": ["revisions"],
+                    "summary": "PNGの版を新しい順に返す。メンバーのみ",
+                    "parameters": [path_param("id")],
+                    "responses": ok("application/json", array(schema("ImageRevision"))),
+                },
+            },
+            "/api/cards/{id}/png/revisions/{revision}": {
+                "get": {
+                    "tags": ["revisions"],
+                    "parameters": [path_param("id"), revision_path_param()],
+                    "responses": image_response(),
+                },
+            },
+            "/api/cards/{id}/png/revisions/{revision}/restore": {
+                "post": {
+                    "tags": ["revisions"],
+                    "summary": "版の内容を現在のPNGにし、新しい版として残す",
+                    "parameters": [path_param("id"), revision_path_param(), if_match_param()],
+                    "responses": restore_responses(),
+                },
+            },
+            "/api/images": {
+                "post": {
+                    "tags": ["images"],
+                    "summary": "カードに貼る画像のアップロード",
+                    "requestBody": body("multipart/form-data", schema("ImageForm")),
+                    "responses": {
+                        "204": { "description": "No Content" },
+                        "403": { "description": "同じIDの画像を他の人が上げている" },
+                    },
+                },
+            },
+            "/api/images/{id}": {
+                "get": { "tags": ["images"], "parameters": [path_param("id")], "responses": image_response() },
+            },
+            "/api/stamps": {
+                "get": {
+                    "tags": ["stamps"],
+                    "parameters": [stamp_type.clone()],
+                    "responses": ok("application/json", array(schema("Stamp"))),
+                },
+            },
+            "/api/stamps/search": {
+                "get": {
+                    "tags": ["stamps"],
+                    "parameters": [q.clone(), mode.clone(), stamp_type, limit.clone(), offset.clone()],
+                    "responses": ok("application/json", schema("StampPage")),
+                },
+            },
+            "/api/stamps/{id}/image": {
+                "get": { "tags": ["stamps"], "parameters": [path_param("id")], "responses": image_response() },
+            },
+            "/api/users": {
+                "get": {
+                    "tags": ["users"],
+                    "parameters": [
+                        query_param("name", json!({ "type": "string" }), "traQ ID"),
+                        query_param("ids", json!({ "type": "string" }), "カンマ区切りのユーザーID。`name`より優先"),
+                    ],
+                    "responses": ok("application/json", array(schema("User"))),
+                },
+            },
+            "/api/users/{id}": {
+                "get": {
+                    "tags": ["users"],
+                    "parameters": [path_param("id")],
+                    "responses": ok("application/json", schema("UserDetail")),
+                },
+            },
+            "/api/users/{id}/icon": {
+                "get": { "tags": ["users"], "parameters": [path_param("id")], "responses": image_response() },
+            },
+            "/api/channels": {
+                "get": { "tags": ["channels"], "responses": ok("application/json", schema("ChannelList")) },
+            },
+            "/api/channels/resolve": {
+                "get": {
+                    "tags": ["channels"],
+                    "parameters": [json!({
+                        "name": "path",
+                        "in": "query",
+                        "required": true,
+                        "schema": { "type": "string", "example": "#gps/times/foo" },
+                    })],
+                    "responses": ok("application/json", schema("ResolvedChannel")),
+                },
+            },
+            "/api/channels/search": {
+                "get": {
+                    "tags": ["channels"],
+                    "parameters": [
+                        q,
+                        mode,
+                        query_param("archived", json!({ "type": "boolean", "default": false }), "アーカイブ済みも含める"),
+                        limit.clone(),
+                        offset.clone(),
+                    ],
+                    "responses": ok("application/json", schema("ChannelPage")),
+                },
+            },
+            "/api/webhooks": {
+                "get": {
+                    "tags": ["webhooks"],
+                    "summary": "登録済みのWebhook。管理者のみ",
+                    "responses": ok("application/json", array(schema("WebhookResponse"))),
+                },
+            },
+            "/api/webhooks/{channel_id}": {
+                "put": {
+                    "tags": ["webhooks"],
+                    "summary": "チャンネルのWebhookを登録する。管理者のみ",
+                    "parameters": [path_param("channel_id")],
+                    "requestBody": body("application/json", schema("WebhookRequest")),
+                    "responses": no_content(),
+                },
+                "delete": {
+                    "tags": ["webhooks"],
+                    "summary": "チャンネルのWebhookの登録を消す。管理者のみ",
+                    "parameters": [path_param("channel_id")],
+                    "responses": no_content(),
+                },
+            },
+            "/api/admin/cards": {
+                "get": {
+                    "tags": ["admin"],
+                    "summary": "全てのカードを投稿日時の新しい順に返す",
+                    "parameters": [
+                        query_param("q", json!({ "type": "string" }), "カードIDの前方一致かメッセージの部分一致"),
+                        query_param("status", schema("CardStatus"), ""),
+                        query_param("owner_id", uuid(), ""),
+                        expand_param(),
+                        limit,
+                        offset,
+                    ],
+                    "responses": ok("application/json", schema("CardPage")),
+                },
+            },
+            "/api/admin/cards/{id}/deliver": {
+                "post": {
+                    "tags": ["admin"],
+                    "summary": "予約済み・失敗・配信中のまま10分以上止まったカードを投稿日時を待たずに配信する",
+                    "parameters": [path_param("id")],
+                    "responses": ok("application/json", schema("CardResponse")),
+                },
+            },
+            "/api/admin/cards/{id}/cancel": {
+                "post": {
+                    "tags": ["admin"],
+                    "summary": "配信済みでないカードを取り消す",
+                    "parameters": [path_param("id")],
+                    "responses": no_content(),
+                },
+            },
+            "/api/admin/cards/{id}/reassign": {
+                "post": {
+                    "tags": ["admin"],
+                    "summary": "差出人を付け替える。元の差出人は編集者になる",
+                    "parameters": [path_param("id")],
+                    "requestBody": body("application/json", schema("ReassignRequest")),
+                    "responses": no_content(),
+                },
+            },
+            "/api/admin/queue": {
+                "get": {
+                    "tags": ["admin"],
+                    "summary": "予約済みと配信中のカードを配信順に返す",
+                    "responses": ok("application/json", array(schema("QueueEntry"))),
+                },
+            },
+            "/api/admin/cron/tick": {
+                "post": {
+                    "tags": ["admin"],
+                    "summary": "cronを1回動かす",
+                    "responses": no_content(),
+                },
+            },
+            "/api/admin/audit": {
+                "get": {
+                    "tags": ["admin"],
+                    "summary": "監査ログを新しい順に返す",
+                    "parameters": [
+                        query_param("card_id", uuid(), ""),
+                        query_param("actor_id", uuid(), ""),
+                        query_param("action", schema("AuditAction"), ""),
+                        query_param("since", json!({ "type": "string", "format": "date-time" }), ""),
+                        query_param("until", json!({ "type": "string", "format": "date-time" }), ""),
+                        query_param("limit", json!({ "type": "integer", "default": 50, "maximum": 200 }), ""),
+                        query_param("offset", json!({ "type": "integer", "default": 0 }), ""),
+                    ],
+                    "responses": ok("application/json", array(schema("AuditEvent"))),
+                },
+            },
+            "/bot": {
+                "post": {
+                    "tags": ["bot"],
+                    "summary": "traQ BOTのイベント受信",
+                    "parameters": [
+                        { "name": "X-TRAQ-BOT-TOKEN", "in": "header", "required": true, "schema": { "type": "string" } },
+                        { "name": "X-TRAQ-BOT-EVENT", "in": "header", "required": true, "schema": { "type": "string" } },
+                    ],
+                    "requestBody": body("application/json", json!({ "type": "object" })),
+                    "responses": no_content(),
+                },
+            },
+        },
+        "components": components(),
+        "security": [{ "forwardedUser": [] }],
+    })
+}
+
+#[rocket::get("/openapi.json")]
+pub fn get_spec() -> Json<Value> {
+    Json(spec())
+}
+
+#[rocket::get("/docs")]
+pub fn get_docs() -> RawHtml<&'static str> {
+    RawHtml(
+        r##"<!DOCTYPE html>
+<html lang="ja">
+<head>
+  <meta charset="utf-8" />
+  <title>Qard API</title>
+  <link rel="stylesheet" href="/api/docs/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="/api/docs/swagger-ui-bundle.js"></script>
+  <script>
+    window.onload = () => {
+      window.ui = SwaggerUIBundle({ url: "/api/openapi.json", dom_id: "#swagger-ui" });
+    };
+  </script>
+</body>
+</html>
+"##,
+    )
+}
+
+/// 同梱したSwagger UIのファイル
+#[rocket::get("/docs/<file>")]
+pub fn get_docs_asset(file: &str) -> Option<Cached<(ContentType, &'static [u8])>> {
+    let (content_type, content) = match file {
+        "swagger-ui.css" => (ContentType::CSS, SWAGGER_UI_CSS),
+        "swagger-ui-bundle.js" => (ContentType::JavaScript, SWAGGER_UI_BUNDLE),
+        _ => return None,
+    };
+    let etag = content_etag(content);
+    Some(Cached::new(
+        (content_type, content),
+        etag,
+        CachePolicy::Revalidate,
+    ))
+}
@@ -0,0 +1,262 @@
+//! 繰り返し配信するカードの発生日時
+
+use chrono::Utc;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{Route, State};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use domain::recurrence::Recurrence;
+use domain::repository::{CardModel, CardStatus, DateTimeUtc, RecurrenceModel};
+use domain::time_zone::to_local_rfc3339;
+
+use crate::auth::AuthUser;
+use crate::cards::editable_card;
+use crate::members::card_role;
+use crate::{DateTimeParam, UuidParam, CR};
+
+pub const DEFAULT_OCCURRENCE_COUNT: usize = 10;
+pub const MAX_OCCURRENCE_COUNT: usize = 100;
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct RecurrenceRequest {
+    /// `FREQ=YEARLY|MONTHLY|WEEKLY`と`INTERVAL`・`COUNT`・`UNTIL`・`BYDAY`(週単位のみ)
+    pub rrule: String,
+    /// `Asia/Tokyo`など。省略するとカードのタイムゾーン
+    #[serde(default)]
+    pub time_zone: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct RecurrenceResponse {
+    pub rrule: String,
+    pub time_zone: String,
+    /// 最初の発生日時
+    pub dtstart: DateTimeUtc,
+    /// 最後に配信した発生日時
+    pub last_occurrence: Option<DateTimeUtc>,
+}
+
+impl From<RecurrenceModel> for RecurrenceResponse {
+    fn from(value: RecurrenceModel) -> Self {
+        Self {
+            rrule: value.rrule,
+            time_zone: value.time_zone,
+            dtstart: value.dtstart,
+            last_occurrence: value.last_occurrence,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+#[schemars(rename = "Occurrence")]
+pub struct OccurrenceResponse {
+    pub at: DateTimeUtc,
+    /// 設定したタイムゾーンでの日時
+    pub local: String,
+    pub skipped: bool,
+}
+
+/// リクエストの繰り返し設定を検証し、最初の発生日時と合わせて返す
+///
+/// 規則と投稿日時が変わっていなければ`current`の起点と配信履歴を引き継ぐ
+pub fn build_recurrence(
+    card_id: Uuid,
+    request: RecurrenceRequest,
+    publish_date: Option<DateTimeUtc>,
+    card_time_zone: &str,
+    current: Option<(&RecurrenceModel, Option<DateTimeUtc>)>,
+) -> Result<(RecurrenceModel, DateTimeUtc), Status> {
+    let Some(publish_date) = publish_date else {
+        eprintln!("recurring card requires publish_date");
+        return Err(Status::BadRequest);
+    };
+    let time_zone = request
+        .time_zone
+        .unwrap_or_else(|| card_time_zone.to_string());
+    if let Some((current, current_date)) = current {
+        if current.rrule == request.rrule
+            && current.time_zone == time_zone
+            && current_date == Some(publish_date)
+        {
+            return Ok((current.clone(), publish_date));
+        }
+    }
+    let model = RecurrenceModel {
+        card_id,
+        rrule: request.rrule,
+        time_zone,
+        dtstart: publish_date,
+        last_occurrence: None,
+    };
+    let recurrence = Recurrence::try_from(&model).map_err(|e| {
+        eprintln!("invalid recurrence: {}", e);
+        Status::BadRequest
+    })?;
+    // BYDAYに起点の曜日が含まれないこともあるので、最初の発生日時に合わせる
+    let first = recurrence.occurrences().next().ok_or_else(|| {
+        eprintln!("recurrence has no occurrence");
+        Status::BadRequest
+    })?;
+    Ok((model, first))
+}
+
+async fn get_card_with_recurrence(
+    id: Uuid,
+    card_repo: &State<CR>,
+) -> Result<(CardModel, RecurrenceModel, Recurrence), Status> {
+    let card = card_repo
+        .0
+        .get_card_by_id(id)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get card by id: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)?;
+    let model = card_repo
+        .0
+        .get_recurrence(id)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get recurrence: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)?;
+    let recurrence = Recurrence::try_from(&model).map_err(|e| {
+        eprintln!("invalid stored recurrence: {}", e);
+        Status::InternalServerError
+    })?;
+    Ok((card, model, recurrence))
+}
+
+async fn get_skipped(id: Uuid, card_repo: &State<CR>) -> Result<Vec<DateTimeUtc>, Status> {
+    card_repo.0.get_skipped_occurrences(id).await.map_err(|e| {
+        eprintln!("error in get skipped occurrences: {}", e);
+        Status::InternalServerError
+    })
+}
+
+/// 予約済みなら投稿日時を飛ばしていない次の発生日時に合わせる
+async fn refresh_publish_date(
+    card: &CardModel,
+    model: &RecurrenceModel,
+    recurrence: &Recurrence,
+    card_repo: &State<CR>,
+) -> Result<(), Status> {
+    if card.status != CardStatus::Scheduled {
+        return Ok(());
+    }
+    let skipped = get_skipped(card.id, card_repo).await?;
+    let now = Utc::now();
+    let after = model.last_occurrence.map_or(now, |l| l.max(now));
+    let Some(next) = recurrence.next_after(after, &skipped) else {
+        // 残りが全て飛ばされていればcronが配信せずに終える
+        return Ok(());
+    };
+    if card.publish_date == Some(next) {
+        return Ok(());
+    }
+    card_repo
+        .0
+        .reschedule_card(card.id, &[CardStatus::Scheduled], next)
+        .await
+        .map_err(|e| {
+            eprintln!("error in reschedule card: {}", e);
+            Status::InternalServerError
+        })?;
+    Ok(())
+}
+
+/// 今後の発生日時(飛ばすものを含む)
+#[rocket::get("/<id>/occurrences?<count>")]
+pub async fn get_occurrences(
+    id: UuidParam,
+    count: Option<usize>,
+    card_repo: &State<CR>,
+    user: AuthUser,
+) -> Result<Json<Vec<OccurrenceResponse>>, Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let (card, _, recurrence) = get_card_with_recurrence(id.0, card_repo).await?;
+    if card_role(user.id, card.id, card_repo).await?.is_none() {
+        return Err(Status::Forbidden);
+    }
+    let skipped = get_skipped(id.0, card_repo).await?;
+    let now = Utc::now();
+    let count = count
+        .unwrap_or(DEFAULT_OCCURRENCE_COUNT)
+        .min(MAX_OCCURRENCE_COUNT);
+    let occurrences = recurrence
+        .occurrences()
+        .skip_while(|o| *o <= now)
+        .take(count)
+        .map(|at| OccurrenceResponse {
+            at,
+            local: to_local_rfc3339(at, recurrence.time_zone),
+            skipped: skipped.contains(&at),
+        })
+        .collect();
+    Ok(Json(occurrences))
+}
+
+#[rocket::put("/<id>/occurrences/<at>/skip")]
+pub async fn skip_occurrence(
+    id: UuidParam,
+    at: DateTimeParam,
+    card_repo: &State<CR>,
+    user: AuthUser,
+) -> Result<Status, Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let (card, model, recurrence) = get_card_with_recurrence(id.0, card_repo).await?;
+    if !editable_card(&user, &card, card_repo).await? {
+        return Err(Status::Forbidden);
+    }
+    let at = at.0;
+    if at <= Utc::now() || !recurrence.contains(at) {
+        return Err(Status::BadRequest);
+    }
+    card_repo
+        .0
+        .save_skipped_occurrence(id.0, at)
+        .await
+        .map_err(|e| {
+            eprintln!("error in save skipped occurrence: {}", e);
+            Status::InternalServerError
+        })?;
+    refresh_publish_date(&card, &model, &recurrence, card_repo).await?;
+    Ok(Status::NoContent)
+}
+
+#[rocket::delete("/<id>/occurrences/<at>/skip")]
+pub async fn unskip_occurrence(
+    id: UuidParam,
+    at: DateTimeParam,
+    card_repo: &State<CR>,
+    user: AuthUser,
+) -> Result<Status, Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let (card, model, recurrence) = get_card_with_recurrence(id.0, card_repo).await?;
+    if !editable_card(&user, &card, card_repo).await? {
+        return Err(Status::Forbidden);
+    }
+    card_repo
+        .0
+        .delete_skipped_occurrence(id.0, at.0)
+        .await
+        .map_err(|e| {
+            eprintln!("error in delete skipped occurrence: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)?;
+    refresh_publish_date(&card, &model, &recurrence, card_repo).await?;
+    Ok(Status::NoContent)
+}
+
+pub fn routes() -> Vec<Route> {
+    rocket::routes![get_occurrences, skip_occurrence, unskip_occurrence]
+}
@@ -0,0 +1,379 @@
+//! `#gps/times/foo`形式のチャンネルパスや`@name`形式のユーザー名をIDに解決する
+
+use std::collections::HashMap;
+use std::fmt;
+
+use rocket::http::Status;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use domain::bot_client::{Channel, ChannelList};
+
+use crate::BC;
+
+/// UUIDまたは`#`始まりのチャンネルパス
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ChannelRef {
+    Id(Uuid),
+    Path(String),
+}
+
+impl JsonSchema for ChannelRef {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "ChannelRef".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        String::json_schema(gen)
+    }
+}
+
+impl TryFrom<String> for ChannelRef {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.strip_prefix('#') {
+            Some(path) => Ok(Self::Path(normalize_path(path))),
+            None => value
+                .parse()
+                .map(Self::Id)
+                .map_err(|_| format!("invalid channel `{}`", value)),
+        }
+    }
+}
+
+impl From<ChannelRef> for String {
+    fn from(value: ChannelRef) -> Self {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for ChannelRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "{}", id),
+            Self::Path(path) => write!(f, "#{}", path),
+        }
+    }
+}
+
+/// UUIDまたは`@`始まりのユーザー名
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum UserRef {
+    Id(Uuid),
+    Name(String),
+}
+
+impl JsonSchema for UserRef {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "UserRef".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        String::json_schema(gen)
+    }
+}
+
+impl TryFrom<String> for UserRef {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.strip_prefix('@') {
+            Some(name) => Ok(Self::Name(name.to_string())),
+            None => value
+                .parse()
+                .map(Self::Id)
+                .map_err(|_| format!("invalid user `{}`", value)),
+        }
+    }
+}
+
+impl From<UserRef> for String {
+    fn from(value: UserRef) -> Self {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for UserRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "{}", id),
+            Self::Name(name) => write!(f, "@{}", name),
+        }
+    }
+}
+
+/// 先頭の`#`と前後の`/`を取り除く
+pub fn normalize_path(path: &str) -> String {
+    path.trim_start_matches('#').trim_matches('/').to_string()
+}
+
+/// チャンネルのフルパス(`gps/times/foo`)とIDの対応
+#[derive(Debug, Clone, Default)]
+pub struct ChannelPathIndex {
+    by_path: HashMap<String, Uuid>,
+    by_id: HashMap<Uuid, String>,
+}
+
+impl ChannelPathIndex {
+    /// ルートチャンネルから`children`を辿ってパスを組み立てる。
+    /// 同じパスのチャンネルがあれば、アーカイブされていないもの、次に一覧で先のものを優先する
+    pub fn new(list: &ChannelList) -> Self {
+        let channels: HashMap<Uuid, &Channel> = list.public.iter().map(|c| (c.id, c)).collect();
+        let mut index = Self::default();
+        let mut stack: Vec<(&Channel, String)> = list
+            .public
+            .iter()
+            .filter(|c| c.parent_id.is_none())
+            .map(|c| (c, c.name.clone()))
+            .collect();
+        // 先に取り出すよう逆順に積む
+        stack.reverse();
+        while let Some((channel, path)) = stack.pop() {
+            if index.by_id.contains_key(&channel.id) {
+                continue;
+            }
+            for child in channel
+                .children
+                .iter()
+                .rev()
+                .filter_map(|id| channels.get(id))
+            {
+                stack.push((child, format!("{}/{}", path, child.name)));
+            }
+            let taken = index
+                .by_path
+                .get(&path)
+                .and_then(|id| channels.get(id))
+                .is_some_and(|c| !c.archived || channel.archived);
+            if !taken {
+                index.by_path.insert(path.clone(), channel.id);
+            }
+            index.by_id.insert(channel.id, path);
+        }
+        index
+    }
+
+    pub fn id(&self, path: &str) -> Option<Uuid> {
+        self.by_path.get(&normalize_path(path)).copied()
+    }
+
+    pub fn path(&self, id: Uuid) -> Option<&str> {
+        self.by_id.get(&id).map(String::as_str)
+    }
+}
+
+/// パスが含まれる時だけチャンネル一覧を取得する。解決できないパスがあれば`BadRequest`
+pub async fn resolve_channels(refs: &[ChannelRef], client: &BC) -> Result<Vec<Uuid>, Status> {
+    let index = if refs.iter().any(|r| matches!(r, ChannelRef::Path(_))) {
+        let list = client.0.get_channels().await.map_err(|e| {
+            eprintln!("Error in get_channels: {}", e);
+            Status::InternalServerError
+        })?;
+        ChannelPathIndex::new(&list)
+    } else {
+        ChannelPathIndex::default()
+    };
+    refs.iter()
+        .map(|r| match r {
+            ChannelRef::Id(id) => Ok(*id),
+            ChannelRef::Path(path) => index.id(path).ok_or_else(|| {
+                eprintln!("channel `#{}` not found", path);
+                Status::BadRequest
+            }),
+        })
+        .collect()
+}
+
+/// 名前が含まれる時だけユーザー一覧を取得する。解決できない名前があれば`BadRequest`
+pub async fn resolve_users(refs: &[UserRef], client: &BC) -> Result<Vec<Uuid>, Status> {
+    let users = if refs.iter().any(|r| matches!(r, UserRef::Name(_))) {
+        client.0.get_users(None).await.map_err(|e| {
+            eprintln!("Error in get_users: {}", e);
+            Status::InternalServerError
+        })?
+    } else {
+        vec![]
+    };
+    refs.iter()
+        .map(|r| match r {
+            UserRef::Id(id) => Ok(*id),
+            UserRef::Name(name) => users
+                .iter()
+                .find(|u| &u.name == name)
+                .map(|u| u.id)
+                .ok_or_else(|| {
+                    eprintln!("user `@{}` not found", name);
+                    Status::BadRequest
+                }),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use domain::bot_client::MockBotClient;
+
+    use super::*;
+
+    fn channel(name: &str, parent: Option<&Channel>) -> Channel {
+        Channel {
+            id: Uuid::new_v4(),
+            parent_id: parent.map(|p| p.id),
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// `children`を親に繋いだチャンネル一覧
+    fn list(mut channels: Vec<Channel>) -> ChannelList {
+        let pairs: Vec<_> = channels.iter().map(|c| (c.id, c.parent_id)).collect();
+        for (id, parent) in pairs {
+            if let Some(parent) = channels.iter_mut().find(|c| Some(c.id) == parent) {
+                parent.children.push(id);
+            }
+        }
+        ChannelList {
+            public: channels,
+            ..Default::default()
+        }
+    }
+
+    fn client_with(list: ChannelList) -> BC {
+        let mut client = MockBotClient::new();
+        client
+            .expect_get_channels()
+            .returning(move || Ok(list.clone()));
+        client.expect_get_users().returning(|_| {
+            Ok(vec![domain::bot_client::User {
+                id: Uuid::nil(),
+                name: "alice".to_string(),
+                ..Default::default()
+            }])
+        });
+        BC::from(client)
+    }
+
+    #[test]
+    fn builds_paths_along_children() {
+        let gps = channel("gps", None);
+        let times = channel("times", Some(&gps));
+        let foo = channel("foo", Some(&times));
+        let index = ChannelPathIndex::new(&list(vec![foo.clone(), gps.clone(), times.clone()]));
+        assert_eq!(index.id("gps"), Some(gps.id));
+        assert_eq!(index.id("gps/times/foo"), Some(foo.id));
+        assert_eq!(index.path(times.id), Some("gps/times"));
+        assert_eq!(index.id("times"), None);
+    }
+
+    #[test]
+    fn normalizes_hash_and_slashes() {
+        assert_eq!(normalize_path("#gps/times/"), "gps/times");
+        assert_eq!(normalize_path("/gps/"), "gps");
+        let gps = channel("gps", None);
+        let times = channel("times", Some(&gps));
+        let index = ChannelPathIndex::new(&list(vec![gps, times.clone()]));
+        assert_eq!(index.id("#gps/times/"), Some(times.id));
+        let parsed = ChannelRef::try_from("#gps/times/".to_string()).unwrap();
+        assert_eq!(parsed, ChannelRef::Path("gps/times".to_string()));
+        assert!(ChannelRef::try_from("gps".to_string()).is_err());
+    }
+
+    #[test]
+    fn prefers_active_channel_over_archived_with_same_path() {
+        let gps = channel("gps", None);
+        let old = Channel {
+            archived: true,
+            ..channel("times", Some(&gps))
+        };
+        let new = channel("times", Some(&gps));
+        for channels in [
+            vec![gps.clone(), old.clone(), new.clone()],
+            vec![gps.clone(), new.clone(), old.clone()],
+        ] {
+            let index = ChannelPathIndex::new(&list(channels));
+            assert_eq!(index.id("gps/times"), Some(new.id));
+            assert_eq!(index.path(old.id), Some("gps/times"));
+        }
+    }
+
+    #[test]
+    fn keeps_first_of_duplicate_names() {
+        let first = channel("gps", None);
+        let second = channel("gps", None);
+        let index = ChannelPathIndex::new(&list(vec![first.clone(), second.clone()]));
+        assert_eq!(index.id("gps"), Some(first.id));
+        assert_eq!(index.path(second.id), Some("gps"));
+    }
+
+    #[tokio::test]
+    async fn unknown_references_are_bad_requests() {
+        let gps = channel("gps", None);
+        let client = client_with(list(vec![gps.clone()]));
+        let id = Uuid::new_v4();
+        let refs = [ChannelRef::Path("gps".to_string()), ChannelRef::Id(id)];
+        assert_eq!(resolve_channels(&refs, &client).await, Ok(vec![gps.id, id]));
+        let refs = [ChannelRef::Path("gps/nowhere".to_string())];
+        assert_eq!(
+            resolve_channels(&refs, &client).await,
+            Err(Status::BadRequest)
+        );
+
+        let refs = [UserRef::Name("alice".to_string()), UserRef::Id(id)];
+        assert_eq!(
+            resolve_users(&refs, &client).await,
+            Ok(vec![Uuid::nil(), id])
+        );
+        let refs = [UserRef::Name("bob".to_string())];
+        assert_eq!(resolve_users(&refs, &client).await, Err(Status::BadRequest));
+    }
+
+    #[tokio::test]
+    async fn ids_need_no_lookup() {
+        // 一覧を取得すると`MockBotClient`が失敗する
+        let client = BC::from(MockBotClient::new());
+        let id = Uuid::new_v4();
+        assert_eq!(
+            resolve_channels(&[ChannelRef::Id(id)], &client).await,
+            Ok(vec![id])
+        );
+        assert_eq!(
+            resolve_users(&[UserRef::Id(id)], &client).await,
+            Ok(vec![id])
+        );
+    }
+
+    #[test]
+    fn resolve_route_is_not_found_for_unknown_path() {
+        use rocket::local::blocking::Client;
+
+        use crate::auth::AuthUserConfig;
+        use crate::traq_api::channels;
+
+        let gps = channel("gps", None);
+        let rocket = rocket::build()
+            .mount("/api/channels", rocket::routes![channels::resolve])
+            .manage(AuthUserConfig(false))
+            .manage(client_with(list(vec![gps.clone()])));
+        let client = Client::tracked(rocket).unwrap();
+        let res = client.get("/api/channels/resolve?path=%23gps/").dispatch();
+        assert_eq!(res.status(), Status::Ok);
+        let res = client
+            .get("/api/channels/resolve?path=%23gps/nowhere")
+            .dispatch();
+        assert_eq!(res.status(), Status::NotFound);
+    }
+}
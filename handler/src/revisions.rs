@@ -0,0 +1,503 @@
+//! カードの画像の版
+//!
+//! SVG・PNGを保存するたびに番号付きの版として残す。現在の画像は最新の版と同じ内容で、
+//! 版を戻すと、その内容で新しい版を作る。`RevisionConfig::max_revisions`を超えた古い版から消す。
+
+use bytes::Bytes;
+use chrono::Utc;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{Route, State};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use domain::bot_client::User;
+use domain::repository::{AuditAction, CardModel, ImageKind, ImageRevisionModel};
+
+use crate::audit::{image_digest, AuditEvent, RequestId};
+use crate::auth::AuthUser;
+use crate::cache::{content_etag, version_etag, CachePolicy, Cached, IfMatch, Tagged};
+use crate::cards::editable_card;
+use crate::images::ImageResponse;
+use crate::members::card_role;
+use crate::{UuidParam, CR, IR};
+
+#[derive(Debug, Clone)]
+pub struct RevisionConfig {
+    /// カード・種類ごとに残す版の数
+    pub max_revisions: usize,
+}
+
+impl Default for RevisionConfig {
+    fn default() -> Self {
+        Self { max_revisions: 20 }
+    }
+}
+
+impl RevisionConfig {
+    pub fn load_env() -> Result<Self, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let mut config = Self::default();
+        if let Ok(limit) = std::env::var("IMAGE_REVISION_LIMIT") {
+            config = config.max_revisions(limit.parse()?);
+        }
+        if config.max_revisions == 0 {
+            return Err("IMAGE_REVISION_LIMIT must be positive".into());
+        }
+        Ok(config)
+    }
+
+    pub fn max_revisions(self, value: usize) -> Self {
+        Self {
+            max_revisions: value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ImageRevision {
+    pub revision: u32,
+    pub author_id: Uuid,
+    pub created_at: chrono::DateTime<Utc>,
+    pub size: u64,
+    /// 内容のSHA-256(16進)
+    pub hash: String,
+}
+
+impl From<ImageRevisionModel> for ImageRevision {
+    fn from(value: ImageRevisionModel) -> Self {
+        Self {
+            revision: value.revision,
+            author_id: value.author_id,
+            created_at: value.created_at,
+            size: value.size,
+            hash: value.hash,
+        }
+    }
+}
+
+/// 画像のETag。版の番号から作る
+pub(crate) fn revision_etag(revision: u32) -> String {
+    version_etag("r", revision)
+}
+
+/// 最新の版の番号。版を残す前に保存された画像なら`None`
+pub(crate) async fn latest_revision(
+    card_id: Uuid,
+    kind: ImageKind,
+    card_repo: &State<CR>,
+) -> Result<Option<u32>, Status> {
+    let revisions = get_revisions(card_id, kind, card_repo).await?;
+    Ok(revisions.last().map(|r| r.revision))
+}
+
+/// 現在の画像の版の番号とETag。版が無ければETagは内容から作り、画像が無ければ`None`
+async fn current(
+    card_id: Uuid,
+    kind: ImageKind,
+    card_repo: &State<CR>,
+    image_repo: &State<IR>,
+) -> Result<(Option<u32>, Option<String>), Status> {
+    if let Some(revision) = latest_revision(card_id, kind, card_repo).await? {
+        return Ok((Some(revision), Some(revision_etag(revision))));
+    }
+    let content = match kind {
+        ImageKind::Svg => image_repo
+            .0
+            .get_svg(card_id)
+            .await
+            .map(|s| s.map(Bytes::from)),
+        ImageKind::Png => image_repo.0.get_png(card_id).await,
+    }
+    .map_err(|e| {
+        eprintln!("error in get {}: {}", kind.extension(), e);
+        Status::InternalServerError
+    })?;
+    Ok((None, content.map(|c| content_etag(&c))))
+}
+
+/// `If-Match`を現在の画像のETagと比べ、次の版の番号を返す
+pub(crate) async fn check_current(
+    card_id: Uuid,
+    kind: ImageKind,
+    if_match: &IfMatch,
+    card_repo: &State<CR>,
+    image_repo: &State<IR>,
+) -> Result<u32, Status> {
+    let (latest, etag) = current(card_id, kind, card_repo, image_repo).await?;
+    if_match.check(etag.as_deref())?;
+    Ok(latest.map_or(1, |r| r + 1))
+}
+
+/// 次の版の番号
+pub(crate) async fn next_revision(
+    card_id: Uuid,
+    kind: ImageKind,
+    card_repo: &State<CR>,
+) -> Result<u32, Status> {
+    let latest = latest_revision(card_id, kind, card_repo).await?;
+    Ok(latest.map_or(1, |r| r + 1))
+}
+
+async fn get_revisions(
+    card_id: Uuid,
+    kind: ImageKind,
+    card_repo: &State<CR>,
+) -> Result<Vec<ImageRevisionModel>, Status> {
+    card_repo
+        .0
+        .get_image_revisions(card_id, kind)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get image revisions: {}", e);
+            Status::InternalServerError
+        })
+}
+
+/// `content`を版`revision`として残し、現在の画像にする
+///
+/// 先に版の行を確保してから書き込む。他の保存が同じ番号を先に確保していれば何も書かずに`None`
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn save_image(
+    card_id: Uuid,
+    kind: ImageKind,
+    revision: u32,
+    content: &Bytes,
+    author: &User,
+    card_repo: &State<CR>,
+    image_repo: &State<IR>,
+    config: &State<RevisionConfig>,
+) -> Result<Option<ImageRevisionModel>, Status> {
+    let model = ImageRevisionModel {
+        card_id,
+        kind,
+        revision,
+        author_id: author.id,
+        created_at: Utc::now(),
+        size: content.len() as u64,
+        hash: hex::encode(Sha256::digest(content)),
+    };
+    let reserved = card_repo.0.save_image_revision(&model).await.map_err(|e| {
+        eprintln!("error in save image revision: {}", e);
+        Status::InternalServerError
+    })?;
+    if reserved.is_none() {
+        return Ok(None);
+    }
+    if let Err(e) = write_image(card_id, kind, revision, content, image_repo).await {
+        eprintln!("error in save {}: {}", kind.extension(), e);
+        // 次の保存が同じ番号を使えるように確保を取り消す
+        if let Err(e) = card_repo
+            .0
+            .delete_image_revision(card_id, kind, revision)
+            .await
+        {
+            eprintln!("error in delete image revision: {}", e);
+        }
+        return Err(Status::InternalServerError);
+    }
+    // 上限を超えた古い版を消す。失敗しても次の保存で消える
+    let revisions = get_revisions(card_id, kind, card_repo).await?;
+    let expired = revisions.len().saturating_sub(config.max_revisions);
+    for old in &revisions[..expired] {
+        if let Err(e) = delete_revision(old, card_repo, image_repo).await {
+            eprintln!("error in delete expired revision: {}", e);
+        }
+    }
+    Ok(Some(model))
+}
+
+/// 版の内容を書いてから現在の画像を置き換える
+async fn write_image(
+    card_id: Uuid,
+    kind: ImageKind,
+    revision: u32,
+    content: &Bytes,
+    image_repo: &State<IR>,
+) -> anyhow::Result<()> {
+    image_repo
+        .0
+        .save_revision(card_id, kind, revision, content)
+        .await?;
+    match kind {
+        ImageKind::Svg => {
+            let svg = std::str::from_utf8(content)?;
+            image_repo.0.save_svg(card_id, svg).await
+        }
+        ImageKind::Png => image_repo.0.save_png(card_id, content).await,
+    }
+}
+
+async fn delete_revision(
+    revision: &ImageRevisionModel,
+    card_repo: &State<CR>,
+    image_repo: &State<IR>,
+) -> anyhow::Result<()> {
+    let ImageRevisionModel {
+        card_id,
+        kind,
+        revision,
+        ..
+    } = *revision;
+    image_repo
+        .0
+        .delete_revision(card_id, kind, revision)
+        .await?;
+    card_repo
+        .0
+        .delete_image_revision(card_id, kind, revision)
+        .await?;
+    Ok(())
+}
+
+async fn get_card(id: Uuid, card_repo: &State<CR>) -> Result<CardModel, Status> {
+    card_repo
+        .0
+        .get_card_by_id(id)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get card by id: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)
+}
+
+/// 版はメンバーだけが見られる
+async fn check_member(user: &User, card_id: Uuid, card_repo: &State<CR>) -> Result<(), Status> {
+    get_card(card_id, card_repo).await?;
+    match card_role(user.id, card_id, card_repo).await? {
+        Some(_) => Ok(()),
+        None => Err(Status::NotFound),
+    }
+}
+
+async fn list(
+    card_id: Uuid,
+    kind: ImageKind,
+    user: AuthUser,
+    card_repo: &State<CR>,
+) -> Result<Json<Vec<ImageRevision>>, Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    check_member(&user, card_id, card_repo).await?;
+    let revisions = get_revisions(card_id, kind, card_repo).await?;
+    Ok(Json(
+        revisions
+            .into_iter()
+            .rev()
+            .map(ImageRevision::from)
+            .collect(),
+    ))
+}
+
+async fn get_content(
+    card_id: Uuid,
+    kind: ImageKind,
+    revision: u32,
+    image_repo: &State<IR>,
+) -> Result<Bytes, Status> {
+    image_repo
+        .0
+        .get_revision(card_id, kind, revision)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get revision: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)
+}
+
+async fn get_one(
+    card_id: Uuid,
+    kind: ImageKind,
+    revision: u32,
+    user: AuthUser,
+    card_repo: &State<CR>,
+    image_repo: &State<IR>,
+) -> Result<Cached<ImageResponse>, Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    check_member(&user, card_id, card_repo).await?;
+    card_repo
+        .0
+        .get_image_revision(card_id, kind, revision)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get image revision: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)?;
+    let content = get_content(card_id, kind, revision, image_repo).await?;
+    // 版の内容は変わらない
+    let etag = content_etag(&content);
+    Ok(Cached::new(
+        ImageResponse(kind.mime_type().to_string(), content),
+        etag,
+        CachePolicy::Immutable,
+    ))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn restore(
+    card_id: Uuid,
+    kind: ImageKind,
+    revision: u32,
+    user: AuthUser,
+    if_match: IfMatch,
+    request_id: RequestId,
+    card_repo: &State<CR>,
+    image_repo: &State<IR>,
+    config: &State<RevisionConfig>,
+) -> Result<Tagged<Json<ImageRevision>>, Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let card = get_card(card_id, card_repo).await?;
+    if !editable_card(&user, &card, card_repo).await? {
+        return Err(Status::Forbidden);
+    }
+    card_repo
+        .0
+        .get_image_revision(card_id, kind, revision)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get image revision: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)?;
+    let next = check_current(card_id, kind, &if_match, card_repo, image_repo).await?;
+    let content = get_content(card_id, kind, revision, image_repo).await?;
+    let (action, before) = match kind {
+        ImageKind::Svg => {
+            let before = image_repo.0.get_svg(card_id).await.ok().flatten();
+            (
+                AuditAction::CardSvg,
+                before.map(|s| image_digest(s.as_bytes())),
+            )
+        }
+        ImageKind::Png => {
+            let before = image_repo.0.get_png(card_id).await.ok().flatten();
+            (AuditAction::CardPng, before.map(|p| image_digest(&p)))
+        }
+    };
+    let restored = save_image(
+        card_id, kind, next, &content, &user, card_repo, image_repo, config,
+    )
+    .await?
+    .ok_or(Status::PreconditionFailed)?;
+    let mut after = image_digest(&content);
+    after["revision"] = restored.revision.into();
+    after["restored_from"] = revision.into();
+    AuditEvent::new(action, Some(&user), &request_id)
+        .card(card_id)
+        .change(before.as_ref(), Some(&after))
+        .record(card_repo)
+        .await;
+    let etag = revision_etag(restored.revision);
+    Ok(Tagged::new(Json(restored.into()), etag))
+}
+
+/// 新しい順
+#[rocket::get("/<id>/svg/revisions")]
+pub async fn get_svg_revisions(
+    id: UuidParam,
+    card_repo: &State<CR>,
+    user: AuthUser,
+) -> Result<Json<Vec<ImageRevision>>, Status> {
+    list(id.0, ImageKind::Svg, user, card_repo).await
+}
+
+#[rocket::get("/<id>/svg/revisions/<revision>")]
+pub async fn get_svg_revision(
+    id: UuidParam,
+    revision: u32,
+    card_repo: &State<CR>,
+    image_repo: &State<IR>,
+    user: AuthUser,
+) -> Result<Cached<ImageResponse>, Status> {
+    get_one(id.0, ImageKind::Svg, revision, user, card_repo, image_repo).await
+}
+
+/// 版の内容を現在のSVGにし、新しい版として残す。`If-Match`に現在のSVGのETagが要る
+#[rocket::post("/<id>/svg/revisions/<revision>/restore")]
+#[allow(clippy::too_many_arguments)]
+pub async fn restore_svg_revision(
+    id: UuidParam,
+    revision: u32,
+    card_repo: &State<CR>,
+    image_repo: &State<IR>,
+    config: &State<RevisionConfig>,
+    user: AuthUser,
+    if_match: IfMatch,
+    request_id: RequestId,
+) -> Result<Tagged<Json<ImageRevision>>, Status> {
+    restore(
+        id.0,
+        ImageKind::Svg,
+        revision,
+        user,
+        if_match,
+        request_id,
+        card_repo,
+        image_repo,
+        config,
+    )
+    .await
+}
+
+/// 新しい順
+#[rocket::get("/<id>/png/revisions")]
+pub async fn get_png_revisions(
+    id: UuidParam,
+    card_repo: &State<CR>,
+    user: AuthUser,
+) -> Result<Json<Vec<ImageRevision>>, Status> {
+    list(id.0, ImageKind::Png, user, card_repo).await
+}
+
+#[rocket::get("/<id>/png/revisions/<revision>")]
+pub async fn get_png_revision(
+    id: UuidParam,
+    revision: u32,
+    card_repo: &State<CR>,
+    image_repo: &State<IR>,
+    user: AuthUser,
+) -> Result<Cached<ImageResponse>, Status> {
+    get_one(id.0, ImageKind::Png, revision, user, card_repo, image_repo).await
+}
+
+/// 版の内容を現在のPNGにし、新しい版として残す。`If-Match`に現在のPNGのETagが要る
+#[rocket::post("/<id>/png/revisions/<revision>/restore")]
+#[allow(clippy::too_many_arguments)]
+pub async fn restore_png_revision(
+    id: UuidParam,
+    revision: u32,
+    card_repo: &State<CR>,
+    image_repo: &State<IR>,
+    config: &State<RevisionConfig>,
+    user: AuthUser,
+    if_match: IfMatch,
+    request_id: RequestId,
+) -> Result<Tagged<Json<ImageRevision>>, Status> {
+    restore(
+        id.0,
+        ImageKind::Png,
+        revision,
+        user,
+        if_match,
+        request_id,
+        card_repo,
+        image_repo,
+        config,
+    )
+    .await
+}
+
+pub fn routes() -> Vec<Route> {
+    rocket::routes![
+        get_svg_revisions,
+        get_svg_revision,
+        restore_svg_revision,
+        get_png_revisions,
+        get_png_revision,
+        restore_png_revision,
+    ]
+}
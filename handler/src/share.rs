@@ -0,0 +1,429 @@
+//! 投稿済みのカードを認証なしで見せる共有リンク
+//!
+//! トークンは`<リンクID>.<期限のUNIX秒>.<HMAC-SHA256>`。署名と期限はDBを引かずに確かめ、
+//! 最後にリンクの行が残っているかで失効を確かめる。
+//! `/share/<token>`はリンクの展開用にOpenGraph・Twitterのメタタグを持つHTMLを返す。
+
+use chrono::{Duration, TimeZone, Utc};
+use hmac::{Hmac, Mac};
+use rocket::http::Status;
+use rocket::response::content::RawHtml;
+use rocket::serde::json::Json;
+use rocket::{Route, State};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use domain::bot_client::User;
+use domain::repository::{AuditAction, CardModel, CardRole, DateTimeUtc, ShareLinkModel};
+
+use crate::audit::{AuditEvent, RequestId};
+use crate::auth::AuthUser;
+use crate::cache::{content_etag, CachePolicy, Cached};
+use crate::cards::Png;
+use crate::members::card_role;
+use crate::{UuidParam, BC, CR, IR};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// `og:description`に載せるメッセージの最大文字数
+const DESCRIPTION_MAX_CHARS: usize = 200;
+
+#[derive(Clone)]
+pub struct ShareConfig {
+    secret: Vec<u8>,
+    /// `og:image`などの絶対URLに使う、このサーバーの公開オリジン
+    pub origin: String,
+    pub default_ttl: Duration,
+    pub max_ttl: Duration,
+}
+
+impl ShareConfig {
+    pub fn new(secret: impl Into<Vec<u8>>, origin: &str) -> Self {
+        Self {
+            secret: secret.into(),
+            origin: origin.trim_end_matches('/').to_string(),
+            default_ttl: Duration::days(7),
+            max_ttl: Duration::days(30),
+        }
+    }
+
+    pub fn load_env() -> Result<Self, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        use std::env::var;
+        let secret = var("SHARE_SECRET")?;
+        if secret.is_empty() {
+            return Err("SHARE_SECRET must not be empty".into());
+        }
+        let origin = var("SHARE_ORIGIN")?;
+        let mut config = Self::new(secret, &origin);
+        if let Ok(secs) = var("SHARE_DEFAULT_TTL_SECS") {
+            config = config.default_ttl(Duration::seconds(secs.parse()?));
+        }
+        if let Ok(secs) = var("SHARE_MAX_TTL_SECS") {
+            config = config.max_ttl(Duration::seconds(secs.parse()?));
+        }
+        Ok(config)
+    }
+
+    pub fn default_ttl(self, value: Duration) -> Self {
+        Self {
+            default_ttl: value,
+            ..self
+        }
+    }
+
+    pub fn max_ttl(self, value: Duration) -> Self {
+        Self {
+            max_ttl: value,
+            ..self
+        }
+    }
+
+    fn mac(&self, id: Uuid, expires: i64) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts any key length");
+        mac.update(format!("{}.{}", id.simple(), expires).as_bytes());
+        mac
+    }
+
+    pub fn sign(&self, link: &ShareLinkModel) -> String {
+        let expires = link.expires_at.timestamp();
+        let signature = self.mac(link.id, expires).finalize().into_bytes();
+        format!(
+            "{}.{}.{}",
+            link.id.simple(),
+            expires,
+            hex::encode(signature)
+        )
+    }
+
+    /// 署名が正しく期限内ならリンクIDを返す
+    pub fn verify(&self, token: &str, now: DateTimeUtc) -> Option<Uuid> {
+        let mut parts = token.splitn(3, '.');
+        let id = Uuid::try_parse(parts.next()?).ok()?;
+        let expires: i64 = parts.next()?.parse().ok()?;
+        let signature = hex::decode(parts.next()?).ok()?;
+        self.mac(id, expires).verify_slice(&signature).ok()?;
+        (now.timestamp() < expires).then_some(id)
+    }
+
+    fn url(&self, token: &str) -> String {
+        format!("{}/share/{}", self.origin, token)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ShareRequest {
+    /// 有効期間の秒数。省略するとサーバーの既定値
+    pub expires_in: Option<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+#[schemars(rename = "ShareLink")]
+pub struct ShareLinkResponse {
+    pub id: Uuid,
+    pub token: String,
+    /// `/share/{token}`の絶対URL
+    pub url: String,
+    pub created_by: Uuid,
+    pub created_at: DateTimeUtc,
+    pub expires_at: DateTimeUtc,
+}
+
+impl ShareLinkResponse {
+    fn new(link: ShareLinkModel, config: &ShareConfig) -> Self {
+        let token = config.sign(&link);
+        Self {
+            id: link.id,
+            url: config.url(&token),
+            token,
+            created_by: link.created_by,
+            created_at: link.created_at,
+            expires_at: link.expires_at,
+        }
+    }
+}
+
+async fn get_card(id: Uuid, card_repo: &State<CR>) -> Result<CardModel, Status> {
+    card_repo
+        .0
+        .get_card_by_id(id)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get card by id: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)
+}
+
+/// 繰り返しのカードは1回でも配信していれば投稿済み
+async fn published(card: &CardModel, card_repo: &State<CR>) -> Result<bool, Status> {
+    if card.status.is_published() {
+        return Ok(true);
+    }
+    let recurrence = card_repo.0.get_recurrence(card.id).await.map_err(|e| {
+        eprintln!("error in get recurrence: {}", e);
+        Status::InternalServerError
+    })?;
+    Ok(recurrence.is_some_and(|r| r.last_occurrence.is_some()))
+}
+
+/// 所有者のみ
+async fn manage(
+    user: AuthUser,
+    id: Uuid,
+    card_repo: &State<CR>,
+) -> Result<(User, CardModel), Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let card = get_card(id, card_repo).await?;
+    let role = card_role(user.id, id, card_repo).await?;
+    if !role.is_some_and(CardRole::can_manage) {
+        return Err(Status::Forbidden);
+    }
+    Ok((user, card))
+}
+
+#[rocket::get("/<id>/shares")]
+pub async fn get_all(
+    id: UuidParam,
+    card_repo: &State<CR>,
+    config: &State<ShareConfig>,
+    user: AuthUser,
+) -> Result<Json<Vec<ShareLinkResponse>>, Status> {
+    manage(user, id.0, card_repo).await?;
+    let now = Utc::now();
+    let links = card_repo.0.get_share_links(id.0).await.map_err(|e| {
+        eprintln!("error in get share links: {}", e);
+        Status::InternalServerError
+    })?;
+    let res = links
+        .into_iter()
+        .filter(|l| l.expires_at > now)
+        .map(|l| ShareLinkResponse::new(l, config))
+        .collect();
+    Ok(Json(res))
+}
+
+/// 投稿済みのカードの共有リンクを作る
+#[rocket::post("/<id>/shares", data = "<share>")]
+pub async fn post(
+    id: UuidParam,
+    share: Json<ShareRequest>,
+    card_repo: &State<CR>,
+    config: &State<ShareConfig>,
+    user: AuthUser,
+    request_id: RequestId,
+) -> Result<Json<ShareLinkResponse>, Status> {
+    let (user, card) = manage(user, id.0, card_repo).await?;
+    if !published(&card, card_repo).await? {
+        return Err(Status::Conflict);
+    }
+    let ttl = match share.expires_in {
+        Some(secs) if secs <= 0 || secs > config.max_ttl.num_seconds() => {
+            return Err(Status::BadRequest)
+        }
+        Some(secs) => Duration::seconds(secs),
+        None => config.default_ttl,
+    };
+    let now = Utc::now();
+    // 署名はUNIX秒で行うので端数を落としておく
+    let expires_at = Utc
+        .timestamp_opt((now + ttl).timestamp(), 0)
+        .single()
+        .ok_or(Status::BadRequest)?;
+    let link = ShareLinkModel {
+        id: Uuid::new_v4(),
+        card_id: id.0,
+        created_by: user.id,
+        created_at: now,
+        expires_at,
+    };
+    card_repo.0.save_share_link(&link).await.map_err(|e| {
+        eprintln!("error in save share link: {}", e);
+        Status::InternalServerError
+    })?;
+    // トークンは残さない
+    AuditEvent::new(AuditAction::ShareCreate, Some(&user), &request_id)
+        .card(id.0)
+        .change(None, Some(&link))
+        .record(card_repo)
+        .await;
+    Ok(Json(ShareLinkResponse::new(link, config)))
+}
+
+/// 共有リンクを失効させる
+#[rocket::delete("/<id>/shares/<share_id>")]
+pub async fn delete(
+    id: UuidParam,
+    share_id: UuidParam,
+    card_repo: &State<CR>,
+    user: AuthUser,
+    request_id: RequestId,
+) -> Result<Status, Status> {
+    let (user, _) = manage(user, id.0, card_repo).await?;
+    let link = card_repo
+        .0
+        .get_share_link(share_id.0)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get share link: {}", e);
+            Status::InternalServerError
+        })?
+        .filter(|l| l.card_id == id.0)
+        .ok_or(Status::NotFound)?;
+    card_repo
+        .0
+        .delete_share_link(link.id)
+        .await
+        .map_err(|e| {
+            eprintln!("error in delete share link: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)?;
+    AuditEvent::new(AuditAction::ShareRevoke, Some(&user), &request_id)
+        .card(id.0)
+        .change(Some(&link), None)
+        .record(card_repo)
+        .await;
+    Ok(Status::NoContent)
+}
+
+/// トークンからカードを引く。理由を問わず見せられなければ404
+async fn shared_card(
+    token: &str,
+    card_repo: &State<CR>,
+    config: &State<ShareConfig>,
+) -> Result<CardModel, Status> {
+    let now = Utc::now();
+    let id = config.verify(token, now).ok_or(Status::NotFound)?;
+    let link = card_repo
+        .0
+        .get_share_link(id)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get share link: {}", e);
+            Status::InternalServerError
+        })?
+        .filter(|l| l.expires_at > now)
+        .ok_or(Status::NotFound)?;
+    let card = get_card(link.card_id, card_repo).await?;
+    if !published(&card, card_repo).await? {
+        return Err(Status::NotFound);
+    }
+    Ok(card)
+}
+
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn render_page(title: &str, description: &str, url: &str, image_url: &str) -> String {
+    let (title, description) = (escape_html(title), escape_html(description));
+    let (url, image_url) = (escape_html(url), escape_html(image_url));
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="ja">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta name="robots" content="noindex">
+<title>{title}</title>
+<meta property="og:type" content="website">
+<meta property="og:site_name" content="Qard">
+<meta property="og:title" content="{title}">
+<meta property="og:description" content="{description}">
+<meta property="og:url" content="{url}">
+<meta property="og:image" content="{image_url}">
+<meta name="twitter:card" content="summary_large_image">
+<meta name="twitter:title" content="{title}">
+<meta name="twitter:description" content="{description}">
+<meta name="twitter:image" content="{image_url}">
+</head>
+<body>
+<main>
+<h1>{title}</h1>
+<img src="{image_url}" alt="{title}" style="max-width: 100%">
+<p>{description}</p>
+</main>
+</body>
+</html>
+"#
+    )
+}
+
+#[rocket::get("/<token>")]
+pub async fn get_page(
+    token: &str,
+    card_repo: &State<CR>,
+    client: &State<BC>,
+    config: &State<ShareConfig>,
+) -> Result<RawHtml<String>, Status> {
+    let card = shared_card(token, card_repo, config).await?;
+    let owner = client
+        .0
+        .get_users_by_ids(&[card.owner_id])
+        .await
+        .map_err(|e| {
+            eprintln!("error in get users by ids: {}", e);
+            Status::InternalServerError
+        })?
+        .pop();
+    let title = match owner {
+        Some(owner) => format!("@{} からのQard", owner.name),
+        None => "Qard".to_string(),
+    };
+    let description: String = card
+        .message
+        .unwrap_or_default()
+        .chars()
+        .take(DESCRIPTION_MAX_CHARS)
+        .collect();
+    let url = config.url(token);
+    let image_url = format!("{}/png", url);
+    Ok(RawHtml(render_page(&title, &description, &url, &image_url)))
+}
+
+#[rocket::get("/<token>/png")]
+pub async fn get_png(
+    token: &str,
+    card_repo: &State<CR>,
+    image_repo: &State<IR>,
+    config: &State<ShareConfig>,
+) -> Result<Cached<Png>, Status> {
+    let card = shared_card(token, card_repo, config).await?;
+    let png = image_repo
+        .0
+        .get_png(card.id)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get png: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)?;
+    let etag = content_etag(&png);
+    Ok(Cached::new(Png(png), etag, CachePolicy::Revalidate))
+}
+
+/// `/api/cards`にマウントする
+pub fn routes() -> Vec<Route> {
+    rocket::routes![get_all, post, delete]
+}
+
+/// 認証なし。`/share`にマウントする
+pub fn public_routes() -> Vec<Route> {
+    rocket::routes![get_page, get_png]
+}
@@ -2,11 +2,14 @@ use rocket::http::Status;
 use rocket::response::{Responder, Response};
 use rocket::serde::json::Json;
 use rocket::{Request, Route, State};
+use uuid::Uuid;
 
 use domain::bot_client::StampType as RawStampType;
-use domain::bot_client::{ChannelList, ImageData, Stamp, User, UserDetail};
+use domain::bot_client::{ChannelList, Image, ImageData, Stamp, User, UserDetail};
 
 use crate::auth::AuthUser;
+use crate::cache::{content_etag, CachePolicy, Cached};
+use crate::catalog::{Catalog, FlatChannel, MatchMode, Page};
 use crate::BC;
 
 type Routes = Vec<Route>;
@@ -14,6 +17,19 @@ type Routes = Vec<Route>;
 #[derive(Debug, Clone)]
 pub struct ResponseImage(pub ImageData);
 
+impl ResponseImage {
+    /// traQが返したETagがあればそれを、無ければ内容のハッシュを使う
+    pub fn cached(image: Image, policy: CachePolicy) -> Cached<Self> {
+        let Image {
+            data,
+            etag,
+            last_modified,
+        } = image;
+        let etag = etag.unwrap_or_else(|| content_etag(data.as_bytes()));
+        Cached::new(ResponseImage(data), etag, policy).last_modified(last_modified)
+    }
+}
+
 impl<'r, 'o: 'r> Responder<'r, 'o> for ResponseImage {
     fn respond_to(self: ResponseImage, _request: &'r Request<'_>) -> rocket::response::Result<'o> {
         use rocket::http::ContentType;
@@ -36,6 +52,10 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for ResponseImage {
                 .header(ContentType::SVG)
                 .sized_body(svg.len(), Cursor::new(svg))
                 .finalize()),
+            ImageData::Webp(webp) => Ok(Response::build()
+                .header(ContentType::WEBP)
+                .sized_body(webp.len(), Cursor::new(webp))
+                .finalize()),
         }
     }
 }
@@ -91,21 +111,36 @@ pub mod stamps {
         id: &str,
         client: &State<BC>,
         _user: AuthUser,
-    ) -> Result<ResponseImage, Status> {
+    ) -> Result<Cached<ResponseImage>, Status> {
         client
             .0
             .get_stamp_image(id)
             .await
-            .map(ResponseImage)
+            .map(|image| ResponseImage::cached(image, CachePolicy::MaxAge(60 * 60)))
             .map_err(|e| {
                 eprintln!("Error in get_stamp_image: {}", e);
                 Status::InternalServerError
             })
     }
 
+    #[rocket::get("/search?<q>&<mode>&<type>&<limit>&<offset>")]
+    pub async fn search(
+        q: Option<&str>,
+        mode: Option<MatchMode>,
+        r#type: Option<StampType>,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        catalog: &State<Catalog>,
+        _user: AuthUser,
+    ) -> Json<Page<Stamp>> {
+        let stamp_type = r#type.unwrap_or(RawStampType::None.into()).0;
+        let stamps = catalog.search_stamps(q, mode.unwrap_or_default(), stamp_type);
+        Json(Page::new(&stamps, offset, limit))
+    }
+
     /// `/stamps`
     pub fn routes() -> Routes {
-        rocket::routes![get_all, get_one]
+        rocket::routes![get_all, search, get_one]
     }
 }
 
@@ -115,16 +150,35 @@ pub mod users {
 
     type Users = Vec<User>;
 
-    #[rocket::get("/?<name>")]
+    /// `ids`はカンマ区切りのユーザーIDで、指定された場合は`name`より優先する
+    #[rocket::get("/?<name>&<ids>")]
     pub async fn get_all(
         name: Option<&str>,
+        ids: Option<&str>,
         client: &State<BC>,
         _user: AuthUser,
     ) -> Result<Json<Users>, Status> {
-        client.0.get_users(name).await.map(Json).map_err(|e| {
-            eprintln!("Error in get_users: {}", e);
-            Status::InternalServerError
-        })
+        let Some(ids) = ids else {
+            return client.0.get_users(name).await.map(Json).map_err(|e| {
+                eprintln!("Error in get_users: {}", e);
+                Status::InternalServerError
+            });
+        };
+        let ids = ids
+            .split(',')
+            .filter(|id| !id.is_empty())
+            .map(|id| id.trim().parse())
+            .collect::<Result<Vec<Uuid>, _>>()
+            .map_err(|_| Status::BadRequest)?;
+        client
+            .0
+            .get_users_by_ids(&ids)
+            .await
+            .map(Json)
+            .map_err(|e| {
+                eprintln!("Error in get_users_by_ids: {}", e);
+                Status::InternalServerError
+            })
     }
 
     #[rocket::get("/<id>")]
@@ -144,12 +198,12 @@ pub mod users {
         id: &str,
         client: &State<BC>,
         _user: AuthUser,
-    ) -> Result<ResponseImage, Status> {
+    ) -> Result<Cached<ResponseImage>, Status> {
         client
             .0
             .get_user_icon(id)
             .await
-            .map(ResponseImage)
+            .map(|image| ResponseImage::cached(image, CachePolicy::MaxAge(5 * 60)))
             .map_err(|e| {
                 eprintln!("Error in get_user_icon: {}", e);
                 Status::InternalServerError
@@ -158,12 +212,16 @@ pub mod users {
 
     /// `/users`
     pub fn routes() -> Routes {
-        rocket::routes![get_all, get_icon]
+        rocket::routes![get_all, get_detail, get_icon]
     }
 }
 
 pub mod channels {
+    use rocket::serde::Serialize;
+    use schemars::JsonSchema;
+
     use super::*;
+    use crate::resolve::{normalize_path, ChannelPathIndex};
 
     #[rocket::get("/")]
     pub async fn get_all(client: &State<BC>, _user: AuthUser) -> Result<Json<ChannelList>, Status> {
@@ -173,7 +231,49 @@ pub mod channels {
         })
     }
 
+    #[derive(Debug, Clone, Serialize, JsonSchema)]
+    #[serde(crate = "rocket::serde")]
+    pub struct ResolvedChannel {
+        pub id: Uuid,
+        pub path: String,
+    }
+
+    /// `path`は`#gps/times/foo`または`gps/times/foo`
+    #[rocket::get("/resolve?<path>")]
+    pub async fn resolve(
+        path: &str,
+        client: &State<BC>,
+        _user: AuthUser,
+    ) -> Result<Json<ResolvedChannel>, Status> {
+        let list = client.0.get_channels().await.map_err(|e| {
+            eprintln!("Error in get_channels: {}", e);
+            Status::InternalServerError
+        })?;
+        let index = ChannelPathIndex::new(&list);
+        let id = index.id(path).ok_or(Status::NotFound)?;
+        Ok(Json(ResolvedChannel {
+            id,
+            path: normalize_path(path),
+        }))
+    }
+
+    /// パス付きで平坦化したチャンネル一覧。`archived`が無ければアーカイブ済みは除く
+    #[rocket::get("/search?<q>&<mode>&<archived>&<limit>&<offset>")]
+    pub async fn search(
+        q: Option<&str>,
+        mode: Option<MatchMode>,
+        archived: Option<bool>,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        catalog: &State<Catalog>,
+        _user: AuthUser,
+    ) -> Json<Page<FlatChannel>> {
+        let channels =
+            catalog.search_channels(q, mode.unwrap_or_default(), archived.unwrap_or(false));
+        Json(Page::new(&channels, offset, limit))
+    }
+
     pub fn routes() -> Routes {
-        rocket::routes![get_all]
+        rocket::routes![get_all, resolve, search]
     }
 }
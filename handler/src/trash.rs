@@ -0,0 +1,160 @@
+//! ゴミ箱
+//!
+//! 削除したカードは`deleted_at`を付けてゴミ箱に入れ、`TrashConfig::retention`の間は所有者が元に戻せる。
+//! 期限を過ぎたカードは画像も含めてcronで完全に消す。
+
+use chrono::{Duration, Utc};
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{Route, State};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use domain::repository::{
+    AuditAction, CardRole, CardStatus, DateTimeUtc, DEFAULT_TRASH_RETENTION_DAYS,
+};
+
+use crate::audit::{AuditEvent, RequestId};
+use crate::auth::AuthUser;
+use crate::cards::{complete_card_response, snapshot, with_owners, CardResponse};
+use crate::members::card_role;
+use crate::{UuidParam, BC, CR};
+
+#[derive(Debug, Clone)]
+pub struct TrashConfig {
+    /// ゴミ箱から元に戻せる期間
+    pub retention: Duration,
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        Self {
+            retention: Duration::days(DEFAULT_TRASH_RETENTION_DAYS),
+        }
+    }
+}
+
+impl TrashConfig {
+    pub fn load_env() -> Result<Self, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let mut config = Self::default();
+        if let Ok(days) = std::env::var("TRASH_RETENTION_DAYS") {
+            config = config.retention(Duration::days(days.parse()?));
+        }
+        if config.retention <= Duration::zero() {
+            return Err("TRASH_RETENTION_DAYS must be positive".into());
+        }
+        Ok(config)
+    }
+
+    pub fn retention(self, value: Duration) -> Self {
+        Self { retention: value }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct TrashedCard {
+    #[serde(flatten)]
+    pub card: CardResponse,
+    pub deleted_at: DateTimeUtc,
+    /// この日時を過ぎると元に戻せなくなる
+    pub purge_at: DateTimeUtc,
+}
+
+/// 所有しているゴミ箱のカード。新しく削除した順
+#[rocket::get("/trash?<expand>")]
+pub async fn get_trash(
+    expand: Option<&str>,
+    card_repo: &State<CR>,
+    client: &State<BC>,
+    config: &State<TrashConfig>,
+    user: AuthUser,
+) -> Result<(Status, Json<Vec<TrashedCard>>), Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let card_models = card_repo.0.get_trashed_cards(user.id).await.map_err(|e| {
+        eprintln!("error in get trashed cards: {}", e);
+        Status::InternalServerError
+    })?;
+    let response = complete_card_response(&card_models, card_repo)
+        .await
+        .map_err(|e| {
+            eprintln!("error in completing card response: {}", e);
+            Status::InternalServerError
+        })?;
+    let response = with_owners(response, expand, client).await?;
+    let response = card_models
+        .iter()
+        .zip(response)
+        .filter_map(|(model, card)| {
+            let deleted_at = model.deleted_at?;
+            Some(TrashedCard {
+                card,
+                deleted_at,
+                purge_at: deleted_at + config.retention,
+            })
+        })
+        .collect();
+    Ok((Status::Ok, Json(response)))
+}
+
+/// ゴミ箱から元に戻す。期限を過ぎていれば410
+#[rocket::post("/<id>/restore")]
+pub async fn restore(
+    id: UuidParam,
+    card_repo: &State<CR>,
+    config: &State<TrashConfig>,
+    user: AuthUser,
+    request_id: RequestId,
+) -> Result<(Status, Json<CardResponse>), Status> {
+    let user = user.0.ok_or(Status::Unauthorized)?;
+    let id = id.0;
+    let card = card_repo
+        .0
+        .get_trashed_card_by_id(id)
+        .await
+        .map_err(|e| {
+            eprintln!("error in get trashed card by id: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)?;
+    // 戻せるのは削除できる人のみ
+    let role = card_role(user.id, id, card_repo).await?;
+    if !role.is_some_and(CardRole::can_manage) {
+        return Err(Status::NotFound);
+    }
+    let now = Utc::now();
+    card_repo
+        .0
+        .restore_card(id, now - config.retention)
+        .await
+        .map_err(|e| {
+            eprintln!("error in restore card: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::Gone)?;
+    // ゴミ箱にある間に投稿日時を過ぎていたら、遅れて配信しないよう下書きに戻す
+    if card.status == CardStatus::Scheduled && card.publish_date.is_some_and(|d| d <= now) {
+        card_repo
+            .0
+            .update_card_status(id, &[CardStatus::Scheduled], CardStatus::Draft)
+            .await
+            .map_err(|e| {
+                eprintln!("error in update card status: {}", e);
+                Status::InternalServerError
+            })?;
+    }
+    let after = snapshot(id, card_repo).await.ok_or_else(|| {
+        eprintln!("restored card {} not found", id);
+        Status::InternalServerError
+    })?;
+    AuditEvent::new(AuditAction::CardRestore, Some(&user), &request_id)
+        .card(id)
+        .change(None, Some(&after))
+        .record(card_repo)
+        .await;
+    Ok((Status::Ok, Json(after)))
+}
+
+pub fn routes() -> Vec<Route> {
+    rocket::routes![get_trash, restore]
+}
@@ -0,0 +1,95 @@
+//! チャンネルごとの配信用Webhookの登録
+//!
+//! 登録されたチャンネルへはcronがBOTではなくWebhookで投稿する。
+//! シークレットを扱うので全て`AdminUser`が必要。
+
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{Route, State};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use domain::repository::WebhookModel;
+
+use crate::auth::AdminUser;
+use crate::{UuidParam, CR};
+
+/// シークレットは返さない
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct WebhookResponse {
+    pub channel_id: Uuid,
+    pub webhook_id: Uuid,
+}
+
+impl From<WebhookModel> for WebhookResponse {
+    fn from(value: WebhookModel) -> Self {
+        Self {
+            channel_id: value.channel_id,
+            webhook_id: value.webhook_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct WebhookRequest {
+    pub webhook_id: Uuid,
+    pub secret: String,
+}
+
+#[rocket::get("/")]
+pub async fn get_all(
+    card_repo: &State<CR>,
+    _admin: AdminUser,
+) -> Result<Json<Vec<WebhookResponse>>, Status> {
+    let webhooks = card_repo.0.get_webhooks().await.map_err(|e| {
+        eprintln!("error in get webhooks: {}", e);
+        Status::InternalServerError
+    })?;
+    Ok(Json(webhooks.into_iter().map(Into::into).collect()))
+}
+
+#[rocket::put("/<channel_id>", data = "<webhook>")]
+pub async fn put(
+    channel_id: UuidParam,
+    webhook: Json<WebhookRequest>,
+    card_repo: &State<CR>,
+    _admin: AdminUser,
+) -> Result<Status, Status> {
+    let WebhookRequest { webhook_id, secret } = webhook.0;
+    let webhook = WebhookModel {
+        channel_id: channel_id.0,
+        webhook_id,
+        secret,
+    };
+    card_repo.0.save_webhook(&webhook).await.map_err(|e| {
+        eprintln!("error in save webhook: {}", e);
+        Status::InternalServerError
+    })?;
+    Ok(Status::NoContent)
+}
+
+#[rocket::delete("/<channel_id>")]
+pub async fn delete_one(
+    channel_id: UuidParam,
+    card_repo: &State<CR>,
+    _admin: AdminUser,
+) -> Result<Status, Status> {
+    card_repo
+        .0
+        .delete_webhook(channel_id.0)
+        .await
+        .map_err(|e| {
+            eprintln!("error in delete webhook: {}", e);
+            Status::InternalServerError
+        })?
+        .ok_or(Status::NotFound)?;
+    Ok(Status::NoContent)
+}
+
+/// `/webhooks`
+pub fn routes() -> Vec<Route> {
+    rocket::routes![get_all, put, delete_one]
+}
@@ -0,0 +1,176 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use domain::bot_client::{MockBotClient, User, UserGroup, UserGroupMember};
+use domain::cron::Cron;
+use domain::repository::{CardModel, CardStatus, MockCardRepository};
+use handler::auth::{AdminConfig, AuthUserConfig};
+use handler::{admin, BC, CR, CRON};
+use rocket::http::{Header, Status};
+use rocket::local::blocking::Client;
+use uuid::Uuid;
+
+/// 強制配信が弾かれた時は呼ばれない
+struct UnreachableCron;
+
+#[async_trait::async_trait]
+impl Cron for UnreachableCron {
+    async fn run(self: Arc<Self>) {}
+    async fn tick(&self) {
+        unreachable!("tick after conflict");
+    }
+    async fn deliver(&self, _card_id: Uuid) -> Option<CardStatus> {
+        unreachable!("deliver after conflict");
+    }
+    async fn preview(&self, _card: &CardModel, _user_id: Uuid) -> bool {
+        unreachable!("preview from admin");
+    }
+}
+
+fn client(card_repo: MockCardRepository) -> Client {
+    let mut bot_client = MockBotClient::new();
+    bot_client.expect_get_users().returning(|name| {
+        Ok(vec![User {
+            id: Uuid::new_v4(),
+            name: name.unwrap_or_default().to_string(),
+            ..Default::default()
+        }])
+    });
+    client_with(
+        card_repo,
+        AdminConfig::new(["alice"].into_iter()),
+        bot_client,
+    )
+}
+
+fn client_with(
+    card_repo: MockCardRepository,
+    admin: AdminConfig,
+    bot_client: MockBotClient,
+) -> Client {
+    let rocket = rocket::build()
+        .mount("/api/admin", admin::routes())
+        .manage(AuthUserConfig(true))
+        .manage(admin)
+        .manage(BC::from(bot_client))
+        .manage(CR::from(card_repo))
+        .manage(CRON::from(Arc::new(UnreachableCron)));
+    Client::tracked(rocket).unwrap()
+}
+
+fn card(status: CardStatus) -> CardModel {
+    CardModel {
+        id: Uuid::new_v4(),
+        owner_id: Uuid::new_v4(),
+        publish_date: Some(Utc::now() - Duration::minutes(1)),
+        message: None,
+        status,
+        time_zone: "Asia/Tokyo".to_string(),
+        version: 1,
+        deleted_at: None,
+    }
+}
+
+fn deliver(client: &Client, card_id: Uuid) -> Status {
+    deliver_as(client, card_id, "alice")
+}
+
+fn deliver_as(client: &Client, card_id: Uuid, user: &str) -> Status {
+    client
+        .post(format!("/api/admin/cards/{}/deliver", card_id))
+        .header(Header::new("X-Forwarded-User", user.to_string()))
+        .dispatch()
+        .status()
+}
+
+#[test]
+fn delivering_card_is_forced_only_when_stuck() {
+    let card = card(CardStatus::Delivering);
+    let id = card.id;
+    let mut card_repo = MockCardRepository::new();
+    card_repo
+        .expect_get_card_by_id()
+        .returning(move |_| Ok(Some(card.clone())));
+    card_repo.expect_reschedule_card().never();
+    // 配信が進んでいる間は止まったとみなさない
+    card_repo
+        .expect_reschedule_stuck_card()
+        .withf(move |card_id, stuck_before, _| {
+            let ago = Utc::now() - *stuck_before;
+            *card_id == id && ago >= Duration::minutes(10) && ago < Duration::minutes(11)
+        })
+        .times(1)
+        .returning(|_, _, _| Ok(None));
+    let client = client(card_repo);
+
+    assert_eq!(deliver(&client, id), Status::Conflict);
+}
+
+#[test]
+fn scheduled_card_is_not_taken_from_delivering() {
+    let card = card(CardStatus::Scheduled);
+    let id = card.id;
+    let mut card_repo = MockCardRepository::new();
+    card_repo
+        .expect_get_card_by_id()
+        .returning(move |_| Ok(Some(card.clone())));
+    card_repo.expect_reschedule_stuck_card().never();
+    // 取得後に配信が始まったら取り合わない
+    card_repo
+        .expect_reschedule_card()
+        .withf(|_, from, _| !from.contains(&CardStatus::Delivering))
+        .times(1)
+        .returning(|_, _, _| Ok(None));
+    let client = client(card_repo);
+
+    assert_eq!(deliver(&client, id), Status::Conflict);
+}
+
+#[test]
+fn admin_group_members_are_cached() {
+    let card = card(CardStatus::Scheduled);
+    let id = card.id;
+    let mut card_repo = MockCardRepository::new();
+    card_repo
+        .expect_get_card_by_id()
+        .returning(move |_| Ok(Some(card.clone())));
+    card_repo
+        .expect_reschedule_card()
+        .returning(|_, _, _| Ok(None));
+    let carol = Uuid::new_v4();
+    let group = Uuid::new_v4();
+    let mut bot_client = MockBotClient::new();
+    bot_client.expect_get_users().returning(move |name| {
+        let name = name.unwrap_or_default().to_string();
+        let id = if name == "carol" {
+            carol
+        } else {
+            Uuid::new_v4()
+        };
+        Ok(vec![User {
+            id,
+            name,
+            ..Default::default()
+        }])
+    });
+    // 管理者の判定ごとにはグループを取らない
+    bot_client
+        .expect_get_user_group()
+        .times(1)
+        .returning(move |_| {
+            Ok(UserGroup {
+                id: group,
+                members: vec![UserGroupMember {
+                    id: carol,
+                    role: String::new(),
+                }],
+                ..Default::default()
+            })
+        });
+    let admin = AdminConfig::new(std::iter::empty()).group(group);
+    let client = client_with(card_repo, admin, bot_client);
+
+    assert_eq!(deliver_as(&client, id, "carol"), Status::Conflict);
+    assert_eq!(deliver_as(&client, id, "carol"), Status::Conflict);
+    assert_eq!(deliver_as(&client, id, "mallory"), Status::Forbidden);
+}
@@ -0,0 +1,40 @@
+use chrono::Duration;
+use handler::audit::{diff, AuditConfig};
+use rocket::serde::json::json;
+
+#[test]
+fn keeps_only_changed_fields() {
+    let before = json!({ "message": "a", "status": "draft", "images": [] });
+    let after = json!({ "message": "b", "status": "draft", "images": [], "recurrence": null });
+    let (before, after) = diff(Some(before), Some(after));
+    assert_eq!(before, Some(json!({ "message": "a" })));
+    assert_eq!(after, Some(json!({ "message": "b", "recurrence": null })));
+}
+
+#[test]
+fn keeps_whole_value_on_create_and_delete() {
+    let card = json!({ "message": "a" });
+    assert_eq!(diff(None, Some(card.clone())), (None, Some(card.clone())));
+    assert_eq!(diff(Some(card.clone()), None), (Some(card), None));
+}
+
+#[test]
+fn keeps_non_object_values() {
+    let (before, after) = diff(Some(json!("draft")), Some(json!("scheduled")));
+    assert_eq!(before, Some(json!("draft")));
+    assert_eq!(after, Some(json!("scheduled")));
+}
+
+#[test]
+fn rejects_non_positive_retention() {
+    assert_eq!(AuditConfig::default().retention, Duration::days(365));
+    // 環境変数を読むのはこのテストだけ
+    for days in ["0", "-1", "x"] {
+        std::env::set_var("AUDIT_RETENTION_DAYS", days);
+        assert!(AuditConfig::load_env().is_err(), "{}", days);
+    }
+    std::env::set_var("AUDIT_RETENTION_DAYS", "30");
+    let config = AuditConfig::load_env().unwrap();
+    std::env::remove_var("AUDIT_RETENTION_DAYS");
+    assert_eq!(config.retention, Duration::days(30));
+}
@@ -0,0 +1,117 @@
+use handler::cache::{content_etag, version_etag, CachePolicy, Cached, IfMatch};
+use rocket::http::{Header, Status};
+use rocket::local::blocking::Client;
+use rocket::{get, routes};
+
+#[get("/revalidate")]
+fn revalidate() -> Cached<&'static str> {
+    Cached::new("hello", content_etag(b"hello"), CachePolicy::Revalidate)
+        .last_modified(Some("Sun, 24 Dec 2023 00:00:00 GMT".to_string()))
+}
+
+#[get("/immutable")]
+fn immutable() -> Cached<&'static str> {
+    Cached::new("hello", content_etag(b"hello"), CachePolicy::Immutable)
+}
+
+fn client() -> Client {
+    Client::tracked(rocket::build().mount("/", routes![revalidate, immutable])).unwrap()
+}
+
+#[test]
+fn content_etag_is_strong_and_stable() {
+    let etag = content_etag(b"hello");
+    assert_eq!(etag, content_etag(b"hello"));
+    assert_ne!(etag, content_etag(b"world"));
+    assert!(etag.starts_with('"') && etag.ends_with('"'));
+    assert!(!etag.starts_with("W/"));
+}
+
+#[test]
+fn sets_cache_headers() {
+    let client = client();
+    let res = client.get("/revalidate").dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    let headers = res.headers();
+    assert_eq!(
+        headers.get_one("ETag"),
+        Some(content_etag(b"hello").as_str())
+    );
+    assert_eq!(headers.get_one("Cache-Control"), Some("private, no-cache"));
+    assert_eq!(
+        headers.get_one("Last-Modified"),
+        Some("Sun, 24 Dec 2023 00:00:00 GMT")
+    );
+    assert_eq!(res.into_string().as_deref(), Some("hello"));
+
+    let res = client.get("/immutable").dispatch();
+    assert_eq!(
+        res.headers().get_one("Cache-Control"),
+        Some("private, max-age=31536000, immutable")
+    );
+}
+
+#[test]
+fn returns_not_modified_for_matching_etag() {
+    let client = client();
+    let etag = content_etag(b"hello");
+    for value in [
+        etag.clone(),
+        format!("W/{}", etag),
+        format!("\"x\", {}", etag),
+    ] {
+        let res = client
+            .get("/revalidate")
+            .header(Header::new("If-None-Match", value))
+            .dispatch();
+        assert_eq!(res.status(), Status::NotModified);
+        assert_eq!(res.headers().get_one("ETag"), Some(etag.as_str()));
+        assert_eq!(res.into_string(), None);
+    }
+}
+
+#[test]
+fn returns_body_for_stale_etag() {
+    let client = client();
+    let res = client
+        .get("/revalidate")
+        .header(Header::new("If-None-Match", content_etag(b"world")))
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    assert_eq!(res.into_string().as_deref(), Some("hello"));
+}
+
+fn if_match(value: &str) -> IfMatch {
+    IfMatch(Some(value.to_string()))
+}
+
+#[test]
+fn requires_if_match() {
+    let etag = version_etag("v", 1);
+    assert_eq!(
+        IfMatch(None).check(Some(&etag)),
+        Err(Status::PreconditionRequired)
+    );
+}
+
+#[test]
+fn accepts_current_version_only() {
+    let etag = version_etag("v", 2);
+    assert_eq!(if_match("\"v2\"").check(Some(&etag)), Ok(()));
+    assert_eq!(if_match("\"v1\", \"v2\"").check(Some(&etag)), Ok(()));
+    assert_eq!(if_match("*").check(Some(&etag)), Ok(()));
+    assert_eq!(
+        if_match("\"v1\"").check(Some(&etag)),
+        Err(Status::PreconditionFailed)
+    );
+}
+
+#[test]
+fn rejects_weak_and_missing_representations() {
+    let etag = version_etag("v", 2);
+    assert_eq!(
+        if_match("W/\"v2\"").check(Some(&etag)),
+        Err(Status::PreconditionFailed)
+    );
+    assert_eq!(if_match("*").check(None), Err(Status::PreconditionFailed));
+}
@@ -0,0 +1,55 @@
+use handler::cards::CardPatch;
+use rocket::serde::json::{from_value, json};
+
+#[test]
+fn distinguishes_missing_and_null_fields() {
+    let patch: CardPatch = from_value(json!({ "message": null })).unwrap();
+    assert_eq!(patch.message, Some(None));
+    assert_eq!(patch.publish_date, None);
+    assert!(patch.recurrence.is_none());
+    assert!(patch.publish_channels.is_none());
+}
+
+#[test]
+fn keeps_supplied_values() {
+    let patch: CardPatch = from_value(json!({
+        "message": "hello",
+        "recurrence": { "rrule": "FREQ=YEARLY" },
+        "publish_groups": [],
+    }))
+    .unwrap();
+    assert_eq!(patch.message, Some(Some("hello".to_string())));
+    assert_eq!(
+        patch.recurrence.flatten().map(|r| r.rrule),
+        Some("FREQ=YEARLY".to_string())
+    );
+    assert_eq!(patch.publish_groups, Some(Some(vec![])));
+    assert_eq!(patch.owner_id, None);
+}
+
+#[test]
+fn clears_recurrence_with_null() {
+    let patch: CardPatch = from_value(json!({ "recurrence": null })).unwrap();
+    assert!(matches!(patch.recurrence, Some(None)));
+}
+
+#[test]
+fn keeps_null_lists_and_time_zone() {
+    let patch: CardPatch = from_value(json!({
+        "time_zone": null,
+        "publish_channels": null,
+        "recipients": null,
+        "publish_groups": null,
+    }))
+    .unwrap();
+    assert_eq!(patch.time_zone, Some(None));
+    assert!(matches!(patch.publish_channels, Some(None)));
+    assert!(matches!(patch.recipients, Some(None)));
+    assert_eq!(patch.publish_groups, Some(None));
+
+    let patch: CardPatch = from_value(json!({})).unwrap();
+    assert_eq!(patch.time_zone, None);
+    assert!(patch.publish_channels.is_none());
+    assert!(patch.recipients.is_none());
+    assert_eq!(patch.publish_groups, None);
+}
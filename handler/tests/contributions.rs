@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+use chrono::{Duration, Utc};
+use domain::bot_client::{MockBotClient, User};
+use domain::repository::{
+    AssetModel, CardMemberModel, CardModel, CardRole, CardStatus, ContributionModel,
+    MockCardRepository, MockImageRepository,
+};
+use handler::auth::AuthUserConfig;
+use handler::{contributions, BC, CR, IR};
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::blocking::Client;
+use rocket::serde::json::json;
+use uuid::Uuid;
+
+struct Fixture {
+    client: Client,
+    card_id: Uuid,
+}
+
+/// `alice`が所有者、`bob`が編集者で、`carol`を寄せ書きに招待したカード。
+/// `uploaded_by`の画像を`layer`として上げてある
+fn fixture(uploaded_by: Option<&'static str>) -> Fixture {
+    let users: HashMap<_, _> = ["alice", "bob", "carol", "mallory"]
+        .into_iter()
+        .map(|name| (name, Uuid::new_v4()))
+        .collect();
+    let mut bot_client = MockBotClient::new();
+    let u = users.clone();
+    bot_client.expect_get_users().returning(move |name| {
+        let name = name.unwrap_or_default().to_string();
+        Ok(vec![User {
+            id: u[name.as_str()],
+            name,
+            ..Default::default()
+        }])
+    });
+    let card = CardModel {
+        id: Uuid::new_v4(),
+        owner_id: users["alice"],
+        publish_date: Some(Utc::now() + Duration::days(1)),
+        message: None,
+        status: CardStatus::Draft,
+        time_zone: "Asia/Tokyo".to_string(),
+        version: 1,
+        deleted_at: None,
+    };
+    let card_id = card.id;
+    let mut card_repo = MockCardRepository::new();
+    card_repo
+        .expect_get_card_by_id()
+        .returning(move |_| Ok(Some(card.clone())));
+    let members = [("alice", CardRole::Owner), ("bob", CardRole::Editor)].map(|(name, role)| {
+        CardMemberModel {
+            card_id,
+            user_id: users[name],
+            role,
+        }
+    });
+    card_repo
+        .expect_get_card_members()
+        .returning(move |_| Ok(members.to_vec()));
+    let carol = users["carol"];
+    card_repo.expect_get_contributions().returning(move |_| {
+        Ok(vec![ContributionModel {
+            card_id,
+            user_id: carol,
+            message: None,
+            layer_id: None,
+            contributed_at: None,
+        }])
+    });
+    let asset = uploaded_by.map(|name| AssetModel {
+        id: Uuid::new_v4(),
+        uploaded_by: users[name],
+        created_at: Utc::now(),
+    });
+    card_repo
+        .expect_get_asset()
+        .returning(move |_| Ok(asset.clone()));
+    card_repo.expect_save_contribution().returning(|_| Ok(()));
+    card_repo.expect_save_audit_event().returning(|_| Ok(()));
+    let mut image_repo = MockImageRepository::new();
+    image_repo
+        .expect_get_asset()
+        .returning(|_| Ok(Some(("image/png".to_string(), Bytes::from_static(b"png")))));
+    let rocket = rocket::build()
+        .mount("/api/cards", contributions::routes())
+        .manage(AuthUserConfig(true))
+        .manage(BC::from(bot_client))
+        .manage(CR::from(card_repo))
+        .manage(IR::from(image_repo));
+    Fixture {
+        client: Client::tracked(rocket).unwrap(),
+        card_id,
+    }
+}
+
+impl Fixture {
+    fn put_layer(&self) -> Status {
+        self.client
+            .put(format!("/api/cards/{}/contributions/me", self.card_id))
+            .header(Header::new("X-Forwarded-User", "carol"))
+            .header(ContentType::JSON)
+            .body(json!({ "message": "hello", "layer_id": Uuid::new_v4() }).to_string())
+            .dispatch()
+            .status()
+    }
+}
+
+#[test]
+fn layer_uploaded_by_contributor_or_member() {
+    for uploader in ["carol", "alice", "bob"] {
+        let f = fixture(Some(uploader));
+        assert_eq!(f.put_layer(), Status::NoContent, "uploaded by {}", uploader);
+    }
+}
+
+#[test]
+fn layer_uploaded_by_stranger_is_forbidden() {
+    assert_eq!(fixture(Some("mallory")).put_layer(), Status::Forbidden);
+    // 上げた人が分からない画像も使わせない
+    assert_eq!(fixture(None).put_layer(), Status::Forbidden);
+}
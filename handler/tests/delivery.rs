@@ -0,0 +1,331 @@
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use chrono::{Duration, Utc};
+use domain::bot_client::{MockBotClient, User};
+use domain::cron::Cron;
+use domain::repository::{
+    CardMemberModel, CardModel, CardRole, CardStatus, MockCardRepository, MockImageRepository,
+};
+use handler::auth::AuthUserConfig;
+use handler::{cards, BC, CR, CRON, IR};
+use rocket::http::{Header, Status};
+use rocket::local::blocking::Client;
+use uuid::Uuid;
+
+/// 呼ばれた`deliver`と`preview`を記録する
+#[derive(Default)]
+struct RecordingCron {
+    deliveries: Mutex<Vec<Uuid>>,
+    previews: Mutex<Vec<(Uuid, Uuid)>>,
+    /// `deliver`が返す配信後のステータス
+    delivered: Option<CardStatus>,
+    preview_ok: bool,
+}
+
+impl RecordingCron {
+    fn new(delivered: Option<CardStatus>, preview_ok: bool) -> Self {
+        Self {
+            delivered,
+            preview_ok,
+            ..Default::default()
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Cron for RecordingCron {
+    async fn run(self: Arc<Self>) {}
+    async fn tick(&self) {
+        unreachable!("tick from cards");
+    }
+    async fn deliver(&self, card_id: Uuid) -> Option<CardStatus> {
+        self.deliveries.lock().unwrap().push(card_id);
+        self.delivered
+    }
+    async fn preview(&self, card: &CardModel, user_id: Uuid) -> bool {
+        self.previews.lock().unwrap().push((card.id, user_id));
+        self.preview_ok
+    }
+}
+
+/// 配信先とPNG
+#[derive(Clone, Default)]
+struct Card {
+    png: bool,
+    channels: Vec<Uuid>,
+    groups: Vec<Uuid>,
+    /// 投稿日時が無い
+    undated: bool,
+    /// 省略すると下書き
+    status: Option<CardStatus>,
+}
+
+struct Fixture {
+    client: Client,
+    card_id: Uuid,
+    alice: Uuid,
+    cron: Arc<RecordingCron>,
+}
+
+/// `alice`が所有者、`bob`が閲覧者の下書き
+fn fixture(
+    spec: Card,
+    cron: RecordingCron,
+    configure: impl FnOnce(&mut MockCardRepository),
+) -> Fixture {
+    let alice = Uuid::new_v4();
+    let bob = Uuid::new_v4();
+    let mut bot_client = MockBotClient::new();
+    bot_client.expect_get_users().returning(move |name| {
+        let name = name.unwrap_or_default().to_string();
+        let id = if name == "alice" { alice } else { bob };
+        Ok(vec![User {
+            id,
+            name,
+            ..Default::default()
+        }])
+    });
+    let card = CardModel {
+        id: Uuid::new_v4(),
+        owner_id: alice,
+        publish_date: (!spec.undated).then(|| Utc::now() + Duration::days(1)),
+        message: None,
+        status: spec.status.unwrap_or(CardStatus::Draft),
+        time_zone: "Asia/Tokyo".to_string(),
+        version: 1,
+        deleted_at: None,
+    };
+    let card_id = card.id;
+    let mut card_repo = MockCardRepository::new();
+    card_repo
+        .expect_get_card_by_id()
+        .returning(move |_| Ok(Some(card.clone())));
+    card_repo.expect_get_card_members().returning(move |_| {
+        Ok(vec![
+            CardMemberModel {
+                card_id,
+                user_id: alice,
+                role: CardRole::Owner,
+            },
+            CardMemberModel {
+                card_id,
+                user_id: bob,
+                role: CardRole::Viewer,
+            },
+        ])
+    });
+    let channels = spec.channels.clone();
+    card_repo
+        .expect_get_publish_channels_by_id()
+        .returning(move |_| Ok(channels.clone()));
+    card_repo
+        .expect_get_recipients_by_id()
+        .returning(|_| Ok(vec![]));
+    let groups = spec.groups.clone();
+    card_repo
+        .expect_get_publish_groups_by_id()
+        .returning(move |_| Ok(groups.clone()));
+    card_repo
+        .expect_get_delivery_logs_by_id()
+        .returning(|_| Ok(vec![]));
+    card_repo.expect_get_recurrence().returning(|_| Ok(None));
+    card_repo
+        .expect_get_contributions()
+        .returning(|_| Ok(vec![]));
+    card_repo.expect_save_audit_event().returning(|_| Ok(()));
+    configure(&mut card_repo);
+    let mut image_repo = MockImageRepository::new();
+    let png = spec.png.then(|| Bytes::from_static(b"png"));
+    image_repo
+        .expect_get_png()
+        .returning(move |_| Ok(png.clone()));
+    let cron = Arc::new(cron);
+    let rocket = rocket::build()
+        .mount(
+            "/api/cards",
+            rocket::routes![
+                cards::schedule,
+                cards::unschedule,
+                cards::preview_delivery,
+                cards::send_now
+            ],
+        )
+        .manage(AuthUserConfig(true))
+        .manage(BC::from(bot_client))
+        .manage(CR::from(card_repo))
+        .manage(IR::from(image_repo))
+        .manage(CRON::from(cron.clone()));
+    Fixture {
+        client: Client::tracked(rocket).unwrap(),
+        card_id,
+        alice,
+        cron,
+    }
+}
+
+impl Fixture {
+    fn post(&self, action: &str, user: &str) -> Status {
+        self.client
+            .post(format!("/api/cards/{}/{}", self.card_id, action))
+            .header(Header::new("X-Forwarded-User", user.to_string()))
+            .dispatch()
+            .status()
+    }
+}
+
+#[test]
+fn send_now_requires_png_and_destination() {
+    let without_png = Card {
+        png: false,
+        channels: vec![Uuid::new_v4()],
+        ..Default::default()
+    };
+    let without_destination = Card {
+        png: true,
+        ..Default::default()
+    };
+    for spec in [without_png, without_destination] {
+        let f = fixture(spec, RecordingCron::default(), |repo| {
+            repo.expect_reschedule_card().never();
+        });
+        assert_eq!(f.post("send-now", "alice"), Status::BadRequest);
+        assert!(f.cron.deliveries.lock().unwrap().is_empty());
+    }
+}
+
+#[test]
+fn send_now_delivers_card_with_group_only() {
+    let spec = Card {
+        png: true,
+        groups: vec![Uuid::new_v4()],
+        ..Default::default()
+    };
+    let cron = RecordingCron::new(Some(CardStatus::Delivered), true);
+    let f = fixture(spec, cron, |repo| {
+        repo.expect_reschedule_card()
+            .withf(|_, from, publish_date| {
+                from == [CardStatus::Draft, CardStatus::Scheduled] && *publish_date <= Utc::now()
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(Some(())));
+    });
+    assert_eq!(f.post("send-now", "alice"), Status::Ok);
+    assert_eq!(*f.cron.deliveries.lock().unwrap(), vec![f.card_id]);
+}
+
+#[test]
+fn send_now_reports_undelivered_card() {
+    let spec = Card {
+        png: true,
+        channels: vec![Uuid::new_v4()],
+        ..Default::default()
+    };
+    for (delivered, status) in [
+        // 繰り返しのカードは次の発生日時で予約し直される
+        (Some(CardStatus::Scheduled), Status::Ok),
+        // 他の配信に先を越された
+        (None, Status::Accepted),
+        (Some(CardStatus::Failed), Status::BadGateway),
+        (Some(CardStatus::Cancelled), Status::Conflict),
+    ] {
+        let f = fixture(spec.clone(), RecordingCron::new(delivered, true), |repo| {
+            repo.expect_reschedule_card()
+                .returning(|_, _, _| Ok(Some(())));
+        });
+        assert_eq!(f.post("send-now", "alice"), status);
+    }
+}
+
+#[test]
+fn schedule_requires_publish_date_png_and_destination() {
+    let complete = Card {
+        png: true,
+        channels: vec![Uuid::new_v4()],
+        ..Default::default()
+    };
+    let undated = Card {
+        undated: true,
+        ..complete.clone()
+    };
+    let without_png = Card {
+        png: false,
+        ..complete.clone()
+    };
+    let without_destination = Card {
+        channels: vec![],
+        ..complete.clone()
+    };
+    for spec in [undated, without_png, without_destination] {
+        let f = fixture(spec, RecordingCron::default(), |repo| {
+            repo.expect_update_card_status().never();
+        });
+        assert_eq!(f.post("schedule", "alice"), Status::BadRequest);
+    }
+
+    let f = fixture(complete, RecordingCron::default(), |repo| {
+        repo.expect_update_card_status()
+            .withf(|_, from, to| from == [CardStatus::Draft] && *to == CardStatus::Scheduled)
+            .times(1)
+            .returning(|_, _, _| Ok(Some(())));
+    });
+    assert_eq!(f.post("schedule", "alice"), Status::NoContent);
+}
+
+#[test]
+fn delivering_card_cannot_be_unscheduled() {
+    let delivering = Card {
+        png: true,
+        channels: vec![Uuid::new_v4()],
+        status: Some(CardStatus::Delivering),
+        ..Default::default()
+    };
+    let f = fixture(delivering, RecordingCron::default(), |repo| {
+        repo.expect_update_card_status().never();
+    });
+    assert_eq!(f.post("unschedule", "alice"), Status::Conflict);
+}
+
+#[test]
+fn preview_goes_to_the_caller() {
+    let spec = Card {
+        png: true,
+        channels: vec![Uuid::new_v4()],
+        ..Default::default()
+    };
+    let f = fixture(spec, RecordingCron::new(None, true), |_| ());
+    assert_eq!(f.post("preview-delivery", "alice"), Status::NoContent);
+    assert_eq!(*f.cron.previews.lock().unwrap(), vec![(f.card_id, f.alice)]);
+
+    // 閲覧者は送れない
+    assert_eq!(f.post("preview-delivery", "bob"), Status::Forbidden);
+    assert_eq!(f.cron.previews.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn preview_requires_png_and_destination() {
+    let without_png = Card {
+        channels: vec![Uuid::new_v4()],
+        ..Default::default()
+    };
+    let without_destination = Card {
+        png: true,
+        ..Default::default()
+    };
+    for spec in [without_png, without_destination] {
+        let f = fixture(spec, RecordingCron::new(None, true), |_| ());
+        assert_eq!(f.post("preview-delivery", "alice"), Status::BadRequest);
+        assert!(f.cron.previews.lock().unwrap().is_empty());
+    }
+}
+
+#[test]
+fn failed_preview_is_bad_gateway() {
+    let spec = Card {
+        png: true,
+        channels: vec![Uuid::new_v4()],
+        ..Default::default()
+    };
+    let f = fixture(spec, RecordingCron::new(None, false), |_| ());
+    assert_eq!(f.post("preview-delivery", "alice"), Status::BadGateway);
+}
@@ -0,0 +1,90 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use domain::bot_client::{MockBotClient, User};
+use domain::repository::{AssetModel, MockCardRepository, MockImageRepository};
+use handler::auth::AuthUserConfig;
+use handler::{images, BC, CR, IR};
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::blocking::Client;
+use uuid::Uuid;
+
+const BOUNDARY: &str = "boundary";
+
+/// `id`にPNGを上げるフォーム
+fn form(id: Uuid) -> String {
+    format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"id\"\r\n\r\n{id}\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"image\"; filename=\"a.png\"\r\n\
+         Content-Type: image/png\r\n\r\npng\r\n--{b}--\r\n",
+        b = BOUNDARY,
+        id = id,
+    )
+}
+
+/// `uploaded_by`が既に上げたアセットがあれば、その人を返すリポジトリで`alice`が上げる
+fn upload(uploaded_by: Option<Uuid>, alice: Uuid) -> (Status, Arc<Mutex<Vec<Uuid>>>) {
+    let mut bot_client = MockBotClient::new();
+    bot_client.expect_get_users().returning(move |name| {
+        Ok(vec![User {
+            id: alice,
+            name: name.unwrap_or_default().to_string(),
+            ..Default::default()
+        }])
+    });
+    let mut card_repo = MockCardRepository::new();
+    let saved = uploaded_by.is_none().then_some(());
+    card_repo
+        .expect_save_asset()
+        .withf(move |asset| asset.uploaded_by == alice)
+        .returning(move |_| Ok(saved));
+    card_repo.expect_get_asset().returning(move |id| {
+        Ok(uploaded_by.map(|uploaded_by| AssetModel {
+            id,
+            uploaded_by,
+            created_at: Utc::now(),
+        }))
+    });
+    card_repo.expect_save_audit_event().returning(|_| Ok(()));
+    let stored = Arc::<Mutex<Vec<Uuid>>>::default();
+    let s = stored.clone();
+    let mut image_repo = MockImageRepository::new();
+    image_repo.expect_save_asset().returning(move |id, _, _| {
+        s.lock().unwrap().push(id);
+        Ok(())
+    });
+    let rocket = rocket::build()
+        .mount("/api/images", images::routes())
+        .manage(AuthUserConfig(true))
+        .manage(BC::from(bot_client))
+        .manage(CR::from(card_repo))
+        .manage(IR::from(image_repo));
+    let client = Client::tracked(rocket).unwrap();
+    let status = client
+        .post("/api/images")
+        .header(Header::new("X-Forwarded-User", "alice"))
+        .header(ContentType::new("multipart", "form-data").with_params(("boundary", BOUNDARY)))
+        .body(form(Uuid::new_v4()))
+        .dispatch()
+        .status();
+    (status, stored)
+}
+
+#[test]
+fn upload_records_uploader() {
+    let alice = Uuid::new_v4();
+    let (status, stored) = upload(None, alice);
+    assert_eq!(status, Status::NoContent);
+    assert_eq!(stored.lock().unwrap().len(), 1);
+
+    // 自分が上げたものは上書きできる
+    let (status, _) = upload(Some(alice), alice);
+    assert_eq!(status, Status::NoContent);
+}
+
+#[test]
+fn upload_does_not_overwrite_others_asset() {
+    let (status, stored) = upload(Some(Uuid::new_v4()), Uuid::new_v4());
+    assert_eq!(status, Status::Forbidden);
+    assert!(stored.lock().unwrap().is_empty());
+}
@@ -0,0 +1,155 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use domain::bot_client::{MockBotClient, User};
+use domain::repository::{
+    AuditAction, AuditEventModel, CardMemberModel, CardModel, CardRole, CardStatus,
+    MockCardRepository,
+};
+use handler::auth::AuthUserConfig;
+use handler::{members, BC, CR};
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::blocking::Client;
+use rocket::serde::json::{from_str, json, Value};
+use uuid::Uuid;
+
+struct Fixture {
+    client: Client,
+    card: CardModel,
+    alice: Uuid,
+    bob: Uuid,
+    events: Arc<Mutex<Vec<AuditEventModel>>>,
+}
+
+/// `alice`が所有者、`bob`が編集者のカード
+fn fixture(configure: impl FnOnce(&mut MockCardRepository)) -> Fixture {
+    let alice = Uuid::new_v4();
+    let bob = Uuid::new_v4();
+    let mut bot_client = MockBotClient::new();
+    bot_client.expect_get_users().returning(move |name| {
+        let name = name.unwrap_or_default().to_string();
+        let id = if name == "alice" { alice } else { bob };
+        Ok(vec![User {
+            id,
+            name,
+            ..Default::default()
+        }])
+    });
+    let card = CardModel {
+        id: Uuid::new_v4(),
+        owner_id: alice,
+        publish_date: Some(Utc::now()),
+        message: None,
+        status: CardStatus::Draft,
+        time_zone: "Asia/Tokyo".to_string(),
+        version: 1,
+        deleted_at: None,
+    };
+    let events = Arc::<Mutex<Vec<AuditEventModel>>>::default();
+    let mut card_repo = MockCardRepository::new();
+    let c = card.clone();
+    card_repo
+        .expect_get_card_by_id()
+        .returning(move |_| Ok(Some(c.clone())));
+    let card_id = card.id;
+    card_repo.expect_get_card_members().returning(move |_| {
+        Ok(vec![
+            CardMemberModel {
+                card_id,
+                user_id: alice,
+                role: CardRole::Owner,
+            },
+            CardMemberModel {
+                card_id,
+                user_id: bob,
+                role: CardRole::Editor,
+            },
+        ])
+    });
+    let e = events.clone();
+    card_repo.expect_save_audit_event().returning(move |event| {
+        e.lock().unwrap().push(event.clone());
+        Ok(())
+    });
+    configure(&mut card_repo);
+    let rocket = rocket::build()
+        .mount("/api/cards", members::routes())
+        .manage(AuthUserConfig(true))
+        .manage(BC::from(bot_client))
+        .manage(CR::from(card_repo));
+    Fixture {
+        client: Client::tracked(rocket).unwrap(),
+        card,
+        alice,
+        bob,
+        events,
+    }
+}
+
+fn as_user(name: &str) -> Header<'static> {
+    Header::new("X-Forwarded-User", name.to_string())
+}
+
+fn json_of(value: &Option<String>) -> Value {
+    from_str(value.as_deref().unwrap()).unwrap()
+}
+
+#[test]
+fn role_change_is_audited() {
+    let f = fixture(|repo| {
+        repo.expect_save_card_member().returning(|_| Ok(()));
+    });
+
+    let res = f
+        .client
+        .put(format!("/api/cards/{}/members/{}", f.card.id, f.bob))
+        .header(as_user("alice"))
+        .header(ContentType::JSON)
+        .body(r#"{"role":"viewer"}"#)
+        .dispatch();
+    assert_eq!(res.status(), Status::NoContent);
+
+    let events = f.events.lock().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].action, AuditAction::MemberUpdate);
+    assert_eq!(events[0].actor_id, Some(f.alice));
+    assert_eq!(events[0].card_id, Some(f.card.id));
+    assert_eq!(json_of(&events[0].before), json!({ "role": "editor" }));
+    assert_eq!(json_of(&events[0].after), json!({ "role": "viewer" }));
+}
+
+#[test]
+fn leaving_and_transfer_are_audited() {
+    let f = fixture(|repo| {
+        repo.expect_delete_card_member()
+            .returning(|_, _| Ok(Some(())));
+        repo.expect_transfer_card_ownership()
+            .returning(|_, _, _| Ok(Some(())));
+    });
+
+    let res = f
+        .client
+        .delete(format!("/api/cards/{}/members/{}", f.card.id, f.bob))
+        .header(as_user("bob"))
+        .dispatch();
+    assert_eq!(res.status(), Status::NoContent);
+    let res = f
+        .client
+        .post(format!("/api/cards/{}/transfer", f.card.id))
+        .header(as_user("alice"))
+        .header(ContentType::JSON)
+        .body(json!({ "user_id": f.bob }).to_string())
+        .dispatch();
+    assert_eq!(res.status(), Status::NoContent);
+
+    let events = f.events.lock().unwrap();
+    let actions: Vec<_> = events.iter().map(|e| e.action).collect();
+    assert_eq!(
+        actions,
+        vec![AuditAction::MemberRemove, AuditAction::CardTransfer]
+    );
+    assert_eq!(events[0].actor_id, Some(f.bob));
+    assert_eq!(events[0].after, None);
+    assert_eq!(json_of(&events[1].before), json!(f.alice));
+    assert_eq!(json_of(&events[1].after), json!(f.bob));
+}
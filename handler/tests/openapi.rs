@@ -0,0 +1,527 @@
+use std::collections::BTreeSet;
+
+use rocket::http::Method;
+use rocket::serde::json::Value;
+
+/// `(メソッド, パス, パスとクエリのパラメータ名)`
+type Operation = (String, String, BTreeSet<String>);
+
+/// `/api/cards/<id>?<expand>` -> `("/api/cards/{id}", {"id", "expand"})`
+fn normalize(path: &str, query: Option<&str>) -> (String, BTreeSet<String>) {
+    let mut params = BTreeSet::new();
+    let mut segments = vec![];
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        match segment.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+            Some(name) => {
+                params.insert(name.to_string());
+                segments.push(format!("{{{}}}", name));
+            }
+            None => segments.push(segment.to_string()),
+        }
+    }
+    for param in query.into_iter().flat_map(|q| q.split('&')) {
+        let name = param.trim_start_matches('<').trim_end_matches('>');
+        params.insert(name.to_string());
+    }
+    (format!("/{}", segments.join("/")), params)
+}
+
+fn routed_operations() -> BTreeSet<Operation> {
+    handler::mounts()
+        .into_iter()
+        .flat_map(|(base, routes)| {
+            routes
+                .into_iter()
+                .map(move |r| r.map_base(|_| base.to_string()).unwrap())
+        })
+        .filter(|r| r.method != Method::Options)
+        .map(|r| {
+            let (path, params) = normalize(r.uri.path(), r.uri.query());
+            (r.method.as_str().to_lowercase(), path, params)
+        })
+        .collect()
+}
+
+fn documented_operations() -> BTreeSet<Operation> {
+    let spec = handler::openapi::spec();
+    let mut operations = BTreeSet::new();
+    for (path, item) in spec["paths"].as_object().unwrap() {
+        for (method, op) in item.as_object().unwrap() {
+            let params = op["parameters"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|p| p["in"] == "path" || p["in"] == "query")
+                .map(|p| p["name"].as_str().unwrap().to_string())
+                .collect();
+            operations.insert((method.clone(), path.clone(), params));
+        }
+    }
+    operations
+}
+
+#[test]
+fn spec_matches_routes() {
+    let routed = routed_operations();
+    let documented = documented_operations();
+    let undocumented: Vec<_> = routed.difference(&documented).collect();
+    let stale: Vec<_> = documented.difference(&routed).collect();
+    assert!(
+        undocumented.is_empty() && stale.is_empty(),
+        "openapi spec drifted from routes\nundocumented: {:#?}\nstale: {:#?}",
+        undocumented,
+        stale
+    );
+}
+
+#[test]
+fn refs_are_defined() {
+    let spec = handler::openapi::spec();
+    let schemas = spec["components"]["schemas"].as_object().unwrap();
+    let mut stack = vec![&spec];
+    while let Some(value) = stack.pop() {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(r)) = map.get("$ref") {
+                    let name = r.trim_start_matches("#/components/schemas/");
+                    assert!(schemas.contains_key(name), "undefined schema {}", r);
+                }
+                stack.extend(map.values());
+            }
+            Value::Array(values) => stack.extend(values),
+            _ => (),
+        }
+    }
+}
+
+/// `$ref`を解決し、`allOf`の`properties`と`required`を1つにまとめる
+fn flatten(spec: &Value, schema: &Value, visited: &mut BTreeSet<String>) -> Value {
+    if let Some(Value::String(r)) = schema.get("$ref") {
+        let name = r.trim_start_matches("#/components/schemas/");
+        visited.insert(name.to_string());
+        return flatten(spec, &spec["components"]["schemas"][name], visited);
+    }
+    let Some(all_of) = schema.get("allOf").and_then(Value::as_array) else {
+        return schema.clone();
+    };
+    let mut merged = schema.as_object().unwrap().clone();
+    merged.remove("allOf");
+    for part in all_of {
+        let part = flatten(spec, part, visited);
+        for (key, value) in part.as_object().unwrap() {
+            match (key.as_str(), merged.get_mut(key)) {
+                ("properties", Some(Value::Object(props))) => {
+                    props.extend(value.as_object().unwrap().clone());
+                }
+                ("required", Some(Value::Array(required))) => {
+                    required.extend(value.as_array().unwrap().clone());
+                }
+                (_, Some(_)) => (),
+                (_, None) => {
+                    merged.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+    Value::Object(merged)
+}
+
+/// `value`が`schema`に合っているか検査する。オブジェクトは文書にある項目が全て含まれていることも見る
+fn check(
+    spec: &Value,
+    schema: &Value,
+    value: &Value,
+    at: &str,
+    visited: &mut BTreeSet<String>,
+    errors: &mut Vec<String>,
+) {
+    let schema = flatten(spec, schema, visited);
+    if value.is_null() {
+        if schema["nullable"] != true {
+            errors.push(format!("{}: null is not nullable", at));
+        }
+        return;
+    }
+    // 説明付きの列挙は値ごとの`oneOf`になる
+    if let Some(variants) = schema["oneOf"].as_array() {
+        let matched = variants.iter().any(|variant| {
+            let mut variant_errors = vec![];
+            check(spec, variant, value, at, visited, &mut variant_errors);
+            variant_errors.is_empty()
+        });
+        if !matched {
+            errors.push(format!("{}: {} matches no variant", at, value));
+        }
+        return;
+    }
+    if let Some(values) = schema["enum"].as_array() {
+        if !values.contains(value) {
+            errors.push(format!("{}: {} is not in {:?}", at, value, values));
+        }
+    }
+    match (schema["type"].as_str(), value) {
+        (Some("string"), Value::String(s)) => {
+            let valid = match schema["format"].as_str() {
+                Some("uuid") => s.parse::<uuid::Uuid>().is_ok(),
+                Some("date-time") => chrono::DateTime::parse_from_rfc3339(s).is_ok(),
+                _ => true,
+            };
+            if !valid {
+                errors.push(format!("{}: {:?} is not {}", at, s, schema["format"]));
+            }
+        }
+        (Some("integer"), Value::Number(n)) if n.is_i64() || n.is_u64() => (),
+        (Some("boolean"), Value::Bool(_)) => (),
+        (Some("array"), Value::Array(items)) => {
+            for (i, item) in items.iter().enumerate() {
+                let at = format!("{}[{}]", at, i);
+                check(spec, &schema["items"], item, &at, visited, errors);
+            }
+        }
+        (Some("object"), Value::Object(fields)) => {
+            let Some(props) = schema["properties"].as_object() else {
+                return;
+            };
+            for (key, value) in fields {
+                match props.get(key) {
+                    Some(prop) => {
+                        let at = format!("{}.{}", at, key);
+                        check(spec, prop, value, &at, visited, errors);
+                    }
+                    None => errors.push(format!("{}.{}: undocumented", at, key)),
+                }
+            }
+            for key in props.keys().filter(|k| !fields.contains_key(*k)) {
+                errors.push(format!("{}.{}: documented but missing", at, key));
+            }
+            for key in schema["required"].as_array().into_iter().flatten() {
+                if !fields.contains_key(key.as_str().unwrap()) {
+                    errors.push(format!("{}.{}: required but missing", at, key));
+                }
+            }
+        }
+        (None, _) => (),
+        (Some(ty), value) => errors.push(format!("{}: {} is not {}", at, value, ty)),
+    }
+}
+
+mod samples {
+    use chrono::{TimeZone, Utc};
+    use domain::bot_client::{Channel, ChannelList, DmChannel, Stamp, User, UserDetail};
+    use domain::repository::{AuditAction, CardRole, CardStatus};
+    use handler::admin::{AuditEventResponse, QueueEntry};
+    use handler::cards::{CardOwner, CardResponse};
+    use handler::catalog::{FlatChannel, Page};
+    use handler::contributions::ContributionResponse;
+    use handler::members::CardMember;
+    use handler::recurrence::{OccurrenceResponse, RecurrenceResponse};
+    use handler::revisions::ImageRevision;
+    use handler::share::ShareLinkResponse;
+    use handler::traq_api::channels::ResolvedChannel;
+    use handler::trash::TrashedCard;
+    use handler::webhooks::WebhookResponse;
+    use rocket::serde::json::{json, to_value, Value};
+    use uuid::Uuid;
+
+    fn value(v: &impl rocket::serde::Serialize) -> Value {
+        to_value(v).unwrap()
+    }
+
+    /// 全ての項目を埋めたレスポンス
+    pub fn responses() -> Vec<(&'static str, Value)> {
+        let at = Utc.with_ymd_and_hms(2023, 12, 24, 0, 0, 0).unwrap();
+        let rfc3339 = at.to_rfc3339();
+        let id = Uuid::new_v4();
+        let card = CardResponse {
+            id,
+            owner_id: id,
+            publish_date: Some(at),
+            local_publish_date: Some("2023-12-24T09:00:00+09:00".to_string()),
+            time_zone: "Asia/Tokyo".to_string(),
+            status: CardStatus::Scheduled,
+            publish_channels: vec![id],
+            recipients: vec![id],
+            publish_groups: vec![id],
+            group_members: vec![id],
+            contributors: vec![id],
+            members: vec![CardMember {
+                user_id: id,
+                role: CardRole::Owner,
+            }],
+            message: Some("hello".to_string()),
+            recurrence: Some(RecurrenceResponse {
+                rrule: "FREQ=YEARLY".to_string(),
+                time_zone: "Asia/Tokyo".to_string(),
+                dtstart: at,
+                last_occurrence: Some(at),
+            }),
+            version: 1,
+            owner: Some(CardOwner {
+                name: "alice".to_string(),
+                display_name: "Alice".to_string(),
+                icon_url: "/api/users/alice/icon".to_string(),
+            }),
+        };
+        let flat_channel = FlatChannel {
+            id,
+            parent_id: Some(id),
+            name: "foo".to_string(),
+            path: "gps/times/foo".to_string(),
+            topic: String::new(),
+            archived: false,
+        };
+        let stamp = Stamp {
+            id,
+            creator_id: id,
+            created_at: rfc3339.clone(),
+            updated_at: rfc3339.clone(),
+            file_id: id,
+            ..Default::default()
+        };
+        let user = User {
+            id,
+            icon_file_id: id,
+            updated_at: rfc3339.clone(),
+            ..Default::default()
+        };
+        let user_detail = UserDetail {
+            id,
+            icon_file_id: id,
+            updated_at: rfc3339.clone(),
+            last_online: Some(rfc3339.clone()),
+            groups: vec![id],
+            home_channel: Some(id),
+            ..Default::default()
+        };
+        let channel = Channel {
+            id,
+            parent_id: Some(id),
+            children: vec![id],
+            ..Default::default()
+        };
+        let page = |items: Value| json!({ "items": items, "total": 1, "offset": 0, "limit": 50 });
+        // 列挙は全ての値を確かめる
+        let actions = AuditAction::ALL.map(|a| ("AuditAction", value(&a)));
+        let mut responses = vec![
+            ("CardResponse", value(&card)),
+            (
+                "TrashedCard",
+                value(&TrashedCard {
+                    card: card.clone(),
+                    deleted_at: at,
+                    purge_at: at,
+                }),
+            ),
+            (
+                "CardPage",
+                value(&Page {
+                    items: vec![card.clone()],
+                    total: 1,
+                    offset: 0,
+                    limit: 50,
+                }),
+            ),
+            (
+                "Contribution",
+                value(&ContributionResponse {
+                    user_id: id,
+                    message: Some("hello".to_string()),
+                    layer_id: Some(id),
+                    contributed_at: Some(at),
+                }),
+            ),
+            (
+                "ImageRevision",
+                value(&ImageRevision {
+                    revision: 1,
+                    author_id: id,
+                    created_at: at,
+                    size: 3,
+                    hash: "00".to_string(),
+                }),
+            ),
+            (
+                "ShareLink",
+                value(&ShareLinkResponse {
+                    id,
+                    token: "token".to_string(),
+                    url: "https://example.com/share/token".to_string(),
+                    created_by: id,
+                    created_at: at,
+                    expires_at: at,
+                }),
+            ),
+            (
+                "Occurrence",
+                value(&OccurrenceResponse {
+                    at,
+                    local: "2023-12-24T09:00:00+09:00".to_string(),
+                    skipped: false,
+                }),
+            ),
+            (
+                "QueueEntry",
+                value(&QueueEntry {
+                    id,
+                    owner_id: id,
+                    status: CardStatus::Delivering,
+                    publish_date: Some(at),
+                    overdue: true,
+                }),
+            ),
+            (
+                "AuditEvent",
+                value(&AuditEventResponse {
+                    id,
+                    at,
+                    actor_id: Some(id),
+                    action: AuditAction::CardUpdate,
+                    card_id: Some(id),
+                    asset_id: Some(id),
+                    before: Some(json!({ "message": "a" })),
+                    after: Some(json!({ "message": "b" })),
+                    request_id: Some("request".to_string()),
+                }),
+            ),
+            (
+                "WebhookResponse",
+                value(&WebhookResponse {
+                    channel_id: id,
+                    webhook_id: id,
+                }),
+            ),
+            ("FlatChannel", value(&flat_channel)),
+            ("ChannelPage", page(value(&vec![flat_channel]))),
+            (
+                "ResolvedChannel",
+                value(&ResolvedChannel {
+                    id,
+                    path: "gps/times/foo".to_string(),
+                }),
+            ),
+            ("Stamp", value(&stamp)),
+            ("StampPage", page(value(&vec![stamp]))),
+            ("User", value(&user)),
+            ("UserDetail", value(&user_detail)),
+            (
+                "ChannelList",
+                value(&ChannelList {
+                    public: vec![channel],
+                    dm: Some(vec![DmChannel { id, user_id: id }]),
+                }),
+            ),
+        ];
+        responses.extend(actions);
+        responses
+    }
+
+    /// 全ての項目を埋めたリクエスト。`T`として読めることも確かめる
+    pub fn requests() -> Vec<(&'static str, Value)> {
+        use handler::admin::ReassignRequest;
+        use handler::cards::{CardPatch, CardRequest};
+        use handler::contributions::ContributionRequest;
+        use handler::members::{MemberRequest, TransferRequest};
+        use handler::recurrence::RecurrenceRequest;
+        use handler::share::ShareRequest;
+        use handler::webhooks::WebhookRequest;
+        use rocket::serde::json::from_value;
+
+        let id = Uuid::new_v4();
+        let recurrence = json!({ "rrule": "FREQ=YEARLY;COUNT=3", "time_zone": "Asia/Tokyo" });
+        let card = json!({
+            "owner_id": id,
+            "publish_date": "2024-12-24T09:00",
+            "time_zone": "Asia/Tokyo",
+            "publish_channels": [id, "#gps/times/foo"],
+            "recipients": [id, "@alice"],
+            "publish_groups": [id],
+            "message": "hello",
+            "images": [id],
+            "recurrence": recurrence,
+        });
+        let mut patch = card.clone();
+        patch.as_object_mut().unwrap().remove("images");
+        let requests = vec![
+            ("CardRequest", card),
+            ("CardPatch", patch),
+            ("RecurrenceRequest", recurrence),
+            (
+                "ContributionRequest",
+                json!({ "message": "hello", "layer_id": id }),
+            ),
+            ("MemberRequest", json!({ "role": "editor" })),
+            ("TransferRequest", json!({ "user_id": id })),
+            ("ShareRequest", json!({ "expires_in": 3600 })),
+            ("ReassignRequest", json!({ "owner_id": id })),
+            (
+                "WebhookRequest",
+                json!({ "webhook_id": id, "secret": "secret" }),
+            ),
+        ];
+        for (name, value) in &requests {
+            let value = value.clone();
+            let parsed = match *name {
+                "CardRequest" => from_value::<CardRequest>(value).map(drop),
+                "CardPatch" => from_value::<CardPatch>(value).map(drop),
+                "RecurrenceRequest" => from_value::<RecurrenceRequest>(value).map(drop),
+                "ContributionRequest" => from_value::<ContributionRequest>(value).map(drop),
+                "MemberRequest" => from_value::<MemberRequest>(value).map(drop),
+                "TransferRequest" => from_value::<TransferRequest>(value).map(drop),
+                "ShareRequest" => from_value::<ShareRequest>(value).map(drop),
+                "ReassignRequest" => from_value::<ReassignRequest>(value).map(drop),
+                "WebhookRequest" => from_value::<WebhookRequest>(value).map(drop),
+                _ => unreachable!(),
+            };
+            if let Err(e) = parsed {
+                panic!("{} sample does not deserialize: {}", name, e);
+            }
+        }
+        requests
+    }
+}
+
+#[test]
+fn schemas_match_rust_types() {
+    let spec = handler::openapi::spec();
+    let mut visited = BTreeSet::new();
+    let mut errors = vec![];
+    for (name, value) in samples::responses().into_iter().chain(samples::requests()) {
+        let schema =
+            rocket::serde::json::json!({ "$ref": format!("#/components/schemas/{}", name) });
+        check(&spec, &schema, &value, name, &mut visited, &mut errors);
+    }
+    assert!(errors.is_empty(), "schemas drifted:\n{}", errors.join("\n"));
+
+    // multipartのフォームはシリアライズする型が無い
+    let unchecked: Vec<_> = spec["components"]["schemas"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .filter(|name| !visited.contains(*name) && *name != "ImageForm")
+        .collect();
+    assert!(unchecked.is_empty(), "no sample for {:?}", unchecked);
+}
+
+#[test]
+fn serves_bundled_swagger_ui() {
+    use rocket::http::{ContentType, Status};
+    use rocket::local::blocking::Client;
+
+    let routes = rocket::routes![handler::openapi::get_docs, handler::openapi::get_docs_asset];
+    let client = Client::tracked(rocket::build().mount("/api", routes)).unwrap();
+    let html = client.get("/api/docs").dispatch().into_string().unwrap();
+    assert!(!html.contains("https://"));
+    for (file, content_type) in [
+        ("swagger-ui.css", ContentType::CSS),
+        ("swagger-ui-bundle.js", ContentType::JavaScript),
+    ] {
+        let path = format!("/api/docs/{}", file);
+        assert!(html.contains(&path));
+        let res = client.get(path).dispatch();
+        assert_eq!(res.status(), Status::Ok);
+        assert_eq!(res.content_type(), Some(content_type));
+        assert!(res.headers().get_one("ETag").is_some());
+    }
+    let res = client.get("/api/docs/index.html").dispatch();
+    assert_eq!(res.status(), Status::NotFound);
+}
@@ -0,0 +1,373 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use chrono::Utc;
+use domain::bot_client::{MockBotClient, User};
+use domain::repository::{
+    CardMemberModel, CardModel, CardRole, CardStatus, ImageKind, ImageRevisionModel,
+    MockCardRepository, MockImageRepository,
+};
+use handler::cache::{content_etag, version_etag};
+use handler::revisions::RevisionConfig;
+use handler::{cards, revisions, BC, CR, IR};
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::blocking::{Client, LocalResponse};
+use uuid::Uuid;
+
+/// カードのPNGと版
+#[derive(Debug, Default)]
+struct Store {
+    png: Option<Bytes>,
+    revisions: Vec<ImageRevisionModel>,
+    contents: BTreeMap<u32, Bytes>,
+    /// 読んだ後に他の保存が確保した番号
+    raced: Vec<u32>,
+    /// 画像の書き込みを失敗させる
+    failing: bool,
+}
+
+impl Store {
+    fn numbers(&self) -> Vec<u32> {
+        self.revisions.iter().map(|r| r.revision).collect()
+    }
+}
+
+struct Fixture {
+    client: Client,
+    card_id: Uuid,
+    store: Arc<Mutex<Store>>,
+}
+
+/// `contents`を1から順に版として持ち、最後の版が現在のPNGのカード
+fn fixture(contents: &[&'static [u8]], config: RevisionConfig) -> Fixture {
+    let alice = Uuid::new_v4();
+    let card = CardModel {
+        id: Uuid::new_v4(),
+        owner_id: alice,
+        publish_date: None,
+        message: None,
+        status: CardStatus::Draft,
+        time_zone: "Asia/Tokyo".to_string(),
+        version: 1,
+        deleted_at: None,
+    };
+    let card_id = card.id;
+    let mut store = Store::default();
+    for (i, content) in contents.iter().enumerate() {
+        let revision = i as u32 + 1;
+        store.revisions.push(ImageRevisionModel {
+            card_id,
+            kind: ImageKind::Png,
+            revision,
+            author_id: alice,
+            created_at: Utc::now(),
+            size: content.len() as u64,
+            hash: String::new(),
+        });
+        store.contents.insert(revision, Bytes::from_static(content));
+        store.png = Some(Bytes::from_static(content));
+    }
+    let store = Arc::new(Mutex::new(store));
+
+    let mut bot_client = MockBotClient::new();
+    bot_client.expect_get_users().returning(move |name| {
+        Ok(vec![User {
+            id: alice,
+            name: name.unwrap_or_default().to_string(),
+            ..Default::default()
+        }])
+    });
+
+    let mut card_repo = MockCardRepository::new();
+    card_repo
+        .expect_get_card_by_id()
+        .returning(move |_| Ok(Some(card.clone())));
+    card_repo.expect_get_card_members().returning(move |_| {
+        Ok(vec![CardMemberModel {
+            card_id,
+            user_id: alice,
+            role: CardRole::Owner,
+        }])
+    });
+    let s = store.clone();
+    card_repo
+        .expect_get_image_revisions()
+        .returning(move |_, _| Ok(s.lock().unwrap().revisions.clone()));
+    let s = store.clone();
+    card_repo
+        .expect_get_image_revision()
+        .returning(move |_, _, revision| {
+            let store = s.lock().unwrap();
+            Ok(store
+                .revisions
+                .iter()
+                .find(|r| r.revision == revision)
+                .cloned())
+        });
+    let s = store.clone();
+    card_repo
+        .expect_save_image_revision()
+        .returning(move |revision| {
+            let mut store = s.lock().unwrap();
+            let n = revision.revision;
+            if store.raced.contains(&n) || store.revisions.iter().any(|r| r.revision == n) {
+                return Ok(None);
+            }
+            store.revisions.push(revision.clone());
+            Ok(Some(()))
+        });
+    let s = store.clone();
+    card_repo
+        .expect_delete_image_revision()
+        .returning(move |_, _, revision| {
+            let mut store = s.lock().unwrap();
+            let before = store.revisions.len();
+            store.revisions.retain(|r| r.revision != revision);
+            Ok((store.revisions.len() < before).then_some(()))
+        });
+    card_repo.expect_save_audit_event().returning(|_| Ok(()));
+
+    let mut image_repo = MockImageRepository::new();
+    let s = store.clone();
+    image_repo
+        .expect_get_png()
+        .returning(move |_| Ok(s.lock().unwrap().png.clone()));
+    let s = store.clone();
+    image_repo.expect_save_png().returning(move |_, content| {
+        let mut store = s.lock().unwrap();
+        if store.failing {
+            anyhow::bail!("unavailable");
+        }
+        store.png = Some(content.clone());
+        Ok(())
+    });
+    let s = store.clone();
+    image_repo
+        .expect_save_revision()
+        .returning(move |_, _, revision, content| {
+            let mut store = s.lock().unwrap();
+            if store.failing {
+                anyhow::bail!("unavailable");
+            }
+            store.contents.insert(revision, content.clone());
+            Ok(())
+        });
+    let s = store.clone();
+    image_repo
+        .expect_get_revision()
+        .returning(move |_, _, revision| Ok(s.lock().unwrap().contents.get(&revision).cloned()));
+    let s = store.clone();
+    image_repo
+        .expect_delete_revision()
+        .returning(move |_, _, revision| {
+            s.lock().unwrap().contents.remove(&revision);
+            Ok(())
+        });
+
+    let routes = rocket::routes![
+        cards::post_png,
+        cards::patch_png,
+        revisions::restore_png_revision
+    ];
+    let rocket = rocket::build()
+        .mount("/api/cards", routes)
+        .manage(handler::auth::AuthUserConfig(true))
+        .manage(BC::from(bot_client))
+        .manage(CR::from(card_repo))
+        .manage(IR::from(image_repo))
+        .manage(config);
+    Fixture {
+        client: Client::tracked(rocket).unwrap(),
+        card_id,
+        store,
+    }
+}
+
+fn as_alice() -> Header<'static> {
+    Header::new("X-Forwarded-User", "alice")
+}
+
+fn etag(res: &LocalResponse) -> Option<String> {
+    res.headers().get_one("ETag").map(|e| e.to_string())
+}
+
+#[test]
+fn expires_oldest_revisions_over_limit() {
+    // 上限を下げた後は、上限を超えた分をまとめて消す
+    let f = fixture(
+        &[b"1", b"2", b"3"],
+        RevisionConfig::default().max_revisions(2),
+    );
+
+    let res = f
+        .client
+        .post(format!("/api/cards/{}/png", f.card_id))
+        .header(as_alice())
+        .header(ContentType::PNG)
+        .body("4")
+        .dispatch();
+    assert_eq!(res.status(), Status::NoContent);
+
+    let store = f.store.lock().unwrap();
+    assert_eq!(store.numbers(), vec![3, 4]);
+    assert_eq!(
+        store.contents.keys().copied().collect::<Vec<_>>(),
+        vec![3, 4]
+    );
+}
+
+#[test]
+fn keeps_revisions_under_limit() {
+    let f = fixture(&[b"1"], RevisionConfig::default().max_revisions(3));
+
+    for body in ["2", "3"] {
+        let res = f
+            .client
+            .post(format!("/api/cards/{}/png", f.card_id))
+            .header(as_alice())
+            .header(ContentType::PNG)
+            .body(body)
+            .dispatch();
+        assert_eq!(res.status(), Status::NoContent);
+    }
+
+    assert_eq!(f.store.lock().unwrap().numbers(), vec![1, 2, 3]);
+}
+
+#[test]
+fn restore_saves_old_content_as_new_revision() {
+    let f = fixture(&[b"one", b"two"], RevisionConfig::default());
+
+    let res = f
+        .client
+        .post(format!("/api/cards/{}/png/revisions/1/restore", f.card_id))
+        .header(as_alice())
+        .header(Header::new("If-Match", version_etag("r", 2)))
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    assert_eq!(etag(&res), Some(version_etag("r", 3)));
+
+    let store = f.store.lock().unwrap();
+    assert_eq!(store.png.as_deref(), Some(&b"one"[..]));
+    assert_eq!(store.numbers(), vec![1, 2, 3]);
+    assert_eq!(store.contents[&3].as_ref(), b"one");
+}
+
+#[test]
+fn restore_of_missing_revision_is_not_found() {
+    let f = fixture(&[b"one"], RevisionConfig::default());
+
+    let res = f
+        .client
+        .post(format!("/api/cards/{}/png/revisions/2/restore", f.card_id))
+        .header(as_alice())
+        .header(Header::new("If-Match", version_etag("r", 1)))
+        .dispatch();
+    assert_eq!(res.status(), Status::NotFound);
+    assert_eq!(f.store.lock().unwrap().numbers(), vec![1]);
+}
+
+#[test]
+fn etag_without_revisions_comes_from_content() {
+    // 版を残す前に保存された画像
+    let f = fixture(&[], RevisionConfig::default());
+    f.store.lock().unwrap().png = Some(Bytes::from_static(b"old"));
+    let patch = |if_match: String| {
+        f.client
+            .patch(format!("/api/cards/{}/png", f.card_id))
+            .header(as_alice())
+            .header(ContentType::PNG)
+            .header(Header::new("If-Match", if_match))
+            .body("new")
+            .dispatch()
+    };
+
+    assert_eq!(
+        patch(content_etag(b"new")).status(),
+        Status::PreconditionFailed
+    );
+    let res = patch(content_etag(b"old"));
+    assert_eq!(res.status(), Status::NoContent);
+    assert_eq!(etag(&res), Some(version_etag("r", 1)));
+    assert_eq!(f.store.lock().unwrap().numbers(), vec![1]);
+}
+
+#[test]
+fn etag_without_image_matches_nothing() {
+    let f = fixture(&[], RevisionConfig::default());
+
+    let res = f
+        .client
+        .patch(format!("/api/cards/{}/png", f.card_id))
+        .header(as_alice())
+        .header(ContentType::PNG)
+        .header(Header::new("If-Match", "*"))
+        .body("new")
+        .dispatch();
+    assert_eq!(res.status(), Status::PreconditionFailed);
+    assert!(f.store.lock().unwrap().png.is_none());
+}
+
+#[test]
+fn restore_requires_current_etag() {
+    let f = fixture(&[b"one", b"two"], RevisionConfig::default());
+    let restore = |if_match: Option<String>| {
+        let mut req = f
+            .client
+            .post(format!("/api/cards/{}/png/revisions/1/restore", f.card_id))
+            .header(as_alice());
+        if let Some(if_match) = if_match {
+            req = req.header(Header::new("If-Match", if_match));
+        }
+        req.dispatch().status()
+    };
+
+    assert_eq!(restore(None), Status::PreconditionRequired);
+    assert_eq!(
+        restore(Some(version_etag("r", 1))),
+        Status::PreconditionFailed
+    );
+    let store = f.store.lock().unwrap();
+    assert_eq!(store.png.as_deref(), Some(&b"two"[..]));
+    assert_eq!(store.numbers(), vec![1, 2]);
+}
+
+#[test]
+fn patch_losing_the_reservation_writes_nothing() {
+    let f = fixture(&[b"one"], RevisionConfig::default());
+    // If-Matchを確かめた後に他の保存が版2を確保した
+    f.store.lock().unwrap().raced.push(2);
+
+    let res = f
+        .client
+        .patch(format!("/api/cards/{}/png", f.card_id))
+        .header(as_alice())
+        .header(ContentType::PNG)
+        .header(Header::new("If-Match", version_etag("r", 1)))
+        .body("two")
+        .dispatch();
+    assert_eq!(res.status(), Status::PreconditionFailed);
+
+    let store = f.store.lock().unwrap();
+    assert_eq!(store.png.as_deref(), Some(&b"one"[..]));
+    assert_eq!(store.numbers(), vec![1]);
+    assert!(!store.contents.contains_key(&2));
+}
+
+#[test]
+fn failed_write_releases_the_reservation() {
+    let f = fixture(&[b"one"], RevisionConfig::default());
+    f.store.lock().unwrap().failing = true;
+
+    let res = f
+        .client
+        .patch(format!("/api/cards/{}/png", f.card_id))
+        .header(as_alice())
+        .header(ContentType::PNG)
+        .header(Header::new("If-Match", version_etag("r", 1)))
+        .body("two")
+        .dispatch();
+    assert_eq!(res.status(), Status::InternalServerError);
+    assert_eq!(f.store.lock().unwrap().numbers(), vec![1]);
+}
@@ -0,0 +1,49 @@
+use chrono::{Duration, TimeZone, Utc};
+use domain::repository::ShareLinkModel;
+use handler::share::ShareConfig;
+use uuid::Uuid;
+
+fn link() -> ShareLinkModel {
+    let created_at = Utc.with_ymd_and_hms(2023, 12, 24, 0, 0, 0).unwrap();
+    ShareLinkModel {
+        id: Uuid::new_v4(),
+        card_id: Uuid::new_v4(),
+        created_by: Uuid::new_v4(),
+        created_at,
+        expires_at: created_at + Duration::days(1),
+    }
+}
+
+#[test]
+fn verifies_own_token_until_expiry() {
+    let config = ShareConfig::new("secret", "https://example.com/");
+    let link = link();
+    let token = config.sign(&link);
+    assert_eq!(config.verify(&token, link.created_at), Some(link.id));
+    assert_eq!(config.verify(&token, link.expires_at), None);
+}
+
+#[test]
+fn rejects_tampered_tokens() {
+    let config = ShareConfig::new("secret", "https://example.com");
+    let link = link();
+    let token = config.sign(&link);
+    let now = link.created_at;
+
+    let other = ShareConfig::new("other", "https://example.com");
+    assert_eq!(other.verify(&token, now), None);
+
+    // 期限を延ばしても署名が合わない
+    let (head, signature) = token.rsplit_once('.').unwrap();
+    let (id, expires) = head.split_once('.').unwrap();
+    let extended = format!(
+        "{}.{}.{}",
+        id,
+        expires.parse::<i64>().unwrap() + 1,
+        signature
+    );
+    assert_eq!(config.verify(&extended, now), None);
+
+    assert_eq!(config.verify("", now), None);
+    assert_eq!(config.verify(&format!("{}.", token), now), None);
+}
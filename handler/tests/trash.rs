@@ -0,0 +1,183 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{Duration, TimeZone, Utc};
+use domain::bot_client::{MockBotClient, User};
+use domain::repository::{
+    CardMemberModel, CardModel, CardRole, CardStatus, DateTimeUtc, MockCardRepository,
+};
+use handler::auth::AuthUserConfig;
+use handler::trash::{self, TrashConfig, TrashedCard};
+use handler::{BC, CR};
+use rocket::http::{Header, Status};
+use rocket::local::blocking::Client;
+use rocket::serde::json::{from_value, json, to_value};
+use uuid::Uuid;
+
+/// `update_card_status`で移した状態
+type Statuses = Arc<Mutex<Vec<(Vec<CardStatus>, CardStatus)>>>;
+
+/// ゴミ箱にある`alice`のカード
+struct Fixture {
+    client: Client,
+    card_id: Uuid,
+    /// `restore_card`に渡した日時
+    since: Arc<Mutex<Vec<DateTimeUtc>>>,
+    statuses: Statuses,
+}
+
+fn fixture(deleted_days_ago: i64, status: CardStatus, publish_date: DateTimeUtc) -> Fixture {
+    let alice = Uuid::new_v4();
+    let deleted_at = Utc::now() - Duration::days(deleted_days_ago);
+    let mut bot_client = MockBotClient::new();
+    bot_client.expect_get_users().returning(move |name| {
+        Ok(vec![User {
+            id: alice,
+            name: name.unwrap_or_default().to_string(),
+            ..Default::default()
+        }])
+    });
+    let card = CardModel {
+        id: Uuid::new_v4(),
+        owner_id: alice,
+        publish_date: Some(publish_date),
+        message: None,
+        status,
+        time_zone: "Asia/Tokyo".to_string(),
+        version: 1,
+        deleted_at: Some(deleted_at),
+    };
+    let card_id = card.id;
+    let since = Arc::<Mutex<Vec<DateTimeUtc>>>::default();
+    let statuses = Statuses::default();
+    let mut card_repo = MockCardRepository::new();
+    let c = card.clone();
+    card_repo
+        .expect_get_trashed_card_by_id()
+        .returning(move |_| Ok(Some(c.clone())));
+    let c = card.clone();
+    card_repo
+        .expect_get_card_by_id()
+        .returning(move |_| Ok(Some(c.clone())));
+    card_repo.expect_get_card_members().returning(move |_| {
+        Ok(vec![CardMemberModel {
+            card_id,
+            user_id: alice,
+            role: CardRole::Owner,
+        }])
+    });
+    let s = since.clone();
+    card_repo.expect_restore_card().returning(move |_, since| {
+        s.lock().unwrap().push(since);
+        Ok((deleted_at >= since).then_some(()))
+    });
+    let s = statuses.clone();
+    card_repo
+        .expect_update_card_status()
+        .returning(move |_, from, to| {
+            s.lock().unwrap().push((from.to_vec(), to));
+            Ok(Some(()))
+        });
+    card_repo
+        .expect_get_publish_channels_by_id()
+        .returning(|_| Ok(vec![]));
+    card_repo
+        .expect_get_recipients_by_id()
+        .returning(|_| Ok(vec![]));
+    card_repo
+        .expect_get_publish_groups_by_id()
+        .returning(|_| Ok(vec![]));
+    card_repo
+        .expect_get_delivery_logs_by_id()
+        .returning(|_| Ok(vec![]));
+    card_repo.expect_get_recurrence().returning(|_| Ok(None));
+    card_repo
+        .expect_get_contributions()
+        .returning(|_| Ok(vec![]));
+    card_repo.expect_save_audit_event().returning(|_| Ok(()));
+    let rocket = rocket::build()
+        .mount("/api/cards", rocket::routes![trash::restore])
+        .manage(AuthUserConfig(true))
+        .manage(TrashConfig::default())
+        .manage(BC::from(bot_client))
+        .manage(CR::from(card_repo));
+    Fixture {
+        client: Client::tracked(rocket).unwrap(),
+        card_id,
+        since,
+        statuses,
+    }
+}
+
+impl Fixture {
+    fn restore(&self) -> Status {
+        self.client
+            .post(format!("/api/cards/{}/restore", self.card_id))
+            .header(Header::new("X-Forwarded-User", "alice"))
+            .dispatch()
+            .status()
+    }
+}
+
+#[test]
+fn keeps_cards_for_thirty_days_by_default() {
+    assert_eq!(TrashConfig::default().retention, Duration::days(30));
+    let config = TrashConfig::default().retention(Duration::days(7));
+    assert_eq!(config.retention, Duration::days(7));
+}
+
+#[test]
+fn flattens_card_fields() {
+    let id = Uuid::new_v4();
+    let deleted_at = Utc.with_ymd_and_hms(2023, 12, 24, 0, 0, 0).unwrap();
+    let value = json!({
+        "id": id,
+        "owner_id": Uuid::new_v4(),
+        "publish_date": null,
+        "local_publish_date": null,
+        "time_zone": "Asia/Tokyo",
+        "status": "draft",
+        "publish_channels": [],
+        "recipients": [],
+        "publish_groups": [],
+        "group_members": [],
+        "contributors": [],
+        "members": [],
+        "message": "hello",
+        "recurrence": null,
+        "version": 1,
+        "deleted_at": deleted_at,
+        "purge_at": deleted_at + Duration::days(30),
+    });
+    let trashed: TrashedCard = from_value(value.clone()).unwrap();
+    assert_eq!(trashed.card.id, id);
+    assert_eq!(trashed.deleted_at, deleted_at);
+    assert_eq!(to_value(&trashed).unwrap(), value);
+}
+
+#[test]
+fn restores_only_within_retention() {
+    let tomorrow = Utc::now() + Duration::days(1);
+    let f = fixture(29, CardStatus::Draft, tomorrow);
+    assert_eq!(f.restore(), Status::Ok);
+    let since = f.since.lock().unwrap()[0];
+    let window = Utc::now() - since;
+    assert!(window >= Duration::days(30) && window < Duration::days(30) + Duration::minutes(1));
+
+    let f = fixture(31, CardStatus::Draft, tomorrow);
+    assert_eq!(f.restore(), Status::Gone);
+    assert!(f.statuses.lock().unwrap().is_empty());
+}
+
+#[test]
+fn restored_card_past_its_date_goes_back_to_draft() {
+    let f = fixture(1, CardStatus::Scheduled, Utc::now() - Duration::hours(1));
+    assert_eq!(f.restore(), Status::Ok);
+    assert_eq!(
+        *f.statuses.lock().unwrap(),
+        vec![(vec![CardStatus::Scheduled], CardStatus::Draft)]
+    );
+
+    let f = fixture(1, CardStatus::Scheduled, Utc::now() + Duration::hours(1));
+    assert_eq!(f.restore(), Status::Ok);
+    assert!(f.statuses.lock().unwrap().is_empty());
+}
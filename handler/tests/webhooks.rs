@@ -0,0 +1,80 @@
+use domain::bot_client::{MockBotClient, User};
+use domain::repository::{MockCardRepository, WebhookModel};
+use handler::auth::{AdminConfig, AuthUserConfig};
+use handler::{webhooks, BC, CR};
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::blocking::Client;
+use uuid::Uuid;
+
+/// `alice`だけが管理者
+fn client(card_repo: MockCardRepository) -> Client {
+    let mut bot_client = MockBotClient::new();
+    bot_client.expect_get_users().returning(|name| {
+        Ok(vec![User {
+            id: Uuid::new_v4(),
+            name: name.unwrap_or_default().to_string(),
+            ..Default::default()
+        }])
+    });
+    let rocket = rocket::build()
+        .mount("/api/webhooks", webhooks::routes())
+        .manage(AuthUserConfig(true))
+        .manage(AdminConfig::new(["alice"].into_iter()))
+        .manage(BC::from(bot_client))
+        .manage(CR::from(card_repo));
+    Client::tracked(rocket).unwrap()
+}
+
+fn as_user(name: &str) -> Header<'static> {
+    Header::new("X-Forwarded-User", name.to_string())
+}
+
+#[test]
+fn normal_user_is_forbidden() {
+    // 管理者でなければリポジトリには触れない
+    let client = client(MockCardRepository::new());
+    let channel_id = Uuid::new_v4();
+    let body = format!(r#"{{"webhook_id":"{}","secret":"secret"}}"#, Uuid::new_v4());
+
+    let res = client
+        .get("/api/webhooks")
+        .header(as_user("bob"))
+        .dispatch();
+    assert_eq!(res.status(), Status::Forbidden);
+    let res = client
+        .put(format!("/api/webhooks/{}", channel_id))
+        .header(as_user("bob"))
+        .header(ContentType::JSON)
+        .body(body)
+        .dispatch();
+    assert_eq!(res.status(), Status::Forbidden);
+    let res = client
+        .delete(format!("/api/webhooks/{}", channel_id))
+        .header(as_user("bob"))
+        .dispatch();
+    assert_eq!(res.status(), Status::Forbidden);
+}
+
+#[test]
+fn admin_lists_webhooks_without_secret() {
+    let channel_id = Uuid::new_v4();
+    let webhook_id = Uuid::new_v4();
+    let mut card_repo = MockCardRepository::new();
+    card_repo.expect_get_webhooks().returning(move || {
+        Ok(vec![WebhookModel {
+            channel_id,
+            webhook_id,
+            secret: "secret".to_string(),
+        }])
+    });
+    let client = client(card_repo);
+
+    let res = client
+        .get("/api/webhooks")
+        .header(as_user("alice"))
+        .dispatch();
+    assert_eq!(res.status(), Status::Ok);
+    let body = res.into_string().unwrap();
+    assert!(body.contains(&webhook_id.to_string()));
+    assert!(!body.contains("secret"));
+}
@@ -1,15 +1,22 @@
+use chrono::Utc;
 use sea_orm::{
+    sea_query::{Expr, OnConflict},
     ActiveValue, ColumnTrait, Condition, ConnectOptions, Database, DatabaseConnection, DbErr,
-    EntityTrait, QueryFilter, TransactionTrait,
+    EntityTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait, TransactionTrait,
+    TryInsertResult,
 };
 use sea_orm_migration::MigratorTrait;
 use std::env::{var, VarError};
 use uuid::Uuid;
 
 use domain::repository::{
-    CardModel, CardRepository, DateTimeUtc, MigrationStrategy, PublishChannelModel, SaveCardParams,
+    AssetModel, AuditEventModel, AuditQuery, CardMemberModel, CardModel, CardRepository, CardRole,
+    CardStatus, ContributionModel, DateTimeUtc, DeliveryLogModel, ImageKind, ImageRevisionModel,
+    MigrationStrategy, PatchCardParams, PublishChannelModel, RecurrenceModel, SaveCardParams,
+    ShareLinkModel, WebhookModel,
 };
 
+use crate::entity::card::Status;
 use crate::entity::prelude::*;
 use crate::error::RepositoryError;
 use crate::migration::Migrator;
@@ -85,6 +92,11 @@ impl CardRepository for CardRepositoryImpl {
             owner_id: ActiveValue::Set(params.owner_id),
             publish_date: ActiveValue::Set(params.publish_date),
             message: ActiveValue::Set(params.message.clone()),
+            status: ActiveValue::Set(params.status.into()),
+            time_zone: ActiveValue::Set(params.time_zone.clone()),
+            version: ActiveValue::Set(1),
+            deleted_at: ActiveValue::Set(None),
+            status_changed_at: ActiveValue::Set(Some(Utc::now())),
         };
         let channels = params
             .channels
@@ -94,36 +106,134 @@ impl CardRepository for CardRepositoryImpl {
                 card_id: ActiveValue::Set(card.id.clone().unwrap()),
             })
             .collect::<Vec<_>>();
+        let recipients = params
+            .recipients
+            .iter()
+            .map(|user_id| RecipientActiveModel {
+                id: ActiveValue::Set(*user_id),
+                card_id: ActiveValue::Set(card.id.clone().unwrap()),
+            })
+            .collect::<Vec<_>>();
+        let groups = params
+            .groups
+            .iter()
+            .map(|group_id| PublishGroupActiveModel {
+                id: ActiveValue::Set(*group_id),
+                card_id: ActiveValue::Set(card.id.clone().unwrap()),
+            })
+            .collect::<Vec<_>>();
+        let owner = CardMemberActiveModel {
+            card_id: ActiveValue::Set(params.id),
+            user_id: ActiveValue::Set(params.owner_id),
+            role: ActiveValue::Set(CardRole::Owner.into()),
+        };
         Card::insert(card).exec(&tx).await?;
+        CardMember::insert(owner).exec(&tx).await?;
         PublishChannel::insert_many(channels).exec(&tx).await?;
+        if !recipients.is_empty() {
+            Recipient::insert_many(recipients).exec(&tx).await?;
+        }
+        if !groups.is_empty() {
+            PublishGroup::insert_many(groups).exec(&tx).await?;
+        }
+        if let Some(recurrence) = &params.recurrence {
+            Recurrence::insert(recurrence_active_model(recurrence))
+                .exec(&tx)
+                .await?;
+        }
 
         tx.commit().await?;
         Ok(())
     }
 
-    async fn update_card(&self, params: &SaveCardParams) -> Result<Option<()>, RepositoryError> {
+    async fn patch_card(&self, params: &PatchCardParams) -> Result<Option<()>, RepositoryError> {
         let db = &self.0;
         let tx = db.begin().await?;
-        let card = CardActiveModel {
-            id: ActiveValue::Set(params.id),
-            owner_id: ActiveValue::Set(params.owner_id),
-            publish_date: ActiveValue::Set(params.publish_date),
-            message: ActiveValue::Set(params.message.clone()),
-        };
-        let result = Card::update(card)
-            .filter(CardColumn::Id.contains(params.id))
-            .exec(&tx)
-            .await;
-        match result {
-            Ok(_) => Ok(Some(())),
-            Err(DbErr::RecordNotFound(_)) => Ok(None),
-            Err(e) => Err(RepositoryError::DbErr(e)),
+        let mut update = Card::update_many()
+            .col_expr(CardColumn::Version, Expr::col(CardColumn::Version).add(1))
+            .filter(
+                Condition::all()
+                    .add(CardColumn::Id.eq(params.id))
+                    .add(CardColumn::Version.eq(params.version))
+                    .add(CardColumn::Status.is_in([Status::Draft, Status::Scheduled])),
+            );
+        if let Some(publish_date) = params.publish_date {
+            update = update.col_expr(CardColumn::PublishDate, Expr::value(publish_date));
+        }
+        if let Some(message) = &params.message {
+            update = update.col_expr(CardColumn::Message, Expr::value(message.clone()));
+        }
+        if let Some(time_zone) = &params.time_zone {
+            update = update.col_expr(CardColumn::TimeZone, Expr::value(time_zone.clone()));
+        }
+        let result = update.exec(&tx).await?;
+        if result.rows_affected == 0 {
+            return Ok(None);
+        }
+        if let Some(channels) = &params.channels {
+            PublishChannel::delete_many()
+                .filter(PublishChannelColumn::CardId.eq(params.id))
+                .exec(&tx)
+                .await?;
+            let channels = channels
+                .iter()
+                .map(|channel_id| PublishChannelActiveModel {
+                    id: ActiveValue::Set(*channel_id),
+                    card_id: ActiveValue::Set(params.id),
+                })
+                .collect::<Vec<_>>();
+            if !channels.is_empty() {
+                PublishChannel::insert_many(channels).exec(&tx).await?;
+            }
+        }
+        if let Some(recipients) = &params.recipients {
+            Recipient::delete_many()
+                .filter(RecipientColumn::CardId.eq(params.id))
+                .exec(&tx)
+                .await?;
+            let recipients = recipients
+                .iter()
+                .map(|user_id| RecipientActiveModel {
+                    id: ActiveValue::Set(*user_id),
+                    card_id: ActiveValue::Set(params.id),
+                })
+                .collect::<Vec<_>>();
+            if !recipients.is_empty() {
+                Recipient::insert_many(recipients).exec(&tx).await?;
+            }
+        }
+        if let Some(groups) = &params.groups {
+            PublishGroup::delete_many()
+                .filter(PublishGroupColumn::CardId.eq(params.id))
+                .exec(&tx)
+                .await?;
+            let groups = groups
+                .iter()
+                .map(|group_id| PublishGroupActiveModel {
+                    id: ActiveValue::Set(*group_id),
+                    card_id: ActiveValue::Set(params.id),
+                })
+                .collect::<Vec<_>>();
+            if !groups.is_empty() {
+                PublishGroup::insert_many(groups).exec(&tx).await?;
+            }
+        }
+        if let Some(recurrence) = &params.recurrence {
+            Recurrence::delete_by_id(params.id).exec(&tx).await?;
+            if let Some(recurrence) = recurrence {
+                Recurrence::insert(recurrence_active_model(recurrence))
+                    .exec(&tx)
+                    .await?;
+            }
         }
+        tx.commit().await?;
+        Ok(Some(()))
     }
 
     async fn get_all_cards(&self) -> Result<Vec<CardModel>, RepositoryError> {
         let db = &self.0;
         let cards = Card::find()
+            .filter(CardColumn::DeletedAt.is_null())
             .all(db)
             .await?
             .into_iter()
@@ -132,17 +242,17 @@ impl CardRepository for CardRepositoryImpl {
         Ok(cards)
     }
 
-    async fn get_card_with_channels_by_date(
+    async fn get_due_cards_with_channels(
         &self,
-        start: DateTimeUtc,
-        end: DateTimeUtc,
+        now: DateTimeUtc,
     ) -> Result<Vec<(CardModel, Vec<PublishChannelModel>)>, RepositoryError> {
         let db = &self.0;
         let cards = Card::find()
             .filter(
                 Condition::all()
-                    .add(CardColumn::PublishDate.gte(start))
-                    .add(CardColumn::PublishDate.lte(end)),
+                    .add(CardColumn::Status.eq(Status::Scheduled))
+                    .add(CardColumn::PublishDate.lte(now))
+                    .add(CardColumn::DeletedAt.is_null()),
             )
             .all(db)
             .await?
@@ -162,10 +272,85 @@ impl CardRepository for CardRepositoryImpl {
         let cards_with_channels = futures::future::join_all(cards_with_channels).await;
         Ok(cards_with_channels)
     }
+    async fn update_card_status(
+        &self,
+        card_id: Uuid,
+        from: &[CardStatus],
+        to: CardStatus,
+    ) -> Result<Option<()>, RepositoryError> {
+        let db = &self.0;
+        let from = from.iter().map(|s| Status::from(*s));
+        let result = Card::update_many()
+            .col_expr(CardColumn::Status, Expr::value(Status::from(to)))
+            .col_expr(CardColumn::StatusChangedAt, Expr::value(Some(Utc::now())))
+            .filter(
+                Condition::all()
+                    .add(CardColumn::Id.eq(card_id))
+                    .add(CardColumn::Status.is_in(from)),
+            )
+            .exec(db)
+            .await?;
+        Ok((result.rows_affected > 0).then_some(()))
+    }
+    async fn reschedule_card(
+        &self,
+        card_id: Uuid,
+        from: &[CardStatus],
+        publish_date: DateTimeUtc,
+    ) -> Result<Option<()>, RepositoryError> {
+        let db = &self.0;
+        let from = from.iter().map(|s| Status::from(*s));
+        let result = Card::update_many()
+            .col_expr(CardColumn::Status, Expr::value(Status::Scheduled))
+            .col_expr(CardColumn::StatusChangedAt, Expr::value(Some(Utc::now())))
+            .col_expr(CardColumn::PublishDate, Expr::value(publish_date))
+            .filter(
+                Condition::all()
+                    .add(CardColumn::Id.eq(card_id))
+                    .add(CardColumn::Status.is_in(from)),
+            )
+            .exec(db)
+            .await?;
+        Ok((result.rows_affected > 0).then_some(()))
+    }
+    async fn reschedule_stuck_card(
+        &self,
+        card_id: Uuid,
+        stuck_before: DateTimeUtc,
+        publish_date: DateTimeUtc,
+    ) -> Result<Option<()>, RepositoryError> {
+        let db = &self.0;
+        let result = Card::update_many()
+            .col_expr(CardColumn::Status, Expr::value(Status::Scheduled))
+            .col_expr(CardColumn::StatusChangedAt, Expr::value(Some(Utc::now())))
+            .col_expr(CardColumn::PublishDate, Expr::value(publish_date))
+            .filter(
+                Condition::all()
+                    .add(CardColumn::Id.eq(card_id))
+                    .add(CardColumn::Status.eq(Status::Delivering))
+                    // 列を足す前から配信中のものは止まっているとみなす
+                    .add(
+                        Condition::any()
+                            .add(CardColumn::StatusChangedAt.is_null())
+                            .add(CardColumn::StatusChangedAt.lt(stuck_before)),
+                    ),
+            )
+            .exec(db)
+            .await?;
+        Ok((result.rows_affected > 0).then_some(()))
+    }
     async fn get_my_cards(&self, user_id: Uuid) -> Result<Vec<CardModel>, RepositoryError> {
         let db = &self.0;
         let cards = Card::find()
-            .filter(CardColumn::OwnerId.eq(user_id))
+            .join(
+                sea_orm::JoinType::InnerJoin,
+                crate::entity::card::Relation::CardMember.def(),
+            )
+            .filter(
+                Condition::all()
+                    .add(CardMemberColumn::UserId.eq(user_id))
+                    .add(CardColumn::DeletedAt.is_null()),
+            )
             .all(db)
             .await?
             .into_iter()
@@ -176,6 +361,7 @@ impl CardRepository for CardRepositoryImpl {
     async fn get_card_by_id(&self, card_id: Uuid) -> Result<Option<CardModel>, RepositoryError> {
         let db = &self.0;
         let card = Card::find_by_id(card_id)
+            .filter(CardColumn::DeletedAt.is_null())
             .one(db)
             .await?
             .map(CardModel::from);
@@ -195,6 +381,56 @@ impl CardRepository for CardRepositoryImpl {
             .collect();
         Ok(pub_chans)
     }
+    async fn get_recipients_by_id(&self, card_id: Uuid) -> Result<Vec<Uuid>, RepositoryError> {
+        let db = &self.0;
+        let recipients = Recipient::find()
+            .filter(RecipientColumn::CardId.eq(card_id))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        Ok(recipients)
+    }
+    async fn get_publish_groups_by_id(&self, card_id: Uuid) -> Result<Vec<Uuid>, RepositoryError> {
+        let db = &self.0;
+        let groups = PublishGroup::find()
+            .filter(PublishGroupColumn::CardId.eq(card_id))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|g| g.id)
+            .collect();
+        Ok(groups)
+    }
+    async fn save_delivery_logs(&self, logs: &[DeliveryLogModel]) -> Result<(), RepositoryError> {
+        if logs.is_empty() {
+            return Ok(());
+        }
+        let db = &self.0;
+        let logs = logs.iter().map(|log| DeliveryLogActiveModel {
+            card_id: ActiveValue::Set(log.card_id),
+            group_id: ActiveValue::Set(log.group_id),
+            user_id: ActiveValue::Set(log.user_id),
+            delivered_at: ActiveValue::Set(log.delivered_at),
+        });
+        DeliveryLog::insert_many(logs).exec(db).await?;
+        Ok(())
+    }
+    async fn get_delivery_logs_by_id(
+        &self,
+        card_id: Uuid,
+    ) -> Result<Vec<DeliveryLogModel>, RepositoryError> {
+        let db = &self.0;
+        let logs = DeliveryLog::find()
+            .filter(DeliveryLogColumn::CardId.eq(card_id))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(DeliveryLogModel::from)
+            .collect();
+        Ok(logs)
+    }
     async fn delete_publish_channel(
         &self,
         card_id: Uuid,
@@ -210,13 +446,631 @@ impl CardRepository for CardRepositoryImpl {
             Err(e) => Err(RepositoryError::DbErr(e)),
         }
     }
-    async fn delete_card(&self, card_id: Uuid) -> Result<Option<()>, RepositoryError> {
+    async fn delete_recipient(
+        &self,
+        card_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<()>, Self::Error> {
+        let db = &self.0;
+        let result = Recipient::delete_by_id((user_id, card_id)).exec(db).await;
+        match result {
+            Ok(_) => Ok(Some(())),
+            Err(DbErr::RecordNotFound(_)) => Ok(None),
+            Err(e) => Err(RepositoryError::DbErr(e)),
+        }
+    }
+    async fn get_recurrence(
+        &self,
+        card_id: Uuid,
+    ) -> Result<Option<RecurrenceModel>, RepositoryError> {
+        let db = &self.0;
+        let recurrence = Recurrence::find_by_id(card_id)
+            .one(db)
+            .await?
+            .map(RecurrenceModel::from);
+        Ok(recurrence)
+    }
+    async fn update_last_occurrence(
+        &self,
+        card_id: Uuid,
+        at: DateTimeUtc,
+    ) -> Result<Option<()>, RepositoryError> {
+        let db = &self.0;
+        let result = Recurrence::update_many()
+            .col_expr(RecurrenceColumn::LastOccurrence, Expr::value(at))
+            .filter(RecurrenceColumn::CardId.eq(card_id))
+            .exec(db)
+            .await?;
+        Ok((result.rows_affected > 0).then_some(()))
+    }
+    async fn delete_recurrence(&self, card_id: Uuid) -> Result<Option<()>, RepositoryError> {
+        let db = &self.0;
+        let tx = db.begin().await?;
+        SkippedOccurrence::delete_many()
+            .filter(SkippedOccurrenceColumn::CardId.eq(card_id))
+            .exec(&tx)
+            .await?;
+        let result = Recurrence::delete_by_id(card_id).exec(&tx).await?;
+        tx.commit().await?;
+        Ok((result.rows_affected > 0).then_some(()))
+    }
+    async fn get_skipped_occurrences(
+        &self,
+        card_id: Uuid,
+    ) -> Result<Vec<DateTimeUtc>, RepositoryError> {
+        let db = &self.0;
+        let skipped = SkippedOccurrence::find()
+            .filter(SkippedOccurrenceColumn::CardId.eq(card_id))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|s| s.occurrence)
+            .collect();
+        Ok(skipped)
+    }
+    async fn save_skipped_occurrence(
+        &self,
+        card_id: Uuid,
+        at: DateTimeUtc,
+    ) -> Result<(), RepositoryError> {
+        let db = &self.0;
+        let model = SkippedOccurrenceActiveModel {
+            card_id: ActiveValue::Set(card_id),
+            occurrence: ActiveValue::Set(at),
+        };
+        SkippedOccurrence::insert(model)
+            .on_conflict(
+                OnConflict::columns([
+                    SkippedOccurrenceColumn::CardId,
+                    SkippedOccurrenceColumn::Occurrence,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .do_nothing()
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+    async fn delete_skipped_occurrence(
+        &self,
+        card_id: Uuid,
+        at: DateTimeUtc,
+    ) -> Result<Option<()>, RepositoryError> {
+        let db = &self.0;
+        let result = SkippedOccurrence::delete_by_id((card_id, at))
+            .exec(db)
+            .await?;
+        Ok((result.rows_affected > 0).then_some(()))
+    }
+    async fn get_contributions(
+        &self,
+        card_id: Uuid,
+    ) -> Result<Vec<ContributionModel>, RepositoryError> {
+        let db = &self.0;
+        let contributions = Contribution::find()
+            .filter(ContributionColumn::CardId.eq(card_id))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(ContributionModel::from)
+            .collect();
+        Ok(contributions)
+    }
+    async fn get_contributions_by_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<ContributionModel>, RepositoryError> {
+        let db = &self.0;
+        let contributions = Contribution::find()
+            .filter(ContributionColumn::UserId.eq(user_id))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(ContributionModel::from)
+            .collect();
+        Ok(contributions)
+    }
+    async fn save_contribution(
+        &self,
+        contribution: &ContributionModel,
+    ) -> Result<(), RepositoryError> {
+        let db = &self.0;
+        let model = ContributionActiveModel {
+            card_id: ActiveValue::Set(contribution.card_id),
+            user_id: ActiveValue::Set(contribution.user_id),
+            message: ActiveValue::Set(contribution.message.clone()),
+            layer_id: ActiveValue::Set(contribution.layer_id),
+            contributed_at: ActiveValue::Set(contribution.contributed_at),
+        };
+        Contribution::insert(model)
+            .on_conflict(
+                OnConflict::columns([ContributionColumn::CardId, ContributionColumn::UserId])
+                    .update_columns([
+                        ContributionColumn::Message,
+                        ContributionColumn::LayerId,
+                        ContributionColumn::ContributedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+    async fn delete_contribution(
+        &self,
+        card_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<()>, RepositoryError> {
+        let db = &self.0;
+        let result = Contribution::delete_by_id((card_id, user_id))
+            .exec(db)
+            .await?;
+        Ok((result.rows_affected > 0).then_some(()))
+    }
+    async fn get_card_members(
+        &self,
+        card_id: Uuid,
+    ) -> Result<Vec<CardMemberModel>, RepositoryError> {
+        let db = &self.0;
+        let members = CardMember::find()
+            .filter(CardMemberColumn::CardId.eq(card_id))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(CardMemberModel::from)
+            .collect();
+        Ok(members)
+    }
+    async fn save_card_member(&self, member: &CardMemberModel) -> Result<(), RepositoryError> {
+        let db = &self.0;
+        let model = CardMemberActiveModel {
+            card_id: ActiveValue::Set(member.card_id),
+            user_id: ActiveValue::Set(member.user_id),
+            role: ActiveValue::Set(member.role.into()),
+        };
+        CardMember::insert(model)
+            .on_conflict(
+                OnConflict::columns([CardMemberColumn::CardId, CardMemberColumn::UserId])
+                    .update_column(CardMemberColumn::Role)
+                    .to_owned(),
+            )
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+    async fn delete_card_member(
+        &self,
+        card_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<()>, RepositoryError> {
+        let db = &self.0;
+        let result = CardMember::delete_by_id((card_id, user_id))
+            .exec(db)
+            .await?;
+        Ok((result.rows_affected > 0).then_some(()))
+    }
+    async fn transfer_card_ownership(
+        &self,
+        card_id: Uuid,
+        from: Uuid,
+        to: Uuid,
+    ) -> Result<Option<()>, RepositoryError> {
+        let db = &self.0;
+        let tx = db.begin().await?;
+        let result = Card::update_many()
+            .col_expr(CardColumn::OwnerId, Expr::value(to))
+            .filter(
+                Condition::all()
+                    .add(CardColumn::Id.eq(card_id))
+                    .add(CardColumn::OwnerId.eq(from)),
+            )
+            .exec(&tx)
+            .await?;
+        if result.rows_affected == 0 {
+            return Ok(None);
+        }
+        for (user_id, role) in [(to, CardRole::Owner), (from, CardRole::Editor)] {
+            let model = CardMemberActiveModel {
+                card_id: ActiveValue::Set(card_id),
+                user_id: ActiveValue::Set(user_id),
+                role: ActiveValue::Set(role.into()),
+            };
+            CardMember::insert(model)
+                .on_conflict(
+                    OnConflict::columns([CardMemberColumn::CardId, CardMemberColumn::UserId])
+                        .update_column(CardMemberColumn::Role)
+                        .to_owned(),
+                )
+                .exec(&tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(Some(()))
+    }
+    async fn get_share_links(&self, card_id: Uuid) -> Result<Vec<ShareLinkModel>, RepositoryError> {
+        let db = &self.0;
+        let links = ShareLink::find()
+            .filter(ShareLinkColumn::CardId.eq(card_id))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(ShareLinkModel::from)
+            .collect();
+        Ok(links)
+    }
+    async fn get_share_link(&self, id: Uuid) -> Result<Option<ShareLinkModel>, RepositoryError> {
+        let db = &self.0;
+        let link = ShareLink::find_by_id(id)
+            .one(db)
+            .await?
+            .map(ShareLinkModel::from);
+        Ok(link)
+    }
+    async fn save_share_link(&self, link: &ShareLinkModel) -> Result<(), RepositoryError> {
+        let db = &self.0;
+        let model = ShareLinkActiveModel {
+            id: ActiveValue::Set(link.id),
+            card_id: ActiveValue::Set(link.card_id),
+            created_by: ActiveValue::Set(link.created_by),
+            created_at: ActiveValue::Set(link.created_at),
+            expires_at: ActiveValue::Set(link.expires_at),
+        };
+        ShareLink::insert(model).exec(db).await?;
+        Ok(())
+    }
+    async fn delete_share_link(&self, id: Uuid) -> Result<Option<()>, RepositoryError> {
+        let db = &self.0;
+        let result = ShareLink::delete_by_id(id).exec(db).await?;
+        Ok((result.rows_affected > 0).then_some(()))
+    }
+    async fn get_image_revisions(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+    ) -> Result<Vec<ImageRevisionModel>, RepositoryError> {
+        let db = &self.0;
+        let kind: crate::entity::image_revision::Kind = kind.into();
+        let revisions = ImageRevision::find()
+            .filter(
+                Condition::all()
+                    .add(ImageRevisionColumn::CardId.eq(card_id))
+                    .add(ImageRevisionColumn::Kind.eq(kind)),
+            )
+            .order_by_asc(ImageRevisionColumn::Revision)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(ImageRevisionModel::from)
+            .collect();
+        Ok(revisions)
+    }
+    async fn get_image_revision(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+        revision: u32,
+    ) -> Result<Option<ImageRevisionModel>, RepositoryError> {
+        let db = &self.0;
+        let revision = ImageRevision::find_by_id((card_id, kind.into(), revision))
+            .one(db)
+            .await?
+            .map(ImageRevisionModel::from);
+        Ok(revision)
+    }
+    async fn save_image_revision(
+        &self,
+        revision: &ImageRevisionModel,
+    ) -> Result<Option<()>, RepositoryError> {
+        let db = &self.0;
+        let model = ImageRevisionActiveModel {
+            card_id: ActiveValue::Set(revision.card_id),
+            kind: ActiveValue::Set(revision.kind.into()),
+            revision: ActiveValue::Set(revision.revision),
+            author_id: ActiveValue::Set(revision.author_id),
+            created_at: ActiveValue::Set(revision.created_at),
+            size: ActiveValue::Set(revision.size),
+            hash: ActiveValue::Set(revision.hash.clone()),
+        };
+        let result = ImageRevision::insert(model)
+            .on_conflict(
+                OnConflict::columns([
+                    ImageRevisionColumn::CardId,
+                    ImageRevisionColumn::Kind,
+                    ImageRevisionColumn::Revision,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .do_nothing()
+            .exec(db)
+            .await?;
+        Ok(matches!(result, TryInsertResult::Inserted(_)).then_some(()))
+    }
+    async fn delete_image_revision(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+        revision: u32,
+    ) -> Result<Option<()>, RepositoryError> {
+        let db = &self.0;
+        let result = ImageRevision::delete_by_id((card_id, kind.into(), revision))
+            .exec(db)
+            .await?;
+        Ok((result.rows_affected > 0).then_some(()))
+    }
+    async fn save_audit_event(&self, event: &AuditEventModel) -> Result<(), RepositoryError> {
+        let db = &self.0;
+        let model = AuditEventActiveModel {
+            id: ActiveValue::Set(event.id),
+            at: ActiveValue::Set(event.at),
+            actor_id: ActiveValue::Set(event.actor_id),
+            action: ActiveValue::Set(event.action.into()),
+            card_id: ActiveValue::Set(event.card_id),
+            asset_id: ActiveValue::Set(event.asset_id),
+            before: ActiveValue::Set(event.before.clone()),
+            after: ActiveValue::Set(event.after.clone()),
+            request_id: ActiveValue::Set(event.request_id.clone()),
+        };
+        AuditEvent::insert(model).exec(db).await?;
+        Ok(())
+    }
+    async fn get_audit_events(
+        &self,
+        query: &AuditQuery,
+    ) -> Result<Vec<AuditEventModel>, RepositoryError> {
+        let db = &self.0;
+        let mut condition = Condition::all();
+        if let Some(card_id) = query.card_id {
+            condition = condition.add(AuditEventColumn::CardId.eq(card_id));
+        }
+        if let Some(actor_id) = query.actor_id {
+            condition = condition.add(AuditEventColumn::ActorId.eq(actor_id));
+        }
+        if let Some(action) = query.action {
+            condition = condition
+                .add(AuditEventColumn::Action.eq(crate::entity::audit_event::Action::from(action)));
+        }
+        if let Some(since) = query.since {
+            condition = condition.add(AuditEventColumn::At.gte(since));
+        }
+        if let Some(until) = query.until {
+            condition = condition.add(AuditEventColumn::At.lt(until));
+        }
+        let events = AuditEvent::find()
+            .filter(condition)
+            .order_by_desc(AuditEventColumn::At)
+            .limit(query.limit)
+            .offset(query.offset)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(AuditEventModel::from)
+            .collect();
+        Ok(events)
+    }
+    async fn delete_audit_events_before(
+        &self,
+        before: DateTimeUtc,
+    ) -> Result<u64, RepositoryError> {
+        let db = &self.0;
+        let result = AuditEvent::delete_many()
+            .filter(AuditEventColumn::At.lt(before))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+    async fn save_asset(&self, asset: &AssetModel) -> Result<Option<()>, RepositoryError> {
+        let db = &self.0;
+        let model = AssetActiveModel {
+            id: ActiveValue::Set(asset.id),
+            uploaded_by: ActiveValue::Set(asset.uploaded_by),
+            created_at: ActiveValue::Set(asset.created_at),
+        };
+        let result = Asset::insert(model)
+            .on_conflict(OnConflict::column(AssetColumn::Id).do_nothing().to_owned())
+            .do_nothing()
+            .exec(db)
+            .await?;
+        Ok(matches!(result, TryInsertResult::Inserted(_)).then_some(()))
+    }
+    async fn get_asset(&self, id: Uuid) -> Result<Option<AssetModel>, RepositoryError> {
+        let db = &self.0;
+        let asset = Asset::find_by_id(id).one(db).await?.map(AssetModel::from);
+        Ok(asset)
+    }
+    async fn get_webhooks(&self) -> Result<Vec<WebhookModel>, RepositoryError> {
+        let db = &self.0;
+        let webhooks = Webhook::find()
+            .all(db)
+            .await?
+            .into_iter()
+            .map(WebhookModel::from)
+            .collect();
+        Ok(webhooks)
+    }
+    async fn get_webhook(&self, channel_id: Uuid) -> Result<Option<WebhookModel>, RepositoryError> {
+        let db = &self.0;
+        let webhook = Webhook::find_by_id(channel_id)
+            .one(db)
+            .await?
+            .map(WebhookModel::from);
+        Ok(webhook)
+    }
+    async fn save_webhook(&self, webhook: &WebhookModel) -> Result<(), RepositoryError> {
+        let db = &self.0;
+        let model = WebhookActiveModel {
+            channel_id: ActiveValue::Set(webhook.channel_id),
+            webhook_id: ActiveValue::Set(webhook.webhook_id),
+            secret: ActiveValue::Set(webhook.secret.clone()),
+        };
+        Webhook::insert(model)
+            .on_conflict(
+                OnConflict::column(WebhookColumn::ChannelId)
+                    .update_columns([WebhookColumn::WebhookId, WebhookColumn::Secret])
+                    .to_owned(),
+            )
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+    async fn delete_webhook(&self, channel_id: Uuid) -> Result<Option<()>, RepositoryError> {
         let db = &self.0;
-        let result = Card::delete_by_id(card_id).exec(db).await;
+        let result = Webhook::delete_by_id(channel_id).exec(db).await?;
+        Ok((result.rows_affected > 0).then_some(()))
+    }
+    async fn delete_publish_group(
+        &self,
+        card_id: Uuid,
+        group_id: Uuid,
+    ) -> Result<Option<()>, Self::Error> {
+        let db = &self.0;
+        let result = PublishGroup::delete_by_id((group_id, card_id))
+            .exec(db)
+            .await;
         match result {
             Ok(_) => Ok(Some(())),
             Err(DbErr::RecordNotFound(_)) => Ok(None),
             Err(e) => Err(RepositoryError::DbErr(e)),
         }
     }
+    async fn trash_card(
+        &self,
+        card_id: Uuid,
+        at: DateTimeUtc,
+    ) -> Result<Option<()>, RepositoryError> {
+        let db = &self.0;
+        let result = Card::update_many()
+            .col_expr(CardColumn::DeletedAt, Expr::value(Some(at)))
+            .filter(
+                Condition::all()
+                    .add(CardColumn::Id.eq(card_id))
+                    .add(CardColumn::DeletedAt.is_null())
+                    .add(CardColumn::Status.is_in([Status::Draft, Status::Scheduled])),
+            )
+            .exec(db)
+            .await?;
+        Ok((result.rows_affected > 0).then_some(()))
+    }
+    async fn restore_card(
+        &self,
+        card_id: Uuid,
+        since: DateTimeUtc,
+    ) -> Result<Option<()>, RepositoryError> {
+        let db = &self.0;
+        let result = Card::update_many()
+            .col_expr(CardColumn::DeletedAt, Expr::value(None::<DateTimeUtc>))
+            .filter(
+                Condition::all()
+                    .add(CardColumn::Id.eq(card_id))
+                    .add(CardColumn::DeletedAt.gte(since)),
+            )
+            .exec(db)
+            .await?;
+        Ok((result.rows_affected > 0).then_some(()))
+    }
+    async fn get_trashed_cards(&self, user_id: Uuid) -> Result<Vec<CardModel>, RepositoryError> {
+        let db = &self.0;
+        let cards = Card::find()
+            .join(
+                sea_orm::JoinType::InnerJoin,
+                crate::entity::card::Relation::CardMember.def(),
+            )
+            .filter(
+                Condition::all()
+                    .add(CardMemberColumn::UserId.eq(user_id))
+                    .add(
+                        CardMemberColumn::Role
+                            .eq(crate::entity::card_member::Role::from(CardRole::Owner)),
+                    )
+                    .add(CardColumn::DeletedAt.is_not_null()),
+            )
+            .order_by_desc(CardColumn::DeletedAt)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(CardModel::from)
+            .collect();
+        Ok(cards)
+    }
+    async fn get_trashed_card_by_id(
+        &self,
+        card_id: Uuid,
+    ) -> Result<Option<CardModel>, RepositoryError> {
+        let db = &self.0;
+        let card = Card::find_by_id(card_id)
+            .filter(CardColumn::DeletedAt.is_not_null())
+            .one(db)
+            .await?
+            .map(CardModel::from);
+        Ok(card)
+    }
+    async fn get_cards_trashed_before(
+        &self,
+        before: DateTimeUtc,
+    ) -> Result<Vec<CardModel>, RepositoryError> {
+        let db = &self.0;
+        let cards = Card::find()
+            .filter(CardColumn::DeletedAt.lt(before))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(CardModel::from)
+            .collect();
+        Ok(cards)
+    }
+    async fn delete_card(&self, card_id: Uuid) -> Result<Option<()>, RepositoryError> {
+        let db = &self.0;
+        let tx = db.begin().await?;
+        PublishChannel::delete_many()
+            .filter(PublishChannelColumn::CardId.eq(card_id))
+            .exec(&tx)
+            .await?;
+        Recipient::delete_many()
+            .filter(RecipientColumn::CardId.eq(card_id))
+            .exec(&tx)
+            .await?;
+        PublishGroup::delete_many()
+            .filter(PublishGroupColumn::CardId.eq(card_id))
+            .exec(&tx)
+            .await?;
+        DeliveryLog::delete_many()
+            .filter(DeliveryLogColumn::CardId.eq(card_id))
+            .exec(&tx)
+            .await?;
+        SkippedOccurrence::delete_many()
+            .filter(SkippedOccurrenceColumn::CardId.eq(card_id))
+            .exec(&tx)
+            .await?;
+        Recurrence::delete_by_id(card_id).exec(&tx).await?;
+        Contribution::delete_many()
+            .filter(ContributionColumn::CardId.eq(card_id))
+            .exec(&tx)
+            .await?;
+        CardMember::delete_many()
+            .filter(CardMemberColumn::CardId.eq(card_id))
+            .exec(&tx)
+            .await?;
+        ShareLink::delete_many()
+            .filter(ShareLinkColumn::CardId.eq(card_id))
+            .exec(&tx)
+            .await?;
+        ImageRevision::delete_many()
+            .filter(ImageRevisionColumn::CardId.eq(card_id))
+            .exec(&tx)
+            .await?;
+        let result = Card::delete_by_id(card_id).exec(&tx).await?;
+        tx.commit().await?;
+        Ok((result.rows_affected > 0).then_some(()))
+    }
+}
+
+fn recurrence_active_model(recurrence: &RecurrenceModel) -> RecurrenceActiveModel {
+    RecurrenceActiveModel {
+        card_id: ActiveValue::Set(recurrence.card_id),
+        rrule: ActiveValue::Set(recurrence.rrule.clone()),
+        time_zone: ActiveValue::Set(recurrence.time_zone.clone()),
+        dtstart: ActiveValue::Set(recurrence.dtstart),
+        last_occurrence: ActiveValue::Set(recurrence.last_occurrence),
+    }
 }
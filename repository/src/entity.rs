@@ -1,3 +1,15 @@
+pub mod asset;
+pub mod audit_event;
 pub mod card;
+pub mod card_member;
+pub mod contribution;
+pub mod delivery_log;
+pub mod image_revision;
 pub mod prelude;
 pub mod publish_channel;
+pub mod publish_group;
+pub mod recipient;
+pub mod recurrence;
+pub mod share_link;
+pub mod skipped_occurrence;
+pub mod webhook;
@@ -0,0 +1,47 @@
+use domain::repository::AssetModel;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "asset")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub uploaded_by: Uuid,
+    pub created_at: DateTimeUtc,
+}
+
+impl From<AssetModel> for Model {
+    fn from(value: AssetModel) -> Self {
+        let AssetModel {
+            id,
+            uploaded_by,
+            created_at,
+        } = value;
+        Self {
+            id,
+            uploaded_by,
+            created_at,
+        }
+    }
+}
+
+impl From<Model> for AssetModel {
+    fn from(value: Model) -> Self {
+        let Model {
+            id,
+            uploaded_by,
+            created_at,
+        } = value;
+        Self {
+            id,
+            uploaded_by,
+            created_at,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,156 @@
+use domain::repository::{AuditAction, AuditEventModel};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "audit_event")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub at: DateTimeUtc,
+    pub actor_id: Option<Uuid>,
+    pub action: Action,
+    pub card_id: Option<Uuid>,
+    pub asset_id: Option<Uuid>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub before: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub after: Option<String>,
+    pub request_id: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(32))")]
+pub enum Action {
+    #[sea_orm(string_value = "card.create")]
+    CardCreate,
+    #[sea_orm(string_value = "card.update")]
+    CardUpdate,
+    #[sea_orm(string_value = "card.delete")]
+    CardDelete,
+    #[sea_orm(string_value = "card.restore")]
+    CardRestore,
+    #[sea_orm(string_value = "card.purge")]
+    CardPurge,
+    #[sea_orm(string_value = "card.schedule")]
+    CardSchedule,
+    #[sea_orm(string_value = "card.unschedule")]
+    CardUnschedule,
+    #[sea_orm(string_value = "card.svg")]
+    CardSvg,
+    #[sea_orm(string_value = "card.png")]
+    CardPng,
+    #[sea_orm(string_value = "card.deliver")]
+    CardDeliver,
+    #[sea_orm(string_value = "card.cancel")]
+    CardCancel,
+    #[sea_orm(string_value = "card.reassign")]
+    CardReassign,
+    #[sea_orm(string_value = "image.upload")]
+    ImageUpload,
+    #[sea_orm(string_value = "card.transfer")]
+    CardTransfer,
+    #[sea_orm(string_value = "member.update")]
+    MemberUpdate,
+    #[sea_orm(string_value = "member.remove")]
+    MemberRemove,
+    #[sea_orm(string_value = "contribution.invite")]
+    ContributionInvite,
+    #[sea_orm(string_value = "contribution.remove")]
+    ContributionRemove,
+    #[sea_orm(string_value = "contribution.update")]
+    ContributionUpdate,
+    #[sea_orm(string_value = "share.create")]
+    ShareCreate,
+    #[sea_orm(string_value = "share.revoke")]
+    ShareRevoke,
+}
+
+impl From<AuditAction> for Action {
+    fn from(value: AuditAction) -> Self {
+        match value {
+            AuditAction::CardCreate => Self::CardCreate,
+            AuditAction::CardUpdate => Self::CardUpdate,
+            AuditAction::CardDelete => Self::CardDelete,
+            AuditAction::CardRestore => Self::CardRestore,
+            AuditAction::CardPurge => Self::CardPurge,
+            AuditAction::CardSchedule => Self::CardSchedule,
+            AuditAction::CardUnschedule => Self::CardUnschedule,
+            AuditAction::CardSvg => Self::CardSvg,
+            AuditAction::CardPng => Self::CardPng,
+            AuditAction::CardDeliver => Self::CardDeliver,
+            AuditAction::CardCancel => Self::CardCancel,
+            AuditAction::CardReassign => Self::CardReassign,
+            AuditAction::ImageUpload => Self::ImageUpload,
+            AuditAction::CardTransfer => Self::CardTransfer,
+            AuditAction::MemberUpdate => Self::MemberUpdate,
+            AuditAction::MemberRemove => Self::MemberRemove,
+            AuditAction::ContributionInvite => Self::ContributionInvite,
+            AuditAction::ContributionRemove => Self::ContributionRemove,
+            AuditAction::ContributionUpdate => Self::ContributionUpdate,
+            AuditAction::ShareCreate => Self::ShareCreate,
+            AuditAction::ShareRevoke => Self::ShareRevoke,
+        }
+    }
+}
+
+impl From<Action> for AuditAction {
+    fn from(value: Action) -> Self {
+        match value {
+            Action::CardCreate => Self::CardCreate,
+            Action::CardUpdate => Self::CardUpdate,
+            Action::CardDelete => Self::CardDelete,
+            Action::CardRestore => Self::CardRestore,
+            Action::CardPurge => Self::CardPurge,
+            Action::CardSchedule => Self::CardSchedule,
+            Action::CardUnschedule => Self::CardUnschedule,
+            Action::CardSvg => Self::CardSvg,
+            Action::CardPng => Self::CardPng,
+            Action::CardDeliver => Self::CardDeliver,
+            Action::CardCancel => Self::CardCancel,
+            Action::CardReassign => Self::CardReassign,
+            Action::ImageUpload => Self::ImageUpload,
+            Action::CardTransfer => Self::CardTransfer,
+            Action::MemberUpdate => Self::MemberUpdate,
+            Action::MemberRemove => Self::MemberRemove,
+            Action::ContributionInvite => Self::ContributionInvite,
+            Action::ContributionRemove => Self::ContributionRemove,
+            Action::ContributionUpdate => Self::ContributionUpdate,
+            Action::ShareCreate => Self::ShareCreate,
+            Action::ShareRevoke => Self::ShareRevoke,
+        }
+    }
+}
+
+impl From<Model> for AuditEventModel {
+    fn from(value: Model) -> Self {
+        let Model {
+            id,
+            at,
+            actor_id,
+            action,
+            card_id,
+            asset_id,
+            before,
+            after,
+            request_id,
+        } = value;
+        Self {
+            id,
+            at,
+            actor_id,
+            action: action.into(),
+            card_id,
+            asset_id,
+            before,
+            after,
+            request_id,
+        }
+    }
+}
+
+/// カードが消えても記録は残すので、リレーションは持たない
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,7 +1,7 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use domain::repository::CardModel;
+use domain::repository::{CardModel, CardStatus};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "card")]
@@ -9,8 +9,56 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: Uuid,
     pub owner_id: Uuid,
-    pub publish_date: DateTimeUtc,
+    pub publish_date: Option<DateTimeUtc>,
     pub message: Option<String>,
+    pub status: Status,
+    pub time_zone: String,
+    pub version: u32,
+    pub deleted_at: Option<DateTimeUtc>,
+    pub status_changed_at: Option<DateTimeUtc>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+pub enum Status {
+    #[sea_orm(string_value = "draft")]
+    Draft,
+    #[sea_orm(string_value = "scheduled")]
+    Scheduled,
+    #[sea_orm(string_value = "delivering")]
+    Delivering,
+    #[sea_orm(string_value = "delivered")]
+    Delivered,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled,
+}
+
+impl From<CardStatus> for Status {
+    fn from(value: CardStatus) -> Self {
+        match value {
+            CardStatus::Draft => Self::Draft,
+            CardStatus::Scheduled => Self::Scheduled,
+            CardStatus::Delivering => Self::Delivering,
+            CardStatus::Delivered => Self::Delivered,
+            CardStatus::Failed => Self::Failed,
+            CardStatus::Cancelled => Self::Cancelled,
+        }
+    }
+}
+
+impl From<Status> for CardStatus {
+    fn from(value: Status) -> Self {
+        match value {
+            Status::Draft => Self::Draft,
+            Status::Scheduled => Self::Scheduled,
+            Status::Delivering => Self::Delivering,
+            Status::Delivered => Self::Delivered,
+            Status::Failed => Self::Failed,
+            Status::Cancelled => Self::Cancelled,
+        }
+    }
 }
 
 impl From<CardModel> for Model {
@@ -20,12 +68,21 @@ impl From<CardModel> for Model {
             owner_id,
             publish_date,
             message,
+            status,
+            time_zone,
+            version,
+            deleted_at,
         } = value;
         Self {
             id,
             owner_id,
             publish_date,
             message,
+            status: status.into(),
+            time_zone,
+            version,
+            deleted_at,
+            status_changed_at: None,
         }
     }
 }
@@ -37,12 +94,21 @@ impl From<Model> for CardModel {
             owner_id,
             publish_date,
             message,
+            status,
+            time_zone,
+            version,
+            deleted_at,
+            status_changed_at: _,
         } = value;
         Self {
             id,
             owner_id,
             publish_date,
             message,
+            status: status.into(),
+            time_zone,
+            version,
+            deleted_at,
         }
     }
 }
@@ -51,6 +117,24 @@ impl From<Model> for CardModel {
 pub enum Relation {
     #[sea_orm(has_many = "super::publish_channel::Entity")]
     PublishChannel,
+    #[sea_orm(has_many = "super::recipient::Entity")]
+    Recipient,
+    #[sea_orm(has_many = "super::publish_group::Entity")]
+    PublishGroup,
+    #[sea_orm(has_many = "super::delivery_log::Entity")]
+    DeliveryLog,
+    #[sea_orm(has_one = "super::recurrence::Entity")]
+    Recurrence,
+    #[sea_orm(has_many = "super::skipped_occurrence::Entity")]
+    SkippedOccurrence,
+    #[sea_orm(has_many = "super::contribution::Entity")]
+    Contribution,
+    #[sea_orm(has_many = "super::card_member::Entity")]
+    CardMember,
+    #[sea_orm(has_many = "super::share_link::Entity")]
+    ShareLink,
+    #[sea_orm(has_many = "super::image_revision::Entity")]
+    ImageRevision,
 }
 
 impl Related<super::publish_channel::Entity> for Entity {
@@ -59,4 +143,58 @@ impl Related<super::publish_channel::Entity> for Entity {
     }
 }
 
+impl Related<super::recipient::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Recipient.def()
+    }
+}
+
+impl Related<super::publish_group::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PublishGroup.def()
+    }
+}
+
+impl Related<super::delivery_log::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DeliveryLog.def()
+    }
+}
+
+impl Related<super::recurrence::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Recurrence.def()
+    }
+}
+
+impl Related<super::skipped_occurrence::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SkippedOccurrence.def()
+    }
+}
+
+impl Related<super::contribution::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Contribution.def()
+    }
+}
+
+impl Related<super::card_member::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CardMember.def()
+    }
+}
+
+impl Related<super::share_link::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ShareLink.def()
+    }
+}
+
+impl Related<super::image_revision::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ImageRevision.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,92 @@
+use domain::repository::{CardMemberModel, CardRole};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "card_member")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub card_id: Uuid,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub user_id: Uuid,
+    pub role: Role,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+pub enum Role {
+    #[sea_orm(string_value = "owner")]
+    Owner,
+    #[sea_orm(string_value = "editor")]
+    Editor,
+    #[sea_orm(string_value = "viewer")]
+    Viewer,
+}
+
+impl From<CardRole> for Role {
+    fn from(value: CardRole) -> Self {
+        match value {
+            CardRole::Owner => Self::Owner,
+            CardRole::Editor => Self::Editor,
+            CardRole::Viewer => Self::Viewer,
+        }
+    }
+}
+
+impl From<Role> for CardRole {
+    fn from(value: Role) -> Self {
+        match value {
+            Role::Owner => Self::Owner,
+            Role::Editor => Self::Editor,
+            Role::Viewer => Self::Viewer,
+        }
+    }
+}
+
+impl From<CardMemberModel> for Model {
+    fn from(value: CardMemberModel) -> Self {
+        let CardMemberModel {
+            card_id,
+            user_id,
+            role,
+        } = value;
+        Self {
+            card_id,
+            user_id,
+            role: role.into(),
+        }
+    }
+}
+
+impl From<Model> for CardMemberModel {
+    fn from(value: Model) -> Self {
+        let Model {
+            card_id,
+            user_id,
+            role,
+        } = value;
+        Self {
+            card_id,
+            user_id,
+            role: role.into(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::card::Entity",
+        from = "Column::CardId",
+        to = "super::card::Column::Id"
+    )]
+    Card,
+}
+
+impl Related<super::card::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Card.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,71 @@
+use domain::repository::ContributionModel;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "contribution")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub card_id: Uuid,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub user_id: Uuid,
+    pub message: Option<String>,
+    pub layer_id: Option<Uuid>,
+    pub contributed_at: Option<DateTimeUtc>,
+}
+
+impl From<ContributionModel> for Model {
+    fn from(value: ContributionModel) -> Self {
+        let ContributionModel {
+            card_id,
+            user_id,
+            message,
+            layer_id,
+            contributed_at,
+        } = value;
+        Self {
+            card_id,
+            user_id,
+            message,
+            layer_id,
+            contributed_at,
+        }
+    }
+}
+
+impl From<Model> for ContributionModel {
+    fn from(value: Model) -> Self {
+        let Model {
+            card_id,
+            user_id,
+            message,
+            layer_id,
+            contributed_at,
+        } = value;
+        Self {
+            card_id,
+            user_id,
+            message,
+            layer_id,
+            contributed_at,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::card::Entity",
+        from = "Column::CardId",
+        to = "super::card::Column::Id"
+    )]
+    Card,
+}
+
+impl Related<super::card::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Card.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,67 @@
+use domain::repository::DeliveryLogModel;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "delivery_log")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub card_id: Uuid,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub group_id: Uuid,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub user_id: Uuid,
+    pub delivered_at: DateTimeUtc,
+}
+
+impl From<DeliveryLogModel> for Model {
+    fn from(value: DeliveryLogModel) -> Self {
+        let DeliveryLogModel {
+            card_id,
+            group_id,
+            user_id,
+            delivered_at,
+        } = value;
+        Self {
+            card_id,
+            group_id,
+            user_id,
+            delivered_at,
+        }
+    }
+}
+
+impl From<Model> for DeliveryLogModel {
+    fn from(value: Model) -> Self {
+        let Model {
+            card_id,
+            group_id,
+            user_id,
+            delivered_at,
+        } = value;
+        Self {
+            card_id,
+            group_id,
+            user_id,
+            delivered_at,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::card::Entity",
+        from = "Column::CardId",
+        to = "super::card::Column::Id"
+    )]
+    Card,
+}
+
+impl Related<super::card::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Card.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,109 @@
+use domain::repository::{ImageKind, ImageRevisionModel};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "image_revision")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub card_id: Uuid,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub kind: Kind,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub revision: u32,
+    pub author_id: Uuid,
+    pub created_at: DateTimeUtc,
+    pub size: u64,
+    pub hash: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(8))")]
+pub enum Kind {
+    #[sea_orm(string_value = "svg")]
+    Svg,
+    #[sea_orm(string_value = "png")]
+    Png,
+}
+
+impl From<ImageKind> for Kind {
+    fn from(value: ImageKind) -> Self {
+        match value {
+            ImageKind::Svg => Self::Svg,
+            ImageKind::Png => Self::Png,
+        }
+    }
+}
+
+impl From<Kind> for ImageKind {
+    fn from(value: Kind) -> Self {
+        match value {
+            Kind::Svg => Self::Svg,
+            Kind::Png => Self::Png,
+        }
+    }
+}
+
+impl From<ImageRevisionModel> for Model {
+    fn from(value: ImageRevisionModel) -> Self {
+        let ImageRevisionModel {
+            card_id,
+            kind,
+            revision,
+            author_id,
+            created_at,
+            size,
+            hash,
+        } = value;
+        Self {
+            card_id,
+            kind: kind.into(),
+            revision,
+            author_id,
+            created_at,
+            size,
+            hash,
+        }
+    }
+}
+
+impl From<Model> for ImageRevisionModel {
+    fn from(value: Model) -> Self {
+        let Model {
+            card_id,
+            kind,
+            revision,
+            author_id,
+            created_at,
+            size,
+            hash,
+        } = value;
+        Self {
+            card_id,
+            kind: kind.into(),
+            revision,
+            author_id,
+            created_at,
+            size,
+            hash,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::card::Entity",
+        from = "Column::CardId",
+        to = "super::card::Column::Id"
+    )]
+    Card,
+}
+
+impl Related<super::card::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Card.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -7,3 +7,63 @@ pub use super::publish_channel::ActiveModel as PublishChannelActiveModel;
 pub use super::publish_channel::Column as PublishChannelColumn;
 pub use super::publish_channel::Entity as PublishChannel;
 pub use super::publish_channel::Model as PublishChannelModel;
+
+pub use super::recipient::ActiveModel as RecipientActiveModel;
+pub use super::recipient::Column as RecipientColumn;
+pub use super::recipient::Entity as Recipient;
+pub use super::recipient::Model as RecipientModel;
+
+pub use super::publish_group::ActiveModel as PublishGroupActiveModel;
+pub use super::publish_group::Column as PublishGroupColumn;
+pub use super::publish_group::Entity as PublishGroup;
+pub use super::publish_group::Model as PublishGroupModel;
+
+pub use super::delivery_log::ActiveModel as DeliveryLogActiveModel;
+pub use super::delivery_log::Column as DeliveryLogColumn;
+pub use super::delivery_log::Entity as DeliveryLog;
+pub use super::delivery_log::Model as DeliveryLogModel;
+
+pub use super::webhook::ActiveModel as WebhookActiveModel;
+pub use super::webhook::Column as WebhookColumn;
+pub use super::webhook::Entity as Webhook;
+pub use super::webhook::Model as WebhookModel;
+
+pub use super::recurrence::ActiveModel as RecurrenceActiveModel;
+pub use super::recurrence::Column as RecurrenceColumn;
+pub use super::recurrence::Entity as Recurrence;
+pub use super::recurrence::Model as RecurrenceModel;
+
+pub use super::skipped_occurrence::ActiveModel as SkippedOccurrenceActiveModel;
+pub use super::skipped_occurrence::Column as SkippedOccurrenceColumn;
+pub use super::skipped_occurrence::Entity as SkippedOccurrence;
+pub use super::skipped_occurrence::Model as SkippedOccurrenceModel;
+
+pub use super::contribution::ActiveModel as ContributionActiveModel;
+pub use super::contribution::Column as ContributionColumn;
+pub use super::contribution::Entity as Contribution;
+pub use super::contribution::Model as ContributionModel;
+
+pub use super::card_member::ActiveModel as CardMemberActiveModel;
+pub use super::card_member::Column as CardMemberColumn;
+pub use super::card_member::Entity as CardMember;
+pub use super::card_member::Model as CardMemberModel;
+
+pub use super::share_link::ActiveModel as ShareLinkActiveModel;
+pub use super::share_link::Column as ShareLinkColumn;
+pub use super::share_link::Entity as ShareLink;
+pub use super::share_link::Model as ShareLinkModel;
+
+pub use super::audit_event::ActiveModel as AuditEventActiveModel;
+pub use super::audit_event::Column as AuditEventColumn;
+pub use super::audit_event::Entity as AuditEvent;
+pub use super::audit_event::Model as AuditEventModel;
+
+pub use super::image_revision::ActiveModel as ImageRevisionActiveModel;
+pub use super::image_revision::Column as ImageRevisionColumn;
+pub use super::image_revision::Entity as ImageRevision;
+pub use super::image_revision::Model as ImageRevisionModel;
+
+pub use super::asset::ActiveModel as AssetActiveModel;
+pub use super::asset::Column as AssetColumn;
+pub use super::asset::Entity as Asset;
+pub use super::asset::Model as AssetModel;
@@ -0,0 +1,44 @@
+use domain::repository::PublishGroupModel;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "publish_group")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub card_id: Uuid,
+}
+
+impl From<PublishGroupModel> for Model {
+    fn from(value: PublishGroupModel) -> Self {
+        let PublishGroupModel { id, card_id } = value;
+        Self { id, card_id }
+    }
+}
+
+impl From<Model> for PublishGroupModel {
+    fn from(value: Model) -> Self {
+        let Model { id, card_id } = value;
+        Self { id, card_id }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::card::Entity",
+        from = "Column::CardId",
+        to = "super::card::Column::Id"
+    )]
+    Card,
+}
+
+impl Related<super::card::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Card.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
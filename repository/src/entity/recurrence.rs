@@ -0,0 +1,70 @@
+use domain::repository::RecurrenceModel;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "card_recurrence")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub card_id: Uuid,
+    pub rrule: String,
+    pub time_zone: String,
+    pub dtstart: DateTimeUtc,
+    pub last_occurrence: Option<DateTimeUtc>,
+}
+
+impl From<RecurrenceModel> for Model {
+    fn from(value: RecurrenceModel) -> Self {
+        let RecurrenceModel {
+            card_id,
+            rrule,
+            time_zone,
+            dtstart,
+            last_occurrence,
+        } = value;
+        Self {
+            card_id,
+            rrule,
+            time_zone,
+            dtstart,
+            last_occurrence,
+        }
+    }
+}
+
+impl From<Model> for RecurrenceModel {
+    fn from(value: Model) -> Self {
+        let Model {
+            card_id,
+            rrule,
+            time_zone,
+            dtstart,
+            last_occurrence,
+        } = value;
+        Self {
+            card_id,
+            rrule,
+            time_zone,
+            dtstart,
+            last_occurrence,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::card::Entity",
+        from = "Column::CardId",
+        to = "super::card::Column::Id"
+    )]
+    Card,
+}
+
+impl Related<super::card::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Card.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
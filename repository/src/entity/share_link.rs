@@ -0,0 +1,70 @@
+use domain::repository::ShareLinkModel;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "share_link")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub card_id: Uuid,
+    pub created_by: Uuid,
+    pub created_at: DateTimeUtc,
+    pub expires_at: DateTimeUtc,
+}
+
+impl From<ShareLinkModel> for Model {
+    fn from(value: ShareLinkModel) -> Self {
+        let ShareLinkModel {
+            id,
+            card_id,
+            created_by,
+            created_at,
+            expires_at,
+        } = value;
+        Self {
+            id,
+            card_id,
+            created_by,
+            created_at,
+            expires_at,
+        }
+    }
+}
+
+impl From<Model> for ShareLinkModel {
+    fn from(value: Model) -> Self {
+        let Model {
+            id,
+            card_id,
+            created_by,
+            created_at,
+            expires_at,
+        } = value;
+        Self {
+            id,
+            card_id,
+            created_by,
+            created_at,
+            expires_at,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::card::Entity",
+        from = "Column::CardId",
+        to = "super::card::Column::Id"
+    )]
+    Card,
+}
+
+impl Related<super::card::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Card.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
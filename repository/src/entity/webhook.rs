@@ -0,0 +1,47 @@
+use domain::repository::WebhookModel;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "webhook")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub channel_id: Uuid,
+    pub webhook_id: Uuid,
+    pub secret: String,
+}
+
+impl From<WebhookModel> for Model {
+    fn from(value: WebhookModel) -> Self {
+        let WebhookModel {
+            channel_id,
+            webhook_id,
+            secret,
+        } = value;
+        Self {
+            channel_id,
+            webhook_id,
+            secret,
+        }
+    }
+}
+
+impl From<Model> for WebhookModel {
+    fn from(value: Model) -> Self {
+        let Model {
+            channel_id,
+            webhook_id,
+            secret,
+        } = value;
+        Self {
+            channel_id,
+            webhook_id,
+            secret,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
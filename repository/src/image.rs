@@ -3,7 +3,7 @@ use bytes::Bytes;
 use s3::{creds::Credentials, error::S3Error, Bucket, Region};
 use uuid::Uuid;
 
-use domain::repository::ImageRepository;
+use domain::repository::{ImageKind, ImageRepository};
 
 pub struct ImageRepositoryConfig {
     pub bucket_name: String,
@@ -143,4 +143,49 @@ impl ImageRepository for ImageRepositoryImpl {
         bucket.delete_object(key).await?;
         Ok(())
     }
+    async fn save_revision(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+        revision: u32,
+        content: &Bytes,
+    ) -> Result<(), RepositoryError> {
+        let bucket = &self.0;
+        let key = revision_key(card_id, kind, revision);
+        bucket
+            .put_object_with_content_type(&key, content, kind.mime_type())
+            .await?;
+        Ok(())
+    }
+    async fn get_revision(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+        revision: u32,
+    ) -> Result<Option<Bytes>, RepositoryError> {
+        let bucket = &self.0;
+        let key = revision_key(card_id, kind, revision);
+        let image = bucket.get_object(&key).await;
+        match image {
+            Ok(x) => Ok(Some(Bytes::from(x.to_vec()))),
+            Err(S3Error::Http(404, _)) => Ok(None),
+            Err(e) => Err(RepositoryError::S3Err(e)),
+        }
+    }
+    async fn delete_revision(
+        &self,
+        card_id: Uuid,
+        kind: ImageKind,
+        revision: u32,
+    ) -> Result<(), RepositoryError> {
+        let bucket = &self.0;
+        let key = revision_key(card_id, kind, revision);
+        bucket.delete_object(key).await?;
+        Ok(())
+    }
+}
+
+/// 現在の画像(`<card_id>.svg`)とは別に、版ごとに置く
+fn revision_key(card_id: Uuid, kind: ImageKind, revision: u32) -> String {
+    format!("revisions/{}/{}.{}", card_id, revision, kind.extension())
 }
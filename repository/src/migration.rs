@@ -1,12 +1,44 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20231220_000002_create_recipient;
+mod m20231221_000003_create_publish_group;
+mod m20231222_000004_create_webhook;
+pub mod m20231223_000005_add_card_status;
+mod m20231224_000006_create_recurrence;
+mod m20231225_000007_add_card_time_zone;
+mod m20231226_000008_create_contribution;
+mod m20231227_000009_create_card_member;
+mod m20231228_000010_create_share_link;
+mod m20231229_000011_create_audit_event;
+mod m20231230_000012_create_image_revision;
+mod m20231231_000013_add_card_version;
+mod m20240101_000014_add_card_deleted_at;
+mod m20240102_000015_add_card_status_changed_at;
+mod m20240103_000016_create_asset;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20231220_000002_create_recipient::Migration),
+            Box::new(m20231221_000003_create_publish_group::Migration),
+            Box::new(m20231222_000004_create_webhook::Migration),
+            Box::new(m20231223_000005_add_card_status::Migration),
+            Box::new(m20231224_000006_create_recurrence::Migration),
+            Box::new(m20231225_000007_add_card_time_zone::Migration),
+            Box::new(m20231226_000008_create_contribution::Migration),
+            Box::new(m20231227_000009_create_card_member::Migration),
+            Box::new(m20231228_000010_create_share_link::Migration),
+            Box::new(m20231229_000011_create_audit_event::Migration),
+            Box::new(m20231230_000012_create_image_revision::Migration),
+            Box::new(m20231231_000013_add_card_version::Migration),
+            Box::new(m20240101_000014_add_card_deleted_at::Migration),
+            Box::new(m20240102_000015_add_card_status_changed_at::Migration),
+            Box::new(m20240103_000016_create_asset::Migration),
+        ]
     }
 }
@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PublishGroup::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PublishGroup::Id).uuid().not_null())
+                    .col(ColumnDef::new(PublishGroup::CardId).uuid().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(DeliveryLog::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(DeliveryLog::CardId).uuid().not_null())
+                    .col(ColumnDef::new(DeliveryLog::GroupId).uuid().not_null())
+                    .col(ColumnDef::new(DeliveryLog::UserId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(DeliveryLog::DeliveredAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PublishGroup::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(DeliveryLog::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PublishGroup {
+    Table,
+    Id,
+    CardId,
+}
+
+#[derive(DeriveIden)]
+enum DeliveryLog {
+    Table,
+    CardId,
+    GroupId,
+    UserId,
+    DeliveredAt,
+}
@@ -0,0 +1,63 @@
+use chrono::Utc;
+use sea_orm_migration::prelude::*;
+
+/// 既に投稿日時を過ぎたカードを配信済みとする
+///
+/// `CURRENT_TIMESTAMP`はMySQLのセッションのタイムゾーンになるので、UTCの今を渡す
+pub fn mark_past_cards_delivered() -> UpdateStatement {
+    Query::update()
+        .table(Card::Table)
+        .value(Card::Status, "delivered")
+        .and_where(Expr::col(Card::PublishDate).lte(Utc::now()))
+        .to_owned()
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Card::Table)
+                    .add_column(
+                        ColumnDef::new(Card::Status)
+                            .string_len(16)
+                            .not_null()
+                            .default("scheduled"),
+                    )
+                    .modify_column(ColumnDef::new(Card::PublishDate).date_time().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager.exec_stmt(mark_past_cards_delivered()).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let drafts = Query::delete()
+            .from_table(Card::Table)
+            .and_where(Expr::col(Card::PublishDate).is_null())
+            .to_owned();
+        manager.exec_stmt(drafts).await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Card::Table)
+                    .drop_column(Card::Status)
+                    .modify_column(ColumnDef::new(Card::PublishDate).date_time().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Card {
+    Table,
+    PublishDate,
+    Status,
+}
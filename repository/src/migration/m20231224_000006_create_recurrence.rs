@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CardRecurrence::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CardRecurrence::CardId)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(CardRecurrence::Rrule).string().not_null())
+                    .col(ColumnDef::new(CardRecurrence::TimeZone).string().not_null())
+                    .col(
+                        ColumnDef::new(CardRecurrence::Dtstart)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(CardRecurrence::LastOccurrence).date_time())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(SkippedOccurrence::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(SkippedOccurrence::CardId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(SkippedOccurrence::Occurrence)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(SkippedOccurrence::CardId)
+                            .col(SkippedOccurrence::Occurrence),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CardRecurrence::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(SkippedOccurrence::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum CardRecurrence {
+    Table,
+    CardId,
+    Rrule,
+    TimeZone,
+    Dtstart,
+    LastOccurrence,
+}
+
+#[derive(DeriveIden)]
+enum SkippedOccurrence {
+    Table,
+    CardId,
+    Occurrence,
+}
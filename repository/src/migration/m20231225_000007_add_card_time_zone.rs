@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 投稿日時はUTCのまま保存し、表示用に作成者のタイムゾーンを持つ
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Card::Table)
+                    .add_column(
+                        ColumnDef::new(Card::TimeZone)
+                            .string_len(64)
+                            .not_null()
+                            .default("Asia/Tokyo"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Card::Table)
+                    .drop_column(Card::TimeZone)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Card {
+    Table,
+    TimeZone,
+}
@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Contribution::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Contribution::CardId).uuid().not_null())
+                    .col(ColumnDef::new(Contribution::UserId).uuid().not_null())
+                    .col(ColumnDef::new(Contribution::Message).string())
+                    .col(ColumnDef::new(Contribution::LayerId).uuid())
+                    .col(ColumnDef::new(Contribution::ContributedAt).date_time())
+                    .primary_key(
+                        Index::create()
+                            .col(Contribution::CardId)
+                            .col(Contribution::UserId),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Contribution::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Contribution {
+    Table,
+    CardId,
+    UserId,
+    Message,
+    LayerId,
+    ContributedAt,
+}
@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CardMember::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(CardMember::CardId).uuid().not_null())
+                    .col(ColumnDef::new(CardMember::UserId).uuid().not_null())
+                    .col(ColumnDef::new(CardMember::Role).string_len(16).not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(CardMember::CardId)
+                            .col(CardMember::UserId),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        // 既存のカードの所有者をメンバーにする
+        let owners = Query::insert()
+            .into_table(CardMember::Table)
+            .columns([CardMember::CardId, CardMember::UserId, CardMember::Role])
+            .select_from(
+                Query::select()
+                    .column(Card::Id)
+                    .column(Card::OwnerId)
+                    .expr(Expr::val("owner"))
+                    .from(Card::Table)
+                    .to_owned(),
+            )
+            .map_err(|e| DbErr::Migration(e.to_string()))?
+            .to_owned();
+        manager.exec_stmt(owners).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CardMember::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum CardMember {
+    Table,
+    CardId,
+    UserId,
+    Role,
+}
+
+#[derive(DeriveIden)]
+enum Card {
+    Table,
+    Id,
+    OwnerId,
+}
@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditEvent::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuditEvent::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AuditEvent::At).date_time().not_null())
+                    .col(ColumnDef::new(AuditEvent::ActorId).uuid())
+                    .col(ColumnDef::new(AuditEvent::Action).string_len(32).not_null())
+                    .col(ColumnDef::new(AuditEvent::CardId).uuid())
+                    .col(ColumnDef::new(AuditEvent::AssetId).uuid())
+                    .col(ColumnDef::new(AuditEvent::Before).text())
+                    .col(ColumnDef::new(AuditEvent::After).text())
+                    .col(ColumnDef::new(AuditEvent::RequestId).string_len(64))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditEvent::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuditEvent {
+    Table,
+    Id,
+    At,
+    ActorId,
+    Action,
+    CardId,
+    AssetId,
+    Before,
+    After,
+    RequestId,
+}
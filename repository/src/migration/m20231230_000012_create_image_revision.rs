@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ImageRevision::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ImageRevision::CardId).uuid().not_null())
+                    .col(ColumnDef::new(ImageRevision::Kind).string_len(8).not_null())
+                    .col(
+                        ColumnDef::new(ImageRevision::Revision)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ImageRevision::AuthorId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(ImageRevision::CreatedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ImageRevision::Size)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ImageRevision::Hash)
+                            .string_len(64)
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(ImageRevision::CardId)
+                            .col(ImageRevision::Kind)
+                            .col(ImageRevision::Revision),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ImageRevision::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ImageRevision {
+    Table,
+    CardId,
+    Kind,
+    Revision,
+    AuthorId,
+    CreatedAt,
+    Size,
+    Hash,
+}
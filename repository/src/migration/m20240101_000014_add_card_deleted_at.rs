@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // ゴミ箱に入れた日時。期限を過ぎたらcronで消す
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Card::Table)
+                    .add_column(ColumnDef::new(Card::DeletedAt).date_time().null())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Card::Table)
+                    .drop_column(Card::DeletedAt)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Card {
+    Table,
+    DeletedAt,
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // ステータスを最後に変えた日時。配信中のまま止まったカードを見分ける
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Card::Table)
+                    .add_column(ColumnDef::new(Card::StatusChangedAt).date_time().null())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Card::Table)
+                    .drop_column(Card::StatusChangedAt)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Card {
+    Table,
+    StatusChangedAt,
+}
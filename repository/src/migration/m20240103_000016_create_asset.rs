@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Asset::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Asset::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Asset::UploadedBy).uuid().not_null())
+                    .col(ColumnDef::new(Asset::CreatedAt).date_time().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Asset::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Asset {
+    Table,
+    Id,
+    UploadedBy,
+    CreatedAt,
+}
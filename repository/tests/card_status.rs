@@ -0,0 +1,195 @@
+use chrono::{Duration, Utc};
+use domain::repository::{CardRepository, CardStatus, PatchCardParams, SaveCardParams};
+use repository::card::CardRepositoryImpl;
+use repository::entity::prelude::*;
+use repository::migration::m20231223_000005_add_card_status::mark_past_cards_delivered;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ConnectOptions, ConnectionTrait, Database, DatabaseConnection,
+    EntityTrait, Schema,
+};
+use uuid::Uuid;
+
+/// エンティティからテーブルを作ったインメモリのSQLite
+async fn database() -> DatabaseConnection {
+    let mut opt = ConnectOptions::new("sqlite::memory:");
+    // 接続ごとに別のDBになるので1本に絞る
+    opt.max_connections(1).sqlx_logging(false);
+    let db = Database::connect(opt).await.unwrap();
+    let backend = db.get_database_backend();
+    let schema = Schema::new(backend);
+    let tables = [
+        schema.create_table_from_entity(Card),
+        schema.create_table_from_entity(PublishChannel),
+        schema.create_table_from_entity(Recipient),
+        schema.create_table_from_entity(PublishGroup),
+        schema.create_table_from_entity(Recurrence),
+        schema.create_table_from_entity(CardMember),
+    ];
+    for table in &tables {
+        db.execute(backend.build(table)).await.unwrap();
+    }
+    db
+}
+
+async fn save_card(repo: &CardRepositoryImpl, status: CardStatus) -> Uuid {
+    let id = Uuid::new_v4();
+    let params = SaveCardParams {
+        id,
+        owner_id: Uuid::new_v4(),
+        publish_date: Some(Utc::now() + Duration::days(1)),
+        message: None,
+        status,
+        time_zone: "Asia/Tokyo".to_string(),
+        channels: vec![Uuid::new_v4()],
+        recipients: vec![],
+        groups: vec![],
+        recurrence: None,
+    };
+    repo.save_card(&params).await.unwrap();
+    id
+}
+
+async fn status(repo: &CardRepositoryImpl, card_id: Uuid) -> CardStatus {
+    repo.get_card_by_id(card_id).await.unwrap().unwrap().status
+}
+
+#[tokio::test]
+async fn update_card_status_is_compare_and_set() {
+    let db = database().await;
+    let repo = CardRepositoryImpl::new(&db);
+    let id = save_card(&repo, CardStatus::Scheduled).await;
+
+    let taken = repo
+        .update_card_status(id, &[CardStatus::Scheduled], CardStatus::Delivering)
+        .await
+        .unwrap();
+    assert_eq!(taken, Some(()));
+    assert_eq!(status(&repo, id).await, CardStatus::Delivering);
+
+    // 同じ遷移は2回目には取れない
+    let again = repo
+        .update_card_status(id, &[CardStatus::Scheduled], CardStatus::Delivering)
+        .await
+        .unwrap();
+    assert_eq!(again, None);
+
+    let wrong = repo
+        .update_card_status(
+            id,
+            &[CardStatus::Draft, CardStatus::Failed],
+            CardStatus::Cancelled,
+        )
+        .await
+        .unwrap();
+    assert_eq!(wrong, None);
+    assert_eq!(status(&repo, id).await, CardStatus::Delivering);
+
+    let missing = repo
+        .update_card_status(
+            Uuid::new_v4(),
+            &[CardStatus::Scheduled],
+            CardStatus::Delivering,
+        )
+        .await
+        .unwrap();
+    assert_eq!(missing, None);
+}
+
+#[tokio::test]
+async fn patch_and_trash_only_editable_cards() {
+    let db = database().await;
+    let repo = CardRepositoryImpl::new(&db);
+    let patch = |id| PatchCardParams {
+        id,
+        version: 1,
+        message: Some(Some("late".to_string())),
+        ..Default::default()
+    };
+    let draft = save_card(&repo, CardStatus::Draft).await;
+    assert_eq!(repo.patch_card(&patch(draft)).await.unwrap(), Some(()));
+    assert_eq!(repo.trash_card(draft, Utc::now()).await.unwrap(), Some(()));
+
+    // 配信が始まったカードは確認の後でも変えない
+    let delivering = save_card(&repo, CardStatus::Scheduled).await;
+    repo.update_card_status(delivering, &[CardStatus::Scheduled], CardStatus::Delivering)
+        .await
+        .unwrap();
+    assert_eq!(repo.patch_card(&patch(delivering)).await.unwrap(), None);
+    assert_eq!(repo.trash_card(delivering, Utc::now()).await.unwrap(), None);
+    let card = repo.get_card_by_id(delivering).await.unwrap().unwrap();
+    assert_eq!(card.message, None);
+    assert_eq!(card.version, 1);
+}
+
+#[tokio::test]
+async fn migration_marks_only_past_cards_delivered() {
+    let db = database().await;
+    let insert = |publish_date| CardActiveModel {
+        id: ActiveValue::Set(Uuid::new_v4()),
+        owner_id: ActiveValue::Set(Uuid::new_v4()),
+        publish_date: ActiveValue::Set(publish_date),
+        message: ActiveValue::Set(None),
+        status: ActiveValue::Set(CardStatus::Scheduled.into()),
+        time_zone: ActiveValue::Set("Asia/Tokyo".to_string()),
+        version: ActiveValue::Set(1),
+        deleted_at: ActiveValue::Set(None),
+        status_changed_at: ActiveValue::Set(None),
+    };
+    let past = insert(Some(Utc::now() - Duration::days(2)))
+        .insert(&db)
+        .await
+        .unwrap();
+    let future = insert(Some(Utc::now() + Duration::days(2)))
+        .insert(&db)
+        .await
+        .unwrap();
+    let draft = insert(None).insert(&db).await.unwrap();
+
+    let backend = db.get_database_backend();
+    db.execute(backend.build(&mark_past_cards_delivered()))
+        .await
+        .unwrap();
+
+    let status = |id| {
+        let db = &db;
+        async move {
+            let card = Card::find_by_id(id).one(db).await.unwrap().unwrap();
+            CardStatus::from(card.status)
+        }
+    };
+    assert_eq!(status(past.id).await, CardStatus::Delivered);
+    assert_eq!(status(future.id).await, CardStatus::Scheduled);
+    assert_eq!(status(draft.id).await, CardStatus::Scheduled);
+}
+
+#[tokio::test]
+async fn reschedule_stuck_card_waits_for_threshold() {
+    let db = database().await;
+    let repo = CardRepositoryImpl::new(&db);
+    let id = save_card(&repo, CardStatus::Scheduled).await;
+    let publish_date = Utc::now();
+
+    // 配信中でなければ触らない
+    let idle = repo
+        .reschedule_stuck_card(id, Utc::now(), publish_date)
+        .await
+        .unwrap();
+    assert_eq!(idle, None);
+
+    repo.update_card_status(id, &[CardStatus::Scheduled], CardStatus::Delivering)
+        .await
+        .unwrap();
+    let fresh = repo
+        .reschedule_stuck_card(id, Utc::now() - Duration::minutes(10), publish_date)
+        .await
+        .unwrap();
+    assert_eq!(fresh, None);
+    assert_eq!(status(&repo, id).await, CardStatus::Delivering);
+
+    let stuck = repo
+        .reschedule_stuck_card(id, Utc::now() + Duration::seconds(1), publish_date)
+        .await
+        .unwrap();
+    assert_eq!(stuck, Some(()));
+    assert_eq!(status(&repo, id).await, CardStatus::Scheduled);
+}